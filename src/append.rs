@@ -0,0 +1,61 @@
+//! `pdf-ops append`: add pages to the end of an existing PDF, keeping everything else in it.
+
+use anyhow::{Context, Result};
+use lopdf::{Document, Object, ObjectId};
+use std::path::Path;
+
+use crate::split;
+
+/// Append the pages of each file in `additions` (in order) to `target` and save to `output`,
+/// or back over `target` when `output` is `None`. The target's catalog, Info, bookmarks and
+/// page tree stay as they are; new pages hang off the root `/Pages` node. Returns the new page count.
+pub fn run(target: &Path, additions: &[&Path], output: Option<&Path>) -> Result<usize> {
+    let mut doc = Document::load(target).with_context(|| format!("加载 PDF 失败: {}", target.display()))?;
+    let root_pages = root_pages_id(&doc).with_context(|| format!("找不到页面树: {}", target.display()))?;
+
+    for add in additions {
+        let src = Document::load(add).with_context(|| format!("加载 PDF 失败: {}", add.display()))?;
+        let total = src.get_pages().len();
+        if total == 0 { anyhow::bail!("没有可追加的页面: {}", add.display()); }
+        // Only what the pages reference, with inherited attributes already resolved
+        let mut part = split::extract_range(&src, 1, total)?;
+        part.renumber_objects_with(doc.max_id + 1);
+        let page_ids: Vec<ObjectId> = part.get_pages().into_values().collect();
+        let skip = part_tree_ids(&part);
+        for (id, mut obj) in part.objects {
+            if skip.contains(&id) { continue; }
+            if page_ids.contains(&id) {
+                if let Ok(d) = obj.as_dict_mut() { d.set("Parent", Object::Reference(root_pages)); }
+            }
+            doc.objects.insert(id, obj);
+        }
+        doc.max_id = doc.max_id.max(part.max_id);
+
+        let pages = doc.get_dictionary_mut(root_pages)?;
+        let count = pages.get(b"Count").and_then(Object::as_i64).unwrap_or(0);
+        pages.set("Count", count + page_ids.len() as i64);
+        let mut kids = pages.get(b"Kids").and_then(Object::as_array).cloned().unwrap_or_default();
+        kids.extend(page_ids.iter().map(|&id| Object::Reference(id)));
+        pages.set("Kids", kids);
+    }
+
+    doc.compress();
+    let dest = output.unwrap_or(target);
+    // Written next to the destination first, so a failed save never truncates the target
+    crate::interrupt::save(&mut doc, dest)?;
+    Ok(doc.get_pages().len())
+}
+
+fn root_pages_id(doc: &Document) -> Result<ObjectId> {
+    Ok(doc.catalog()?.get(b"Pages")?.as_reference()?)
+}
+
+// The fresh Catalog and Pages objects extract_range built; the target keeps its own
+fn part_tree_ids(part: &Document) -> Vec<ObjectId> {
+    let mut ids = Vec::new();
+    if let Ok(Object::Reference(catalog)) = part.trailer.get(b"Root") {
+        ids.push(*catalog);
+    }
+    if let Ok(pages) = root_pages_id(part) { ids.push(pages); }
+    ids
+}
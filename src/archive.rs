@@ -0,0 +1,135 @@
+//! Reading PDFs out of a zip archive (`merge --from-zip`). Only what such archives use in
+//! practice is supported: stored and deflated entries, no encryption, no zip64.
+
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+
+use crate::scan;
+
+const EOCD_SIG: u32 = 0x0605_4b50;
+const CENTRAL_SIG: u32 = 0x0201_4b50;
+const LOCAL_SIG: u32 = 0x0403_4b50;
+
+/// One file entry from the archive's central directory.
+#[derive(Debug, Clone)]
+pub struct ZipEntry {
+    /// Entry name as stored, '/'-separated
+    pub name: String,
+    method: u16,
+    flags: u16,
+    crc32: u32,
+    compressed_size: u64,
+    size: u64,
+    local_offset: u64,
+}
+
+fn u16_at(b: &[u8], i: usize) -> u16 { u16::from_le_bytes([b[i], b[i + 1]]) }
+fn u32_at(b: &[u8], i: usize) -> u32 { u32::from_le_bytes([b[i], b[i + 1], b[i + 2], b[i + 3]]) }
+
+/// Entries of the central directory, in archive order (directories left out).
+pub fn entries(archive: &Path) -> Result<Vec<ZipEntry>> {
+    let mut f = File::open(archive).with_context(|| format!("打开压缩包失败: {}", archive.display()))?;
+    let len = f.metadata()?.len();
+    // End of central directory: 22 bytes plus a comment of up to 64 KiB, at the very end
+    let tail_len = len.min(22 + 0xFFFF);
+    let mut tail = vec![0u8; tail_len as usize];
+    f.seek(SeekFrom::Start(len - tail_len))?;
+    f.read_exact(&mut tail)?;
+    let eocd = (0..tail.len().saturating_sub(21)).rev()
+        .find(|&i| u32_at(&tail, i) == EOCD_SIG)
+        .with_context(|| format!("不是有效的 zip 文件: {}", archive.display()))?;
+    let count = u16_at(&tail, eocd + 10) as usize;
+    let (cd_size, cd_offset) = (u32_at(&tail, eocd + 12), u32_at(&tail, eocd + 16));
+    if count == 0xFFFF || cd_offset == 0xFFFF_FFFF {
+        anyhow::bail!("不支持 zip64 压缩包: {}", archive.display());
+    }
+
+    let mut cd = vec![0u8; cd_size as usize];
+    f.seek(SeekFrom::Start(cd_offset as u64))?;
+    f.read_exact(&mut cd).with_context(|| format!("读取 zip 目录失败: {}", archive.display()))?;
+    let mut out = Vec::with_capacity(count);
+    let mut i = 0;
+    for _ in 0..count {
+        if i + 46 > cd.len() || u32_at(&cd, i) != CENTRAL_SIG {
+            anyhow::bail!("zip 目录已损坏: {}", archive.display());
+        }
+        let (name_len, extra_len, comment_len) = (u16_at(&cd, i + 28) as usize, u16_at(&cd, i + 30) as usize, u16_at(&cd, i + 32) as usize);
+        let name_bytes = cd.get(i + 46..i + 46 + name_len).with_context(|| format!("zip 目录已损坏: {}", archive.display()))?;
+        let entry = ZipEntry {
+            name: String::from_utf8_lossy(name_bytes).replace('\\', "/"),
+            flags: u16_at(&cd, i + 8),
+            method: u16_at(&cd, i + 10),
+            crc32: u32_at(&cd, i + 16),
+            compressed_size: u32_at(&cd, i + 20) as u64,
+            size: u32_at(&cd, i + 24) as u64,
+            local_offset: u32_at(&cd, i + 42) as u64,
+        };
+        i += 46 + name_len + extra_len + comment_len;
+        if !entry.name.ends_with('/') { out.push(entry); }
+    }
+    Ok(out)
+}
+
+/// Where an entry lands under `dest`; `None` for names that would escape it (absolute, `..`).
+fn entry_path(dest: &Path, name: &str) -> Option<PathBuf> {
+    let rel = Path::new(name);
+    rel.components().all(|c| matches!(c, Component::Normal(_))).then(|| dest.join(rel))
+}
+
+fn extract(f: &mut File, entry: &ZipEntry, to: &Path) -> Result<()> {
+    if entry.flags & 1 != 0 { anyhow::bail!("不支持加密条目: {}", entry.name); }
+    let mut local = [0u8; 30];
+    f.seek(SeekFrom::Start(entry.local_offset))?;
+    f.read_exact(&mut local)?;
+    if u32_at(&local, 0) != LOCAL_SIG { anyhow::bail!("zip 条目已损坏: {}", entry.name); }
+    let skip = u16_at(&local, 26) as i64 + u16_at(&local, 28) as i64;
+    f.seek(SeekFrom::Current(skip))?;
+    let mut raw = (&mut *f).take(entry.compressed_size);
+    let mut data = Vec::with_capacity(entry.size as usize);
+    match entry.method {
+        0 => { raw.read_to_end(&mut data)?; }
+        8 => { flate2::read::DeflateDecoder::new(raw).read_to_end(&mut data)?; }
+        m => anyhow::bail!("不支持的压缩方式 {}: {}", m, entry.name),
+    }
+    let mut crc = flate2::Crc::new();
+    crc.update(&data);
+    if data.len() as u64 != entry.size || crc.sum() != entry.crc32 {
+        anyhow::bail!("zip 条目校验失败: {}", entry.name);
+    }
+    if let Some(parent) = to.parent() { std::fs::create_dir_all(parent)?; }
+    std::fs::write(to, data).with_context(|| format!("写入失败: {}", to.display()))
+}
+
+/// Extract the `.pdf` entries whose names pass the include/exclude globs into `dest`, keeping
+/// their paths inside the archive. Returns the written files in entry order.
+pub fn extract_pdfs(archive: &Path, dest: &Path, includes: &[String], excludes: &[String], case_insensitive: bool) -> Result<Vec<PathBuf>> {
+    let include_set = scan::build_globset(includes, case_insensitive).context("包含规则无效")?;
+    let exclude_set = scan::build_globset(excludes, case_insensitive).context("排除规则无效")?;
+    let mut f = File::open(archive).with_context(|| format!("打开压缩包失败: {}", archive.display()))?;
+    let mut out = Vec::new();
+    for entry in entries(archive)? {
+        let is_pdf = Path::new(&entry.name).extension().map(|e| e.eq_ignore_ascii_case("pdf")).unwrap_or(false);
+        if !is_pdf { continue; }
+        if !include_set.is_empty() && !include_set.is_match(&entry.name) { continue; }
+        if exclude_set.is_match(&entry.name) { continue; }
+        let to = entry_path(dest, &entry.name).with_context(|| format!("zip 条目路径不安全: {}", entry.name))?;
+        extract(&mut f, &entry, &to).with_context(|| format!("解压失败: {}", archive.display()))?;
+        out.push(to);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entry_paths_stay_inside_dest() {
+        let dest = Path::new("/tmp/x");
+        assert_eq!(entry_path(dest, "a/b.pdf"), Some(dest.join("a/b.pdf")));
+        assert_eq!(entry_path(dest, "../b.pdf"), None);
+        assert_eq!(entry_path(dest, "/etc/b.pdf"), None);
+    }
+}
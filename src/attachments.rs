@@ -0,0 +1,115 @@
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use std::collections::HashSet;
+use std::ops::ControlFlow;
+
+use crate::meta;
+use crate::tree::{self, deref_array, deref_dict};
+
+/// What merge does with file attachments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttachmentMode {
+    /// Lossy: the `/EmbeddedFiles` trees are not carried over (attachment annotations on pages stay)
+    #[default]
+    Drop,
+    /// Merge every input's `/EmbeddedFiles` tree into the output, renaming clashing names
+    Keep,
+    /// Drop the trees and remove `/FileAttachment` annotations as well
+    Strip,
+}
+
+/// The catalog `/Names` `/EmbeddedFiles` entries of `doc` as (name, file specification) pairs,
+/// in tree order.
+pub(crate) fn embedded_files(doc: &Document) -> Vec<(String, Object)> {
+    let mut out = Vec::new();
+    let root = doc.catalog().ok()
+        .and_then(|c| deref_dict(doc, c.get(b"Names").ok()))
+        .and_then(|names| deref_dict(doc, names.get(b"EmbeddedFiles").ok()));
+    if let Some(root) = root {
+        let _: ControlFlow<()> = tree::walk_leaves(doc, root, b"Names", &mut |names| {
+            for pair in names.chunks(2) {
+                let [Object::String(key, _), value] = pair else { continue };
+                out.push((meta::decode_text(key), value.clone()));
+            }
+            ControlFlow::Continue(())
+        });
+    }
+    out
+}
+
+/// Add a `/Names` dictionary holding one flat `/EmbeddedFiles` tree for `files` and return its
+/// id, or `None` when there is nothing to attach. A name already taken becomes `name (2).ext`,
+/// `name (3).ext`, ...
+pub(crate) fn write_embedded_files(doc: &mut Document, files: Vec<(String, Object)>) -> Option<ObjectId> {
+    if files.is_empty() { return None; }
+    let mut taken: HashSet<String> = HashSet::new();
+    let mut entries: Vec<(Object, Object)> = files.into_iter().map(|(name, spec)| {
+        let name = unique_name(&name, &taken);
+        taken.insert(name.clone());
+        (meta::encode_text(&name), spec)
+    }).collect();
+    // Name tree keys must be in byte order
+    entries.sort_by(|a, b| a.0.as_str().unwrap_or_default().cmp(b.0.as_str().unwrap_or_default()));
+    let names: Vec<Object> = entries.into_iter().flat_map(|(k, v)| [k, v]).collect();
+    let mut tree = Dictionary::new();
+    tree.set("Names", names);
+    let mut dict = Dictionary::new();
+    dict.set("EmbeddedFiles", tree);
+    Some(doc.add_object(dict))
+}
+
+fn unique_name(name: &str, taken: &HashSet<String>) -> String {
+    if !taken.contains(name) { return name.to_string(); }
+    let (stem, ext) = match name.rfind('.') {
+        Some(dot) if dot > 0 => name.split_at(dot),
+        _ => (name, ""),
+    };
+    (2..).map(|n| format!("{} ({}){}", stem, n, ext)).find(|c| !taken.contains(c)).expect("unbounded range")
+}
+
+/// Remove `/FileAttachment` annotations from `page_ids`. Returns the number removed.
+pub(crate) fn strip_file_attachments(doc: &mut Document, page_ids: &[ObjectId]) -> usize {
+    let mut removed = 0;
+    for &pid in page_ids {
+        let Ok(page) = doc.get_dictionary(pid) else { continue };
+        let Some(annots) = deref_array(doc, page.get(b"Annots").ok()) else { continue };
+        let kept: Vec<Object> = annots.iter().filter(|a| !is_file_attachment(doc, a)).cloned().collect();
+        if kept.len() == annots.len() { continue; }
+        removed += annots.len() - kept.len();
+        if let Ok(page) = doc.get_dictionary_mut(pid) { page.set("Annots", kept); }
+    }
+    removed
+}
+
+fn is_file_attachment(doc: &Document, annot: &Object) -> bool {
+    deref_dict(doc, Some(annot))
+        .is_some_and(|d| matches!(d.get(b"Subtype"), Ok(Object::Name(n)) if n == b"FileAttachment"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::dictionary;
+
+    #[test]
+    fn clashing_names_are_renamed_and_sorted() {
+        let mut doc = Document::with_version("1.5");
+        let files = vec![
+            ("report.pdf".to_string(), Object::Integer(1)),
+            ("data.csv".to_string(), Object::Integer(2)),
+            ("report.pdf".to_string(), Object::Integer(3)),
+            ("report.pdf".to_string(), Object::Integer(4)),
+        ];
+        let names_id = write_embedded_files(&mut doc, files).unwrap();
+        let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Names" => names_id });
+        doc.trailer.set("Root", catalog_id);
+
+        let found: Vec<(String, i64)> = embedded_files(&doc).into_iter().map(|(n, v)| (n, v.as_i64().unwrap())).collect();
+        assert_eq!(found, [
+            ("data.csv".to_string(), 2),
+            ("report (2).pdf".to_string(), 3),
+            ("report (3).pdf".to_string(), 4),
+            ("report.pdf".to_string(), 1),
+        ]);
+    }
+}
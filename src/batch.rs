@@ -0,0 +1,119 @@
+//! `pdf-ops batch jobs.csv`: run merge/split jobs listed in a CSV, one per row.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::merge::{self, MergeOptions};
+use crate::progress::ProgressSink;
+use crate::split::{self, SplitMode, SplitOptions};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobOp {
+    Merge,
+    Split,
+}
+
+/// One CSV row. Relative paths resolve against the CSV file's directory.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Job {
+    pub op: JobOp,
+    /// Merge: input directory; split: input PDF
+    pub input: PathBuf,
+    /// Merge: output file; split: output directory
+    pub output: PathBuf,
+    /// Merge: `--pages`; split: `--ranges` (empty = one file per page)
+    #[serde(default)]
+    pub pages: Option<String>,
+}
+
+/// Outcome of one row (1-based, header excluded).
+#[derive(Debug)]
+pub struct JobResult {
+    pub row: usize,
+    pub op: Option<JobOp>,
+    pub output: Option<PathBuf>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct BatchSummary {
+    pub results: Vec<JobResult>,
+    /// Rows left unrun after a failure with `fail_fast`
+    pub skipped: usize,
+}
+
+impl BatchSummary {
+    pub fn failures(&self) -> usize { self.results.iter().filter(|r| r.error.is_some()).count() }
+
+    pub fn report(&self) -> String {
+        let mut out = String::new();
+        for r in &self.results {
+            let op = match r.op { Some(JobOp::Merge) => "merge", Some(JobOp::Split) => "split", None => "?" };
+            match (&r.error, &r.output) {
+                (None, Some(o)) => out.push_str(&format!("  ✅ #{} {} -> {}\n", r.row, op, o.display())),
+                (Some(e), _) => out.push_str(&format!("  ❌ #{} {}: {}\n", r.row, op, e)),
+                (None, None) => {}
+            }
+        }
+        out.push_str(&format!("成功 {}，失败 {}", self.results.len() - self.failures(), self.failures()));
+        if self.skipped > 0 { out.push_str(&format!("，跳过 {}", self.skipped)); }
+        out
+    }
+}
+
+/// Run every row of `csv_path` in order. A failing row is recorded and the batch goes on,
+/// unless `fail_fast`. `progress` counts jobs; each job reports into `progress.child()`.
+pub fn run(csv_path: &Path, force: bool, fail_fast: bool, progress: &dyn ProgressSink) -> Result<BatchSummary> {
+    let base = csv_path.parent().unwrap_or(Path::new("")).to_path_buf();
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_path(csv_path)
+        .with_context(|| format!("读取任务文件失败: {}", csv_path.display()))?;
+    let rows: Vec<csv::Result<Job>> = reader.deserialize().collect();
+
+    let total = rows.len();
+    progress.set_len(total as u64);
+    let mut summary = BatchSummary::default();
+    for (i, row) in rows.into_iter().enumerate() {
+        let result = match row {
+            Ok(job) => {
+                progress.set_message(std::borrow::Cow::Owned(format!("#{} {}", i + 1, job.input.display())));
+                let output = base.join(&job.output);
+                let error = run_job(&job, &base, force, progress.child()).err().map(|e| format!("{:#}", e));
+                JobResult { row: i + 1, op: Some(job.op), output: Some(output), error }
+            }
+            Err(e) => JobResult { row: i + 1, op: None, output: None, error: Some(format!("无效的任务行: {}", e)) },
+        };
+        let failed = result.error.is_some();
+        summary.results.push(result);
+        progress.inc(1);
+        if failed && fail_fast {
+            summary.skipped = total - (i + 1);
+            break;
+        }
+    }
+    progress.finish(std::borrow::Cow::from("批处理完成"));
+    Ok(summary)
+}
+
+fn run_job(job: &Job, base: &Path, force: bool, progress: &dyn ProgressSink) -> Result<()> {
+    let input = base.join(&job.input);
+    let output = base.join(&job.output);
+    let pages = job.pages.clone().filter(|p| !p.is_empty());
+    match job.op {
+        JobOp::Merge => {
+            let opts = MergeOptions { pages, force, ..Default::default() };
+            merge::run(&input, &output, &opts, progress).map(|_| ())
+        }
+        JobOp::Split => {
+            let mode = match pages.as_deref() {
+                Some(spec) => SplitMode::ranges(spec)?,
+                None => SplitMode::Each,
+            };
+            let opts = SplitOptions { mode, force, ..Default::default() };
+            split::run(&input, &output, &opts, progress)
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use lopdf::content::Content;
+use lopdf::{Document, ObjectId};
+
+// Operators that put marks on the page: text showing, path painting,
+// XObjects (images/forms), shadings and inline images.
+const PAINT_OPS: &[&str] = &[
+    "Tj", "TJ", "'", "\"",
+    "S", "s", "f", "F", "f*", "B", "B*", "b", "b*",
+    "Do", "sh", "BI", "EI",
+];
+
+/// Heuristic blank-page check used by `--exclude-blank`.
+///
+/// A page counts as blank when it has no `/Contents`, or when its decoded
+/// content contains no painting operators (only state changes such as `q`/`Q`/`cm`).
+/// Limits: annotations and form fields are not inspected, and marks painted in
+/// white or outside the MediaBox still count as content. Undecodable content is
+/// treated as non-blank so real pages are never dropped by accident.
+pub(crate) fn is_blank_page(doc: &Document, page_id: ObjectId) -> bool {
+    let data = match doc.get_page_content(page_id) {
+        Ok(d) => d,
+        Err(_) => return false,
+    };
+    if data.iter().all(|b| b.is_ascii_whitespace()) { return true; }
+    match Content::decode(&data) {
+        Ok(content) => !content.operations.iter().any(|op| PAINT_OPS.contains(&op.operator.as_str())),
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{Dictionary, Object, Stream};
+
+    fn doc_with_page(content: Option<&[u8]>) -> (Document, ObjectId) {
+        let mut doc = Document::with_version("1.5");
+        let mut page = Dictionary::new();
+        page.set("Type", "Page");
+        if let Some(c) = content {
+            let cid = doc.add_object(Object::Stream(Stream::new(Dictionary::new(), c.to_vec())));
+            page.set("Contents", Object::Reference(cid));
+        }
+        let page_id = doc.add_object(Object::Dictionary(page));
+        (doc, page_id)
+    }
+
+    #[test]
+    fn missing_or_trivial_contents_is_blank() {
+        let (doc, pid) = doc_with_page(None);
+        assert!(is_blank_page(&doc, pid));
+        let (doc, pid) = doc_with_page(Some(b"  \n "));
+        assert!(is_blank_page(&doc, pid));
+        let (doc, pid) = doc_with_page(Some(b"q 1 0 0 1 0 0 cm Q"));
+        assert!(is_blank_page(&doc, pid));
+    }
+
+    #[test]
+    fn text_or_paths_are_not_blank() {
+        let (doc, pid) = doc_with_page(Some(b"BT /F1 12 Tf 10 10 Td (Hi) Tj ET"));
+        assert!(!is_blank_page(&doc, pid));
+        let (doc, pid) = doc_with_page(Some(b"0 0 10 10 re f"));
+        assert!(!is_blank_page(&doc, pid));
+    }
+}
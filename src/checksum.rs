@@ -0,0 +1,53 @@
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+
+/// Hex SHA-256 of a file, read in chunks.
+pub(crate) fn sha256_file(path: &Path) -> Result<String> {
+    let file = File::open(path).with_context(|| format!("打开文件失败: {}", path.display()))?;
+    let mut reader = BufReader::new(file);
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buf).with_context(|| format!("读取文件失败: {}", path.display()))?;
+        if n == 0 { break; }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Sidecar for `output`: `<output>.sha256`
+pub(crate) fn manifest_path(output: &Path) -> PathBuf {
+    let mut name = output.as_os_str().to_owned();
+    name.push(".sha256");
+    PathBuf::from(name)
+}
+
+/// Write a `sha256sum`-style manifest (`hash␠␠path`, inputs in order, then the output) next to
+/// `output`, so `sha256sum -c` can check it later from the same working directory.
+pub(crate) fn write_manifest(inputs: &[&Path], output: &Path) -> Result<PathBuf> {
+    let mut body = String::new();
+    for path in inputs.iter().copied().chain(std::iter::once(output)) {
+        body.push_str(&format!("{}  {}\n", sha256_file(path)?, path.display()));
+    }
+    let manifest = manifest_path(output);
+    crate::interrupt::write_via_part(&manifest, |tmp| Ok(std::fs::write(tmp, body)?))
+        .with_context(|| format!("写入校验清单失败: {}", manifest.display()))?;
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_known_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let p = dir.path().join("abc.txt");
+        std::fs::write(&p, b"abc").unwrap();
+        assert_eq!(sha256_file(&p).unwrap(), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        assert_eq!(manifest_path(Path::new("out/merged.pdf")), PathBuf::from("out/merged.pdf.sha256"));
+    }
+}
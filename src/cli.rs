@@ -1,11 +1,84 @@
 use clap::{Parser, Subcommand, Args};
 use std::path::PathBuf;
 
+use pdf_ops::attachments::AttachmentMode;
+use pdf_ops::labels::Renumber;
+use pdf_ops::merge::{CheckMode, OnEmpty, Orientation, PageAlign, PageSize};
+use pdf_ops::meta::MetadataMode;
+use pdf_ops::sort::SortBy;
+use pdf_ops::split::{DirStructure, NamedOutput, PagesRule, SplitMode, TitleSanitize};
+use pdf_ops::stamp::Corner;
+use pdf_ops::version::PdfVersion;
+
+fn parse_octal_mode(s: &str) -> Result<u32, String> {
+    u32::from_str_radix(s.trim_start_matches("0o"), 8)
+        .ok()
+        .filter(|m| *m <= 0o7777)
+        .ok_or_else(|| format!("invalid octal mode: {}", s))
+}
+
+// Plain bytes, or with a K/M/G suffix (powers of 1024)
+fn parse_byte_size(s: &str) -> Result<u64, String> {
+    let (digits, unit) = match s.trim().char_indices().last() {
+        Some((i, c)) if c.is_ascii_alphabetic() => (&s.trim()[..i], c.to_ascii_uppercase()),
+        _ => (s.trim(), 'B'),
+    };
+    let shift = match unit { 'B' => 0, 'K' => 10, 'M' => 20, 'G' => 30, _ => return Err(format!("unknown size unit: {}", s)) };
+    digits.trim().parse::<u64>().ok()
+        .and_then(|n| n.checked_mul(1 << shift))
+        .ok_or_else(|| format!("invalid size: {}", s))
+}
+
+fn parse_split_mode(s: &str) -> Result<SplitMode, String> {
+    s.parse().map_err(|e: anyhow::Error| format!("{:#}", e))
+}
+
+fn parse_scale(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(f) if f.is_finite() && f > 0.0 => Ok(f),
+        _ => Err(format!("scale must be a positive number: {}", s)),
+    }
+}
+
+fn parse_named_output(s: &str) -> Result<NamedOutput, String> {
+    s.parse().map_err(|e: anyhow::Error| format!("{:#}", e))
+}
+
+fn parse_pages_rule(s: &str) -> Result<PagesRule, String> {
+    s.parse().map_err(|e: anyhow::Error| format!("{:#}", e))
+}
+
+fn parse_page_size(s: &str) -> Result<PageSize, String> {
+    s.parse().map_err(|e: anyhow::Error| format!("{:#}", e))
+}
+
 #[derive(Parser, Debug)]
 #[command(version, about = "pdf-ops: merge/split PDFs via CLI")]
 pub struct Cli {
     #[command(subcommand)]
     pub command: Option<Commands>,
+    /// Config file with defaults (default: ./pdf-ops.toml, then ~/.config/pdf-ops/config.toml)
+    #[arg(long, global = true, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+    /// Also emit structured events (scan, load, write, completion) on stderr in this format
+    #[arg(long, global = true, value_enum, value_name = "FORMAT")]
+    pub log_format: Option<LogFormat>,
+    /// Reject page specs with empty segments ("1,,3", ",1") instead of skipping them; a single trailing separator is still fine
+    #[arg(long, global = true)]
+    pub strict_spec: bool,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InfoFormat {
+    Table,
+    Json,
+    Csv,
 }
 
 #[derive(Subcommand, Debug)]
@@ -14,14 +87,36 @@ pub enum Commands {
     Merge(MergeArgs),
     /// Split a single PDF into multiple files
     Split(SplitArgs),
+    /// Show page count, title and per-page sizes of a PDF
+    Info(InfoArgs),
+    /// Print the text shown on pages of a PDF (plain Tj/TJ text; see README for limits)
+    ExtractText(ExtractTextArgs),
+    /// Check a PDF's structure (catalog, page tree and /Count, MediaBox, dangling /Kids and /Annots references)
+    Validate(ValidateArgs),
+    /// Resolve a page spec against a page count, without a document
+    ExplainSpec(ExplainSpecArgs),
+    /// Run merge/split jobs from a CSV (columns: op,input,output,pages)
+    Batch(BatchArgs),
+    /// Append pages of other PDFs to the end of an existing one
+    Append(AppendArgs),
+    /// Interleave a front-side and a back-side scan into duplex order
+    Zip(ZipArgs),
+    /// List the PDFs found under a directory, as merge would see them (for checking globs)
+    Scan(ScanArgs),
+    /// Print a shell completion script to stdout, e.g. `pdf-ops completions bash > /etc/bash_completion.d/pdf-ops`
+    #[command(hide = true)]
+    Completions(CompletionsArgs),
+    /// Print the man page of pdf-ops or one subcommand, or write all of them to a directory
+    #[command(hide = true)]
+    Man(ManArgs),
     /// Launch terminal UI (requires `tui` feature)
     #[cfg(feature = "tui")]
     Tui(TuiArgs),
 }
 
 impl Cli {
-    pub fn default_to_merge(self) -> Commands {
-        match self.command {
+    pub fn default_to_merge(&mut self) -> Commands {
+        match self.command.take() {
             Some(cmd) => cmd,
             None => Commands::Merge(MergeArgs::default()),
         }
@@ -33,34 +128,226 @@ pub struct MergeArgs {
     /// Input directory to scan recursively
     #[arg(short, long, value_name = "DIR", default_value = ".")]
     pub input_dir: String,
-    /// Output file (relative resolves under input_dir)
+    /// Output file (relative resolves under input_dir); a directory (existing, or ending in '/') gets merged.pdf inside it
     #[arg(short, long, value_name = "FILE", default_value = "merged.pdf")]
     pub output: String,
-    /// Page spec applied to each input, e.g. "1-3,5,10-"
+    /// Prepend TEXT to generated output names (merged.pdf in an output directory, <subdir>.pdf with --group-by-subdir)
+    #[arg(long, value_name = "TEXT")]
+    pub prefix: Option<String>,
+    /// Append TEXT to generated output names, before .pdf
+    #[arg(long, value_name = "TEXT")]
+    pub suffix: Option<String>,
+    /// Merge the PDFs inside this zip archive instead of scanning input_dir (globs match entry names)
+    #[cfg(feature = "zip")]
+    #[arg(long, value_name = "ARCHIVE", conflicts_with_all = ["group_by_subdir", "check", "input_list_json"])]
+    pub from_zip: Option<PathBuf>,
+    /// Merge exactly the files of this JSON list, in order, instead of scanning input_dir: [{"path": "a.pdf", "pages": "1-2"}, ...]; "pages" is optional and replaces --pages/--first-pages for that file
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["group_by_subdir", "check"])]
+    pub input_list_json: Option<PathBuf>,
+    /// Page spec applied to each input, e.g. "1-3,5,10-"; "first N" / "last N" also work
     #[arg(long, value_name = "SPEC")]
     pub pages: Option<String>,
+    /// Page spec applied only to the first file (overrides --pages for it), e.g. "1" to keep a cover
+    #[arg(long, value_name = "SPEC")]
+    pub first_pages: Option<String>,
+    /// Take only the pages of the bookmark titled NAME from each input (up to the next bookmark at the same or a higher level)
+    #[arg(long, value_name = "NAME", conflicts_with_all = ["pages", "first_pages", "covers"])]
+    pub section: Option<String>,
     /// Include only files matching these globs (relative to input_dir). Repeatable.
     #[arg(long, value_name = "GLOB")]
     pub include: Vec<String>,
     /// Exclude files matching these globs (relative to input_dir). Repeatable.
     #[arg(long, value_name = "GLOB")]
     pub exclude: Vec<String>,
+    /// Match --include/--exclude ignoring case (e.g. "*.PDF" also matches "file.pdf")
+    #[arg(long)]
+    pub glob_case_insensitive: bool,
+    /// Only PDFs whose text contains TEXT, ignoring case (slow: loads every file; text as extract-text sees it)
+    #[arg(long, value_name = "TEXT", visible_alias = "content-search")]
+    pub contains: Option<String>,
     /// Overwrite output if it already exists
     #[arg(long)]
     pub force: bool,
+    /// Drop pages without visible content (heuristic: no painting operators)
+    #[arg(long)]
+    pub exclude_blank: bool,
+    /// Drop pages identical (same content and resources) to an earlier page of the same input; heuristic, byte-level
+    #[arg(long)]
+    pub dedupe_pages: bool,
+    /// After merging, list output pages identical to an earlier page (across inputs too) without removing them
+    #[arg(long, visible_alias = "detect-duplicates-across-merge")]
+    pub report_duplicates: bool,
+    /// Abort without writing if the merged output would exceed N pages
+    #[arg(long, value_name = "N")]
+    pub max_output_pages: Option<usize>,
+    /// Input ordering: name (path), mtime, size, or title (Info /Title or first bookmark)
+    #[arg(long, visible_alias = "page-order", value_enum, value_name = "KEY", default_value_t = SortBy::Name)]
+    pub sort_by: SortBy,
+    /// Seed for --sort-by shuffle (same seed, same order)
+    #[arg(long, value_name = "N")]
+    pub seed: Option<u64>,
+    /// Output Info: first (copy first input's), none, or merged (union keywords, distinct authors, earliest CreationDate)
+    #[arg(long, value_enum, value_name = "MODE", default_value_t = MetadataMode::None)]
+    pub metadata: MetadataMode,
+    /// Add one top-level bookmark per input, named after the file stem, at its first page
+    #[arg(long)]
+    pub bookmark_files: bool,
+    /// One output per immediate subdirectory (<subdir>.pdf); --output names the output directory (default: the input directory)
+    #[arg(long)]
+    pub group_by_subdir: bool,
+    /// Only the first page of each input (an index of covers); replaces --pages/--first-pages
+    #[arg(long, conflicts_with_all = ["pages", "first_pages"])]
+    pub covers: bool,
+    /// Remove JavaScript (/OpenAction, /AA, /JS, JavaScript actions) from the output
+    #[arg(long)]
+    pub strip_javascript: bool,
+    /// Keep every page box (Crop/Bleed/Trim/Art, including inherited ones) on merged pages; pass false to skip
+    #[arg(long, value_name = "BOOL", default_value_t = true, action = clap::ArgAction::Set)]
+    pub preserve_page_boxes: bool,
+    /// Apply each page's /Rotate to its content (swapping width and height for quarter turns) and clear it
+    #[arg(long)]
+    pub bake_rotation: bool,
+    /// Put a blank page (sized like the page before it) between the pages of consecutive inputs, e.g. for collating printouts
+    #[arg(long, visible_alias = "interleave-blank")]
+    pub separator_blank: bool,
+    /// Put every page of FILE between the pages of consecutive inputs
+    #[arg(long, value_name = "FILE", conflicts_with = "separator_blank")]
+    pub separator: Option<PathBuf>,
+    /// Set /Rotate so every page shows in the --target orientation (by MediaBox aspect; square pages untouched)
+    #[arg(long)]
+    pub rotate_auto: bool,
+    /// Orientation for --rotate-auto
+    #[arg(long = "target", value_enum, value_name = "ORIENTATION", default_value_t = Orientation::Portrait)]
+    pub rotate_target: Orientation,
+    /// Give every page the same size: a4, a3, a5, letter, legal or WIDTHxHEIGHT in points (each page keeps its orientation)
+    #[arg(long, value_name = "SIZE", value_parser = parse_page_size)]
+    pub normalize: Option<PageSize>,
+    /// How --normalize fits pages of another size: center them as they are (margins, or cropping if larger) or scale them to fit
+    #[arg(long, value_enum, value_name = "HOW", default_value_t = PageAlign::Center, requires = "normalize")]
+    pub align_page_size: PageAlign,
+    /// Stamp each merged page with its source filename (printable ASCII only; other characters become '?')
+    #[arg(long)]
+    pub annotate_source: bool,
+    /// Corner for --annotate-source
+    #[arg(long, value_enum, value_name = "CORNER", default_value_t = Corner::BottomRight)]
+    pub annotate_position: Corner,
+    /// Number the merged document 1..N across inputs: page labels (the default), stamped numbers (bottom right, or after the --annotate-source name) or both
+    #[arg(long, value_enum, value_name = "HOW", num_args = 0..=1, default_missing_value = "labels")]
+    pub renumber: Option<Renumber>,
+    /// First number for --renumber
+    #[arg(long, value_name = "N", default_value_t = 1, requires = "renumber", value_parser = clap::value_parser!(u64).range(1..))]
+    pub start_at: u64,
+    /// PDF/A-oriented cleanup, not certified conformance: strip JavaScript and external references, drop encryption, require embedded fonts, write Info/XMP
+    #[cfg(feature = "pdfa")]
+    #[arg(long)]
+    pub pdfa: bool,
+    /// Best-effort greyscale: rewrite colour operators in page content and indexed-image palettes to DeviceGray; photos (RGB/CMYK image data) keep their colour
+    #[cfg(feature = "grayscale")]
+    #[arg(long)]
+    pub grayscale: bool,
+    /// Write <output>.sha256 (sha256sum format) with each input's hash and the output's
+    #[arg(long)]
+    pub checksum_manifest: bool,
+    /// Retry loading an input up to N more times on I/O errors (network mounts), with backoff
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub retries: u32,
+    /// Output PDF version; fails if the content needs a newer one (1.4 also writes a classic xref table)
+    #[arg(long, value_enum, value_name = "VERSION", default_value_t = PdfVersion::V1_5)]
+    pub pdf_version: PdfVersion,
+    /// Embedded files: drop (current behavior, attachments are lost), keep (merge them, renaming clashes) or strip (also remove attachment annotations)
+    #[arg(long, value_enum, value_name = "MODE", default_value_t = AttachmentMode::Drop)]
+    pub attachments: AttachmentMode,
+    /// Reject inputs larger than SIZE before loading them (bytes, or with K/M/G suffix, e.g. 500M)
+    #[arg(long, value_name = "SIZE", value_parser = parse_byte_size)]
+    pub max_file_size: Option<u64>,
+    /// Skip inputs that are too large or fail to load, with a warning, instead of failing
+    #[arg(long)]
+    pub skip_errors: bool,
+    /// /Producer written in the output's document info, replacing any an input had
+    #[arg(long, value_name = "NAME", default_value = pdf_ops::meta::DEFAULT_PRODUCER)]
+    pub producer: String,
+    /// Leave /Producer out of the output's document info
+    #[arg(long, conflicts_with = "producer")]
+    pub strip_producer: bool,
+    /// Write a random trailer /ID (with --metadata first, the first input's /ID is kept instead)
+    #[arg(long)]
+    pub trailer_id: bool,
+    /// Derive the trailer /ID from the inputs' content, so identical inputs give the same ID (implies --trailer-id)
+    #[arg(long)]
+    pub deterministic: bool,
+    /// Always rebuild the output; by default a merge of exactly one input with no page-changing option and the same /Producer copies it verbatim
+    #[arg(long = "no-passthrough", action = clap::ArgAction::SetFalse)]
+    pub passthrough_single: bool,
+    /// Copy the first input's XMP metadata stream (catalog /Metadata) onto the output; it may describe the input rather than the merge
+    #[arg(long, conflicts_with = "strip_xmp")]
+    pub copy_xmp: bool,
+    /// Remove all XMP metadata streams (document, page and image level) from the output
+    #[arg(long)]
+    pub strip_xmp: bool,
+    /// Keep how the first input opens: its /PageMode, /PageLayout and an /OpenAction that goes to a page (never script)
+    #[arg(long, visible_alias = "keep-navigation")]
+    pub keep_open_action: bool,
+    /// Keep the first input's /ViewerPreferences, including the reading direction (/Direction /R2L for Arabic or Hebrew)
+    #[arg(long, visible_alias = "keep-direction")]
+    pub keep_viewer_preferences: bool,
+    /// Write the output's title, author, page count, bookmark tree and page labels as JSON to FILE
+    #[arg(long, value_name = "FILE", conflicts_with = "group_by_subdir")]
+    pub output_metadata_file: Option<PathBuf>,
+    /// Unix permissions for the output (octal, e.g. 600 or 0644); ignored with a warning elsewhere
+    #[arg(long, value_name = "MODE", value_parser = parse_octal_mode)]
+    pub chmod: Option<u32>,
+    /// Open the merged file (the output directory with --group-by-subdir) in the default application afterwards
+    #[arg(long, visible_alias = "open-after")]
+    pub open: bool,
+    /// Merge without asking even when there are more inputs than --confirm-threshold
+    #[arg(short = 'y', long, visible_alias = "assume-yes")]
+    pub yes: bool,
+    /// Ask before merging more than N files (on a terminal; elsewhere --yes is required)
+    #[arg(long, value_name = "N", default_value_t = 1000)]
+    pub confirm_threshold: usize,
+    /// When no PDFs are found: error (default), skip (exit 0 without writing) or touch (write an empty placeholder PDF)
+    #[arg(long, value_enum, value_name = "POLICY", default_value_t = OnEmpty::Error)]
+    pub on_empty: OnEmpty,
+    /// Bound memory on very large merges: write each processed input to <output>.spill and assemble the output from it (slower; same pages and objects)
+    #[arg(long)]
+    pub spill: bool,
+    /// Print output object count and size, and each input's object contribution
+    #[arg(short, long)]
+    pub verbose: bool,
+    /// Print per-file load+copy time (slowest first) and the total
+    #[arg(long)]
+    pub timings: bool,
+    /// Merge in memory and compare with the existing output instead of writing it: exit 0 if unchanged, 1 if it would change (content: page count and hash, the default; pages: page count only)
+    #[arg(long, value_enum, value_name = "WHAT", num_args = 0..=1, default_missing_value = "content", conflicts_with_all = ["group_by_subdir", "open"])]
+    pub check: Option<CheckMode>,
 }
 
 impl Default for MergeArgs {
     fn default() -> Self {
-        MergeArgs { input_dir: ".".into(), output: "merged.pdf".into(), pages: None, include: vec![], exclude: vec![], force: false }
+        MergeArgs { input_dir: ".".into(), output: "merged.pdf".into(), prefix: None, suffix: None, #[cfg(feature = "zip")] from_zip: None, input_list_json: None, pages: None, first_pages: None, section: None, include: vec![], exclude: vec![], glob_case_insensitive: false, contains: None, force: false, exclude_blank: false, dedupe_pages: false, report_duplicates: false, max_output_pages: None, sort_by: SortBy::Name, seed: None, metadata: MetadataMode::None, bookmark_files: false, group_by_subdir: false, covers: false, strip_javascript: false, preserve_page_boxes: true, bake_rotation: false, separator_blank: false, separator: None, rotate_auto: false, rotate_target: Orientation::Portrait, normalize: None, align_page_size: PageAlign::Center, annotate_source: false, annotate_position: Corner::BottomRight, renumber: None, start_at: 1, #[cfg(feature = "pdfa")] pdfa: false, #[cfg(feature = "grayscale")] grayscale: false, checksum_manifest: false, retries: 0, pdf_version: PdfVersion::V1_5, attachments: AttachmentMode::Drop, max_file_size: None, skip_errors: false, producer: pdf_ops::meta::DEFAULT_PRODUCER.into(), strip_producer: false, trailer_id: false, deterministic: false, passthrough_single: true, copy_xmp: false, strip_xmp: false, keep_open_action: false, keep_viewer_preferences: false, output_metadata_file: None, chmod: None, open: false, yes: false, confirm_threshold: 1000, on_empty: OnEmpty::Error, spill: false, verbose: false, timings: false, check: None }
     }
 }
 
 #[derive(Args, Debug)]
 pub struct SplitArgs {
-    /// Input PDF file
-    #[arg(short = 'i', long, value_name = "FILE")]
+    /// Input PDF file, or a directory: every PDF directly inside it is split into the output directory
+    #[arg(short = 'i', long, value_name = "FILE|DIR")]
     pub input: PathBuf,
+    /// With a directory input: split up to N files at a time
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+    pub jobs: u64,
+    /// With a directory input: one job per CPU core (same as --jobs <cores>)
+    #[arg(long, conflicts_with = "jobs")]
+    pub parallel: bool,
+    /// With a directory input: keep running and split each new or changed PDF as it arrives, until Ctrl-C; files already split are remembered in a state file
+    #[arg(long, conflicts_with_all = ["jobs", "parallel", "open"])]
+    pub watch: bool,
+    /// With --watch: how often to look for new files, in milliseconds
+    #[arg(long, value_name = "MS", default_value_t = 2000, requires = "watch", value_parser = clap::value_parser!(u64).range(10..))]
+    pub watch_interval: u64,
+    /// With --watch: where to remember split files (default: .pdf-ops-watch.json in the output directory)
+    #[arg(long, value_name = "FILE", requires = "watch")]
+    pub watch_state: Option<PathBuf>,
     /// Output directory
     #[arg(short = 'd', long, value_name = "DIR", default_value = ".")]
     pub out_dir: PathBuf,
@@ -70,12 +357,231 @@ pub struct SplitArgs {
     /// Ranges to split, e.g. "1-3,4-6,7-" (one output per range)
     #[arg(long, value_name = "SPEC")]
     pub ranges: Option<String>,
-    /// Output filename pattern, supports {base},{start},{end},{index}
+    /// Split into N near-equal parts (10 pages into 3 -> 4,3,3); same as --mode into:N
+    #[arg(long, value_name = "N", conflicts_with_all = ["each", "ranges"], value_parser = clap::value_parser!(u64).range(1..))]
+    pub into: Option<u64>,
+    /// Grouping mode: each | every:N | into:N | ranges:SPEC (replaces --each/--ranges)
+    #[arg(long, value_name = "MODE", conflicts_with_all = ["each", "ranges", "into"], value_parser = parse_split_mode)]
+    pub mode: Option<SplitMode>,
+    /// Copy the input's XMP metadata stream (catalog /Metadata) onto every part
+    #[arg(long, conflicts_with = "strip_xmp")]
+    pub copy_xmp: bool,
+    /// Remove all XMP metadata streams (document, page and image level) from every part
+    #[arg(long)]
+    pub strip_xmp: bool,
+    /// Split only the pages of the bookmark titled NAME (up to the next bookmark at the same or a higher level)
+    #[arg(long, value_name = "NAME", conflicts_with_all = ["pages_for", "outputs", "even_split_at_bookmarks"])]
+    pub section: Option<String>,
+    /// Split only these pages of inputs whose file name matches GLOB, e.g. '*cover*=1'; the first matching rule wins, other inputs are split whole. Repeatable.
+    #[arg(long, value_name = "GLOB=SPEC", value_parser = parse_pages_rule, conflicts_with_all = ["outputs", "even_split_at_bookmarks"])]
+    pub pages_for: Vec<PagesRule>,
+    /// Named output with its own page spec, e.g. chapter1=1-5 (writes chapter1.pdf). Repeatable.
+    #[arg(long = "out", visible_alias = "page-spec-per-output", value_name = "NAME=SPEC", value_parser = parse_named_output, conflicts_with_all = ["each", "ranges", "into", "mode", "pattern"])]
+    pub outputs: Vec<NamedOutput>,
+    /// One output per top-level bookmark, named after its title; combine with --max to cut long chapters
+    #[arg(long, conflicts_with_all = ["each", "ranges", "into", "mode", "pattern", "outputs"])]
+    pub even_split_at_bookmarks: bool,
+    /// With --even-split-at-bookmarks: cut chapters longer than N pages into N-page pieces ({title}-1, {title}-2, …)
+    #[arg(long, value_name = "N", requires = "even_split_at_bookmarks", value_parser = clap::value_parser!(u64).range(1..))]
+    pub max: Option<u64>,
+    /// With --even-split-at-bookmarks: split at bookmarks down to outline level N (1 = chapters, 2 = also sections)
+    #[arg(long, visible_alias = "chunk-by-toc-level", value_name = "N", default_value_t = 1, requires = "even_split_at_bookmarks", value_parser = clap::value_parser!(u64).range(1..))]
+    pub toc_level: u64,
+    /// With --even-split-at-bookmarks: how titles become file names (strict = ASCII letters, digits and '-')
+    #[arg(long, value_enum, value_name = "MODE", default_value_t = TitleSanitize::Keep, requires = "even_split_at_bookmarks")]
+    pub title_sanitize: TitleSanitize,
+    /// With --even-split-at-bookmarks: cut titles to at most N characters
+    #[arg(long, value_name = "N", requires = "even_split_at_bookmarks", value_parser = clap::value_parser!(u64).range(1..))]
+    pub title_max_len: Option<u64>,
+    /// Don't print the group summary
+    #[arg(short, long)]
+    pub quiet: bool,
+    /// Output filename pattern, supports {base},{start},{end},{index}; numbers can be zero-padded, e.g. {index:03}
     #[arg(long, value_name = "PATTERN", default_value = "{base}-{start}-{end}.pdf")]
     pub pattern: String,
+    /// Number of the first part in {index} (e.g. to continue a previous split's numbering)
+    #[arg(long, value_name = "N", default_value_t = 1, visible_alias = "output-index-base")]
+    pub index_base: usize,
     /// Overwrite output files if they already exist
     #[arg(long)]
     pub force: bool,
+    /// Reload the input for each output instead of keeping it in memory (slower)
+    #[arg(long)]
+    pub low_memory: bool,
+    /// Reload every written part and check its page count; bad parts are renamed to *.bad
+    #[arg(long)]
+    pub verify: bool,
+    /// Remove JavaScript (/OpenAction, /AA, /JS, JavaScript actions) from every part
+    #[arg(long)]
+    pub strip_javascript: bool,
+    /// Retry loading the input up to N more times on I/O errors (network mounts), with backoff
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub retries: u32,
+    /// Output PDF version; fails if the content needs a newer one (1.4 also writes a classic xref table)
+    #[arg(long, value_enum, value_name = "VERSION", default_value_t = PdfVersion::V1_5)]
+    pub pdf_version: PdfVersion,
+    /// Keep every page box (Crop/Bleed/Trim/Art, including inherited ones) on each part; pass false to skip
+    #[arg(long, value_name = "BOOL", default_value_t = true, action = clap::ArgAction::Set)]
+    pub preserve_page_boxes: bool,
+    /// Scale page content and page boxes by FACTOR (e.g. 0.5 for half size), without rasterizing
+    #[arg(long, visible_alias = "zoom", value_name = "FACTOR", value_parser = parse_scale)]
+    pub scale: Option<f32>,
+    /// Unix permissions for every part (octal, e.g. 600 or 0644); ignored with a warning elsewhere
+    #[arg(long, value_name = "MODE", value_parser = parse_octal_mode)]
+    pub chmod: Option<u32>,
+    /// Open the output directory in the file manager afterwards
+    #[arg(long, visible_alias = "open-after")]
+    pub open: bool,
+    /// Split without asking even when more than --confirm-threshold parts would be created
+    #[arg(short = 'y', long, visible_alias = "assume-yes")]
+    pub yes: bool,
+    /// Ask before creating more than N parts from one input (on a terminal; elsewhere --yes is required)
+    #[arg(long, value_name = "N", default_value_t = 1000)]
+    pub confirm_threshold: usize,
+    /// For a directory input: flat = only PDFs directly in it, parts all in --out-dir; mirror = PDFs in subdirectories too, parts under the same subdirectory of --out-dir
+    #[arg(long, value_enum, value_name = "LAYOUT", default_value_t = DirStructure::Flat)]
+    pub output_dir_structure: DirStructure,
+}
+
+#[derive(Args, Debug)]
+pub struct InfoArgs {
+    /// Input PDF file; repeat for several (one report, JSON array element or CSV row each)
+    #[arg(short = 'i', long, value_name = "FILE", required = true)]
+    pub input: Vec<PathBuf>,
+    /// Output format: table (human-readable), json or csv (path,pages,version,title,author,size_bytes)
+    #[arg(long, visible_alias = "output-format", value_enum, value_name = "FORMAT", default_value_t = InfoFormat::Table)]
+    pub format: InfoFormat,
+    /// Same as --format json
+    #[arg(long, conflicts_with = "format")]
+    pub json: bool,
+    /// List fonts used by the pages and whether each is embedded
+    #[arg(long)]
+    pub fonts: bool,
+    /// List pages using transparency (alpha, soft masks, blend modes, transparency groups), which basic viewers may render differently after a merge
+    #[arg(long)]
+    pub transparency: bool,
+    /// Also write each input's title, author, page count, bookmark tree and page labels as JSON to FILE
+    #[arg(long, value_name = "FILE")]
+    pub output_metadata_file: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct ExtractTextArgs {
+    /// Input PDF file
+    #[arg(short = 'i', long, value_name = "FILE")]
+    pub input: PathBuf,
+    /// Only these pages, e.g. "1-3,5" (all by default)
+    #[arg(long, value_name = "SPEC", allow_hyphen_values = true)]
+    pub pages: Option<String>,
+    /// Print a JSON array of {page, text} instead of the text with pages separated by form feeds
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct CompletionsArgs {
+    /// Shell to generate completions for
+    #[arg(value_enum, value_name = "SHELL")]
+    pub shell: clap_complete::Shell,
+}
+
+#[derive(Args, Debug)]
+pub struct ManArgs {
+    /// Subcommand whose page to print (pdf-ops-SUBCOMMAND.1); the top-level page by default
+    #[arg(value_name = "SUBCOMMAND", conflicts_with = "out_dir")]
+    pub subcommand: Option<String>,
+    /// Write pdf-ops.1 and one pdf-ops-SUBCOMMAND.1 per subcommand into DIR instead of printing
+    #[arg(long, value_name = "DIR")]
+    pub out_dir: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct ValidateArgs {
+    /// Input PDF file
+    #[arg(short = 'i', long, value_name = "FILE")]
+    pub input: PathBuf,
+}
+
+#[derive(Args, Debug)]
+pub struct ScanArgs {
+    /// Directory to scan
+    #[arg(short, long, value_name = "DIR", default_value = ".")]
+    pub input_dir: PathBuf,
+    /// Descend at most N levels (1 = only the directory itself); unlimited by default
+    #[arg(long, value_name = "N")]
+    pub depth: Option<usize>,
+    /// Include only files matching these globs (relative to the directory). Repeatable.
+    #[arg(long, value_name = "GLOB")]
+    pub include: Vec<String>,
+    /// Exclude files matching these globs (relative to the directory). Repeatable.
+    #[arg(long, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+    /// Match --include/--exclude ignoring case (e.g. "*.PDF" also matches "file.pdf")
+    #[arg(long)]
+    pub glob_case_insensitive: bool,
+    /// Only PDFs whose text contains TEXT, ignoring case (slow: loads every file; text as extract-text sees it)
+    #[arg(long, value_name = "TEXT", visible_alias = "content-search")]
+    pub contains: Option<String>,
+    /// Print a JSON array of {path, size_bytes, pages} instead of one path per line
+    #[arg(long)]
+    pub json: bool,
+    /// Also load each file for its page count (slower)
+    #[arg(long)]
+    pub with_pages: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct ExplainSpecArgs {
+    /// Page spec, e.g. "1-3,5,10-" or "first 3, last 2"
+    #[arg(value_name = "SPEC", allow_hyphen_values = true)]
+    pub spec: String,
+    /// Hypothetical document page count
+    #[arg(long, value_name = "N")]
+    pub total: usize,
+}
+
+#[derive(Args, Debug)]
+pub struct BatchArgs {
+    /// CSV with a header row: op (merge|split), input, output, pages; relative paths resolve against the CSV's directory
+    #[arg(value_name = "CSV")]
+    pub jobs: PathBuf,
+    /// Stop at the first failing row
+    #[arg(long)]
+    pub fail_fast: bool,
+    /// Overwrite outputs that already exist
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct AppendArgs {
+    /// Existing PDF to extend (its metadata and bookmarks are kept)
+    #[arg(long, value_name = "FILE")]
+    pub to: PathBuf,
+    /// PDF whose pages go at the end. Repeatable, appended in order.
+    #[arg(long, value_name = "FILE", required = true)]
+    pub add: Vec<PathBuf>,
+    /// Write here instead of back over --to
+    #[arg(short, long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct ZipArgs {
+    /// PDF with the front sides (pages 1, 3, 5, … of the result)
+    #[arg(value_name = "FRONT")]
+    pub front: PathBuf,
+    /// PDF with the back sides, same page count as FRONT
+    #[arg(value_name = "BACK")]
+    pub back: PathBuf,
+    #[arg(short, long, value_name = "FILE")]
+    pub output: PathBuf,
+    /// BACK runs last page first (the stack was flipped over for the second pass)
+    #[arg(long)]
+    pub reverse_back: bool,
+    /// Overwrite the output if it exists
+    #[arg(long)]
+    pub force: bool,
 }
 
 #[derive(Args, Debug)]
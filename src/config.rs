@@ -0,0 +1,257 @@
+use anyhow::{Context, Result};
+use clap::parser::ValueSource;
+use clap::ArgMatches;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::cli::{MergeArgs, SplitArgs};
+use pdf_ops::attachments::AttachmentMode;
+use pdf_ops::labels::Renumber;
+use pdf_ops::merge::{OnEmpty, Orientation, PageAlign, PageSize};
+use pdf_ops::meta::MetadataMode;
+use pdf_ops::sort::SortBy;
+use pdf_ops::split::DirStructure;
+use pdf_ops::stamp::Corner;
+use pdf_ops::version::PdfVersion;
+
+/// Defaults loaded from `pdf-ops.toml`; every field is optional and explicit CLI flags win.
+///
+/// ```toml
+/// [merge]
+/// input_dir = "./scans"
+/// sort_by = "title"
+///
+/// [split]
+/// pattern = "{base}-{index:03}.pdf"
+/// force = true
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub merge: MergeDefaults,
+    pub split: SplitDefaults,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MergeDefaults {
+    pub input_dir: Option<String>,
+    pub output: Option<String>,
+    pub prefix: Option<String>,
+    pub suffix: Option<String>,
+    pub pages: Option<String>,
+    pub include: Option<Vec<String>>,
+    pub exclude: Option<Vec<String>>,
+    pub glob_case_insensitive: Option<bool>,
+    pub contains: Option<String>,
+    pub force: Option<bool>,
+    pub exclude_blank: Option<bool>,
+    pub dedupe_pages: Option<bool>,
+    pub report_duplicates: Option<bool>,
+    pub max_output_pages: Option<usize>,
+    pub sort_by: Option<SortBy>,
+    pub seed: Option<u64>,
+    pub metadata: Option<MetadataMode>,
+    pub bookmark_files: Option<bool>,
+    pub group_by_subdir: Option<bool>,
+    pub covers: Option<bool>,
+    pub strip_javascript: Option<bool>,
+    pub preserve_page_boxes: Option<bool>,
+    pub bake_rotation: Option<bool>,
+    pub separator_blank: Option<bool>,
+    pub separator: Option<PathBuf>,
+    pub rotate_auto: Option<bool>,
+    pub rotate_target: Option<Orientation>,
+    pub normalize: Option<PageSize>,
+    pub align_page_size: Option<PageAlign>,
+    pub annotate_source: Option<bool>,
+    pub annotate_position: Option<Corner>,
+    pub renumber: Option<Renumber>,
+    pub start_at: Option<u64>,
+    pub checksum_manifest: Option<bool>,
+    pub retries: Option<u32>,
+    pub pdf_version: Option<PdfVersion>,
+    pub attachments: Option<AttachmentMode>,
+    pub max_file_size: Option<u64>,
+    pub skip_errors: Option<bool>,
+    pub producer: Option<String>,
+    pub strip_producer: Option<bool>,
+    pub trailer_id: Option<bool>,
+    pub deterministic: Option<bool>,
+    pub passthrough_single: Option<bool>,
+    pub copy_xmp: Option<bool>,
+    pub strip_xmp: Option<bool>,
+    pub keep_open_action: Option<bool>,
+    pub keep_viewer_preferences: Option<bool>,
+    pub on_empty: Option<OnEmpty>,
+    pub spill: Option<bool>,
+    pub confirm_threshold: Option<usize>,
+    #[cfg(feature = "pdfa")]
+    pub pdfa: Option<bool>,
+    #[cfg(feature = "grayscale")]
+    pub grayscale: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SplitDefaults {
+    pub out_dir: Option<PathBuf>,
+    pub pattern: Option<String>,
+    pub index_base: Option<usize>,
+    pub force: Option<bool>,
+    pub low_memory: Option<bool>,
+    pub verify: Option<bool>,
+    pub strip_javascript: Option<bool>,
+    pub retries: Option<u32>,
+    pub preserve_page_boxes: Option<bool>,
+    pub pdf_version: Option<PdfVersion>,
+    pub copy_xmp: Option<bool>,
+    pub strip_xmp: Option<bool>,
+    pub confirm_threshold: Option<usize>,
+    pub output_dir_structure: Option<DirStructure>,
+}
+
+const LOCAL_FILE: &str = "pdf-ops.toml";
+
+/// Load `explicit`, or the first of `./pdf-ops.toml` and `$XDG_CONFIG_HOME/pdf-ops/config.toml`
+/// (falling back to `~/.config`). Missing auto-discovered files are not an error.
+pub fn load(explicit: Option<&Path>) -> Result<Option<Config>> {
+    if let Some(p) = explicit {
+        return read(p).map(Some);
+    }
+    let mut candidates = vec![PathBuf::from(LOCAL_FILE)];
+    let xdg = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")));
+    if let Some(dir) = xdg {
+        candidates.push(dir.join("pdf-ops").join("config.toml"));
+    }
+    match candidates.into_iter().find(|p| p.is_file()) {
+        Some(p) => read(&p).map(Some),
+        None => Ok(None),
+    }
+}
+
+fn read(path: &Path) -> Result<Config> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("读取配置文件失败: {}", path.display()))?;
+    toml::from_str(&text).with_context(|| format!("解析配置文件失败: {}", path.display()))
+}
+
+// A value counts as explicit when it came from the command line (or env), not a clap default
+fn explicit(m: Option<&ArgMatches>, id: &str) -> bool {
+    matches!(
+        m.and_then(|m| m.value_source(id)),
+        Some(ValueSource::CommandLine) | Some(ValueSource::EnvVariable)
+    )
+}
+
+fn seed<T>(m: Option<&ArgMatches>, id: &str, slot: &mut T, value: Option<T>) {
+    if let Some(v) = value {
+        if !explicit(m, id) { *slot = v; }
+    }
+}
+
+/// Apply `[merge]` defaults to values the user did not pass explicitly.
+pub fn apply_merge(args: &mut MergeArgs, m: Option<&ArgMatches>, d: MergeDefaults) {
+    seed(m, "input_dir", &mut args.input_dir, d.input_dir);
+    seed(m, "output", &mut args.output, d.output);
+    seed(m, "prefix", &mut args.prefix, d.prefix.map(Some));
+    seed(m, "suffix", &mut args.suffix, d.suffix.map(Some));
+    seed(m, "pages", &mut args.pages, d.pages.map(Some));
+    seed(m, "include", &mut args.include, d.include);
+    seed(m, "exclude", &mut args.exclude, d.exclude);
+    seed(m, "glob_case_insensitive", &mut args.glob_case_insensitive, d.glob_case_insensitive);
+    seed(m, "contains", &mut args.contains, d.contains.map(Some));
+    seed(m, "force", &mut args.force, d.force);
+    seed(m, "exclude_blank", &mut args.exclude_blank, d.exclude_blank);
+    seed(m, "dedupe_pages", &mut args.dedupe_pages, d.dedupe_pages);
+    seed(m, "report_duplicates", &mut args.report_duplicates, d.report_duplicates);
+    seed(m, "max_output_pages", &mut args.max_output_pages, d.max_output_pages.map(Some));
+    seed(m, "sort_by", &mut args.sort_by, d.sort_by);
+    seed(m, "seed", &mut args.seed, d.seed.map(Some));
+    seed(m, "metadata", &mut args.metadata, d.metadata);
+    seed(m, "bookmark_files", &mut args.bookmark_files, d.bookmark_files);
+    seed(m, "group_by_subdir", &mut args.group_by_subdir, d.group_by_subdir);
+    seed(m, "covers", &mut args.covers, d.covers);
+    seed(m, "strip_javascript", &mut args.strip_javascript, d.strip_javascript);
+    seed(m, "preserve_page_boxes", &mut args.preserve_page_boxes, d.preserve_page_boxes);
+    seed(m, "bake_rotation", &mut args.bake_rotation, d.bake_rotation);
+    seed(m, "separator_blank", &mut args.separator_blank, d.separator_blank);
+    seed(m, "separator", &mut args.separator, d.separator.map(Some));
+    seed(m, "rotate_auto", &mut args.rotate_auto, d.rotate_auto);
+    seed(m, "rotate_target", &mut args.rotate_target, d.rotate_target);
+    seed(m, "normalize", &mut args.normalize, d.normalize.map(Some));
+    seed(m, "align_page_size", &mut args.align_page_size, d.align_page_size);
+    seed(m, "annotate_source", &mut args.annotate_source, d.annotate_source);
+    seed(m, "annotate_position", &mut args.annotate_position, d.annotate_position);
+    seed(m, "renumber", &mut args.renumber, d.renumber.map(Some));
+    seed(m, "start_at", &mut args.start_at, d.start_at);
+    seed(m, "checksum_manifest", &mut args.checksum_manifest, d.checksum_manifest);
+    seed(m, "retries", &mut args.retries, d.retries);
+    seed(m, "pdf_version", &mut args.pdf_version, d.pdf_version);
+    seed(m, "attachments", &mut args.attachments, d.attachments);
+    seed(m, "max_file_size", &mut args.max_file_size, d.max_file_size.map(Some));
+    seed(m, "skip_errors", &mut args.skip_errors, d.skip_errors);
+    seed(m, "producer", &mut args.producer, d.producer);
+    seed(m, "strip_producer", &mut args.strip_producer, d.strip_producer);
+    seed(m, "trailer_id", &mut args.trailer_id, d.trailer_id);
+    seed(m, "deterministic", &mut args.deterministic, d.deterministic);
+    seed(m, "passthrough_single", &mut args.passthrough_single, d.passthrough_single);
+    seed(m, "copy_xmp", &mut args.copy_xmp, d.copy_xmp);
+    seed(m, "strip_xmp", &mut args.strip_xmp, d.strip_xmp);
+    seed(m, "keep_open_action", &mut args.keep_open_action, d.keep_open_action);
+    seed(m, "keep_viewer_preferences", &mut args.keep_viewer_preferences, d.keep_viewer_preferences);
+    seed(m, "on_empty", &mut args.on_empty, d.on_empty);
+    seed(m, "spill", &mut args.spill, d.spill);
+    seed(m, "confirm_threshold", &mut args.confirm_threshold, d.confirm_threshold);
+    #[cfg(feature = "pdfa")]
+    seed(m, "pdfa", &mut args.pdfa, d.pdfa);
+    #[cfg(feature = "grayscale")]
+    seed(m, "grayscale", &mut args.grayscale, d.grayscale);
+}
+
+/// Apply `[split]` defaults to values the user did not pass explicitly.
+pub fn apply_split(args: &mut SplitArgs, m: Option<&ArgMatches>, d: SplitDefaults) {
+    seed(m, "out_dir", &mut args.out_dir, d.out_dir);
+    seed(m, "pattern", &mut args.pattern, d.pattern);
+    seed(m, "index_base", &mut args.index_base, d.index_base);
+    seed(m, "force", &mut args.force, d.force);
+    seed(m, "low_memory", &mut args.low_memory, d.low_memory);
+    seed(m, "verify", &mut args.verify, d.verify);
+    seed(m, "strip_javascript", &mut args.strip_javascript, d.strip_javascript);
+    seed(m, "retries", &mut args.retries, d.retries);
+    seed(m, "preserve_page_boxes", &mut args.preserve_page_boxes, d.preserve_page_boxes);
+    seed(m, "pdf_version", &mut args.pdf_version, d.pdf_version);
+    seed(m, "copy_xmp", &mut args.copy_xmp, d.copy_xmp);
+    seed(m, "strip_xmp", &mut args.strip_xmp, d.strip_xmp);
+    seed(m, "confirm_threshold", &mut args.confirm_threshold, d.confirm_threshold);
+    seed(m, "output_dir_structure", &mut args.output_dir_structure, d.output_dir_structure);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{Cli, Commands};
+    use clap::{CommandFactory, FromArgMatches};
+
+    fn parse(argv: &[&str]) -> (Cli, ArgMatches) {
+        let m = Cli::command().get_matches_from(argv);
+        (Cli::from_arg_matches(&m).unwrap(), m)
+    }
+
+    #[test]
+    fn cli_flags_win_over_config() {
+        let cfg: Config = toml::from_str("[split]\npattern = \"{base}-{index:03}.pdf\"\nforce = true\n").unwrap();
+        let (cli, m) = parse(&["pdf-ops", "split", "-i", "a.pdf", "--pattern", "x-{index}.pdf"]);
+        let Some(Commands::Split(mut args)) = cli.command else { panic!("expected split") };
+        apply_split(&mut args, m.subcommand_matches("split"), cfg.split);
+        assert_eq!(args.pattern, "x-{index}.pdf");
+        assert!(args.force);
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        assert!(toml::from_str::<Config>("[merge]\nfoo = 1\n").is_err());
+    }
+}
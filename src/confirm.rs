@@ -0,0 +1,31 @@
+//! Guard against accidentally huge jobs (`--confirm-threshold`, `--yes`).
+//!
+//! Operations with a `confirm_above` limit stop with `NeedsConfirmation` before writing anything
+//! when they would go past it; the caller decides whether to ask and run again without the limit.
+
+use std::fmt;
+
+/// Returned inside an `anyhow::Error` when a job is larger than its `confirm_above` limit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NeedsConfirmation {
+    /// What the job would do to `count` files, e.g. "创建"
+    pub action: &'static str,
+    pub count: usize,
+    pub threshold: usize,
+}
+
+impl fmt::Display for NeedsConfirmation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "将{} {} 个文件，超过确认阈值 {}（加 --yes 跳过确认）", self.action, self.count, self.threshold)
+    }
+}
+
+impl std::error::Error for NeedsConfirmation {}
+
+/// Fail with `NeedsConfirmation` when `count` is above `limit`.
+pub(crate) fn check(action: &'static str, count: usize, limit: Option<usize>) -> anyhow::Result<()> {
+    match limit {
+        Some(threshold) if count > threshold => Err(NeedsConfirmation { action, count, threshold }.into()),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,105 @@
+use lopdf::{Document, Object, ObjectId};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+
+use crate::page;
+
+// Guards against absurdly deep resource graphs
+const MAX_DEPTH: usize = 32;
+
+/// Fingerprint of what a page draws, used by `--dedupe-pages`: its decoded content plus
+/// its (inherited) resources, followed through references so two copies of the same scan
+/// match even though their images are separate objects. Heuristic: pages that look the
+/// same but differ in bytes get different digests.
+pub(crate) fn page_digest(doc: &Document, page_id: ObjectId) -> Option<[u8; 32]> {
+    let mut page = doc.get_dictionary(page_id).ok()?.clone();
+    page::inherit_page_attributes(doc, &mut page);
+    let mut hasher = Sha256::new();
+    hasher.update(doc.get_page_content(page_id).unwrap_or_default());
+    hasher.update(b"\0resources\0");
+    if let Ok(res) = page.get(b"Resources") {
+        hash_object(doc, res, 0, &mut HashSet::new(), &mut hasher);
+    }
+    Some(hasher.finalize().into())
+}
+
+/// Pages of `doc` (1-based, in order) that repeat an earlier page, each with the first page
+/// it repeats, by `page_digest`.
+pub(crate) fn duplicate_pages(doc: &Document) -> Vec<(usize, usize)> {
+    let mut first_seen: std::collections::HashMap<[u8; 32], usize> = Default::default();
+    let mut duplicates = Vec::new();
+    for (number, page_id) in doc.get_pages() {
+        let Some(digest) = page_digest(doc, page_id) else { continue };
+        let number = number as usize;
+        match first_seen.get(&digest) {
+            Some(&first) => duplicates.push((number, first)),
+            None => { first_seen.insert(digest, number); }
+        }
+    }
+    duplicates
+}
+
+fn hash_object(doc: &Document, obj: &Object, depth: usize, seen: &mut HashSet<ObjectId>, h: &mut Sha256) {
+    if depth > MAX_DEPTH { return; }
+    match obj {
+        Object::Reference(id) => {
+            // Object ids differ between copies; only what they point to counts
+            if !seen.insert(*id) { h.update(b"<seen>"); return; }
+            match doc.get_object(*id) {
+                Ok(target) => hash_object(doc, target, depth + 1, seen, h),
+                Err(_) => h.update(b"null"),
+            }
+        }
+        Object::Dictionary(d) => {
+            h.update(b"<<");
+            let mut entries: Vec<_> = d.iter().filter(|(k, _)| k.as_slice() != b"Parent").collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            for (k, v) in entries {
+                h.update(k);
+                hash_object(doc, v, depth + 1, seen, h);
+            }
+            h.update(b">>");
+        }
+        Object::Array(a) => {
+            h.update(b"[");
+            for v in a { hash_object(doc, v, depth + 1, seen, h); }
+            h.update(b"]");
+        }
+        Object::Stream(s) => {
+            hash_object(doc, &Object::Dictionary(s.dict.clone()), depth, seen, h);
+            h.update(&s.content);
+        }
+        other => h.update(format!("{:?}", other).as_bytes()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{Dictionary, Stream};
+
+    // A page drawing `image` through its own copy of an image XObject
+    fn add_page(doc: &mut Document, image: &[u8]) -> ObjectId {
+        let img = doc.add_object(Stream::new(Dictionary::new(), image.to_vec()));
+        let content = doc.add_object(Stream::new(Dictionary::new(), b"q 100 0 0 100 0 0 cm /Im0 Do Q".to_vec()));
+        let mut xobjects = Dictionary::new();
+        xobjects.set("Im0", Object::Reference(img));
+        let mut res = Dictionary::new();
+        res.set("XObject", xobjects);
+        let mut page = Dictionary::new();
+        page.set("Type", "Page");
+        page.set("Contents", Object::Reference(content));
+        page.set("Resources", res);
+        doc.add_object(page)
+    }
+
+    #[test]
+    fn separate_copies_of_a_page_match() {
+        let mut doc = Document::with_version("1.5");
+        let a = add_page(&mut doc, b"scan-1");
+        let b = add_page(&mut doc, b"scan-1");
+        let c = add_page(&mut doc, b"scan-2");
+        assert_eq!(page_digest(&doc, a), page_digest(&doc, b));
+        assert_ne!(page_digest(&doc, a), page_digest(&doc, c));
+    }
+}
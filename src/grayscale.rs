@@ -0,0 +1,247 @@
+//! `merge --grayscale` (feature `grayscale`): a best-effort colour-to-grey pass to shrink
+//! archives, without rasterizing anything.
+//!
+//! Rewritten to DeviceGray: the colour operators of page and form content streams (`rg`/`RG`,
+//! `k`/`K`, and `sc`/`scn`/`SC`/`SCN` after `cs`/`CS` selected an RGB or CMYK space) and the
+//! palettes of indexed images. Left in colour: the pixel data of RGB and CMYK images, shadings,
+//! patterns, and streams with inline images (which lopdf does not parse).
+
+use lopdf::content::{Content, Operation};
+use lopdf::{Dictionary, Document, Object, ObjectId, StringFormat};
+use std::collections::HashSet;
+
+use crate::page;
+
+/// Convert `page_id`, the forms it draws and the indexed images it uses to grey. Returns how
+/// many streams changed; running it again changes nothing.
+pub(crate) fn convert_page(doc: &mut Document, page_id: ObjectId) -> usize {
+    let Ok(mut page) = doc.get_dictionary(page_id).cloned() else { return 0 };
+    page::inherit_page_attributes(doc, &mut page);
+    let resources = resolve_dict(doc, page.get(b"Resources").ok());
+    let mut changed = 0;
+    for id in doc.get_page_contents(page_id) {
+        changed += rewrite_stream(doc, id, resources.as_ref()) as usize;
+    }
+    let mut visited = HashSet::new();
+    changed + convert_xobjects(doc, resources.as_ref(), &mut visited)
+}
+
+// Forms and images named in `resources`, recursing into nested forms
+fn convert_xobjects(doc: &mut Document, resources: Option<&Dictionary>, visited: &mut HashSet<ObjectId>) -> usize {
+    let Some(xobjects) = resources.and_then(|r| resolve_dict(doc, r.get(b"XObject").ok())) else { return 0 };
+    let mut changed = 0;
+    for (_, obj) in xobjects.iter() {
+        let Ok(id) = obj.as_reference() else { continue };
+        if !visited.insert(id) { continue; }
+        let Ok(stream) = doc.get_object(id).and_then(Object::as_stream) else { continue };
+        match stream.dict.get(b"Subtype").and_then(Object::as_name_str) {
+            Ok("Form") => {
+                // A form without its own resources uses those of whatever draws it
+                let own = resolve_dict(doc, stream.dict.get(b"Resources").ok());
+                let form_resources = own.as_ref().or(resources);
+                changed += rewrite_stream(doc, id, form_resources) as usize;
+                changed += convert_xobjects(doc, own.as_ref(), visited);
+            }
+            Ok("Image") => changed += convert_palette(doc, id) as usize,
+            _ => {}
+        }
+    }
+    changed
+}
+
+fn rewrite_stream(doc: &mut Document, id: ObjectId, resources: Option<&Dictionary>) -> bool {
+    let Ok(stream) = doc.get_object(id).and_then(Object::as_stream) else { return false };
+    let data = stream.decompressed_content().unwrap_or_else(|_| stream.content.clone());
+    let Ok(mut content) = Content::decode(&data) else { return false };
+    if content.operations.iter().any(|op| op.operator == "BI" || op.operator == "ID") {
+        return false;
+    }
+    if !convert_operations(doc, &mut content.operations, resources) { return false; }
+    let Ok(bytes) = content.encode() else { return false };
+    match doc.get_object_mut(id) {
+        Ok(Object::Stream(stream)) => { stream.set_plain_content(bytes); true }
+        _ => false,
+    }
+}
+
+// Colour components of the spaces this pass converts (fill, stroke); `None` for any other
+#[derive(Clone, Copy, Default)]
+struct Spaces {
+    fill: Option<usize>,
+    stroke: Option<usize>,
+}
+
+fn convert_operations(doc: &Document, ops: &mut [Operation], resources: Option<&Dictionary>) -> bool {
+    let mut spaces = Spaces::default();
+    let mut saved: Vec<Spaces> = Vec::new();
+    let mut changed = false;
+    for op in ops.iter_mut() {
+        let fill = op.operator.chars().all(|c| c.is_ascii_lowercase());
+        match op.operator.as_str() {
+            "q" => saved.push(spaces),
+            "Q" => spaces = saved.pop().unwrap_or_default(),
+            "g" => spaces.fill = None,
+            "G" => spaces.stroke = None,
+            "rg" | "RG" | "k" | "K" => {
+                let n = if op.operator.eq_ignore_ascii_case("rg") { 3 } else { 4 };
+                let Some(v) = gray_of(&op.operands, n) else { continue };
+                *op = Operation::new(if fill { "g" } else { "G" }, vec![Object::Real(v)]);
+                changed = true;
+                // The space now in effect is grey; later `sc` operands still have n components
+                if fill { spaces.fill = Some(n) } else { spaces.stroke = Some(n) }
+            }
+            "cs" | "CS" => {
+                let n = op.operands.first().and_then(|space| components(doc, space, resources));
+                if fill { spaces.fill = n } else { spaces.stroke = n }
+                if n.is_some() {
+                    op.operands = vec![Object::Name(b"DeviceGray".to_vec())];
+                    changed = true;
+                }
+            }
+            "sc" | "scn" | "SC" | "SCN" => {
+                let Some(n) = (if fill { spaces.fill } else { spaces.stroke }) else { continue };
+                let Some(v) = gray_of(&op.operands, n) else { continue };
+                op.operands = vec![Object::Real(v)];
+                changed = true;
+            }
+            _ => {}
+        }
+    }
+    changed
+}
+
+// Grey level of `n` numeric operands (RGB or CMYK), or `None` when they are not that
+fn gray_of(operands: &[Object], n: usize) -> Option<f32> {
+    let values: Vec<f32> = operands.iter().map(|o| o.as_float().ok()).collect::<Option<_>>()?;
+    (values.len() == n).then(|| gray(&values))
+}
+
+fn gray(c: &[f32]) -> f32 {
+    let v = match c {
+        [r, g, b] => 0.299 * r + 0.587 * g + 0.114 * b,
+        [c, m, y, k] => 1.0 - (0.3 * c + 0.59 * m + 0.11 * y + k).min(1.0),
+        _ => 0.0,
+    };
+    // Three decimals are finer than any grey a viewer shows, and keep the operators short
+    (v.clamp(0.0, 1.0) * 1000.0).round() / 1000.0
+}
+
+// 3 or 4 for RGB and CMYK spaces (device, CalRGB, ICC-based), resolving resource names
+fn components(doc: &Document, space: &Object, resources: Option<&Dictionary>) -> Option<usize> {
+    match space {
+        Object::Name(name) => match name.as_slice() {
+            b"DeviceRGB" => Some(3),
+            b"DeviceCMYK" => Some(4),
+            _ => {
+                let named = resolve_dict(doc, resources?.get(b"ColorSpace").ok())?;
+                // No resources for the lookup this time: a name cannot point at another name
+                components(doc, named.get(name).ok()?, None)
+            }
+        },
+        Object::Reference(id) => components(doc, doc.get_object(*id).ok()?, resources),
+        Object::Array(parts) => match parts.first()?.as_name_str().ok()? {
+            "CalRGB" => Some(3),
+            "ICCBased" => {
+                let profile = doc.get_object(parts.get(1)?.as_reference().ok()?).and_then(Object::as_stream).ok()?;
+                match profile.dict.get(b"N").and_then(Object::as_i64).ok()? {
+                    3 => Some(3),
+                    4 => Some(4),
+                    _ => None,
+                }
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// An `/Indexed` image over an RGB or CMYK base gets a grey palette; its pixels are indexes
+// and stay as they are
+fn convert_palette(doc: &mut Document, image_id: ObjectId) -> bool {
+    let Ok(image) = doc.get_object(image_id).and_then(Object::as_stream) else { return false };
+    let space = match image.dict.get(b"ColorSpace") {
+        Ok(Object::Reference(id)) => doc.get_object(*id).ok(),
+        other => other.ok(),
+    };
+    let Some(Object::Array(parts)) = space else { return false };
+    let [Object::Name(kind), base, hival, lookup] = parts.as_slice() else { return false };
+    if kind.as_slice() != b"Indexed" { return false; }
+    let (Some(n), Ok(hival)) = (components(doc, base, None), hival.as_i64()) else { return false };
+    let bytes = match lookup {
+        Object::String(bytes, _) => bytes.clone(),
+        Object::Reference(id) => match doc.get_object(*id).and_then(Object::as_stream) {
+            Ok(s) => s.decompressed_content().unwrap_or_else(|_| s.content.clone()),
+            Err(_) => return false,
+        },
+        _ => return false,
+    };
+    let entries = (hival.clamp(0, 255) + 1) as usize;
+    if bytes.len() < entries * n { return false; }
+    let palette: Vec<u8> = bytes.chunks(n).take(entries)
+        .map(|c| (gray(&c.iter().map(|&b| b as f32 / 255.0).collect::<Vec<_>>()) * 255.0).round() as u8)
+        .collect();
+    let space = vec![
+        Object::Name(b"Indexed".to_vec()),
+        Object::Name(b"DeviceGray".to_vec()),
+        Object::Integer(hival),
+        Object::String(palette, StringFormat::Hexadecimal),
+    ];
+    match doc.get_object_mut(image_id) {
+        Ok(Object::Stream(image)) => { image.dict.set("ColorSpace", space); true }
+        _ => false,
+    }
+}
+
+// Owned, since the document is changed while these are still in use
+fn resolve_dict(doc: &Document, obj: Option<&Object>) -> Option<Dictionary> {
+    crate::tree::deref_dict(doc, obj).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{dictionary, Stream};
+
+    fn page_with(doc: &mut Document, content: &[u8], resources: Dictionary) -> (ObjectId, ObjectId) {
+        let pages = doc.new_object_id();
+        let contents = doc.add_object(Stream::new(Dictionary::new(), content.to_vec()));
+        let page = doc.add_object(dictionary! { "Type" => "Page", "Parent" => pages, "Contents" => contents, "Resources" => resources });
+        doc.objects.insert(pages, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => vec![page.into()], "Count" => 1 }));
+        (page, contents)
+    }
+
+    fn content(doc: &Document, id: ObjectId) -> String {
+        String::from_utf8_lossy(&doc.get_object(id).unwrap().as_stream().unwrap().content).into_owned()
+    }
+
+    #[test]
+    fn colour_operators_become_grey() {
+        let mut doc = Document::with_version("1.5");
+        let icc = doc.add_object(Stream::new(dictionary! { "N" => 3 }, Vec::new()));
+        let resources = dictionary! { "ColorSpace" => dictionary! { "CS0" => vec![Object::Name(b"ICCBased".to_vec()), icc.into()] } };
+        let original = b"1 0 0 rg 0 0 10 10 re f q 0 0 0 1 K /CS0 cs 0 0 1 sc Q 0 1 0 sc /Pattern cs /P0 scn";
+        let (page, contents) = page_with(&mut doc, original, resources);
+        assert_eq!(convert_page(&mut doc, page), 1);
+        assert_eq!(content(&doc, contents).lines().collect::<Vec<_>>(), [
+            "0.299 g", "0 0 10 10 re", "f", "q", "0 G", "/DeviceGray cs", "0.114 sc", "Q",
+            // `rg` still governs after Q, so its three operands are converted too
+            "0.587 sc", "/Pattern cs", "/P0 scn",
+        ]);
+        assert_eq!(convert_page(&mut doc, page), 0);
+    }
+
+    #[test]
+    fn indexed_palettes_become_grey_and_inline_images_are_left_alone() {
+        let mut doc = Document::with_version("1.5");
+        let space = vec!["Indexed".into(), "DeviceRGB".into(), 1.into(), Object::String(vec![255, 0, 0, 255, 255, 255], StringFormat::Hexadecimal)];
+        let image = doc.add_object(Stream::new(dictionary! { "Subtype" => "Image", "ColorSpace" => space }, vec![0, 1]));
+        let inline = "BI /W 1 /H 1 /CS /RGB /BPC 8 ID abc EI 1 0 0 rg";
+        let (page, contents) = page_with(&mut doc, inline.as_bytes(), dictionary! { "XObject" => dictionary! { "Im0" => image } });
+        assert_eq!(convert_page(&mut doc, page), 1);
+        let image = doc.get_object(image).unwrap().as_stream().unwrap();
+        let space = image.dict.get(b"ColorSpace").unwrap().as_array().unwrap();
+        assert_eq!(space[1].as_name_str().unwrap(), "DeviceGray");
+        assert_eq!(space[3].as_str().unwrap(), [76, 255]);
+        assert_eq!(content(&doc, contents), inline);
+    }
+}
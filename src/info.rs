@@ -0,0 +1,521 @@
+use anyhow::{Context, Result};
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::{labels, meta, outline, page};
+
+/// Sides below one inch or above 50 inches are almost always scan/export mistakes.
+const MIN_SIDE_PT: f32 = 72.0;
+const MAX_SIDE_PT: f32 = 3600.0;
+/// Relative tolerance when comparing a page against the document's dominant size
+const SIZE_TOLERANCE: f32 = 0.05;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SizeFlag {
+    Small,
+    Large,
+    /// Differs from the most common page size (orientation ignored)
+    OddSize,
+}
+
+impl SizeFlag {
+    pub fn describe(self) -> &'static str {
+        match self {
+            SizeFlag::Small => "尺寸过小",
+            SizeFlag::Large => "尺寸过大",
+            SizeFlag::OddSize => "与多数页面尺寸不同",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PageInfo {
+    /// 1-based
+    pub number: usize,
+    pub width_pt: f32,
+    pub height_pt: f32,
+    pub width_in: f32,
+    pub height_in: f32,
+    pub flags: Vec<SizeFlag>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FontInfo {
+    /// `/BaseFont`, or the resource name when missing
+    pub name: String,
+    pub subtype: String,
+    /// Has a `/FontFile`, `/FontFile2` or `/FontFile3` (Type3 fonts always count as embedded)
+    pub embedded: bool,
+    /// 1-based pages using the font
+    pub pages: Vec<usize>,
+}
+
+/// Why a page counts as using transparency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TransparencyKind {
+    /// Page `/Group` with `/S /Transparency`
+    PageGroup,
+    /// ExtGState `/CA` or `/ca` below 1
+    Alpha,
+    /// ExtGState `/SMask` other than `/None`
+    SoftMask,
+    /// ExtGState `/BM` other than `Normal`/`Compatible`
+    BlendMode,
+    /// Form XObject with a transparency group
+    FormGroup,
+    /// Image XObject with an `/SMask`
+    ImageMask,
+}
+
+impl TransparencyKind {
+    pub fn describe(self) -> &'static str {
+        match self {
+            TransparencyKind::PageGroup => "页面透明度组",
+            TransparencyKind::Alpha => "不透明度 < 1",
+            TransparencyKind::SoftMask => "软蒙版",
+            TransparencyKind::BlendMode => "混合模式",
+            TransparencyKind::FormGroup => "表单透明度组",
+            TransparencyKind::ImageMask => "图像软蒙版",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TransparencyInfo {
+    /// 1-based
+    pub page: usize,
+    pub kinds: Vec<TransparencyKind>,
+}
+
+/// What `inspect` collects beyond page sizes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InspectOptions {
+    /// Walk page font resources (`info --fonts`)
+    pub fonts: bool,
+    /// Look for transparency on each page (`info --transparency`)
+    pub transparency: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DocInfo {
+    pub path: PathBuf,
+    pub page_count: usize,
+    /// PDF header version, e.g. "1.7"
+    pub version: String,
+    pub title: Option<String>,
+    pub author: Option<String>,
+    /// File size on disk
+    pub size_bytes: u64,
+    pub pages: Vec<PageInfo>,
+    /// Only collected when asked for (`info --fonts`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fonts: Option<Vec<FontInfo>>,
+    /// Only collected when asked for (`info --transparency`); pages without transparency are left out
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transparency: Option<Vec<TransparencyInfo>>,
+}
+
+/// Load `path` and describe it, with the extras `opts` asks for.
+pub fn inspect(path: &Path, opts: &InspectOptions) -> Result<DocInfo> {
+    let doc = Document::load(path).with_context(|| format!("加载 PDF 失败: {}", path.display()))?;
+    let mut pages = Vec::new();
+    for (n, id) in doc.get_pages() {
+        let [llx, lly, urx, ury] = page::media_box(&doc, id).unwrap_or([0.0, 0.0, 612.0, 792.0]);
+        let (w, h) = (urx - llx, ury - lly);
+        pages.push(PageInfo { number: n as usize, width_pt: w, height_pt: h, width_in: w / 72.0, height_in: h / 72.0, flags: Vec::new() });
+    }
+    flag_sizes(&mut pages);
+    let fonts = opts.fonts.then(|| collect_fonts(&doc));
+    let transparency = opts.transparency.then(|| collect_transparency(&doc));
+    let size_bytes = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    Ok(DocInfo {
+        path: path.to_path_buf(),
+        page_count: pages.len(),
+        version: doc.version.clone(),
+        title: meta::read_title(&doc),
+        author: meta::info_string(&doc, b"Author"),
+        size_bytes,
+        pages,
+        fonts,
+        transparency,
+    })
+}
+
+// Fonts keyed by object id, or by resource name for direct font dictionaries
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone)]
+enum FontKey {
+    Indirect(ObjectId),
+    Direct(Vec<u8>),
+}
+
+pub(crate) fn collect_fonts(doc: &Document) -> Vec<FontInfo> {
+    let mut fonts: BTreeMap<FontKey, FontInfo> = BTreeMap::new();
+    for (n, page_id) in doc.get_pages() {
+        let Ok(page) = doc.get_dictionary(page_id) else { continue };
+        let mut page = page.clone();
+        page::inherit_page_attributes(doc, &mut page);
+        let mut seen = HashSet::new();
+        walk_resources(doc, page.get(b"Resources").ok(), n as usize, &mut seen, &mut fonts);
+    }
+    fonts.into_values().collect()
+}
+
+// Fonts of a resource dictionary, descending into form XObjects
+fn walk_resources(
+    doc: &Document,
+    resources: Option<&Object>,
+    page: usize,
+    seen: &mut HashSet<ObjectId>,
+    fonts: &mut BTreeMap<FontKey, FontInfo>,
+) {
+    let Some(res) = resolve_dict(doc, resources) else { return };
+    if let Some(font_dict) = resolve_dict(doc, res.get(b"Font").ok()) {
+        for (res_name, obj) in font_dict.iter() {
+            let key = match obj {
+                Object::Reference(id) => FontKey::Indirect(*id),
+                _ => FontKey::Direct(res_name.clone()),
+            };
+            let Some(font) = resolve_dict(doc, Some(obj)) else { continue };
+            let entry = fonts.entry(key).or_insert_with(|| describe_font(doc, res_name, font));
+            if entry.pages.last() != Some(&page) { entry.pages.push(page); }
+        }
+    }
+    if let Some(xobjects) = resolve_dict(doc, res.get(b"XObject").ok()) {
+        for (_, obj) in xobjects.iter() {
+            let Object::Reference(id) = obj else { continue };
+            if !seen.insert(*id) { continue; }
+            let Ok(Object::Stream(form)) = doc.get_object(*id) else { continue };
+            if form.dict.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Form".as_slice()) {
+                walk_resources(doc, form.dict.get(b"Resources").ok(), page, seen, fonts);
+            }
+        }
+    }
+}
+
+pub(crate) fn collect_transparency(doc: &Document) -> Vec<TransparencyInfo> {
+    let mut out = Vec::new();
+    for (n, page_id) in doc.get_pages() {
+        let Ok(page) = doc.get_dictionary(page_id) else { continue };
+        let mut page = page.clone();
+        page::inherit_page_attributes(doc, &mut page);
+        let mut kinds = BTreeSet::new();
+        if is_transparency_group(doc, page.get(b"Group").ok()) { kinds.insert(TransparencyKind::PageGroup); }
+        walk_transparency(doc, page.get(b"Resources").ok(), &mut HashSet::new(), &mut kinds);
+        if !kinds.is_empty() { out.push(TransparencyInfo { page: n as usize, kinds: kinds.into_iter().collect() }); }
+    }
+    out
+}
+
+// Transparency used by a resource dictionary, descending into form XObjects
+fn walk_transparency(doc: &Document, resources: Option<&Object>, seen: &mut HashSet<ObjectId>, kinds: &mut BTreeSet<TransparencyKind>) {
+    let Some(res) = resolve_dict(doc, resources) else { return };
+    if let Some(states) = resolve_dict(doc, res.get(b"ExtGState").ok()) {
+        for (_, obj) in states.iter() {
+            let Some(gs) = resolve_dict(doc, Some(obj)) else { continue };
+            let below_one = |key: &[u8]| gs.get(key).and_then(Object::as_float).is_ok_and(|v| v < 1.0);
+            if below_one(b"CA") || below_one(b"ca") { kinds.insert(TransparencyKind::Alpha); }
+            match gs.get(b"SMask") {
+                Ok(Object::Name(n)) if n == b"None" => {}
+                Ok(_) => { kinds.insert(TransparencyKind::SoftMask); }
+                Err(_) => {}
+            }
+            let blend = match gs.get(b"BM") {
+                Ok(Object::Name(n)) => Some(n.as_slice()),
+                // An array lists fallbacks; the first entry is the one used
+                Ok(Object::Array(a)) => a.first().and_then(|o| o.as_name().ok()),
+                _ => None,
+            };
+            if blend.is_some_and(|b| b != b"Normal" && b != b"Compatible") { kinds.insert(TransparencyKind::BlendMode); }
+        }
+    }
+    if let Some(xobjects) = resolve_dict(doc, res.get(b"XObject").ok()) {
+        for (_, obj) in xobjects.iter() {
+            let Object::Reference(id) = obj else { continue };
+            if !seen.insert(*id) { continue; }
+            let Ok(Object::Stream(x)) = doc.get_object(*id) else { continue };
+            match x.dict.get(b"Subtype").and_then(Object::as_name).ok() {
+                Some(b"Form") => {
+                    if is_transparency_group(doc, x.dict.get(b"Group").ok()) { kinds.insert(TransparencyKind::FormGroup); }
+                    walk_transparency(doc, x.dict.get(b"Resources").ok(), seen, kinds);
+                }
+                Some(b"Image") if x.dict.has(b"SMask") => { kinds.insert(TransparencyKind::ImageMask); }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn is_transparency_group(doc: &Document, group: Option<&Object>) -> bool {
+    resolve_dict(doc, group).is_some_and(|g| g.get(b"S").and_then(Object::as_name).ok() == Some(b"Transparency".as_slice()))
+}
+
+fn describe_font(doc: &Document, res_name: &[u8], font: &Dictionary) -> FontInfo {
+    let name_of = |d: &Dictionary, key: &[u8]| d.get(key).and_then(Object::as_name).ok().map(|n| String::from_utf8_lossy(n).into_owned());
+    let subtype = name_of(font, b"Subtype").unwrap_or_default();
+    // Composite fonts keep the descriptor on their descendant
+    let descriptor_owner = match font.get(b"DescendantFonts") {
+        Ok(obj) => resolve_array_first(doc, obj).unwrap_or(font),
+        Err(_) => font,
+    };
+    let embedded = subtype == "Type3"
+        || resolve_dict(doc, descriptor_owner.get(b"FontDescriptor").ok())
+            .is_some_and(|fd| [b"FontFile".as_slice(), b"FontFile2", b"FontFile3"].iter().any(|k| fd.has(k)));
+    FontInfo {
+        name: name_of(font, b"BaseFont").unwrap_or_else(|| String::from_utf8_lossy(res_name).into_owned()),
+        subtype,
+        embedded,
+        pages: Vec::new(),
+    }
+}
+
+fn resolve_dict<'a>(doc: &'a Document, obj: Option<&'a Object>) -> Option<&'a Dictionary> {
+    match obj? {
+        Object::Reference(id) => match doc.get_object(*id).ok()? {
+            Object::Dictionary(d) => Some(d),
+            Object::Stream(s) => Some(&s.dict),
+            _ => None,
+        },
+        Object::Dictionary(d) => Some(d),
+        _ => None,
+    }
+}
+
+fn resolve_array_first<'a>(doc: &'a Document, obj: &'a Object) -> Option<&'a Dictionary> {
+    let arr = match obj {
+        Object::Array(a) => a,
+        Object::Reference(id) => doc.get_object(*id).ok()?.as_array().ok()?,
+        _ => return None,
+    };
+    resolve_dict(doc, arr.first())
+}
+
+// Normalized (short, long) side, rounded to whole points
+fn size_key(p: &PageInfo) -> (i64, i64) {
+    let (a, b) = (p.width_pt.round() as i64, p.height_pt.round() as i64);
+    (a.min(b), a.max(b))
+}
+
+fn flag_sizes(pages: &mut [PageInfo]) {
+    let mut counts: HashMap<(i64, i64), usize> = HashMap::new();
+    for p in pages.iter() { *counts.entry(size_key(p)).or_default() += 1; }
+    // Ties go to the smaller size so the result is deterministic
+    let dominant = counts.into_iter().max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0))).map(|(k, _)| k);
+
+    for p in pages.iter_mut() {
+        let (short, long) = (p.width_pt.min(p.height_pt), p.width_pt.max(p.height_pt));
+        if short < MIN_SIDE_PT { p.flags.push(SizeFlag::Small); }
+        if long > MAX_SIDE_PT { p.flags.push(SizeFlag::Large); }
+        if let Some((ds, dl)) = dominant {
+            let off = |v: f32, d: i64| (v - d as f32).abs() > d as f32 * SIZE_TOLERANCE;
+            if off(short, ds) || off(long, dl) { p.flags.push(SizeFlag::OddSize); }
+        }
+    }
+}
+
+/// Human-readable report, one line per page.
+pub fn render(info: &DocInfo) -> String {
+    let mut out = format!("文件: {}\n页数: {}\n版本: PDF {}\n大小: {} 字节\n", info.path.display(), info.page_count, info.version, info.size_bytes);
+    if let Some(t) = &info.title { out.push_str(&format!("标题: {}\n", t)); }
+    if let Some(a) = &info.author { out.push_str(&format!("作者: {}\n", a)); }
+    out.push_str("页面尺寸 (MediaBox):\n");
+    for p in &info.pages {
+        out.push_str(&format!(
+            "  {:>4}  {} x {} pt  ({:.2} x {:.2} in)",
+            p.number, p.width_pt, p.height_pt, p.width_in, p.height_in
+        ));
+        if !p.flags.is_empty() {
+            let notes: Vec<&str> = p.flags.iter().map(|f| f.describe()).collect();
+            out.push_str(&format!("  ⚠️ {}", notes.join(", ")));
+        }
+        out.push('\n');
+    }
+    if let Some(fonts) = &info.fonts {
+        out.push_str(&format!("字体 ({}):\n", fonts.len()));
+        for f in fonts {
+            let pages: Vec<String> = f.pages.iter().map(|p| p.to_string()).collect();
+            let state = if f.embedded { "已嵌入" } else { "⚠️ 未嵌入" };
+            out.push_str(&format!("  {} ({})  {}  页: {}\n", f.name, f.subtype, state, pages.join(", ")));
+        }
+    }
+    if let Some(pages) = &info.transparency {
+        if pages.is_empty() {
+            out.push_str("透明度: 无\n");
+        } else {
+            out.push_str(&format!("透明度 ({} 页，基础查看器中合并结果可能显示不同):\n", pages.len()));
+            for t in pages {
+                let kinds: Vec<&str> = t.kinds.iter().map(|k| k.describe()).collect();
+                out.push_str(&format!("  {:>4}  ⚠️ {}\n", t.page, kinds.join(", ")));
+            }
+        }
+    }
+    out
+}
+
+/// One CSV row per document: path, pages, version, title, author, size_bytes (with header).
+pub fn render_csv(infos: &[DocInfo]) -> String {
+    let mut w = csv::Writer::from_writer(Vec::new());
+    w.write_record(["path", "pages", "version", "title", "author", "size_bytes"]).expect("in-memory write");
+    for d in infos {
+        w.write_record([
+            d.path.display().to_string(),
+            d.page_count.to_string(),
+            d.version.clone(),
+            d.title.clone().unwrap_or_default(),
+            d.author.clone().unwrap_or_default(),
+            d.size_bytes.to_string(),
+        ]).expect("in-memory write");
+    }
+    String::from_utf8(w.into_inner().expect("in-memory write")).expect("UTF-8 fields")
+}
+
+/// What `--output-metadata-file` writes: enough for a search index to skip parsing the PDF.
+#[derive(Debug, Clone, Serialize)]
+pub struct DocMetadata {
+    pub path: PathBuf,
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub page_count: usize,
+    /// The outline as a tree, in reading order
+    pub bookmarks: Vec<Bookmark>,
+    /// One label per page, only when the document defines `/PageLabels`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_labels: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Bookmark {
+    pub title: String,
+    /// 1-based target page, if the destination resolves
+    pub page: Option<usize>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<Bookmark>,
+}
+
+pub(crate) fn doc_metadata(path: &Path, doc: &Document) -> DocMetadata {
+    DocMetadata {
+        path: path.to_path_buf(),
+        title: meta::read_title(doc),
+        author: meta::info_string(doc, b"Author"),
+        page_count: doc.get_pages().len(),
+        bookmarks: bookmark_tree(&outline::read_outline(doc)),
+        page_labels: labels::page_labels(doc),
+    }
+}
+
+// Rebuild the nesting from the flattened pre-order list (levels only ever step down by one)
+fn bookmark_tree(items: &[outline::OutlineItem]) -> Vec<Bookmark> {
+    fn take(items: &[outline::OutlineItem], i: &mut usize, level: usize) -> Vec<Bookmark> {
+        let mut out = Vec::new();
+        while let Some(item) = items.get(*i) {
+            if item.level < level { break; }
+            *i += 1;
+            let children = take(items, i, item.level + 1);
+            out.push(Bookmark { title: item.title.clone(), page: item.page, children });
+        }
+        out
+    }
+    take(items, &mut 0, 1)
+}
+
+/// Load each PDF in `pdfs` and write its metadata to `dest` as JSON: an object for one file,
+/// an array for several.
+pub fn write_metadata_file(pdfs: &[&Path], dest: &Path) -> Result<()> {
+    let mut docs = Vec::with_capacity(pdfs.len());
+    for &path in pdfs {
+        let doc = Document::load(path).with_context(|| format!("加载 PDF 失败: {}", path.display()))?;
+        docs.push(doc_metadata(path, &doc));
+    }
+    let json = match docs.as_slice() {
+        [one] => serde_json::to_string_pretty(one),
+        _ => serde_json::to_string_pretty(&docs),
+    }.expect("serializable");
+    crate::interrupt::write_via_part(dest, |tmp| Ok(std::fs::write(tmp, json + "\n")?))
+        .with_context(|| format!("写入元数据文件失败: {}", dest.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(number: usize, w: f32, h: f32) -> PageInfo {
+        PageInfo { number, width_pt: w, height_pt: h, width_in: w / 72.0, height_in: h / 72.0, flags: vec![] }
+    }
+
+    #[test]
+    fn bookmark_levels_nest_into_a_tree() {
+        let item = |title: &str, level, page| outline::OutlineItem { title: title.into(), level, page: Some(page) };
+        let tree = bookmark_tree(&[item("A", 1, 1), item("A.1", 2, 2), item("A.1.a", 3, 2), item("A.2", 2, 3), item("B", 1, 4)]);
+        let titles: Vec<(&str, usize)> = tree.iter().map(|b| (b.title.as_str(), b.children.len())).collect();
+        assert_eq!(titles, [("A", 2), ("B", 0)]);
+        assert_eq!(tree[0].children[0].children[0].title, "A.1.a");
+        assert_eq!(tree[0].children[1].page, Some(3));
+    }
+
+    #[test]
+    fn fonts_report_embedding() {
+        let mut doc = Document::with_version("1.5");
+        let mut helv = Dictionary::new();
+        helv.set("Type", "Font");
+        helv.set("Subtype", "Type1");
+        helv.set("BaseFont", "Helvetica");
+        let helv_id = doc.add_object(helv);
+        let file_id = doc.add_object(lopdf::Stream::new(Dictionary::new(), vec![0; 4]));
+        let mut fd = Dictionary::new();
+        fd.set("FontFile2", Object::Reference(file_id));
+        let mut arial = Dictionary::new();
+        arial.set("Subtype", "TrueType");
+        arial.set("BaseFont", "ABCDEF+Arial");
+        arial.set("FontDescriptor", fd);
+        let mut fonts = Dictionary::new();
+        fonts.set("F1", Object::Reference(helv_id));
+        fonts.set("F2", arial);
+        let mut res = Dictionary::new();
+        res.set("Font", fonts);
+
+        let mut found = BTreeMap::new();
+        walk_resources(&doc, Some(&Object::Dictionary(res.clone())), 1, &mut HashSet::new(), &mut found);
+        walk_resources(&doc, Some(&Object::Dictionary(res)), 2, &mut HashSet::new(), &mut found);
+        let got: Vec<(String, bool, Vec<usize>)> = found.into_values().map(|f| (f.name, f.embedded, f.pages)).collect();
+        assert_eq!(got, vec![("Helvetica".into(), false, vec![1, 2]), ("ABCDEF+Arial".into(), true, vec![1, 2])]);
+    }
+
+    #[test]
+    fn transparency_found_through_forms() {
+        let mut doc = Document::with_version("1.5");
+        let mut gs = Dictionary::new();
+        gs.set("ca", 0.5);
+        gs.set("BM", vec![Object::Name(b"Normal".to_vec())]);
+        let mut opaque = Dictionary::new();
+        opaque.set("CA", 1);
+        opaque.set("SMask", "None");
+        let mut states = Dictionary::new();
+        states.set("GS1", gs);
+        states.set("GS2", opaque);
+        let mut form_res = Dictionary::new();
+        form_res.set("ExtGState", states);
+        let mut form = lopdf::Stream::new(Dictionary::new(), Vec::new());
+        form.dict.set("Subtype", "Form");
+        form.dict.set("Resources", form_res);
+        let form_id = doc.add_object(form);
+        let mut xobjects = Dictionary::new();
+        xobjects.set("Fm1", Object::Reference(form_id));
+        let mut res = Dictionary::new();
+        res.set("XObject", xobjects);
+
+        let mut kinds = BTreeSet::new();
+        walk_transparency(&doc, Some(&Object::Dictionary(res)), &mut HashSet::new(), &mut kinds);
+        assert_eq!(kinds.into_iter().collect::<Vec<_>>(), vec![TransparencyKind::Alpha]);
+    }
+
+    #[test]
+    fn flags_outliers_against_dominant_size() {
+        let mut pages = vec![page(1, 612.0, 792.0), page(2, 792.0, 612.0), page(3, 595.0, 842.0), page(4, 612.0, 792.0), page(5, 40.0, 60.0)];
+        flag_sizes(&mut pages);
+        let flags: Vec<Vec<SizeFlag>> = pages.into_iter().map(|p| p.flags).collect();
+        assert_eq!(flags, vec![vec![], vec![], vec![SizeFlag::OddSize], vec![], vec![SizeFlag::Small, SizeFlag::OddSize]]);
+    }
+}
@@ -0,0 +1,149 @@
+//! Interrupted runs must not leave half-written outputs behind.
+//!
+//! Outputs are saved to `<name>.part` and renamed into place once complete, so a crash or a
+//! failed write leaves either the old file or the complete new one. While a `.part` file is
+//! being written it is registered here, so a Ctrl-C handler can delete it with
+//! `remove_partial_outputs` before exiting.
+//!
+//! Commands that can stop cleanly (e.g. `scan`, which closes its JSON output) call
+//! `set_graceful(true)`; the first Ctrl-C then only sets `stop_requested` and a second one exits.
+
+use anyhow::{Context, Result};
+use lopdf::Document;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+static IN_FLIGHT: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+static GRACEFUL: AtomicBool = AtomicBool::new(false);
+static STOP_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Whether the running command winds down by itself on Ctrl-C (see `request_stop`).
+pub fn set_graceful(on: bool) {
+    GRACEFUL.store(on, Ordering::SeqCst);
+}
+
+/// For the Ctrl-C handler: if the command is graceful and no stop was requested yet, record the
+/// request and return true (the handler should not exit). Otherwise false.
+pub fn request_stop() -> bool {
+    GRACEFUL.load(Ordering::SeqCst) && !STOP_REQUESTED.swap(true, Ordering::SeqCst)
+}
+
+/// Ctrl-C was pressed while the command was graceful.
+pub fn stop_requested() -> bool {
+    STOP_REQUESTED.load(Ordering::SeqCst)
+}
+
+/// Delete every `.part` file still being written. Safe to call from a signal handler thread.
+pub fn remove_partial_outputs() -> usize {
+    remove_registered(&IN_FLIGHT)
+}
+
+// Empty `registry`, deleting its files; returns how many were there to delete
+fn remove_registered(registry: &Mutex<Vec<PathBuf>>) -> usize {
+    let Ok(mut paths) = registry.lock() else { return 0 };
+    let removed = paths.drain(..).filter(|p| std::fs::remove_file(p).is_ok()).count();
+    removed
+}
+
+// Registered for as long as it lives
+pub(crate) struct Partial(PathBuf);
+
+impl Partial {
+    pub(crate) fn new(path: PathBuf) -> Self {
+        if let Ok(mut paths) = IN_FLIGHT.lock() { paths.push(path.clone()); }
+        Partial(path)
+    }
+}
+
+impl Drop for Partial {
+    fn drop(&mut self) {
+        if let Ok(mut paths) = IN_FLIGHT.lock() { paths.retain(|p| p != &self.0); }
+    }
+}
+
+fn part_path(output: &Path) -> PathBuf {
+    let mut name = output.as_os_str().to_owned();
+    name.push(".part");
+    PathBuf::from(name)
+}
+
+/// `doc.save(output)`, but through `<output>.part` so `output` only ever holds a complete file.
+/// A failed save removes the `.part` file.
+pub(crate) fn save(doc: &mut Document, output: &Path) -> Result<()> {
+    write_via_part(output, |tmp| doc.save(tmp).map(drop).map_err(Into::into))
+}
+
+/// Copy `src` to `output` through the same `.part` file as [`save`].
+pub(crate) fn copy(src: &Path, output: &Path) -> Result<()> {
+    write_via_part(output, |tmp| std::fs::copy(src, tmp).map(drop).map_err(Into::into))
+}
+
+/// Let `write` create `<output>.part`, then rename it into place; on failure it is removed and
+/// an existing `output` is left as it was.
+pub(crate) fn write_via_part(output: &Path, write: impl FnOnce(&Path) -> Result<()>) -> Result<()> {
+    let tmp = part_path(output);
+    let guard = Partial::new(tmp.clone());
+    let result = write(&tmp).and_then(|()| {
+        // Replacing a file: flush the new data first, so a power loss cannot leave the rename
+        // on disk without it. New outputs skip this; there is nothing to lose.
+        if output.exists() {
+            std::fs::File::open(&tmp)?.sync_all()?;
+        }
+        std::fs::rename(&tmp, output)?;
+        Ok(())
+    });
+    if result.is_err() {
+        std::fs::remove_file(&tmp).ok();
+    }
+    drop(guard);
+    result.with_context(|| format!("写入输出失败: {}", output.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interrupted_write_leaves_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("merged.pdf");
+        // A save cut off halfway: the .part file exists and is registered
+        let guard = Partial::new(part_path(&output));
+        assert!(IN_FLIGHT.lock().unwrap().contains(&guard.0));
+        std::fs::write(&guard.0, b"%PDF-1.5\n% truncated").unwrap();
+        // Other tests register their own writes in IN_FLIGHT meanwhile, so removal is checked
+        // on a registry of this test's own
+        let registry = Mutex::new(vec![guard.0.clone()]);
+        assert_eq!(remove_registered(&registry), 1);
+        assert!(registry.lock().unwrap().is_empty());
+        assert!(!guard.0.exists());
+        assert!(!output.exists());
+        drop(guard);
+
+        // A completed save is renamed into place and no longer tracked
+        let mut doc = Document::with_version("1.5");
+        save(&mut doc, &output).unwrap();
+        assert!(output.exists());
+        assert!(!part_path(&output).exists());
+        assert!(IN_FLIGHT.lock().unwrap().iter().all(|p| !p.starts_with(dir.path())));
+    }
+
+    #[test]
+    fn failed_write_keeps_existing_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("merged.pdf");
+        std::fs::write(&output, b"old").unwrap();
+        // The writer gets halfway, then fails (e.g. disk full)
+        let err = write_via_part(&output, |tmp| {
+            std::fs::write(tmp, b"%PDF-1.5\n% half")?;
+            anyhow::bail!("disk full")
+        }).unwrap_err();
+        assert!(format!("{:#}", err).contains("disk full"));
+        assert_eq!(std::fs::read(&output).unwrap(), b"old");
+        assert!(!part_path(&output).exists());
+
+        write_via_part(&output, |tmp| Ok(std::fs::write(tmp, b"new")?)).unwrap();
+        assert_eq!(std::fs::read(&output).unwrap(), b"new");
+    }
+}
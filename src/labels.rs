@@ -0,0 +1,166 @@
+use lopdf::{Dictionary, Document, Object};
+use std::ops::ControlFlow;
+
+use crate::meta;
+use crate::tree::{self, deref_dict};
+// `/St` and labels beyond these come from broken or hostile files; such pages are shown as
+// their number, as if unlabeled. PDF integers are 32-bit in practice (ISO 32000 Annex C).
+const MAX_START: i64 = i32::MAX as i64;
+const MAX_LABEL_LEN: usize = 64;
+
+/// How `merge --renumber` numbers the assembled document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Renumber {
+    /// One decimal `/PageLabels` range over all pages, so viewers show 1..N
+    Labels,
+    /// Print the number on each page (see `stamp::stamp_page`)
+    Stamp,
+    /// Both
+    Both,
+}
+
+impl Renumber {
+    pub(crate) fn labels(self) -> bool { matches!(self, Renumber::Labels | Renumber::Both) }
+    pub(crate) fn stamps(self) -> bool { matches!(self, Renumber::Stamp | Renumber::Both) }
+}
+
+/// A `/PageLabels` number tree labelling every page with a plain decimal, starting at `start`.
+pub(crate) fn continuous_labels(start: usize) -> Dictionary {
+    let mut range = Dictionary::new();
+    range.set("S", "D");
+    if start != 1 { range.set("St", start as i64); }
+    let mut tree = Dictionary::new();
+    tree.set("Nums", vec![Object::Integer(0), Object::Dictionary(range)]);
+    tree
+}
+
+/// The label of every page (index = physical page - 1) from the catalog `/PageLabels`,
+/// or `None` if the document has no labels.
+pub(crate) fn page_labels(doc: &Document) -> Option<Vec<String>> {
+    let catalog = doc.catalog().ok()?;
+    let root = deref_dict(doc, catalog.get(b"PageLabels").ok())?;
+    // (first page index, label dict) pairs
+    let mut ranges: Vec<(usize, &Dictionary)> = Vec::new();
+    let _: ControlFlow<()> = tree::walk_leaves(doc, root, b"Nums", &mut |nums| {
+        for pair in nums.chunks(2) {
+            let [key, value] = pair else { break };
+            let (Ok(start), Some(dict)) = (key.as_i64(), deref_dict(doc, Some(value))) else { continue };
+            if start >= 0 { ranges.push((start as usize, dict)); }
+        }
+        ControlFlow::Continue(())
+    });
+    if ranges.is_empty() { return None; }
+    ranges.sort_by_key(|&(start, _)| start);
+
+    let total = doc.get_pages().len();
+    let labels = (0..total).map(|i| {
+        // Pages before the first range have no label style; show them as their number
+        let Some(&(start, range)) = ranges.iter().rev().find(|&&(s, _)| s <= i) else { return (i + 1).to_string() };
+        let prefix = match range.get(b"P") {
+            Ok(Object::String(bytes, _)) => meta::decode_text(bytes),
+            _ => String::new(),
+        };
+        let first = match range.get(b"St").and_then(Object::as_i64) {
+            Ok(st) if st > MAX_START => return (i + 1).to_string(),
+            Ok(st) => st.max(1) as usize,
+            Err(_) => 1,
+        };
+        let Some(n) = first.checked_add(i - start) else { return (i + 1).to_string() };
+        let number = match range.get(b"S").and_then(Object::as_name).ok() {
+            Some(b"D") => Some(n.to_string()),
+            Some(b"R") => roman(n),
+            Some(b"r") => roman(n).map(|r| r.to_ascii_lowercase()),
+            Some(b"A") => letters(n),
+            Some(b"a") => letters(n).map(|l| l.to_ascii_lowercase()),
+            // No /S: the label is the prefix alone
+            _ => Some(String::new()),
+        };
+        let Some(number) = number else { return (i + 1).to_string() };
+        prefix + &number
+    }).collect();
+    Some(labels)
+}
+
+// Upper-case roman numeral; thousands past 3 just repeat M. `None` when longer than MAX_LABEL_LEN.
+fn roman(mut n: usize) -> Option<String> {
+    if n / 1000 > MAX_LABEL_LEN { return None; }
+    const TABLE: [(usize, &str); 13] = [
+        (1000, "M"), (900, "CM"), (500, "D"), (400, "CD"), (100, "C"), (90, "XC"),
+        (50, "L"), (40, "XL"), (10, "X"), (9, "IX"), (5, "V"), (4, "IV"), (1, "I"),
+    ];
+    let mut out = String::new();
+    for (value, digits) in TABLE {
+        while n >= value { out.push_str(digits); n -= value; }
+    }
+    (out.len() <= MAX_LABEL_LEN).then_some(out)
+}
+
+// A..Z, then AA..ZZ, AAA..: the letter repeated once per pass through the alphabet.
+// `None` when longer than MAX_LABEL_LEN.
+fn letters(n: usize) -> Option<String> {
+    let n = n.max(1) - 1;
+    let len = n / 26 + 1;
+    if len > MAX_LABEL_LEN { return None; }
+    let letter = (b'A' + (n % 26) as u8) as char;
+    Some(letter.to_string().repeat(len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::dictionary;
+
+    #[test]
+    fn numerals_and_letters() {
+        assert_eq!(roman(4).unwrap(), "IV");
+        assert_eq!(roman(1994).unwrap(), "MCMXCIV");
+        assert_eq!(letters(1).unwrap(), "A");
+        assert_eq!(letters(26).unwrap(), "Z");
+        assert_eq!(letters(28).unwrap(), "BB");
+        assert!(roman(usize::MAX).is_none());
+        assert!(letters(usize::MAX).is_none());
+    }
+
+    #[test]
+    fn labels_from_number_tree() {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let kids: Vec<Object> = (0..7).map(|_| doc.add_object(dictionary! { "Type" => "Page", "Parent" => pages_id }).into()).collect();
+        doc.objects.insert(pages_id, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => kids, "Count" => 7 }));
+        let appendix = doc.add_object(dictionary! { "S" => "D", "P" => Object::string_literal("A-"), "St" => 3 });
+        let kid = doc.add_object(dictionary! { "Nums" => vec![5.into(), appendix.into()] });
+        let labels = dictionary! {
+            "Nums" => vec![0.into(), dictionary! { "S" => "r" }.into(), 3.into(), dictionary! { "S" => "D" }.into()],
+            "Kids" => vec![kid.into()],
+        };
+        let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id, "PageLabels" => labels });
+        doc.trailer.set("Root", catalog_id);
+
+        assert_eq!(page_labels(&doc).unwrap(), ["i", "ii", "iii", "1", "2", "A-3", "A-4"]);
+
+        doc.catalog_mut().unwrap().set("PageLabels", continuous_labels(10));
+        assert_eq!(page_labels(&doc).unwrap(), ["10", "11", "12", "13", "14", "15", "16"]);
+    }
+
+    #[test]
+    fn absurd_start_is_unlabeled() {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let kids: Vec<Object> = (0..4).map(|_| doc.add_object(dictionary! { "Type" => "Page", "Parent" => pages_id }).into()).collect();
+        doc.objects.insert(pages_id, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => kids, "Count" => 4 }));
+        let huge = 2_000_000_000_000_000_000i64;
+        let labels = dictionary! {
+            "Nums" => vec![
+                0.into(), dictionary! { "S" => "A", "St" => huge }.into(),
+                1.into(), dictionary! { "S" => "R", "St" => huge }.into(),
+                2.into(), dictionary! { "S" => "D", "St" => i64::MAX }.into(),
+                3.into(), dictionary! { "S" => "a", "St" => 100_000 }.into(),
+            ],
+        };
+        let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id, "PageLabels" => labels });
+        doc.trailer.set("Root", catalog_id);
+
+        assert_eq!(page_labels(&doc).unwrap(), ["1", "2", "3", "4"]);
+    }
+}
@@ -0,0 +1,40 @@
+//! pdf-ops: merge and split PDFs. The `pdf-ops` binary is a thin CLI over these modules.
+
+pub mod append;
+#[cfg(feature = "zip")]
+pub mod archive;
+pub mod attachments;
+pub mod batch;
+mod blank;
+mod checksum;
+pub mod confirm;
+mod dedupe;
+pub mod merge;
+pub mod info;
+pub mod interrupt;
+pub mod labels;
+mod load;
+pub mod man;
+pub mod meta;
+mod outline;
+mod page;
+pub mod pathutil;
+#[cfg(feature = "pdfa")]
+mod pdfa;
+#[cfg(feature = "grayscale")]
+mod grayscale;
+mod sanitize;
+mod spill;
+pub mod progress;
+pub mod scan;
+pub mod sort;
+pub mod spec;
+pub mod split;
+pub mod stamp;
+pub mod text;
+mod tree;
+pub mod validate;
+pub mod zip;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod version;
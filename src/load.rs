@@ -0,0 +1,78 @@
+use anyhow::{Context, Result};
+use lopdf::Document;
+use std::borrow::Cow;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::progress::ProgressSink;
+
+// First retry waits this long; every further retry doubles it
+const BASE_DELAY: Duration = Duration::from_millis(200);
+
+/// `Document::load` with up to `retries` extra attempts on I/O errors (network mounts fail
+/// transiently); parse errors fail at once. Each retry is reported through `progress`.
+pub(crate) fn load_pdf(path: &Path, retries: u32, progress: &dyn ProgressSink) -> Result<Document> {
+    load_with(path, retries, BASE_DELAY, progress, |p| Document::load(p))
+}
+
+fn load_with<F>(path: &Path, retries: u32, base_delay: Duration, progress: &dyn ProgressSink, mut load: F) -> Result<Document>
+where
+    F: FnMut(&Path) -> lopdf::Result<Document>,
+{
+    let mut attempt = 0;
+    loop {
+        match load(path) {
+            Err(lopdf::Error::IO(e)) if attempt < retries => {
+                attempt += 1;
+                tracing::warn!(name: "load_retry", path = %path.display(), attempt, retries, error = %e, "load_retry");
+                progress.set_message(Cow::Owned(format!("重试 {}/{}: {} ({})", attempt, retries, path.display(), e)));
+                std::thread::sleep(base_delay * 2u32.pow(attempt - 1));
+            }
+            Ok(doc) => {
+                tracing::info!(name: "file_loaded", path = %path.display(), objects = doc.objects.len(), "file_loaded");
+                return Ok(doc);
+            }
+            Err(e) => return Err(e).with_context(|| format!("加载 PDF 失败: {}", path.display())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::progress::{ClosureProgress, ProgressEvent};
+    use std::io;
+
+    #[test]
+    fn retries_io_errors_then_recovers() {
+        let mut calls = 0;
+        let mut events = Vec::new();
+        let res = {
+            let sink = ClosureProgress::new(|e| events.push(e));
+            load_with(Path::new("flaky.pdf"), 2, Duration::ZERO, &sink, |_| {
+                calls += 1;
+                if calls == 1 { Err(lopdf::Error::IO(io::Error::new(io::ErrorKind::TimedOut, "timed out"))) } else { Ok(Document::with_version("1.5")) }
+            })
+        };
+        assert!(res.is_ok());
+        assert_eq!(calls, 2);
+        assert!(matches!(&events[..], [ProgressEvent::Message(m)] if m.starts_with("重试 1/2: flaky.pdf")));
+    }
+
+    #[test]
+    fn parse_errors_and_exhausted_retries_fail() {
+        let mut calls = 0;
+        let sink = crate::progress::NullProgress;
+        let res = load_with(Path::new("bad.pdf"), 3, Duration::ZERO, &sink, |_| { calls += 1; Err(lopdf::Error::Header) });
+        assert!(res.is_err());
+        assert_eq!(calls, 1);
+
+        calls = 0;
+        let res = load_with(Path::new("gone.pdf"), 2, Duration::ZERO, &sink, |_| {
+            calls += 1;
+            Err(lopdf::Error::IO(io::Error::new(io::ErrorKind::NotFound, "gone")))
+        });
+        assert!(res.is_err());
+        assert_eq!(calls, 3);
+    }
+}
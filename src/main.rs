@@ -1,44 +1,419 @@
 mod cli;
-mod merge;
-mod spec;
-mod split;
-mod progress;
-mod scan;
-mod pathutil;
+mod config;
+
+use pdf_ops::{append, batch, confirm, info, merge, spec, split, text, validate, zip};
 #[cfg(feature = "tui")]
-mod tui;
+use pdf_ops::tui;
 
 use cli::{Cli, Commands};
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches};
 use std::path::PathBuf;
-use progress::IndicatifProgress;
+use pdf_ops::progress::{IndicatifProgress, MultiBarProgress};
 
 fn main() {
-    let cmd = Cli::parse().default_to_merge();
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    let config = match config::load(cli.config.as_deref()) {
+        Ok(c) => c.unwrap_or_default(),
+        Err(e) => {
+            eprintln!("❌ 配置加载失败: {:#}", e);
+            std::process::exit(1);
+        }
+    };
+    init_logging(cli.log_format);
+    install_interrupt_handler();
+    let cmd = cli.default_to_merge();
     match cmd {
-        Commands::Merge(args) => {
+        Commands::Merge(mut args) => {
+            config::apply_merge(&mut args, matches.subcommand_matches("merge"), config.merge);
+            let list = args.input_list_json.as_deref().map(merge::read_input_list).transpose().unwrap_or_else(|e| {
+                eprintln!("❌ 合并失败: {:#}", e);
+                std::process::exit(1);
+            });
+            check_specs(cli.strict_spec, args.pages.iter().chain(&args.first_pages)
+                .chain(list.iter().flatten().filter_map(|e| e.pages.as_ref()))
+                .map(String::as_str));
             let mut output_path = PathBuf::from(&args.output);
+            // Grouped outputs land next to the subdirectories unless a directory was given
+            if args.group_by_subdir && args.output == cli::MergeArgs::default().output {
+                output_path = PathBuf::new();
+            }
             if output_path.is_relative() {
                 let mut new_path = PathBuf::from(&args.input_dir);
                 new_path.push(&output_path);
                 output_path = new_path;
             }
             let input_dir = PathBuf::from(&args.input_dir);
+            let opts = merge::MergeOptions {
+                pages: args.pages,
+                first_pages: args.first_pages,
+                section: args.section,
+                includes: args.include,
+                excludes: args.exclude,
+                glob_case_insensitive: args.glob_case_insensitive,
+                contains: args.contains,
+                force: args.force,
+                exclude_blank: args.exclude_blank,
+                dedupe_pages: args.dedupe_pages,
+                report_duplicates: args.report_duplicates,
+                max_output_pages: args.max_output_pages,
+                sort_by: args.sort_by,
+                seed: args.seed,
+                metadata: args.metadata,
+                producer: (!args.strip_producer).then(|| args.producer.clone()),
+                bookmark_files: args.bookmark_files,
+                covers: args.covers,
+                strip_javascript: args.strip_javascript,
+                preserve_page_boxes: args.preserve_page_boxes,
+                bake_rotation: args.bake_rotation,
+                separator: match (args.separator_blank, args.separator) {
+                    (true, _) => Some(merge::Separator::Blank),
+                    (false, Some(path)) => Some(merge::Separator::File(path)),
+                    (false, None) => None,
+                },
+                rotate_auto: args.rotate_auto.then_some(args.rotate_target),
+                normalize: args.normalize,
+                align: args.align_page_size,
+                annotate_source: args.annotate_source.then_some(args.annotate_position),
+                renumber: args.renumber,
+                start_at: args.start_at as usize,
+                #[cfg(feature = "pdfa")]
+                pdfa: args.pdfa,
+                #[cfg(feature = "grayscale")]
+                grayscale: args.grayscale,
+                checksum_manifest: args.checksum_manifest,
+                retries: args.retries,
+                chmod: args.chmod,
+                pdf_version: args.pdf_version,
+                attachments: args.attachments,
+                max_file_size: args.max_file_size,
+                skip_errors: args.skip_errors,
+                trailer_id: match (args.deterministic, args.trailer_id) {
+                    (true, _) => pdf_ops::meta::TrailerId::Deterministic,
+                    (false, true) => pdf_ops::meta::TrailerId::Random,
+                    (false, false) => pdf_ops::meta::TrailerId::None,
+                },
+                xmp: xmp_mode(args.copy_xmp, args.strip_xmp),
+                keep_navigation: args.keep_open_action,
+                keep_viewer_preferences: args.keep_viewer_preferences,
+                passthrough_single: args.passthrough_single,
+                metadata_file: args.output_metadata_file.clone(),
+                name_prefix: args.prefix.clone().unwrap_or_default(),
+                name_suffix: args.suffix.clone().unwrap_or_default(),
+                spill: args.spill,
+                on_empty: args.on_empty,
+                confirm_above: (!args.yes).then_some(args.confirm_threshold),
+            };
+            if !args.group_by_subdir {
+                if args.output.ends_with(std::path::is_separator) || output_path.is_dir() {
+                    output_path.push(opts.output_file_name("merged"));
+                } else if args.prefix.is_some() || args.suffix.is_some() {
+                    eprintln!("❌ --prefix/--suffix 仅用于生成的文件名：请将 -o 设为目录（以 / 结尾）或使用 --group-by-subdir");
+                    std::process::exit(2);
+                }
+            }
+            warn_chmod_unsupported(args.chmod);
             let pb = IndicatifProgress::new();
-            if let Err(e) = merge::run(&input_dir, &output_path, args.pages.as_deref(), &args.include, &args.exclude, args.force, &pb) {
-                eprintln!("❌ 合并失败: {}", e);
+            if args.group_by_subdir {
+                let outputs = confirmed(opts.confirm_above, |limit| {
+                    merge::run_grouped(&input_dir, &output_path, &merge::MergeOptions { confirm_above: limit, ..opts.clone() }, &pb)
+                }).unwrap_or_else(|e| {
+                    tracing::error!(name: "merge_failed", error = %format!("{:#}", e), "merge_failed");
+                    eprintln!("❌ 合并失败: {}", e);
+                    std::process::exit(1);
+                });
+                for (path, summary) in &outputs {
+                    println!("✅ 合并完成 -> {} ({} 页)", path.display(), summary.pages);
+                    print_skipped(&summary.skipped);
+                    if args.dedupe_pages { println!("🧹 已去除重复页: {}", summary.duplicates_removed); }
+                    if args.report_duplicates { print_duplicates(&summary.duplicate_pages); }
+                    if let Some(m) = &summary.manifest { println!("🧾 校验清单 -> {}", m.display()); }
+                    if args.verbose { println!("{}", summary.size_report()); }
+                    if args.timings { println!("{}", summary.timings_report()); }
+                }
+                if args.open { open_after(&output_path); }
+                return;
+            }
+            if let Some(mode) = args.check {
+                let report = merge::check(&input_dir, &output_path, mode, &opts, &pb).unwrap_or_else(|e| {
+                    eprintln!("❌ 检查失败: {}", e);
+                    std::process::exit(2);
+                });
+                if report.unchanged() {
+                    println!("✅ 输出无变化: {} ({} 页)", output_path.display(), report.merged_pages);
+                    return;
+                }
+                match (report.existing_pages, report.same_content) {
+                    (None, _) => println!("⚠️ 输出将会改变: {} 尚不存在（合并后 {} 页）", output_path.display(), report.merged_pages),
+                    (Some(old), _) if old != report.merged_pages => println!("⚠️ 输出将会改变: 页数 {} -> {}", old, report.merged_pages),
+                    _ => println!("⚠️ 输出将会改变: 页数相同（{} 页）但内容不同", report.merged_pages),
+                }
                 std::process::exit(1);
             }
+            let result = confirmed(opts.confirm_above, |limit| {
+                let opts = merge::MergeOptions { confirm_above: limit, ..opts.clone() };
+                if let Some(list) = &list {
+                    return merge::run_with_list(list, &output_path, &opts, &pb);
+                }
+                #[cfg(feature = "zip")]
+                if let Some(archive) = &args.from_zip {
+                    return merge::run_from_zip(archive, &output_path, &opts, &pb);
+                }
+                merge::run(&input_dir, &output_path, &opts, &pb)
+            });
+            let summary = match result {
+                Ok(s) => s,
+                Err(e) => {
+                    tracing::error!(name: "merge_failed", error = %format!("{:#}", e), "merge_failed");
+                    eprintln!("❌ 合并失败: {}", e);
+                    std::process::exit(1);
+                }
+            };
+            if summary.no_inputs {
+                if args.on_empty == merge::OnEmpty::Touch {
+                    println!("⚠️ 未找到 PDF，已写入空白占位文件 -> {}", output_path.display());
+                }
+                return;
+            }
             println!("✅ 合并完成 -> {}", output_path.display());
+            print_skipped(&summary.skipped);
+            if args.dedupe_pages { println!("🧹 已去除重复页: {}", summary.duplicates_removed); }
+            if args.report_duplicates { print_duplicates(&summary.duplicate_pages); }
+            if let Some(m) = &summary.manifest { println!("🧾 校验清单 -> {}", m.display()); }
+            if let Some(m) = &summary.metadata_file { println!("🗂️ 元数据 -> {}", m.display()); }
+            if args.verbose { println!("{}", summary.size_report()); }
+            if args.timings { println!("{}", summary.timings_report()); }
+            if args.open { open_after(&output_path); }
         }
-        Commands::Split(args) => {
-            let each = if args.ranges.is_none() { true } else { args.each };
+        Commands::Split(mut args) => {
+            config::apply_split(&mut args, matches.subcommand_matches("split"), config.split);
+            check_specs(cli.strict_spec, args.ranges.iter().map(String::as_str)
+                .chain(args.pages_for.iter().map(|r| r.spec.as_str()))
+                .chain(args.outputs.iter().map(|o| o.spec.as_str())));
+            let mode = match (args.mode, args.into, args.ranges.as_deref()) {
+                (Some(m), _, _) => Ok(m),
+                (None, Some(n), _) => Ok(split::SplitMode::Into(n as usize)),
+                (None, None, Some(spec)) => split::SplitMode::ranges(spec),
+                (None, None, None) => Ok(split::SplitMode::Each),
+            };
+            let mode = mode.unwrap_or_else(|e| {
+                eprintln!("❌ 分割失败: {:#}", e);
+                std::process::exit(1);
+            });
+            let opts = split::SplitOptions {
+                mode,
+                pattern: args.pattern,
+                index_base: args.index_base,
+                force: args.force,
+                low_memory: args.low_memory,
+                preview: !args.quiet,
+                verify: args.verify,
+                strip_javascript: args.strip_javascript,
+                retries: args.retries,
+                chmod: args.chmod,
+                scale: args.scale,
+                preserve_page_boxes: args.preserve_page_boxes,
+                toc_level: args.toc_level as usize,
+                title_sanitize: args.title_sanitize,
+                title_max_len: args.title_max_len.map(|n| n as usize),
+                pdf_version: args.pdf_version,
+                xmp: xmp_mode(args.copy_xmp, args.strip_xmp),
+                section: args.section,
+                pages_for: args.pages_for,
+                // Directory inputs cannot stop to ask per file, so there the limit just fails that file
+                confirm_above: (!args.yes).then_some(args.confirm_threshold),
+                dir_structure: args.output_dir_structure,
+            };
+            warn_chmod_unsupported(args.chmod);
+            if args.watch && !args.input.is_dir() {
+                eprintln!("❌ 分割失败: --watch 需要目录输入: {}", args.input.display());
+                std::process::exit(1);
+            }
+            if args.input.is_dir() {
+                if args.even_split_at_bookmarks || !args.outputs.is_empty() {
+                    eprintln!("❌ 分割失败: 目录输入不支持 --out / --even-split-at-bookmarks");
+                    std::process::exit(1);
+                }
+                if args.watch {
+                    let state = args.watch_state.unwrap_or_else(|| args.out_dir.join(split::WATCH_STATE_FILE));
+                    let interval = std::time::Duration::from_millis(args.watch_interval);
+                    if let Err(e) = split::run_watch(&args.input, &args.out_dir, &state, interval, &opts) {
+                        eprintln!("❌ 分割失败: {:#}", e);
+                        std::process::exit(1);
+                    }
+                    return;
+                }
+                let jobs = if args.parallel {
+                    std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+                } else {
+                    args.jobs as usize
+                };
+                let pb = MultiBarProgress::new();
+                let results = split::run_dir(&args.input, &args.out_dir, &opts, jobs, &pb).unwrap_or_else(|e| {
+                    tracing::error!(name: "split_failed", error = %format!("{:#}", e), "split_failed");
+                    eprintln!("❌ 分割失败: {:#}", e);
+                    std::process::exit(1);
+                });
+                let mut failed = 0;
+                for (file, res) in &results {
+                    match res {
+                        Ok(()) => println!("  ✅ {}", file.display()),
+                        Err(e) => {
+                            failed += 1;
+                            tracing::error!(name: "split_failed", input = %file.display(), error = %format!("{:#}", e), "split_failed");
+                            println!("  ❌ {}: {:#}", file.display(), e);
+                        }
+                    }
+                }
+                println!("成功 {}，失败 {}", results.len() - failed, failed);
+                if failed > 0 { std::process::exit(1); }
+                println!("✅ 分割完成 -> {}", args.out_dir.display());
+                if args.open { open_after(&args.out_dir); }
+                return;
+            }
             let pb = IndicatifProgress::new();
-            if let Err(e) = split::run(&args.input, &args.out_dir, each, args.ranges.as_deref(), &args.pattern, args.force, &pb) {
+            let res = if args.even_split_at_bookmarks {
+                confirmed(opts.confirm_above, |limit| {
+                    split::run_bookmarks(&args.input, &args.out_dir, args.max.map(|n| n as usize), &split::SplitOptions { confirm_above: limit, ..opts.clone() }, &pb)
+                })
+            } else if args.outputs.is_empty() {
+                confirmed(opts.confirm_above, |limit| {
+                    split::run(&args.input, &args.out_dir, &split::SplitOptions { confirm_above: limit, ..opts.clone() }, &pb)
+                })
+            } else {
+                confirmed(opts.confirm_above, |limit| {
+                    split::run_named(&args.input, &args.out_dir, &args.outputs, &split::SplitOptions { confirm_above: limit, ..opts.clone() }, &pb)
+                })
+            };
+            if let Err(e) = res {
+                tracing::error!(name: "split_failed", error = %format!("{:#}", e), "split_failed");
                 eprintln!("❌ 分割失败: {}", e);
                 std::process::exit(1);
             }
             println!("✅ 分割完成 -> {}", args.out_dir.display());
+            if args.open { open_after(&args.out_dir); }
+        }
+        Commands::Info(args) => {
+            let opts = info::InspectOptions { fonts: args.fonts, transparency: args.transparency };
+            let docs: Vec<info::DocInfo> = args.input.iter().map(|path| {
+                info::inspect(path, &opts).unwrap_or_else(|e| {
+                    eprintln!("❌ 读取信息失败: {:#}", e);
+                    std::process::exit(1);
+                })
+            }).collect();
+            let format = if args.json { cli::InfoFormat::Json } else { args.format };
+            match format {
+                cli::InfoFormat::Table => {
+                    let reports: Vec<String> = docs.iter().map(info::render).collect();
+                    print!("{}", reports.join("\n"));
+                }
+                // A single file stays a plain object, as before multiple inputs were accepted
+                cli::InfoFormat::Json if docs.len() == 1 => println!("{}", serde_json::to_string_pretty(&docs[0]).expect("serializable")),
+                cli::InfoFormat::Json => println!("{}", serde_json::to_string_pretty(&docs).expect("serializable")),
+                cli::InfoFormat::Csv => print!("{}", info::render_csv(&docs)),
+            }
+            if let Some(dest) = &args.output_metadata_file {
+                let inputs: Vec<&std::path::Path> = args.input.iter().map(|p| p.as_path()).collect();
+                if let Err(e) = info::write_metadata_file(&inputs, dest) {
+                    eprintln!("❌ 写入元数据失败: {:#}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Commands::ExtractText(args) => {
+            check_specs(cli.strict_spec, args.pages.as_deref());
+            let pages = text::extract(&args.input, args.pages.as_deref()).unwrap_or_else(|e| {
+                eprintln!("❌ 提取文本失败: {:#}", e);
+                std::process::exit(1);
+            });
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&pages).expect("serializable"));
+            } else {
+                let texts: Vec<&str> = pages.iter().map(|p| p.text.as_str()).collect();
+                println!("{}", texts.join("\n\x0c"));
+            }
+        }
+        Commands::Validate(args) => {
+            let problems = validate::validate(&args.input).unwrap_or_else(|e| {
+                eprintln!("❌ 校验失败: {:#}", e);
+                std::process::exit(2);
+            });
+            if problems.is_empty() {
+                println!("✅ 未发现结构问题: {}", args.input.display());
+                return;
+            }
+            println!("❌ 发现 {} 个结构问题: {}", problems.len(), args.input.display());
+            for p in &problems {
+                println!("  - {}", p);
+            }
+            std::process::exit(1);
+        }
+        Commands::Completions(args) => {
+            clap_complete::generate(args.shell, &mut Cli::command(), env!("CARGO_BIN_NAME"), &mut std::io::stdout());
+        }
+        Commands::Man(args) => {
+            if let Err(e) = pdf_ops::man::render(Cli::command(), args.subcommand.as_deref(), args.out_dir.as_deref()) {
+                eprintln!("❌ 生成 man 手册失败: {:#}", e);
+                std::process::exit(1);
+            }
+        }
+        Commands::ExplainSpec(args) => {
+            check_specs(cli.strict_spec, [args.spec.as_str()]);
+            let ranges = spec::parse_spec_with_phrases(&args.spec, args.total).unwrap_or_else(|e| {
+                eprintln!("❌ 解析页码范围失败: {} ({})", args.spec, e);
+                std::process::exit(1);
+            });
+            let pages: Vec<String> = spec::expand_to_indexes(&ranges, args.total).iter().map(|i| (i + 1).to_string()).collect();
+            println!("页码 ({} 页): {}", pages.len(), pages.join(", "));
+            for r in spec::out_of_range(&ranges, args.total) {
+                println!("⚠️ 超出范围: {} (总页数 {})", r, args.total);
+            }
+        }
+        Commands::Append(args) => {
+            let adds: Vec<&std::path::Path> = args.add.iter().map(|p| p.as_path()).collect();
+            let dest = args.output.as_deref().unwrap_or(&args.to);
+            match append::run(&args.to, &adds, args.output.as_deref()) {
+                Ok(pages) => println!("✅ 追加完成 -> {} ({} 页)", dest.display(), pages),
+                Err(e) => {
+                    eprintln!("❌ 追加失败: {:#}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Commands::Zip(args) => {
+            let opts = zip::ZipOptions { reverse_back: args.reverse_back, force: args.force };
+            match zip::run(&args.front, &args.back, &args.output, &opts) {
+                Ok(pages) => println!("✅ 合并完成 -> {} ({} 页)", args.output.display(), pages),
+                Err(e) => {
+                    eprintln!("❌ 合并失败: {:#}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Commands::Scan(args) => {
+            if !args.input_dir.is_dir() {
+                eprintln!("❌ 扫描失败: 目录不存在: {}", args.input_dir.display());
+                std::process::exit(1);
+            }
+            let cfg = pdf_ops::scan::ScanConfig {
+                input_dir: args.input_dir,
+                includes: args.include,
+                excludes: args.exclude,
+                max_depth: args.depth,
+                case_insensitive: args.glob_case_insensitive,
+                contains: args.contains,
+                ..Default::default()
+            };
+            if pdf_ops::scan::print_stream(cfg, args.json, args.with_pages) { std::process::exit(130); }
+        }
+        Commands::Batch(args) => {
+            let pb = MultiBarProgress::new();
+            let summary = batch::run(&args.jobs, args.force, args.fail_fast, &pb).unwrap_or_else(|e| {
+                eprintln!("❌ 批处理失败: {:#}", e);
+                std::process::exit(1);
+            });
+            println!("{}", summary.report());
+            if summary.failures() > 0 { std::process::exit(1); }
         }
         #[cfg(feature = "tui")]
         Commands::Tui(args) => {
@@ -49,3 +424,100 @@ fn main() {
         }
     }
 }
+
+// Ctrl-C: drop half-written outputs, give the terminal back, exit 130 like a shell would
+fn install_interrupt_handler() {
+    let _ = ctrlc::set_handler(|| {
+        if pdf_ops::interrupt::request_stop() { return; }
+        let removed = pdf_ops::interrupt::remove_partial_outputs();
+        #[cfg(feature = "tui")]
+        tui::restore_terminal();
+        if removed > 0 {
+            eprintln!("\n⚠️ 已中断，已删除 {} 个未写完的输出", removed);
+        } else {
+            eprintln!("\n⚠️ 已中断");
+        }
+        std::process::exit(130);
+    });
+}
+
+// With --strict-spec, exit on the first spec holding an empty segment
+fn check_specs<'a>(strict: bool, specs: impl IntoIterator<Item = &'a str>) {
+    if !strict { return; }
+    for s in specs {
+        if let Err(e) = spec::check_strict(s) {
+            eprintln!("❌ 解析页码范围失败: {} ({})", s, e);
+            std::process::exit(1);
+        }
+    }
+}
+
+// Without --log-format no subscriber is installed and the events are dropped
+fn init_logging(format: Option<cli::LogFormat>) {
+    let Some(format) = format else { return };
+    let builder = tracing_subscriber::fmt().with_writer(std::io::stderr).with_target(false);
+    match format {
+        cli::LogFormat::Text => builder.init(),
+        cli::LogFormat::Json => builder.json().init(),
+    }
+}
+
+// Runs `job` with `limit` as its confirmation limit. When it stops at the limit, asks on a
+// terminal and runs it again without one; elsewhere the `NeedsConfirmation` error stands.
+fn confirmed<T>(limit: Option<usize>, job: impl Fn(Option<usize>) -> anyhow::Result<T>) -> anyhow::Result<T> {
+    use std::io::{IsTerminal, Write};
+    let err = match job(limit) {
+        Err(e) if e.is::<confirm::NeedsConfirmation>() => e,
+        other => return other,
+    };
+    if !std::io::stdin().is_terminal() {
+        return Err(err);
+    }
+    let need = err.downcast_ref::<confirm::NeedsConfirmation>().expect("checked above");
+    eprint!("⚠️ 将{} {} 个文件（确认阈值 {}），继续吗？[y/N] ", need.action, need.count, need.threshold);
+    std::io::stderr().flush().ok();
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    if matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+        job(None)
+    } else {
+        anyhow::bail!("已取消")
+    }
+}
+
+fn warn_chmod_unsupported(mode: Option<u32>) {
+    if mode.is_some() && !cfg!(unix) {
+        eprintln!("⚠️ --chmod 仅在 Unix 上生效，已忽略");
+    }
+}
+
+// --copy-xmp and --strip-xmp conflict, so at most one is set
+fn xmp_mode(copy: bool, strip: bool) -> pdf_ops::meta::XmpMode {
+    match (copy, strip) {
+        (true, _) => pdf_ops::meta::XmpMode::Copy,
+        (false, true) => pdf_ops::meta::XmpMode::Strip,
+        (false, false) => pdf_ops::meta::XmpMode::Drop,
+    }
+}
+
+// --open: the run already succeeded, so a missing desktop only earns a warning
+fn open_after(path: &std::path::Path) {
+    if let Err(e) = pdf_ops::pathutil::open_in_default_app(path) {
+        eprintln!("⚠️ 无法打开 {}: {:#}", path.display(), e);
+    }
+}
+
+fn print_skipped(skipped: &[(PathBuf, String)]) {
+    for (path, reason) in skipped {
+        eprintln!("⚠️ 已跳过 {}: {}", path.display(), reason);
+    }
+}
+
+fn print_duplicates(duplicates: &[(usize, usize)]) {
+    if duplicates.is_empty() {
+        println!("🔁 未发现重复页");
+        return;
+    }
+    let pages: Vec<String> = duplicates.iter().map(|(page, first)| format!("{} (同第 {} 页)", page, first)).collect();
+    println!("🔁 重复页: {}", pages.join(", "));
+}
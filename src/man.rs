@@ -0,0 +1,44 @@
+//! Man pages for `pdf-ops man`, rendered from the CLI definition with `clap_mangen`.
+
+use anyhow::{Context, Result};
+use clap::Command;
+use std::path::Path;
+
+/// The top-level page of `root` and one `<name>-<sub>` page per documented subcommand, as
+/// (file stem, command).
+pub fn pages(mut root: Command) -> Vec<(String, Command)> {
+    root.build();
+    let name = root.get_name().to_string();
+    let mut pages = vec![(name.clone(), root.clone())];
+    for sub in root.get_subcommands().filter(|s| !s.is_hide_set() && s.get_name() != "help") {
+        let stem = format!("{}-{}", name, sub.get_name());
+        // After `build` the display name is already `pdf-ops-<sub>`, which the page is titled by
+        pages.push((stem, sub.clone()));
+    }
+    pages
+}
+
+/// Print the page of `subcommand` (the top-level one when `None`) to stdout, or, with
+/// `out_dir`, write every page there as `<stem>.1`.
+pub fn render(root: Command, subcommand: Option<&str>, out_dir: Option<&Path>) -> Result<()> {
+    let name = root.get_name().to_string();
+    let pages = pages(root);
+    let Some(dir) = out_dir else {
+        let stem = match subcommand {
+            Some(sub) => format!("{}-{}", name, sub),
+            None => name,
+        };
+        let (_, cmd) = pages.into_iter().find(|(s, _)| *s == stem)
+            .with_context(|| format!("没有这个子命令: {}", subcommand.unwrap_or_default()))?;
+        return Ok(clap_mangen::Man::new(cmd).render(&mut std::io::stdout())?);
+    };
+    std::fs::create_dir_all(dir).with_context(|| format!("创建输出目录失败: {}", dir.display()))?;
+    for (stem, cmd) in pages {
+        let path = dir.join(format!("{}.1", stem));
+        let mut buf = Vec::new();
+        clap_mangen::Man::new(cmd).render(&mut buf)?;
+        std::fs::write(&path, buf).with_context(|| format!("写入失败: {}", path.display()))?;
+        println!("📄 {}", path.display());
+    }
+    Ok(())
+}
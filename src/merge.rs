@@ -1,67 +1,656 @@
 use lopdf::{Dictionary, Document, Object, ObjectId};
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use anyhow::{Context, Result};
 
+use crate::attachments::{self, AttachmentMode};
+use crate::blank;
+use crate::checksum;
+use crate::confirm;
+use crate::dedupe;
+use crate::info;
+use crate::labels::{self, Renumber};
+use crate::load;
+use crate::meta::{self, MetadataMode, TrailerId, XmpMode};
+use crate::outline::{self, OutlineEntry};
+use crate::page;
+use crate::spill::Spill;
+use crate::pathutil;
 use crate::spec;
+use crate::split;
 use crate::progress::ProgressSink;
 use crate::scan::{self, ScanConfig};
+use crate::sanitize;
+use crate::sort::{self, SortBy};
+use crate::stamp::{self, Corner};
+use crate::version::{self, PdfVersion};
+
+#[derive(Debug, Clone)]
+pub struct MergeOptions {
+    /// Page spec applied to each input
+    pub pages: Option<String>,
+    /// Page spec applied only to the first input (overrides `pages` for it)
+    pub first_pages: Option<String>,
+    /// Take only this bookmarked section of each input (see `outline::section_range`); replaces the page specs
+    pub section: Option<String>,
+    pub includes: Vec<String>,
+    pub excludes: Vec<String>,
+    /// Match `includes`/`excludes` ignoring case
+    pub glob_case_insensitive: bool,
+    /// Only inputs whose text contains this, ignoring case (see `text`)
+    pub contains: Option<String>,
+    pub force: bool,
+    /// Skip pages detected as blank (see `blank::is_blank_page`)
+    pub exclude_blank: bool,
+    /// Drop pages identical to an earlier page of the same input (see `dedupe::page_digest`)
+    pub dedupe_pages: bool,
+    /// After writing, look for identical pages in the output and list them in
+    /// `MergeSummary::duplicate_pages`; nothing is removed
+    pub report_duplicates: bool,
+    /// Abort before writing when the assembled page count exceeds this
+    pub max_output_pages: Option<usize>,
+    /// Input ordering applied after scanning
+    pub sort_by: SortBy,
+    /// Seed for `SortBy::Shuffle`
+    pub seed: Option<u64>,
+    /// How the output `/Info` is built
+    pub metadata: MetadataMode,
+    /// The output's `/Info /Producer`, replacing any from the inputs; `None` removes it
+    pub producer: Option<String>,
+    /// Add a top-level bookmark per input (file stem) at its first merged page
+    pub bookmark_files: bool,
+    /// Take only the first page of each input (pruned before copying)
+    pub covers: bool,
+    /// Remove `/OpenAction`, `/AA`, `/JS` and JavaScript actions before writing
+    pub strip_javascript: bool,
+    /// Copy every page box a page inherits onto the page before it leaves its page tree
+    pub preserve_page_boxes: bool,
+    /// Turn pages with a `/Rotate` upright in their content and clear the flag
+    pub bake_rotation: bool,
+    /// Inserted between the contributions of consecutive inputs (inputs with no selected pages
+    /// get none)
+    pub separator: Option<Separator>,
+    /// Add a quarter turn to pages whose MediaBox is not this orientation (before `bake_rotation`)
+    pub rotate_auto: Option<Orientation>,
+    /// Give every page this size, fitted as `align` says (after `bake_rotation`)
+    pub normalize: Option<PageSize>,
+    pub align: PageAlign,
+    /// Stamp each page with its source filename in this corner
+    pub annotate_source: Option<Corner>,
+    /// Number the output continuously (page labels and/or stamped numbers), from `start_at`
+    pub renumber: Option<Renumber>,
+    pub start_at: usize,
+    /// Write `<output>.sha256` with the hashes of every input and the output
+    pub checksum_manifest: bool,
+    /// Extra load attempts per input on I/O errors, with exponential backoff
+    pub retries: u32,
+    /// Unix permission bits for the output (and manifest)
+    pub chmod: Option<u32>,
+    /// PDF/A-oriented cleanup before writing (see `pdfa::cleanup`)
+    #[cfg(feature = "pdfa")]
+    pub pdfa: bool,
+    /// Rewrite colour operators and indexed-image palettes to grey (see `grayscale`)
+    #[cfg(feature = "grayscale")]
+    pub grayscale: bool,
+    /// Header version of the output; fails if the merged content needs a newer one
+    pub pdf_version: PdfVersion,
+    /// What happens to the inputs' embedded files and attachment annotations
+    pub attachments: AttachmentMode,
+    /// Inputs larger than this many bytes are rejected before loading
+    pub max_file_size: Option<u64>,
+    /// Skip oversized or unloadable inputs with a warning instead of failing
+    pub skip_errors: bool,
+    /// Trailer `/ID` of the output; with `MetadataMode::First` the first input's `/ID` is kept instead
+    pub trailer_id: TrailerId,
+    /// Catalog XMP: dropped, copied from the first input, or stripped from everything
+    pub xmp: XmpMode,
+    /// Keep the first input's `/PageMode`, `/PageLayout` and page-destination `/OpenAction`
+    /// (see `meta::Navigation`)
+    pub keep_navigation: bool,
+    /// Keep the first input's catalog `/ViewerPreferences`, e.g. `/Direction /R2L` for
+    /// right-to-left documents
+    pub keep_viewer_preferences: bool,
+    /// Copy a lone input byte-for-byte when no other option would change it (see `rewrites_pages`)
+    /// and its `/Producer` already is `producer`
+    pub passthrough_single: bool,
+    /// Also write the output's metadata (title, author, bookmarks, page labels) as JSON here
+    pub metadata_file: Option<PathBuf>,
+    /// What to do when no PDFs are found
+    pub on_empty: OnEmpty,
+    /// Keep memory bounded by writing each processed input's objects to `<output>.spill`
+    /// (see `spill`); slower, same document
+    pub spill: bool,
+    /// Stop with `confirm::NeedsConfirmation` before loading anything when there are more inputs
+    pub confirm_above: Option<usize>,
+    /// Added around generated output names (`<subdir>.pdf`, or `merged.pdf` in an output directory)
+    pub name_prefix: String,
+    pub name_suffix: String,
+}
+
+impl MergeOptions {
+    /// File name for a generated output: `<prefix><stem><suffix>.pdf`
+    pub fn output_file_name(&self, stem: &str) -> String {
+        format!("{}{}{}.pdf", self.name_prefix, stem, self.name_suffix)
+    }
+
+    /// Whether these options change what a single input looks like, beyond rebuilding its page tree
+    fn rewrites_pages(&self) -> bool {
+        #[cfg(feature = "pdfa")]
+        if self.pdfa { return true; }
+        #[cfg(feature = "grayscale")]
+        if self.grayscale { return true; }
+        self.pages.is_some() || self.first_pages.is_some() || self.section.is_some() || self.exclude_blank || self.dedupe_pages
+            || self.covers || self.bookmark_files || self.strip_javascript || self.bake_rotation
+            || self.rotate_auto.is_some() || self.normalize.is_some()
+            || self.annotate_source.is_some() || self.renumber.is_some() || self.pdf_version != PdfVersion::default()
+            || self.attachments == AttachmentMode::Strip || self.trailer_id != TrailerId::None
+            || self.xmp == XmpMode::Strip || self.producer.as_deref() != Some(meta::DEFAULT_PRODUCER)
+    }
+}
+
+impl Default for MergeOptions {
+    fn default() -> Self {
+        Self {
+            pages: None, first_pages: None, section: None, includes: Vec::new(), excludes: Vec::new(), glob_case_insensitive: false, contains: None, force: false, exclude_blank: false, dedupe_pages: false, report_duplicates: false,
+            max_output_pages: None, sort_by: SortBy::default(), seed: None, metadata: MetadataMode::default(), producer: Some(meta::DEFAULT_PRODUCER.into()), bookmark_files: false,
+            covers: false, strip_javascript: false, preserve_page_boxes: true, bake_rotation: false, separator: None, rotate_auto: None, normalize: None, align: PageAlign::default(), annotate_source: None, renumber: None, start_at: 1,
+            checksum_manifest: false, retries: 0, chmod: None, #[cfg(feature = "pdfa")] pdfa: false, #[cfg(feature = "grayscale")] grayscale: false, pdf_version: PdfVersion::default(), attachments: AttachmentMode::default(),
+            max_file_size: None, skip_errors: false, trailer_id: TrailerId::default(), xmp: XmpMode::default(), keep_navigation: false, keep_viewer_preferences: false, passthrough_single: true, metadata_file: None,
+            name_prefix: String::new(), name_suffix: String::new(), spill: false, on_empty: OnEmpty::default(), confirm_above: None,
+        }
+    }
+}
+
+/// Per-input share of a merge.
+#[derive(Debug, Clone)]
+pub struct InputStats {
+    pub path: PathBuf,
+    /// Load + copy time
+    pub elapsed: Duration,
+    /// Objects this input added to the output
+    pub objects: usize,
+}
+
+/// What a merge produced, for reporting.
+#[derive(Debug, Clone, Default)]
+pub struct MergeSummary {
+    /// Pages written to the output
+    pub pages: usize,
+    /// In merge order
+    pub inputs: Vec<InputStats>,
+    pub total: Duration,
+    /// Objects in the saved output
+    pub objects: usize,
+    /// Output file size, from the written file
+    pub bytes: u64,
+    /// The `.sha256` sidecar, when requested
+    pub manifest: Option<PathBuf>,
+    /// The metadata JSON sidecar, when requested
+    pub metadata_file: Option<PathBuf>,
+    /// Pages dropped by `dedupe_pages`
+    pub duplicates_removed: usize,
+    /// With `report_duplicates`: output pages (1-based) repeating an earlier one, with that page
+    pub duplicate_pages: Vec<(usize, usize)>,
+    /// Inputs left out under `skip_errors`, with the reason
+    pub skipped: Vec<(PathBuf, String)>,
+    /// No PDFs were found and `on_empty` let that pass (nothing written, or a placeholder)
+    pub no_inputs: bool,
+}
+
+impl MergeSummary {
+    /// Per-file timings, slowest first
+    pub fn timings_report(&self) -> String {
+        let mut rows: Vec<&InputStats> = self.inputs.iter().collect();
+        rows.sort_by_key(|r| std::cmp::Reverse(r.elapsed));
+        let mut out = String::from("耗时（最慢在前）:\n");
+        for r in rows {
+            out.push_str(&format!("  {:>10.1} ms  {}\n", r.elapsed.as_secs_f64() * 1000.0, r.path.display()));
+        }
+        out.push_str(&format!("总计: {:.1} ms", self.total.as_secs_f64() * 1000.0));
+        out
+    }
+
+    /// Output size and per-input object counts, largest first
+    pub fn size_report(&self) -> String {
+        let mut rows: Vec<&InputStats> = self.inputs.iter().collect();
+        rows.sort_by_key(|r| std::cmp::Reverse(r.objects));
+        let mut out = format!("输出: {} 页, {} 个对象, {} 字节\n各输入对象数（最多在前）:", self.pages, self.objects, self.bytes);
+        for r in rows {
+            out.push_str(&format!("\n  {:>8}  {}", r.objects, r.path.display()));
+        }
+        out
+    }
+}
 
 pub fn run(
     input_dir: &Path,
     output: &Path,
-    pages_spec: Option<&str>,
-    includes: &[String],
-    excludes: &[String],
-    force: bool,
+    opts: &MergeOptions,
     progress: &dyn ProgressSink,
-) -> Result<()> {
+) -> Result<MergeSummary> {
     // Resolve output directory
     if let Some(parent) = output.parent() {
         std::fs::create_dir_all(parent)
             .with_context(|| format!("创建输出目录失败: {}", parent.display()))?;
     }
 
-    // Scan pdf files (reuse scanner) — CLI uses infinite depth by default
+    let pdf_files = merge_inputs(input_dir, output, opts)?;
+    if pdf_files.is_empty() {
+        return no_inputs(format!("未在目录中找到 PDF: {}", input_dir.display()), output, opts);
+    }
+    confirm::check("合并", pdf_files.len(), opts.confirm_above)?;
+    progress.set_len(pdf_files.len() as u64);
+    progress.set_message(std::borrow::Cow::from("准备合并..."));
+    let summary = merge_selected_pages(&pdf_files, &[], output, opts, progress)?;
+    progress.finish(std::borrow::Cow::from("合并完成"));
+    Ok(summary)
+}
+
+/// Merge the PDFs inside a zip archive. Entries passing the include/exclude globs (matched on
+/// entry names) are extracted to a scratch directory, removed afterwards, and sorted as usual;
+/// `SortBy::Scan` keeps archive order.
+#[cfg(feature = "zip")]
+pub fn run_from_zip(archive: &Path, output: &Path, opts: &MergeOptions, progress: &dyn ProgressSink) -> Result<MergeSummary> {
+    if let Some(parent) = output.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("创建输出目录失败: {}", parent.display()))?;
+    }
+    let scratch = ScratchDir::new()?;
+    let mut pdf_files = crate::archive::extract_pdfs(archive, &scratch.0, &opts.includes, &opts.excludes, opts.glob_case_insensitive)?;
+    if let Some(query) = &opts.contains {
+        pdf_files.retain(|p| crate::text::pdf_contains(p, query));
+    }
+    if pdf_files.is_empty() {
+        return no_inputs(format!("压缩包中没有匹配的 PDF: {}", archive.display()), output, opts);
+    }
+    confirm::check("合并", pdf_files.len(), opts.confirm_above)?;
+    sort::sort_files(&mut pdf_files, opts.sort_by, opts.seed);
+    progress.set_len(pdf_files.len() as u64);
+    progress.set_message(std::borrow::Cow::from("准备合并..."));
+    let summary = merge_selected_pages(&pdf_files, &[], output, opts, progress)?;
+    progress.finish(std::borrow::Cow::from("合并完成"));
+    Ok(summary)
+}
+
+// Scan pdf files (reuse scanner) — CLI uses infinite depth by default
+fn merge_inputs(input_dir: &Path, output: &Path, opts: &MergeOptions) -> Result<Vec<PathBuf>> {
+    let mut extra_exclude_paths = vec![output.to_path_buf()];
+    // A separator kept next to the inputs is not one of them
+    if let Some(Separator::File(path)) = &opts.separator { extra_exclude_paths.push(path.clone()); }
     let cfg = ScanConfig {
         input_dir: input_dir.to_path_buf(),
-        includes: includes.to_vec(),
-        excludes: excludes.to_vec(),
-        extra_exclude_paths: vec![output.to_path_buf()],
+        includes: opts.includes.clone(),
+        excludes: opts.excludes.clone(),
+        extra_exclude_paths,
         max_depth: None,
         follow_links: false,
+        case_insensitive: opts.glob_case_insensitive,
+        contains: opts.contains.clone(),
     };
-    let pdf_files = scan::collect_pdfs_cfg(&cfg)?;
+    let mut pdf_files = scan::collect_pdfs_walk_order(&cfg)?;
+    sort::sort_files(&mut pdf_files, opts.sort_by, opts.seed);
+    Ok(pdf_files)
+}
+
+/// Pages put between the pages of consecutive inputs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Separator {
+    /// One empty page the size of the page before it
+    Blank,
+    /// Every page of this PDF
+    File(PathBuf),
+}
+
+/// Orientation `--rotate-auto` turns pages to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Orientation {
+    #[default]
+    Portrait,
+    Landscape,
+}
+
+/// Page size `--normalize` gives every page, in points; each page gets it in its own
+/// orientation. Parsed from a name (`a3`, `a4`, `a5`, `letter`, `legal`) or `WIDTHxHEIGHT`.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize)]
+#[serde(try_from = "String")]
+pub struct PageSize {
+    pub width: f32,
+    pub height: f32,
+}
 
+impl std::str::FromStr for PageSize {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (width, height) = match s.to_ascii_lowercase().as_str() {
+            "a3" => (842.0, 1191.0),
+            "a4" => (595.0, 842.0),
+            "a5" => (420.0, 595.0),
+            "letter" => (612.0, 792.0),
+            "legal" => (612.0, 1008.0),
+            other => other.split_once('x')
+                .and_then(|(w, h)| Some((w.trim().parse::<f32>().ok()?, h.trim().parse::<f32>().ok()?)))
+                .filter(|&(w, h)| w > 0.0 && h > 0.0 && w.is_finite() && h.is_finite())
+                .ok_or_else(|| anyhow::anyhow!("无效的页面尺寸: {}（可用 a3、a4、a5、letter、legal 或 宽x高，单位 pt）", s))?,
+        };
+        Ok(PageSize { width, height })
+    }
+}
+
+impl TryFrom<String> for PageSize {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+/// How `--normalize` fits a page of another size: centred as it is (smaller pages get a margin,
+/// larger ones are cropped) or scaled to fill the target.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PageAlign {
+    #[default]
+    Center,
+    Scale,
+}
+
+/// `--on-empty`: a merge whose scan finds no PDFs fails (the default), succeeds without
+/// writing anything, or writes a valid PDF with no pages as a placeholder.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnEmpty {
+    #[default]
+    Error,
+    Skip,
+    Touch,
+}
+
+// No PDFs to merge: fail with `msg`, or let a batch loop carry on (see `OnEmpty`)
+fn no_inputs(msg: String, output: &Path, opts: &MergeOptions) -> Result<MergeSummary> {
+    let started = Instant::now();
+    let summary = MergeSummary { no_inputs: true, ..MergeSummary::default() };
+    match opts.on_empty {
+        OnEmpty::Error => Err(anyhow::anyhow!(msg)),
+        OnEmpty::Skip => Ok(summary),
+        OnEmpty::Touch => {
+            if output.exists() && !opts.force {
+                anyhow::bail!("输出文件已存在: {} (使用 --force 覆盖)", output.display());
+            }
+            let mut doc = Document::with_version(opts.pdf_version.as_str());
+            let mut pages = Dictionary::new();
+            pages.set("Type", "Pages");
+            pages.set("Kids", Object::Array(Vec::new()));
+            pages.set("Count", 0);
+            let pages_id = doc.add_object(pages);
+            let mut catalog = Dictionary::new();
+            catalog.set("Type", "Catalog");
+            catalog.set("Pages", Object::Reference(pages_id));
+            let catalog_id = doc.add_object(catalog);
+            doc.trailer.set("Root", Object::Reference(catalog_id));
+            version::apply(&mut doc, opts.pdf_version)?;
+            crate::interrupt::save(&mut doc, output)?;
+            let bytes = std::fs::metadata(output).map(|m| m.len()).unwrap_or(0);
+            finish(output, opts, MergeSummary { bytes, objects: doc.objects.len(), ..summary }, started)
+        }
+    }
+}
+
+/// What `merge --check` compares against the existing output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum CheckMode {
+    /// Page count and the SHA-256 of the whole file
+    Content,
+    /// Page count only
+    Pages,
+}
+
+/// Result of `check`: the existing output against what a merge would write now.
+#[derive(Debug, Clone)]
+pub struct CheckReport {
+    /// `None` when there is no output yet
+    pub existing_pages: Option<usize>,
+    pub merged_pages: usize,
+    /// Whether the bytes match; `None` unless compared (`CheckMode::Content` and an existing output)
+    pub same_content: Option<bool>,
+}
+
+impl CheckReport {
+    pub fn unchanged(&self) -> bool {
+        self.existing_pages == Some(self.merged_pages) && self.same_content != Some(false)
+    }
+}
+
+/// Merge as `run` would, but into a scratch file, and compare the result with `output`, which
+/// is left untouched. Sidecars (manifest, metadata file) are not written.
+pub fn check(input_dir: &Path, output: &Path, mode: CheckMode, opts: &MergeOptions, progress: &dyn ProgressSink) -> Result<CheckReport> {
+    let pdf_files = merge_inputs(input_dir, output, opts)?;
     if pdf_files.is_empty() {
         anyhow::bail!("未在目录中找到 PDF: {}", input_dir.display());
     }
+    // Same file name as the real output: PDF/A takes a missing title from it
+    let scratch = ScratchDir::new()?;
+    let name = output.file_name().map(|n| n.to_os_string()).unwrap_or_else(|| "merged.pdf".into());
+    let candidate = scratch.0.join(name);
+    let opts = MergeOptions { force: true, checksum_manifest: false, chmod: None, metadata_file: None, ..opts.clone() };
     progress.set_len(pdf_files.len() as u64);
+    let summary = merge_selected_pages(&pdf_files, &[], &candidate, &opts, progress)?;
+    progress.finish(std::borrow::Cow::from("检查完成"));
+
+    let existing_pages = output.exists()
+        .then(|| Document::load(output).map(|d| d.get_pages().len()))
+        .transpose()
+        .with_context(|| format!("加载现有输出失败: {}", output.display()))?;
+    let same_content = match (mode, existing_pages) {
+        (CheckMode::Content, Some(_)) => Some(checksum::sha256_file(output)? == checksum::sha256_file(&candidate)?),
+        _ => None,
+    };
+    Ok(CheckReport { existing_pages, merged_pages: summary.pages, same_content })
+}
+
+// A private directory under the system temp dir, removed on drop
+struct ScratchDir(PathBuf);
+
+impl ScratchDir {
+    fn new() -> Result<Self> {
+        let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+        let dir = std::env::temp_dir().join(format!("pdf-ops-{}-{}", std::process::id(), nanos));
+        std::fs::create_dir_all(&dir).with_context(|| format!("创建临时目录失败: {}", dir.display()))?;
+        Ok(Self(dir))
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) { std::fs::remove_dir_all(&self.0).ok(); }
+}
+
+/// Merge each immediate subdirectory of `input_dir` into `<out_dir>/<subdir>.pdf` (see
+/// `MergeOptions::output_file_name`).
+/// PDFs directly in `input_dir` belong to no group and are skipped, as are subdirectories
+/// without PDFs. Returns the written outputs in name order.
+pub fn run_grouped(
+    input_dir: &Path,
+    out_dir: &Path,
+    opts: &MergeOptions,
+    progress: &dyn ProgressSink,
+) -> Result<Vec<(PathBuf, MergeSummary)>> {
+    std::fs::create_dir_all(out_dir)
+        .with_context(|| format!("创建输出目录失败: {}", out_dir.display()))?;
+    let cfg = ScanConfig {
+        input_dir: input_dir.to_path_buf(),
+        includes: opts.includes.clone(),
+        excludes: opts.excludes.clone(),
+        extra_exclude_paths: vec![],
+        max_depth: None,
+        follow_links: false,
+        case_insensitive: opts.glob_case_insensitive,
+        contains: opts.contains.clone(),
+    };
+    let mut files = scan::collect_pdfs_walk_order(&cfg)?;
+    // Previous outputs must not feed back in when out_dir sits inside the tree
+    if out_dir != input_dir {
+        files.retain(|p| !p.starts_with(out_dir));
+    }
+    let groups = group_by_subdir(input_dir, files);
+    if groups.is_empty() {
+        anyhow::bail!("未在子目录中找到 PDF: {}", input_dir.display());
+    }
+    confirm::check("合并", groups.iter().map(|(_, f)| f.len()).sum(), opts.confirm_above)?;
+
+    progress.set_len(groups.iter().map(|(_, f)| f.len() as u64).sum());
     progress.set_message(std::borrow::Cow::from("准备合并..."));
-    merge_selected_pages(&pdf_files, output, pages_spec, progress, force)?;
+    let mut outputs = Vec::new();
+    for (name, mut group) in groups {
+        sort::sort_files(&mut group, opts.sort_by, opts.seed);
+        let output = out_dir.join(opts.output_file_name(&name));
+        let summary = merge_selected_pages(&group, &[], &output, opts, progress)?;
+        outputs.push((output, summary));
+    }
     progress.finish(std::borrow::Cow::from("合并完成"));
-    Ok(())
+    Ok(outputs)
+}
+
+// Files keyed by their first path component under `root`
+fn group_by_subdir(root: &Path, files: Vec<PathBuf>) -> Vec<(String, Vec<PathBuf>)> {
+    let mut groups: std::collections::BTreeMap<String, Vec<PathBuf>> = Default::default();
+    for f in files {
+        let Ok(rel) = f.strip_prefix(root) else { continue };
+        let mut comps = rel.components();
+        let first = comps.next();
+        // A single component is a file directly under root
+        if comps.next().is_none() { continue; }
+        if let Some(first) = first {
+            groups.entry(first.as_os_str().to_string_lossy().into_owned()).or_default().push(f);
+        }
+    }
+    groups.into_iter().collect()
 }
 
-pub(crate) fn merge_selected_pages(files: &[PathBuf], output: &Path, pages_spec: Option<&str>, progress: &dyn ProgressSink, force: bool) -> Result<()> {
+// `file_specs[i]`, when given, is the page spec of `files[i]` and overrides --pages/--first-pages
+pub(crate) fn merge_selected_pages(files: &[PathBuf], file_specs: &[Option<String>], output: &Path, opts: &MergeOptions, progress: &dyn ProgressSink) -> Result<MergeSummary> {
     // Overwrite protection handled here to ensure we fail early
-    if output.exists() && !force {
+    if output.exists() && !opts.force {
         anyhow::bail!("输出文件已存在: {} (使用 --force 覆盖)", output.display());
     }
+    // PDF/A cleanup writes its own XMP packet, which a copied one would be replaced by and stripping would remove
+    #[cfg(feature = "pdfa")]
+    if opts.pdfa && opts.xmp != XmpMode::Drop {
+        anyhow::bail!("--pdfa 会写入自己的 XMP 元数据，不能与 --copy-xmp / --strip-xmp 同时使用");
+    }
+    // The PDF/A pass needs every object in memory at once
+    #[cfg(feature = "pdfa")]
+    if opts.pdfa && opts.spill {
+        anyhow::bail!("--pdfa 不能与 --spill 同时使用");
+    }
+    let started = Instant::now();
+    let mut summary = MergeSummary::default();
     let mut doc = Document::with_version("1.5");
     let mut page_ids: Vec<ObjectId> = Vec::new();
+    let mut infos: Vec<Option<Dictionary>> = Vec::new();
+    let mut bookmarks: Vec<OutlineEntry> = Vec::new();
+    let mut stamp_font: Option<ObjectId> = None;
+    // MediaBox of the last page so far, for a blank separator
+    let mut prev_media: Option<[f32; 4]> = None;
+    let mut embedded: Vec<(String, Object)> = Vec::new();
+    let mut first_id: Option<Object> = None;
+    let mut xmp: Option<lopdf::Stream> = None;
+    let mut navigation: Option<meta::Navigation> = None;
+    let mut viewer_preferences: Option<Dictionary> = None;
+    let separator = match &opts.separator {
+        Some(Separator::File(path)) => {
+            let mut sep = load::load_pdf(path, opts.retries, progress)?;
+            drop_stream_containers(&mut sep);
+            if sep.get_pages().is_empty() { anyhow::bail!("分隔页文件没有页面: {}", path.display()); }
+            Some(sep)
+        }
+        _ => None,
+    };
+    // Pages the inputs had before any selection, to tell filtered-out from empty inputs
+    let mut available_pages = 0usize;
+
+    // Parsed once here rather than per file; only label/phrase segments are resolved per input
+    let pages_spec = opts.pages.as_deref().map(|s| (s, spec::CompiledSpec::compile(s)));
+    let first_spec = opts.first_pages.as_deref().map(|s| (s, spec::CompiledSpec::compile(s)));
+    let entry_specs: Vec<Option<(&str, spec::CompiledSpec)>> = file_specs.iter()
+        .map(|s| s.as_deref().map(|s| (s, spec::CompiledSpec::compile(s))))
+        .collect();
 
-    for path in files {
+    // Sizes come from metadata before anything is loaded, so one huge input cannot exhaust memory
+    let mut usable: Vec<(usize, &PathBuf)> = Vec::with_capacity(files.len());
+    for (i, path) in files.iter().enumerate() {
+        match check_size(path, opts.max_file_size) {
+            Ok(()) => usable.push((i, path)),
+            Err(e) if opts.skip_errors => skip_input(&mut summary, path, e, progress),
+            Err(e) => return Err(e),
+        }
+    }
+    let selects_pages = opts.rewrites_pages() || entry_specs.iter().any(Option::is_some);
+    if let ([(_, only)], true, false) = (usable.as_slice(), opts.passthrough_single, selects_pages) {
+        let pdf = match load::load_pdf(only, opts.retries, progress) {
+            Ok(pdf) => pdf,
+            Err(e) if opts.skip_errors => {
+                skip_input(&mut summary, only, e, progress);
+                anyhow::bail!("所有输入均被跳过，没有可合并的文件");
+            }
+            Err(e) => return Err(e),
+        };
+        // A copy keeps the input's /Producer, so it is only taken when that is what a rebuild writes
+        let producer = meta::read_info(&pdf)
+            .and_then(|info| info.get(b"Producer").and_then(Object::as_str).map(meta::decode_text).ok());
+        if producer == opts.producer {
+            return passthrough(only, pdf, output, opts, summary, started, progress);
+        }
+    }
+    let mut spill = if opts.spill { Some(Spill::create(output)?) } else { None };
+
+    for (file_idx, &(list_idx, path)) in usable.iter().enumerate() {
+        let file_started = Instant::now();
         let msg = path
             .file_name()
             .and_then(|s| s.to_str())
             .map(|s| s.to_string())
             .unwrap_or_else(|| "加载中...".to_string());
         progress.set_message(std::borrow::Cow::from(msg));
-        let mut pdf = Document::load(path)
-            .with_context(|| format!("加载 PDF 失败: {}", path.display()))?;
+        let mut pdf = match load::load_pdf(path, opts.retries, progress) {
+            Ok(pdf) => pdf,
+            Err(e) if opts.skip_errors => { skip_input(&mut summary, path, e, progress); continue; }
+            Err(e) => return Err(e),
+        };
+        drop_stream_containers(&mut pdf);
+        if opts.metadata != MetadataMode::None {
+            infos.push(meta::read_info(&pdf));
+        }
+        if opts.metadata == MetadataMode::First && summary.inputs.is_empty() {
+            first_id = meta::read_trailer_id(&pdf);
+        }
+        if opts.xmp == XmpMode::Copy && summary.inputs.is_empty() {
+            xmp = meta::read_xmp(&pdf);
+        }
+        if opts.covers {
+            // Keep only what page 1 references; the rest of the input is dropped before copying
+            pdf = split::extract_range(&pdf, 1, 1)
+                .with_context(|| format!("提取首页失败: {}", path.display()))?;
+        }
         let total_pages = pdf.get_pages().len();
-        let indices: Option<Vec<usize>> = if let Some(spec_str) = pages_spec {
-            let ranges = spec::parse_spec(spec_str)
+        available_pages += total_pages;
+        // An input list entry or the first input may carry its own spec (e.g. keep only the cover)
+        let entry_spec = entry_specs.get(list_idx).and_then(Option::as_ref);
+        let file_spec = match (opts.covers, entry_spec, file_idx, &first_spec) {
+            (true, _, _, _) => None,
+            (false, Some(entry), _, _) => Some(entry),
+            (false, None, 0, Some(first)) => Some(first),
+            _ => pages_spec.as_ref(),
+        };
+        let indices: Option<Vec<usize>> = if let Some(name) = &opts.section {
+            let (start, end) = outline::section_range(&outline::read_outline(&pdf), total_pages, name)
+                // Top-level message: the available sections are the useful part
+                .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
+            Some((start - 1..end).collect())
+        } else if let Some((spec_str, compiled)) = file_spec {
+            let labels = if compiled.needs_labels() { labels::page_labels(&pdf) } else { None };
+            let ranges = compiled.resolve(total_pages, labels.as_deref())
                 .with_context(|| format!("解析页码范围失败: {}", spec_str))?;
             Some(spec::expand_to_indexes(&ranges, total_pages))
         } else { None };
@@ -69,21 +658,128 @@ pub(crate) fn merge_selected_pages(files: &[PathBuf], output: &Path, pages_spec:
         let offset = doc.max_id + 1;
         pdf.renumber_objects_with(offset);
         doc.max_id = pdf.max_id;
+        if opts.keep_navigation && summary.inputs.is_empty() {
+            // Read after renumbering, so the open action already points at the merged page ids
+            navigation = Some(meta::read_navigation(&pdf));
+        }
+        if opts.keep_viewer_preferences && summary.inputs.is_empty() {
+            viewer_preferences = meta::read_viewer_preferences(&pdf);
+        }
 
         let pages_map = pdf.get_pages();
         // Collect in natural order
         let mut current: Vec<ObjectId> = Vec::new();
+        // Duplicates are only looked for within one input
+        let mut digests: HashSet<[u8; 32]> = HashSet::new();
         for (i, (_, pid)) in pages_map.into_iter().enumerate() {
             if let Some(ref idxs) = indices {
                 if !idxs.contains(&i) { continue; }
             }
+            if opts.exclude_blank && blank::is_blank_page(&pdf, pid) { continue; }
+            if opts.dedupe_pages {
+                if let Some(digest) = dedupe::page_digest(&pdf, pid) {
+                    if !digests.insert(digest) { summary.duplicates_removed += 1; continue; }
+                }
+            }
             current.push(pid);
         }
-        page_ids.extend(current);
+        if let (true, Some(&first)) = (opts.bookmark_files, current.first()) {
+            let title = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+            bookmarks.push(OutlineEntry { title, level: 1, page: first });
+        }
+        if opts.preserve_page_boxes {
+            for &pid in &current {
+                let Ok(mut page) = pdf.get_dictionary(pid).cloned() else { continue };
+                page::pin_page_boxes(&pdf, &mut page);
+                pdf.objects.insert(pid, Object::Dictionary(page));
+            }
+        }
+        if opts.attachments == AttachmentMode::Keep {
+            embedded.extend(attachments::embedded_files(&pdf));
+        }
+        let input_objects = pdf.objects.len();
         doc.objects.extend(pdf.objects);
+        if opts.attachments == AttachmentMode::Strip {
+            attachments::strip_file_attachments(&mut doc, &current);
+        }
+        if let Some(target) = opts.rotate_auto {
+            for &pid in &current {
+                page::rotate_to_orientation(&mut doc, pid, target == Orientation::Landscape)
+                    .with_context(|| format!("自动旋转页面失败: {}", path.display()))?;
+            }
+        }
+        if opts.bake_rotation {
+            for &pid in &current {
+                page::bake_rotation(&mut doc, pid)
+                    .with_context(|| format!("应用页面旋转失败: {}", path.display()))?;
+            }
+        }
+        if let Some(size) = opts.normalize {
+            for &pid in &current {
+                page::fit_to_size(&mut doc, pid, size.width, size.height, opts.align == PageAlign::Scale)
+                    .with_context(|| format!("统一页面尺寸失败: {}", path.display()))?;
+            }
+        }
+        #[cfg(feature = "grayscale")]
+        if opts.grayscale {
+            for &pid in &current { crate::grayscale::convert_page(&mut doc, pid); }
+        }
+        if let (Some(_), Some(_), false) = (&opts.separator, page_ids.last(), current.is_empty()) {
+            let pages = separator_pages(&mut doc, prev_media, separator.as_ref());
+            page_ids.extend(pages);
+        }
+        let stamp_numbers = opts.renumber.is_some_and(Renumber::stamps);
+        if opts.annotate_source.is_some() || stamp_numbers {
+            let font_id = *stamp_font.get_or_insert_with(|| stamp::add_font(&mut doc));
+            let name = path.file_name().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+            let corner = opts.annotate_source.unwrap_or_default();
+            for (i, &pid) in current.iter().enumerate() {
+                // Numbers count across inputs; with a source stamp they share its corner
+                let number = opts.start_at + page_ids.len() + i;
+                let text = match (opts.annotate_source.is_some(), stamp_numbers) {
+                    (true, true) => format!("{}  {}", name, number),
+                    (true, false) => name.clone(),
+                    (false, _) => number.to_string(),
+                };
+                stamp::stamp_page(&mut doc, pid, font_id, &text, corner)
+                    .with_context(|| format!("标注页面失败: {}", path.display()))?;
+            }
+        }
+        if let (Some(_), Some(&last)) = (&opts.separator, current.last()) {
+            // Read now: with --spill an inherited or indirect MediaBox leaves memory below
+            prev_media = page::media_box(&doc, last);
+        }
+        page_ids.extend(current);
+        if let Some(spill) = spill.as_mut() {
+            // The whole-document passes below only see what is still in memory, so the
+            // spilled part gets them now
+            if opts.strip_javascript { sanitize::strip_javascript(&mut doc); }
+            if opts.xmp == XmpMode::Strip { meta::strip_xmp(&mut doc); }
+            version::apply(&mut doc, opts.pdf_version)?;
+            doc.compress();
+            // Pages get their /Parent once the page tree exists
+            spill.spill(&mut doc, &page_ids.iter().copied().collect())
+                .with_context(|| format!("写入临时文件失败: {}", path.display()))?;
+        }
+        summary.inputs.push(InputStats { path: path.clone(), elapsed: file_started.elapsed(), objects: input_objects });
         progress.inc(1);
     }
 
+    if summary.inputs.is_empty() {
+        anyhow::bail!("所有输入均被跳过，没有可合并的文件");
+    }
+    if page_ids.is_empty() {
+        if available_pages == 0 {
+            anyhow::bail!("输入文件中没有任何页面（文件可能已损坏或缺少页面树）");
+        }
+        anyhow::bail!("未从任何输入中选中页面：共 {} 页均被 --pages 等筛选条件排除", available_pages);
+    }
+    if let Some(limit) = opts.max_output_pages {
+        if page_ids.len() > limit {
+            anyhow::bail!("输出页数 {} 超过上限 {} (--max-output-pages)", page_ids.len(), limit);
+        }
+    }
+
     let pages_id = doc.new_object_id();
     for &pid in &page_ids {
         let page_obj = doc
@@ -110,18 +806,279 @@ pub(crate) fn merge_selected_pages(files: &[PathBuf], output: &Path, pages_spec:
     let mut catalog_dict = Dictionary::new();
     catalog_dict.set("Type", "Catalog");
     catalog_dict.set("Pages", Object::Reference(pages_id));
+    if let Some(outline_id) = outline::write_outline(&mut doc, &bookmarks) {
+        catalog_dict.set("Outlines", Object::Reference(outline_id));
+        catalog_dict.set("PageMode", "UseOutlines");
+    }
+    if let Some(names_id) = attachments::write_embedded_files(&mut doc, embedded) {
+        catalog_dict.set("Names", Object::Reference(names_id));
+    }
+    if opts.renumber.is_some_and(Renumber::labels) {
+        catalog_dict.set("PageLabels", labels::continuous_labels(opts.start_at));
+    }
     doc.objects.insert(catalog_id, Object::Dictionary(catalog_dict));
 
     doc.trailer = Dictionary::new();
     doc.trailer.set("Root", Object::Reference(catalog_id));
+    if let Some(xmp) = xmp {
+        meta::set_catalog_xmp(&mut doc, xmp)?;
+    }
+    let mut info = meta::combine_info(&infos, opts.metadata);
+    match &opts.producer {
+        Some(producer) => { info.get_or_insert_with(Dictionary::new).set("Producer", meta::encode_text(producer)); }
+        None => { if let Some(info) = &mut info { info.remove(b"Producer"); } }
+    }
+    if let Some(info) = info.filter(|info| !info.is_empty()) {
+        let info_id = doc.add_object(info);
+        doc.trailer.set("Info", Object::Reference(info_id));
+    }
+    let id = match (first_id, opts.trailer_id) {
+        (Some(id), _) => Some(id),
+        (None, TrailerId::None) => None,
+        (None, TrailerId::Random) => Some(meta::trailer_id(&meta::random_id_seed(output))),
+        (None, TrailerId::Deterministic) => {
+            let mut seed = String::new();
+            for input in &summary.inputs {
+                seed.push_str(&checksum::sha256_file(&input.path)?);
+            }
+            Some(meta::trailer_id(seed.as_bytes()))
+        }
+    };
+    if let Some(id) = id {
+        doc.trailer.set("ID", id);
+    }
+    if opts.strip_javascript {
+        sanitize::strip_javascript(&mut doc);
+    }
+    // After the JavaScript pass: a page destination is no script, so it survives --strip-javascript
+    if let Some(navigation) = navigation {
+        navigation.apply(&mut doc, &page_ids)?;
+    }
+    if let Some(prefs) = viewer_preferences {
+        doc.catalog_mut()?.set("ViewerPreferences", prefs);
+    }
+    #[cfg(feature = "pdfa")]
+    if opts.pdfa {
+        let title = output.file_stem().and_then(|s| s.to_str()).unwrap_or("merged");
+        crate::pdfa::cleanup(&mut doc, catalog_id, title, opts.producer.as_deref())?;
+    }
+    if opts.xmp == XmpMode::Strip {
+        meta::strip_xmp(&mut doc);
+    }
+    version::apply(&mut doc, opts.pdf_version)?;
     doc.compress();
-    doc.save(output)
-        .with_context(|| format!("写入输出失败: {}", output.display()))?;
+    summary.objects = doc.objects.len();
+    match spill {
+        Some(spill) => {
+            summary.objects += spill.objects();
+            spill.finish(&mut doc, output)?;
+        }
+        None => crate::interrupt::save(&mut doc, output)?,
+    }
+    summary.bytes = std::fs::metadata(output)
+        .with_context(|| format!("读取输出文件信息失败: {}", output.display()))?
+        .len();
+    summary.pages = page_ids.len();
+    finish(output, opts, summary, started)
+}
+
+// The single input is still loaded (by the caller, which checks its /Producer), so a broken file
+// fails (or is skipped) like in a real merge and the page limit applies, but what gets written is
+// a plain copy of it.
+fn passthrough(path: &Path, pdf: Document, output: &Path, opts: &MergeOptions, mut summary: MergeSummary, started: Instant, progress: &dyn ProgressSink) -> Result<MergeSummary> {
+    let file_started = Instant::now();
+    summary.pages = pdf.get_pages().len();
+    if summary.pages == 0 {
+        anyhow::bail!("输入文件中没有任何页面（文件可能已损坏或缺少页面树）: {}", path.display());
+    }
+    if let Some(limit) = opts.max_output_pages {
+        if summary.pages > limit {
+            anyhow::bail!("输出页数 {} 超过上限 {} (--max-output-pages)", summary.pages, limit);
+        }
+    }
+    crate::interrupt::copy(path, output)?;
+    summary.objects = pdf.objects.len();
+    summary.bytes = std::fs::metadata(output)
+        .with_context(|| format!("读取输出文件信息失败: {}", output.display()))?
+        .len();
+    summary.inputs.push(InputStats { path: path.to_path_buf(), elapsed: file_started.elapsed(), objects: summary.objects });
+    progress.inc(1);
+    finish(output, opts, summary, started)
+}
+
+// Permissions, manifest and the completion event, shared by both ways of producing the output
+fn finish(output: &Path, opts: &MergeOptions, mut summary: MergeSummary, started: Instant) -> Result<MergeSummary> {
+    if let Some(mode) = opts.chmod {
+        pathutil::set_mode(output, mode)?;
+    }
+    if opts.checksum_manifest {
+        let inputs: Vec<&Path> = summary.inputs.iter().map(|i| i.path.as_path()).collect();
+        let manifest = checksum::write_manifest(&inputs, output)?;
+        if let Some(mode) = opts.chmod { pathutil::set_mode(&manifest, mode)?; }
+        summary.manifest = Some(manifest);
+    }
+    if opts.report_duplicates {
+        let written = Document::load(output).with_context(|| format!("加载输出失败: {}", output.display()))?;
+        summary.duplicate_pages = dedupe::duplicate_pages(&written);
+    }
+    if let Some(dest) = &opts.metadata_file {
+        info::write_metadata_file(&[output], dest)?;
+        if let Some(mode) = opts.chmod { pathutil::set_mode(dest, mode)?; }
+        summary.metadata_file = Some(dest.clone());
+    }
+    summary.total = started.elapsed();
+    tracing::info!(
+        name: "merge_complete",
+        output = %output.display(),
+        pages = summary.pages,
+        inputs = summary.inputs.len(),
+        bytes = summary.bytes,
+        elapsed_ms = summary.total.as_millis() as u64,
+        "merge_complete"
+    );
+    Ok(summary)
+}
+
+// A blank page of the previous page's `media` box, or a fresh copy of the separator document's pages
+fn separator_pages(doc: &mut Document, media: Option<[f32; 4]>, separator: Option<&Document>) -> Vec<ObjectId> {
+    let Some(separator) = separator else {
+        let media = media.unwrap_or([0.0, 0.0, 612.0, 792.0]);
+        let mut page = Dictionary::new();
+        page.set("Type", "Page");
+        page.set("MediaBox", media.iter().map(|&v| Object::Real(v)).collect::<Vec<_>>());
+        page.set("Resources", Dictionary::new());
+        return vec![doc.add_object(page)];
+    };
+    let mut copy = separator.clone();
+    copy.renumber_objects_with(doc.max_id + 1);
+    doc.max_id = copy.max_id;
+    let ids: Vec<ObjectId> = copy.get_pages().into_values().collect();
+    for &pid in &ids {
+        // The copy's page tree is left behind, so inherited attributes move onto the pages
+        let Ok(mut page) = copy.get_dictionary(pid).cloned() else { continue };
+        page::inherit_page_attributes(&copy, &mut page);
+        page::pin_page_boxes(&copy, &mut page);
+        copy.objects.insert(pid, Object::Dictionary(page));
+    }
+    doc.objects.extend(copy.objects);
+    ids
+}
+
+// lopdf expands object streams on load but keeps the `/ObjStm` and `/XRef` stream objects
+// themselves. Their payload still uses the input's numbering, and the writer skips them anyway,
+// so they only inflate renumbering and object counts.
+fn drop_stream_containers(doc: &mut Document) {
+    doc.objects.retain(|_, obj| !matches!(obj.type_name(), Ok("ObjStm") | Ok("XRef")));
+}
+
+fn check_size(path: &Path, limit: Option<u64>) -> Result<()> {
+    let Some(limit) = limit else { return Ok(()) };
+    let size = std::fs::metadata(path)
+        .with_context(|| format!("读取文件信息失败: {}", path.display()))?
+        .len();
+    if size > limit {
+        anyhow::bail!("文件过大: {} ({} 字节，上限 {} 字节，--max-file-size)", path.display(), size, limit);
+    }
     Ok(())
 }
 
-pub fn run_with_files(files: &[PathBuf], output: &Path, pages_spec: Option<&str>, force: bool, progress: &dyn ProgressSink) -> Result<()> {
-    merge_selected_pages(files, output, pages_spec, progress, force)
+fn skip_input(summary: &mut MergeSummary, path: &Path, err: anyhow::Error, progress: &dyn ProgressSink) {
+    let reason = format!("{:#}", err);
+    tracing::warn!(name: "input_skipped", path = %path.display(), reason = %reason, "input_skipped");
+    summary.skipped.push((path.to_path_buf(), reason));
+    progress.inc(1);
+}
+
+pub fn run_with_files(files: &[PathBuf], output: &Path, opts: &MergeOptions, progress: &dyn ProgressSink) -> Result<MergeSummary> {
+    merge_selected_pages(files, &[], output, opts, progress)
+}
+
+/// One input of `--input-list-json`: a PDF and, optionally, its own page spec (which takes the
+/// place of `--pages` / `--first-pages` for it).
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ListEntry {
+    pub path: PathBuf,
+    #[serde(default)]
+    pub pages: Option<String>,
+}
+
+/// Read a JSON array of `ListEntry` objects, e.g. `[{"path": "a.pdf", "pages": "1-2"}, {"path": "b.pdf"}]`.
+/// Relative paths resolve against the JSON file's directory; every file must exist.
+pub fn read_input_list(list: &Path) -> Result<Vec<ListEntry>> {
+    let text = std::fs::read_to_string(list).with_context(|| format!("读取输入列表失败: {}", list.display()))?;
+    let mut entries: Vec<ListEntry> = serde_json::from_str(&text).with_context(|| format!("解析输入列表失败: {}", list.display()))?;
+    let base = list.parent().unwrap_or(Path::new(""));
+    for entry in &mut entries {
+        if entry.path.is_relative() { entry.path = base.join(&entry.path); }
+        if !entry.path.is_file() {
+            anyhow::bail!("输入列表中的文件不存在: {}", entry.path.display());
+        }
+    }
+    Ok(entries)
+}
+
+/// Merge exactly the entries of an input list, in list order: no scan, globs or sorting.
+pub fn run_with_list(entries: &[ListEntry], output: &Path, opts: &MergeOptions, progress: &dyn ProgressSink) -> Result<MergeSummary> {
+    if let Some(parent) = output.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("创建输出目录失败: {}", parent.display()))?;
+    }
+    if entries.is_empty() {
+        return no_inputs("输入列表为空".into(), output, opts);
+    }
+    confirm::check("合并", entries.len(), opts.confirm_above)?;
+    let files: Vec<PathBuf> = entries.iter().map(|e| e.path.clone()).collect();
+    let specs: Vec<Option<String>> = entries.iter().map(|e| e.pages.clone()).collect();
+    progress.set_len(files.len() as u64);
+    progress.set_message(std::borrow::Cow::from("准备合并..."));
+    let summary = merge_selected_pages(&files, &specs, output, opts, progress)?;
+    progress.finish(std::borrow::Cow::from("合并完成"));
+    Ok(summary)
 }
 
 // scanner helpers moved to crate::scan
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::dictionary;
+
+    #[test]
+    fn pages_spec_is_parsed_once_per_merge() {
+        let dir = tempfile::tempdir().unwrap();
+        let files: Vec<PathBuf> = (0..5).map(|i| {
+            let mut doc = Document::with_version("1.5");
+            let pages_id = doc.new_object_id();
+            let kids: Vec<Object> = (0..4).map(|_| doc.add_object(dictionary! {
+                "Type" => "Page", "Parent" => pages_id, "MediaBox" => vec![0.into(), 0.into(), 100.into(), 100.into()],
+            }).into()).collect();
+            doc.objects.insert(pages_id, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => kids, "Count" => 4 }));
+            let catalog = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+            doc.trailer.set("Root", catalog);
+            let path = dir.path().join(format!("{i}.pdf"));
+            doc.save(&path).unwrap();
+            path
+        }).collect();
+
+        let before = spec::COMPILED.with(|c| c.get());
+        let opts = MergeOptions { pages: Some("1-2, last 1".into()), ..Default::default() };
+        let summary = merge_selected_pages(&files, &[], &dir.path().join("out.pdf"), &opts, &crate::progress::NullProgress).unwrap();
+        assert_eq!(summary.pages, 5 * 3);
+        assert_eq!(spec::COMPILED.with(|c| c.get()) - before, 1);
+    }
+
+    #[test]
+    fn groups_by_first_component_and_skips_root_files() {
+        let root = Path::new("/scans");
+        let files = vec![
+            root.join("top.pdf"),
+            root.join("b/x.pdf"),
+            root.join("a/2.pdf"),
+            root.join("a/deep/1.pdf"),
+        ];
+        let groups = group_by_subdir(root, files);
+        let names: Vec<(&str, usize)> = groups.iter().map(|(n, f)| (n.as_str(), f.len())).collect();
+        assert_eq!(names, vec![("a", 2), ("b", 1)]);
+    }
+}
@@ -0,0 +1,348 @@
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream, StringFormat};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+use crate::outline;
+
+/// How the merged output's `/Info` is built from the inputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MetadataMode {
+    /// Copy the first input's Info
+    First,
+    /// Write no Info
+    #[default]
+    None,
+    /// First input's Info, plus the union of keywords, distinct authors and the earliest CreationDate
+    Merged,
+}
+
+/// Decode a PDF text string: UTF-16BE/LE with BOM, otherwise bytes as (lossy) UTF-8.
+pub(crate) fn decode_text(bytes: &[u8]) -> String {
+    let utf16 = |be: bool| -> String {
+        let units: Vec<u16> = bytes[2..]
+            .chunks_exact(2)
+            .map(|c| if be { u16::from_be_bytes([c[0], c[1]]) } else { u16::from_le_bytes([c[0], c[1]]) })
+            .collect();
+        String::from_utf16_lossy(&units)
+    };
+    match bytes {
+        [0xfe, 0xff, ..] => utf16(true),
+        [0xff, 0xfe, ..] => utf16(false),
+        _ => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+/// Encode text as a PDF string: literal when ASCII, UTF-16BE with BOM otherwise.
+pub(crate) fn encode_text(text: &str) -> Object {
+    if text.is_ascii() {
+        return Object::string_literal(text);
+    }
+    let mut bytes = vec![0xfe, 0xff];
+    bytes.extend(text.encode_utf16().flat_map(|u| u.to_be_bytes()));
+    Object::String(bytes, StringFormat::Hexadecimal)
+}
+
+fn info_dict(doc: &Document) -> Option<&Dictionary> {
+    match doc.trailer.get(b"Info").ok()? {
+        Object::Reference(id) => doc.get_dictionary(*id).ok(),
+        Object::Dictionary(d) => Some(d),
+        _ => None,
+    }
+}
+
+/// Read an entry of the trailer `/Info` dictionary as text.
+pub(crate) fn info_string(doc: &Document, key: &[u8]) -> Option<String> {
+    match info_dict(doc)?.get(key).ok()? {
+        Object::String(bytes, _) => Some(decode_text(bytes)),
+        _ => None,
+    }
+}
+
+/// The trailer `/Info` with indirect values resolved, so it can outlive `doc`'s object table.
+pub(crate) fn read_info(doc: &Document) -> Option<Dictionary> {
+    let mut out = Dictionary::new();
+    for (key, value) in info_dict(doc)?.iter() {
+        let value = match value {
+            Object::Reference(id) => match doc.get_object(*id) {
+                Ok(obj) => obj.clone(),
+                Err(_) => continue,
+            },
+            other => other.clone(),
+        };
+        out.set(key.clone(), value);
+    }
+    Some(out)
+}
+
+fn text_entry(info: &Dictionary, key: &[u8]) -> Option<String> {
+    match info.get(key).ok()? {
+        Object::String(bytes, _) => Some(decode_text(bytes)).filter(|t| !t.trim().is_empty()),
+        _ => None,
+    }
+}
+
+// Digits of "D:YYYYMMDDHHmmSS", padded so partial dates compare as the start of their period
+fn date_key(date: &str) -> String {
+    let digits: String = date.trim_start_matches("D:").chars().take_while(|c| c.is_ascii_digit()).take(14).collect();
+    format!("{:0<14}", digits)
+}
+
+fn push_distinct(out: &mut Vec<String>, item: &str) {
+    let item = item.trim();
+    if !item.is_empty() && !out.iter().any(|x| x == item) {
+        out.push(item.to_string());
+    }
+}
+
+/// `/Producer` written on merged output unless another is configured
+pub const DEFAULT_PRODUCER: &str = "pdf-ops";
+
+/// Build the output Info from the inputs' Info dictionaries (in merge order; `None` = input had none).
+pub(crate) fn combine_info(infos: &[Option<Dictionary>], mode: MetadataMode) -> Option<Dictionary> {
+    match mode {
+        MetadataMode::None => None,
+        MetadataMode::First => infos.first().cloned().flatten(),
+        MetadataMode::Merged => {
+            let present: Vec<&Dictionary> = infos.iter().flatten().collect();
+            let mut out = (*present.first()?).clone();
+
+            let mut keywords = Vec::new();
+            let mut authors = Vec::new();
+            let mut earliest: Option<String> = None;
+            for info in &present {
+                if let Some(k) = text_entry(info, b"Keywords") {
+                    k.split([',', ';']).for_each(|w| push_distinct(&mut keywords, w));
+                }
+                if let Some(a) = text_entry(info, b"Author") {
+                    push_distinct(&mut authors, &a);
+                }
+                if let Some(d) = text_entry(info, b"CreationDate") {
+                    if earliest.as_ref().is_none_or(|e| date_key(&d) < date_key(e)) {
+                        earliest = Some(d);
+                    }
+                }
+            }
+            if !keywords.is_empty() { out.set("Keywords", encode_text(&keywords.join(", "))); }
+            if !authors.is_empty() { out.set("Author", encode_text(&authors.join("; "))); }
+            if let Some(d) = earliest { out.set("CreationDate", encode_text(&d)); }
+            Some(out)
+        }
+    }
+}
+
+/// How the merged output's trailer `/ID` is made (a first input's `/ID` kept by
+/// `MetadataMode::First` takes precedence).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TrailerId {
+    /// No `/ID`
+    #[default]
+    None,
+    /// From the clock, process and output path; differs on every run
+    Random,
+    /// From the inputs' content, so identical inputs give the same ID
+    Deterministic,
+}
+
+/// A trailer `/ID` array derived from `seed`: two equal 16-byte strings, as for a new file.
+pub(crate) fn trailer_id(seed: &[u8]) -> Object {
+    let digest = Sha256::digest(seed);
+    let part = Object::String(digest[..16].to_vec(), StringFormat::Hexadecimal);
+    Object::Array(vec![part.clone(), part])
+}
+
+/// Seed for a `TrailerId::Random` ID.
+pub(crate) fn random_id_seed(output: &Path) -> Vec<u8> {
+    let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+    format!("{}:{}:{}", nanos, std::process::id(), output.display()).into_bytes()
+}
+
+/// The trailer `/ID` of `doc`, if it is a two-element array (indirect parts resolved).
+pub(crate) fn read_trailer_id(doc: &Document) -> Option<Object> {
+    let id = match doc.trailer.get(b"ID").ok()? {
+        Object::Reference(r) => doc.get_object(*r).ok()?,
+        other => other,
+    };
+    let parts: Vec<Object> = id.as_array().ok()?.iter()
+        .map(|p| match p { Object::Reference(r) => doc.get_object(*r).ok().cloned(), o => Some(o.clone()) })
+        .collect::<Option<_>>()?;
+    (parts.len() == 2 && parts.iter().all(|p| p.as_str().is_ok())).then_some(Object::Array(parts))
+}
+
+/// What happens to XMP metadata (`/Metadata` streams).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum XmpMode {
+    /// The catalog stream is not carried over; XMP attached to pages or images stays
+    #[default]
+    Drop,
+    /// Put the first input's catalog stream on the output catalog (it may no longer describe the output)
+    Copy,
+    /// Remove every `/Metadata` stream, catalog, page and image alike
+    Strip,
+}
+
+/// A copy of the catalog `/Metadata` stream of `doc`.
+pub(crate) fn read_xmp(doc: &Document) -> Option<Stream> {
+    let id = doc.catalog().ok()?.get(b"Metadata").ok()?.as_reference().ok()?;
+    doc.get_object(id).ok()?.as_stream().ok().cloned()
+}
+
+/// Add `xmp` to `doc` as its catalog `/Metadata`. The stream is stored as found: dictionary,
+/// filters and data unchanged, and never recompressed by `Document::compress`.
+pub(crate) fn set_catalog_xmp(doc: &mut Document, mut xmp: Stream) -> lopdf::Result<()> {
+    xmp.allows_compression = false;
+    let id = doc.add_object(xmp);
+    doc.catalog_mut()?.set("Metadata", id);
+    Ok(())
+}
+
+/// Catalog entries deciding how a viewer opens the document: `/PageMode`, `/PageLayout`, and an
+/// `/OpenAction` that only goes to a page (a destination array, or a `GoTo` action holding one).
+/// Script actions and named destinations are never taken.
+#[derive(Debug, Default)]
+pub(crate) struct Navigation {
+    page_mode: Option<Object>,
+    page_layout: Option<Object>,
+    open_dest: Option<Vec<Object>>,
+}
+
+/// The `Navigation` entries of the catalog of `doc`.
+pub(crate) fn read_navigation(doc: &Document) -> Navigation {
+    let Ok(catalog) = doc.catalog() else { return Navigation::default() };
+    let name = |key: &[u8]| catalog.get(key).ok().filter(|o| o.as_name().is_ok()).cloned();
+    let resolve = |o: &Object| match o {
+        Object::Reference(id) => doc.get_object(*id).ok().cloned(),
+        other => Some(other.clone()),
+    };
+    let open_dest = catalog.get(b"OpenAction").ok().and_then(resolve).and_then(|action| match action {
+        Object::Array(dest) => Some(dest),
+        Object::Dictionary(d) if d.get(b"S").and_then(Object::as_name_str).ok() == Some("GoTo") => {
+            match d.get(b"D").ok().and_then(resolve)? {
+                Object::Array(dest) => Some(dest),
+                _ => None,
+            }
+        }
+        _ => None,
+    });
+    Navigation { page_mode: name(b"PageMode"), page_layout: name(b"PageLayout"), open_dest }
+}
+
+impl Navigation {
+    /// Set these entries on the catalog of `doc`; the open action only when its page is one of
+    /// `pages` (it was not dropped by a page selection).
+    pub(crate) fn apply(self, doc: &mut Document, pages: &[ObjectId]) -> lopdf::Result<()> {
+        let catalog = doc.catalog_mut()?;
+        if let Some(mode) = self.page_mode { catalog.set("PageMode", mode); }
+        if let Some(layout) = self.page_layout { catalog.set("PageLayout", layout); }
+        if let Some(dest) = self.open_dest {
+            if matches!(dest.first(), Some(Object::Reference(id)) if pages.contains(id)) {
+                catalog.set("OpenAction", dest);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The catalog `/ViewerPreferences` of `doc` (reading `/Direction`, window and print settings).
+/// Entries holding references are left out; they would dangle in another document.
+pub(crate) fn read_viewer_preferences(doc: &Document) -> Option<Dictionary> {
+    let prefs = match doc.catalog().ok()?.get(b"ViewerPreferences").ok()? {
+        Object::Reference(id) => doc.get_dictionary(*id).ok()?,
+        Object::Dictionary(d) => d,
+        _ => return None,
+    };
+    let mut direct = Dictionary::new();
+    for (key, value) in prefs.iter() {
+        let nested_ref = matches!(value, Object::Array(a) if a.iter().any(|o| matches!(o, Object::Reference(_))));
+        if !matches!(value, Object::Reference(_)) && !nested_ref {
+            direct.set(key.clone(), value.clone());
+        }
+    }
+    Some(direct)
+}
+
+/// Remove every `/Metadata` entry of `doc` and the streams they point to. Returns how many
+/// streams were dropped.
+pub(crate) fn strip_xmp(doc: &mut Document) -> usize {
+    let mut streams: Vec<ObjectId> = Vec::new();
+    for obj in doc.objects.values_mut() {
+        let dict = match obj {
+            Object::Dictionary(d) => d,
+            Object::Stream(s) => &mut s.dict,
+            _ => continue,
+        };
+        if let Some(Object::Reference(id)) = dict.remove(b"Metadata") {
+            streams.push(id);
+        }
+    }
+    streams.sort();
+    streams.dedup();
+    streams.into_iter().filter(|id| doc.objects.remove(id).is_some()).count()
+}
+
+/// Document title: Info `/Title`, falling back to the first bookmark.
+pub(crate) fn read_title(doc: &Document) -> Option<String> {
+    info_string(doc, b"Title")
+        .filter(|t| !t.trim().is_empty())
+        .or_else(|| outline::read_outline(doc).into_iter().next().map(|it| it.title))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_xmp_drops_keys_and_streams() {
+        let mut doc = Document::with_version("1.5");
+        let xmp = doc.add_object(Stream::new(Dictionary::new(), b"<x/>".to_vec()));
+        let mut page = Dictionary::new();
+        page.set("Metadata", xmp);
+        let page_id = doc.add_object(page);
+        let mut image = Stream::new(Dictionary::new(), Vec::new());
+        image.dict.set("Metadata", xmp);
+        let image_id = doc.add_object(image);
+
+        assert_eq!(strip_xmp(&mut doc), 1);
+        assert!(doc.get_object(xmp).is_err());
+        assert!(!doc.get_dictionary(page_id).unwrap().has(b"Metadata"));
+        assert!(!doc.get_object(image_id).unwrap().as_stream().unwrap().dict.has(b"Metadata"));
+    }
+
+    #[test]
+    fn decode_utf16_and_plain() {
+        assert_eq!(decode_text(b"Chapter 2"), "Chapter 2");
+        assert_eq!(decode_text(&[0xfe, 0xff, 0x00, 0x41, 0x00, 0x42]), "AB");
+        assert_eq!(decode_text(&[0xff, 0xfe, 0x41, 0x00]), "A");
+    }
+
+    fn info(pairs: &[(&str, &str)]) -> Option<Dictionary> {
+        let mut d = Dictionary::new();
+        for (k, v) in pairs { d.set(*k, encode_text(v)); }
+        Some(d)
+    }
+
+    #[test]
+    fn merged_info_unions_and_picks_earliest() {
+        let infos = [
+            info(&[("Title", "Report"), ("Author", "Ann"), ("Keywords", "alpha, beta"), ("CreationDate", "D:20230501120000Z")]),
+            None,
+            info(&[("Author", "Bo"), ("Keywords", "beta; gamma"), ("CreationDate", "D:2021")]),
+            info(&[("Author", "Ann"), ("CreationDate", "D:20220101")]),
+        ];
+        let out = combine_info(&infos, MetadataMode::Merged).unwrap();
+        let get = |k: &[u8]| text_entry(&out, k).unwrap();
+        assert_eq!(get(b"Title"), "Report");
+        assert_eq!(get(b"Keywords"), "alpha, beta, gamma");
+        assert_eq!(get(b"Author"), "Ann; Bo");
+        assert_eq!(get(b"CreationDate"), "D:2021");
+
+        assert!(combine_info(&infos, MetadataMode::None).is_none());
+        assert_eq!(text_entry(&combine_info(&infos, MetadataMode::First).unwrap(), b"Author").unwrap(), "Ann");
+    }
+
+    #[test]
+    fn encode_roundtrips_non_ascii() {
+        let Object::String(bytes, _) = encode_text("報告") else { panic!("expected string") };
+        assert_eq!(decode_text(&bytes), "報告");
+    }
+}
@@ -0,0 +1,306 @@
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use std::collections::{BTreeMap, HashSet};
+use std::ops::ControlFlow;
+
+use crate::meta;
+use crate::tree::{self, deref_dict};
+
+/// One bookmark in document order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct OutlineItem {
+    pub title: String,
+    /// 1 = top level
+    pub level: usize,
+    /// 1-based target page, if the destination resolves
+    pub page: Option<usize>,
+}
+
+// Depth limit for the outline tree; cycles are cut by the visited set
+const MAX_DEPTH: usize = 64;
+
+/// Flatten the catalog `/Outlines` tree in reading order (pre-order, siblings via `/Next`).
+pub(crate) fn read_outline(doc: &Document) -> Vec<OutlineItem> {
+    let mut out = Vec::new();
+    let Ok(catalog) = doc.catalog() else { return out };
+    let Some(root) = deref_dict(doc, catalog.get(b"Outlines").ok()) else { return out };
+    let page_numbers: BTreeMap<ObjectId, usize> = doc.get_pages().into_iter().map(|(n, id)| (id, n as usize)).collect();
+    let mut seen = HashSet::new();
+    walk(doc, root.get(b"First").ok(), 1, &page_numbers, &mut seen, &mut out);
+    out
+}
+
+fn walk(
+    doc: &Document,
+    first: Option<&Object>,
+    level: usize,
+    pages: &BTreeMap<ObjectId, usize>,
+    seen: &mut HashSet<ObjectId>,
+    out: &mut Vec<OutlineItem>,
+) {
+    if level > MAX_DEPTH { return; }
+    let mut cur = first.and_then(|o| o.as_reference().ok());
+    while let Some(id) = cur {
+        if !seen.insert(id) { break; }
+        let Ok(item) = doc.get_dictionary(id) else { break };
+        let title = match item.get(b"Title") {
+            Ok(Object::String(bytes, _)) => meta::decode_text(bytes),
+            _ => String::new(),
+        };
+        let page = item_dest(doc, item).and_then(|pid| pages.get(&pid).copied());
+        out.push(OutlineItem { title, level, page });
+        walk(doc, item.get(b"First").ok(), level + 1, pages, seen, out);
+        cur = item.get(b"Next").ok().and_then(|o| o.as_reference().ok());
+    }
+}
+
+// Target page object of an outline item: `/Dest` or a `/GoTo` action's `/D`
+fn item_dest(doc: &Document, item: &Dictionary) -> Option<ObjectId> {
+    if let Ok(dest) = item.get(b"Dest") {
+        return resolve_dest(doc, dest, 0);
+    }
+    let action = deref_dict(doc, item.get(b"A").ok())?;
+    match action.get(b"S") {
+        Ok(Object::Name(s)) if s == b"GoTo" => resolve_dest(doc, action.get(b"D").ok()?, 0),
+        _ => None,
+    }
+}
+
+fn resolve_dest(doc: &Document, dest: &Object, depth: usize) -> Option<ObjectId> {
+    if depth > 8 { return None; }
+    match dest {
+        Object::Array(arr) => arr.first().and_then(|o| o.as_reference().ok()),
+        Object::Reference(id) => resolve_dest(doc, doc.get_object(*id).ok()?, depth + 1),
+        // `<< /D [...] >>` form used by named destinations
+        Object::Dictionary(d) => resolve_dest(doc, d.get(b"D").ok()?, depth + 1),
+        Object::Name(name) => {
+            let dests = deref_dict(doc, doc.catalog().ok()?.get(b"Dests").ok())?;
+            resolve_dest(doc, dests.get(name).ok()?, depth + 1)
+        }
+        Object::String(name, _) => {
+            let names = deref_dict(doc, doc.catalog().ok()?.get(b"Names").ok())?;
+            let names = deref_dict(doc, names.get(b"Dests").ok())?;
+            let found = tree::walk_leaves(doc, names, b"Names", &mut |pairs| {
+                for pair in pairs.chunks(2) {
+                    if let [Object::String(k, _), v] = pair {
+                        if k == name { return ControlFlow::Break(v); }
+                    }
+                }
+                ControlFlow::Continue(())
+            });
+            match found {
+                ControlFlow::Break(v) => resolve_dest(doc, v, depth + 1),
+                ControlFlow::Continue(()) => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Pages (1-based, inclusive) of the bookmark titled `name` (case-insensitive, any level): from
+/// its page up to the page before the next bookmark at the same or a higher level, or the end.
+/// Missing or ambiguous names fail with the list of available sections.
+pub(crate) fn section_range(items: &[OutlineItem], total_pages: usize, name: &str) -> anyhow::Result<(usize, usize)> {
+    let wanted = name.trim().to_lowercase();
+    let usable = |it: &&OutlineItem| it.page.is_some_and(|p| (1..=total_pages).contains(&p));
+    let matches: Vec<usize> = (0..items.len())
+        .filter(|&i| usable(&&items[i]) && items[i].title.trim().to_lowercase() == wanted)
+        .collect();
+    let available = || {
+        let titles: Vec<String> = items.iter().filter(usable).map(|it| format!("{:?}", it.title.trim())).collect();
+        if titles.is_empty() { "（无书签）".to_string() } else { titles.join(", ") }
+    };
+    let i = match matches.as_slice() {
+        [i] => *i,
+        [] => anyhow::bail!("未找到章节 {:?}，可用章节: {}", name, available()),
+        _ => anyhow::bail!("章节 {:?} 不唯一（{} 个书签同名），可用章节: {}", name, matches.len(), available()),
+    };
+    let (level, start) = (items[i].level, items[i].page.unwrap_or(1));
+    let end = items[i + 1..].iter()
+        .filter(|it| it.level <= level)
+        .filter_map(|it| it.page.filter(|&p| p > start && p <= total_pages))
+        .next()
+        .map_or(total_pages, |p| p - 1);
+    Ok((start, end))
+}
+
+/// An outline item to write, in reading order; `level` 1 = top.
+#[derive(Debug, Clone)]
+pub(crate) struct OutlineEntry {
+    pub title: String,
+    pub level: usize,
+    pub page: ObjectId,
+}
+
+/// Write `entries` as an `/Outlines` tree (all items open) and return the root, or `None` if empty.
+/// An entry nests under the nearest preceding entry with a lower level.
+pub(crate) fn write_outline(doc: &mut Document, entries: &[OutlineEntry]) -> Option<ObjectId> {
+    if entries.is_empty() { return None; }
+    let root_id = doc.new_object_id();
+    let ids: Vec<ObjectId> = entries.iter().map(|_| doc.new_object_id()).collect();
+
+    // children[0] = top level, children[i + 1] = children of entry i
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); entries.len() + 1];
+    let mut stack: Vec<usize> = Vec::new();
+    for (i, e) in entries.iter().enumerate() {
+        while stack.last().is_some_and(|&top| entries[top].level >= e.level) { stack.pop(); }
+        children[stack.last().map_or(0, |&p| p + 1)].push(i);
+        stack.push(i);
+    }
+
+    let mut dicts: Vec<Dictionary> = entries.iter().map(|e| {
+        let mut d = Dictionary::new();
+        d.set("Title", meta::encode_text(&e.title));
+        d.set("Dest", vec![Object::Reference(e.page), "Fit".into()]);
+        d
+    }).collect();
+    let mut root = Dictionary::new();
+    root.set("Type", "Outlines");
+
+    // Descendant counts, filled bottom-up (children always follow their parent)
+    let mut descendants = vec![0i64; entries.len()];
+    for i in (0..entries.len()).rev() {
+        descendants[i] = children[i + 1].iter().map(|&c| 1 + descendants[c]).sum();
+    }
+    for (slot, kids) in children.iter().enumerate() {
+        let (Some(&first), Some(&last)) = (kids.first(), kids.last()) else { continue };
+        let parent_id = if slot == 0 { root_id } else { ids[slot - 1] };
+        for (k, &c) in kids.iter().enumerate() {
+            dicts[c].set("Parent", Object::Reference(parent_id));
+            if k > 0 { dicts[c].set("Prev", Object::Reference(ids[kids[k - 1]])); }
+            if let Some(&next) = kids.get(k + 1) { dicts[c].set("Next", Object::Reference(ids[next])); }
+        }
+        let parent = if slot == 0 { &mut root } else { &mut dicts[slot - 1] };
+        parent.set("First", Object::Reference(ids[first]));
+        parent.set("Last", Object::Reference(ids[last]));
+        if slot > 0 { parent.set("Count", descendants[slot - 1]); }
+    }
+    root.set("Count", entries.len() as i64);
+
+    for (id, d) in ids.into_iter().zip(dicts) {
+        doc.objects.insert(id, Object::Dictionary(d));
+    }
+    doc.objects.insert(root_id, Object::Dictionary(root));
+    Some(root_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::Bookmark;
+
+    #[test]
+    fn section_range_ends_before_next_sibling_or_parent() {
+        let item = |title: &str, level, page| OutlineItem { title: title.into(), level, page: Some(page) };
+        let items = [item("Intro", 1, 1), item("Results", 1, 3), item("Tables", 2, 4), item("Discussion", 1, 5), item("Notes", 2, 6), item("Tables", 2, 7)];
+        assert_eq!(section_range(&items, 8, "results").unwrap(), (3, 4));
+        assert_eq!(section_range(&items, 8, "Notes").unwrap(), (6, 6));
+        assert_eq!(section_range(&items, 8, "Discussion").unwrap(), (5, 8));
+        let err = section_range(&items, 8, "Tables").unwrap_err().to_string();
+        assert!(err.contains("不唯一") && err.contains("\"Intro\""), "{err}");
+        let err = section_range(&items, 8, "Methods").unwrap_err().to_string();
+        assert!(err.contains("未找到章节") && err.contains("\"Discussion\""), "{err}");
+    }
+
+    // Three empty pages; returns the page ids
+    fn three_pages(doc: &mut Document) -> (ObjectId, Vec<ObjectId>) {
+        let mut kids = Vec::new();
+        let pages_id = doc.new_object_id();
+        for _ in 0..3 {
+            let mut page = Dictionary::new();
+            page.set("Type", "Page");
+            page.set("Parent", Object::Reference(pages_id));
+            kids.push(Object::Reference(doc.add_object(Object::Dictionary(page))));
+        }
+        let page_ids: Vec<ObjectId> = kids.iter().map(|k| k.as_reference().unwrap()).collect();
+        let mut pages = Dictionary::new();
+        pages.set("Type", "Pages");
+        pages.set("Count", 3);
+        pages.set("Kids", Object::Array(kids));
+        doc.objects.insert(pages_id, Object::Dictionary(pages));
+        (pages_id, page_ids)
+    }
+
+    fn set_catalog(doc: &mut Document, pages_id: ObjectId, outline_id: ObjectId) {
+        let mut catalog = Dictionary::new();
+        catalog.set("Type", "Catalog");
+        catalog.set("Pages", Object::Reference(pages_id));
+        catalog.set("Outlines", Object::Reference(outline_id));
+        let catalog_id = doc.add_object(Object::Dictionary(catalog));
+        doc.trailer.set("Root", Object::Reference(catalog_id));
+    }
+
+    fn summary(doc: &Document) -> Vec<(String, usize, Option<usize>)> {
+        read_outline(doc).into_iter().map(|i| (i.title, i.level, i.page)).collect()
+    }
+
+    #[test]
+    fn nested_bookmarks_in_reading_order() {
+        let mut doc = Document::with_version("1.5");
+        let (pages_id, page_ids) = three_pages(&mut doc);
+        let ch1 = doc.add_bookmark(Bookmark::new("Chapter 1".into(), [0.0; 3], 0, page_ids[0]), None);
+        doc.add_bookmark(Bookmark::new("Section 1.1".into(), [0.0; 3], 0, page_ids[1]), Some(ch1));
+        doc.add_bookmark(Bookmark::new("Chapter 2".into(), [0.0; 3], 0, page_ids[2]), None);
+        let outline_id = doc.build_outline().unwrap();
+        set_catalog(&mut doc, pages_id, outline_id);
+
+        assert_eq!(summary(&doc), vec![
+            ("Chapter 1".into(), 1, Some(1)),
+            ("Section 1.1".into(), 2, Some(2)),
+            ("Chapter 2".into(), 1, Some(3)),
+        ]);
+    }
+
+    #[test]
+    fn written_outline_reads_back() {
+        let mut doc = Document::with_version("1.5");
+        let (pages_id, page_ids) = three_pages(&mut doc);
+        let entry = |title: &str, level, page| OutlineEntry { title: title.into(), level, page };
+        let entries = [
+            entry("第一章", 1, page_ids[0]),
+            entry("1.1", 2, page_ids[0]),
+            entry("1.1.1", 3, page_ids[1]),
+            entry("1.2", 2, page_ids[1]),
+            entry("Appendix", 1, page_ids[2]),
+        ];
+        let outline_id = write_outline(&mut doc, &entries).unwrap();
+        set_catalog(&mut doc, pages_id, outline_id);
+
+        let got = summary(&doc);
+        let want: Vec<(String, usize, Option<usize>)> = vec![
+            ("第一章".into(), 1, Some(1)),
+            ("1.1".into(), 2, Some(1)),
+            ("1.1.1".into(), 3, Some(2)),
+            ("1.2".into(), 2, Some(2)),
+            ("Appendix".into(), 1, Some(3)),
+        ];
+        assert_eq!(got, want);
+        let first = doc.get_dictionary(doc.get_dictionary(outline_id).unwrap().get(b"First").unwrap().as_reference().unwrap()).unwrap();
+        assert_eq!(first.get(b"Count").unwrap().as_i64().unwrap(), 3);
+        assert!(write_outline(&mut doc, &[]).is_none());
+    }
+
+    #[test]
+    fn cyclic_name_tree_is_searched_once() {
+        let mut doc = Document::with_version("1.5");
+        let (pages_id, _) = three_pages(&mut doc);
+        // A named destination whose name tree node lists itself as both kids, and no match
+        let node_id = doc.new_object_id();
+        let mut node = Dictionary::new();
+        node.set("Kids", vec![Object::Reference(node_id), Object::Reference(node_id)]);
+        doc.objects.insert(node_id, Object::Dictionary(node));
+        let mut item = Dictionary::new();
+        item.set("Title", Object::string_literal("Loop"));
+        item.set("Dest", Object::string_literal("missing"));
+        let item_id = doc.add_object(Object::Dictionary(item));
+        let mut outline = Dictionary::new();
+        outline.set("First", Object::Reference(item_id));
+        outline.set("Last", Object::Reference(item_id));
+        let outline_id = doc.add_object(Object::Dictionary(outline));
+        set_catalog(&mut doc, pages_id, outline_id);
+        let mut dests = Dictionary::new();
+        dests.set("Dests", Object::Reference(node_id));
+        doc.catalog_mut().unwrap().set("Names", dests);
+
+        assert_eq!(summary(&doc), vec![("Loop".into(), 1, None)]);
+    }
+}
@@ -0,0 +1,343 @@
+use anyhow::{Context, Result};
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream};
+
+const BOXES: [&[u8]; 5] = [b"MediaBox", b"CropBox", b"BleedBox", b"TrimBox", b"ArtBox"];
+
+const INHERITABLE: [&[u8]; 4] = [b"Resources", b"MediaBox", b"CropBox", b"Rotate"];
+
+// Copy inheritable attributes missing on the page from its nearest ancestor
+pub(crate) fn inherit_page_attributes(doc: &Document, page: &mut Dictionary) {
+    inherit_keys(doc, page, &INHERITABLE);
+}
+
+/// Give the page its own copy of every page box (MediaBox, CropBox, BleedBox, TrimBox, ArtBox)
+/// found on it or an ancestor, so the boxes survive moving the page to another page tree.
+/// Bleed/Trim/ArtBox are not inheritable per the spec, but some producers put them on `/Pages`.
+pub(crate) fn pin_page_boxes(doc: &Document, page: &mut Dictionary) {
+    inherit_keys(doc, page, &BOXES);
+    for key in BOXES {
+        if let Some(Object::Reference(id)) = page.get(key).ok().cloned() {
+            if let Ok(arr) = doc.get_object(id).and_then(Object::as_array) { page.set(key, arr.clone()); }
+        }
+    }
+}
+
+fn inherit_keys(doc: &Document, page: &mut Dictionary, keys: &[&[u8]]) {
+    let mut parent = page.get(b"Parent").and_then(Object::as_reference).ok();
+    let mut guard = 0;
+    while let Some(pid) = parent {
+        guard += 1;
+        let Ok(node) = doc.get_dictionary(pid) else { break };
+        if guard > 64 { break; }
+        for &key in keys {
+            if !page.has(key) {
+                if let Ok(v) = node.get(key) { page.set(key, v.clone()); }
+            }
+        }
+        parent = node.get(b"Parent").and_then(Object::as_reference).ok();
+    }
+}
+
+/// `/MediaBox` as `[llx, lly, urx, ury]`, resolving inheritance; `None` if missing or malformed.
+pub(crate) fn media_box(doc: &Document, page_id: ObjectId) -> Option<[f32; 4]> {
+    let mut page = doc.get_dictionary(page_id).ok()?.clone();
+    inherit_page_attributes(doc, &mut page);
+    box_of(doc, &page, b"MediaBox")
+}
+
+pub(crate) fn box_of(doc: &Document, page: &Dictionary, key: &[u8]) -> Option<[f32; 4]> {
+    let arr = match page.get(key).ok()? {
+        Object::Array(a) => a,
+        Object::Reference(id) => doc.get_object(*id).ok()?.as_array().ok()?,
+        _ => return None,
+    };
+    let nums: Vec<f32> = arr.iter().filter_map(|o| o.as_float().ok()).collect();
+    match nums[..] {
+        [a, b, c, d] => Some([a.min(c), b.min(d), a.max(c), b.max(d)]),
+        _ => None,
+    }
+}
+
+/// Scale a page by `factor` without rasterizing: the content is wrapped in `q s 0 0 s 0 0 cm … Q`,
+/// and the page boxes and annotation rectangles are scaled to match.
+pub(crate) fn scale_page(doc: &mut Document, page_id: ObjectId, factor: f32) -> Result<()> {
+    let mut page = doc.get_dictionary(page_id).context("读取页面失败")?.clone();
+    inherit_page_attributes(doc, &mut page);
+    for key in BOXES {
+        if let Some(b) = box_of(doc, &page, key) {
+            page.set(key, b.iter().map(|v| Object::Real(v * factor)).collect::<Vec<_>>());
+        }
+    }
+
+    wrap_contents(doc, &mut page, &format!("{f} 0 0 {f} 0 0", f = factor));
+    for_each_annot(doc, &mut page, |d| {
+        if let Ok(Object::Array(r)) = d.get(b"Rect") {
+            let scaled: Vec<Object> = r.iter().filter_map(|o| o.as_float().ok()).map(|v| Object::Real(v * factor)).collect();
+            d.set("Rect", scaled);
+        }
+    });
+
+    doc.objects.insert(page_id, Object::Dictionary(page));
+    Ok(())
+}
+
+/// Give the page a `width` x `height` MediaBox (swapped to the page's own orientation), fitting
+/// its visible area (CropBox, else MediaBox) in the middle. `scale` resizes the content to fill
+/// as much of it as the aspect ratio allows (`q s 0 0 s tx ty cm … Q`, boxes and annotation
+/// rectangles mapped to match); otherwise the content keeps its size and only the MediaBox
+/// moves around it, so a larger page is cropped. The CropBox is dropped either way.
+pub(crate) fn fit_to_size(doc: &mut Document, page_id: ObjectId, width: f32, height: f32, scale: bool) -> Result<()> {
+    let mut page = doc.get_dictionary(page_id).context("读取页面失败")?.clone();
+    inherit_page_attributes(doc, &mut page);
+    let [x0, y0, x1, y1] = box_of(doc, &page, b"CropBox").or_else(|| box_of(doc, &page, b"MediaBox")).context("页面缺少 MediaBox")?;
+    let (w, h) = (x1 - x0, y1 - y0);
+    if w <= 0.0 || h <= 0.0 { anyhow::bail!("页面尺寸无效: {} x {}", w, h); }
+    let (tw, th) = if (w > h) == (width > height) { (width, height) } else { (height, width) };
+    if scale {
+        let s = (tw / w).min(th / h);
+        let (e, f) = ((tw - w * s) / 2.0 - x0 * s, (th - h * s) / 2.0 - y0 * s);
+        let map = |r: [f32; 4]| [r[0] * s + e, r[1] * s + f, r[2] * s + e, r[3] * s + f];
+        for key in [&b"BleedBox"[..], b"TrimBox", b"ArtBox"] {
+            if let Some(r) = box_of(doc, &page, key) {
+                page.set(key, map(r).iter().map(|&v| Object::Real(v)).collect::<Vec<_>>());
+            }
+        }
+        wrap_contents(doc, &mut page, &format!("{s} 0 0 {s} {e} {f}"));
+        for_each_annot(doc, &mut page, |d| {
+            let Ok(Object::Array(r)) = d.get(b"Rect") else { return };
+            let nums: Vec<f32> = r.iter().filter_map(|o| o.as_float().ok()).collect();
+            if let [p, q, u, v] = nums[..] {
+                d.set("Rect", map([p, q, u, v]).iter().map(|&v| Object::Real(v)).collect::<Vec<_>>());
+            }
+        });
+        page.set("MediaBox", [0.0, 0.0, tw, th].iter().map(|&v| Object::Real(v)).collect::<Vec<_>>());
+    } else {
+        let (dx, dy) = ((tw - w) / 2.0, (th - h) / 2.0);
+        page.set("MediaBox", [x0 - dx, y0 - dy, x1 + dx, y1 + dy].iter().map(|&v| Object::Real(v)).collect::<Vec<_>>());
+    }
+    page.remove(b"CropBox");
+    doc.objects.insert(page_id, Object::Dictionary(page));
+    Ok(())
+}
+
+/// Turn the page a quarter (adding 90 to `/Rotate`) when its MediaBox, as shown with its current
+/// `/Rotate`, is not the wanted orientation. Square pages are left alone. Returns whether the
+/// page was turned.
+pub(crate) fn rotate_to_orientation(doc: &mut Document, page_id: ObjectId, landscape: bool) -> Result<bool> {
+    let mut page = doc.get_dictionary(page_id).context("读取页面失败")?.clone();
+    inherit_page_attributes(doc, &mut page);
+    let rotate = page.get(b"Rotate").and_then(Object::as_i64).unwrap_or(0).rem_euclid(360);
+    let [x0, y0, x1, y1] = box_of(doc, &page, b"MediaBox").context("页面缺少 MediaBox")?;
+    let (mut w, mut h) = (x1 - x0, y1 - y0);
+    if rotate % 180 == 90 { std::mem::swap(&mut w, &mut h); }
+    if w == h || (w > h) == landscape { return Ok(false); }
+    page.set("Rotate", (rotate + 90) % 360);
+    doc.objects.insert(page_id, Object::Dictionary(page));
+    Ok(true)
+}
+
+/// Apply a page's `/Rotate` to its content and clear it: the content is wrapped in a `cm`
+/// turning it upright, the boxes and annotation rectangles are mapped the same way (so a
+/// quarter turn swaps the MediaBox width and height). Returns whether the page was rotated.
+pub(crate) fn bake_rotation(doc: &mut Document, page_id: ObjectId) -> Result<bool> {
+    let mut page = doc.get_dictionary(page_id).context("读取页面失败")?.clone();
+    inherit_page_attributes(doc, &mut page);
+    let rotate = page.get(b"Rotate").and_then(Object::as_i64).unwrap_or(0).rem_euclid(360);
+    if rotate % 90 != 0 { anyhow::bail!("无效的 /Rotate: {}", rotate); }
+    if rotate == 0 {
+        page.remove(b"Rotate");
+        doc.objects.insert(page_id, Object::Dictionary(page));
+        return Ok(false);
+    }
+    let [x0, y0, x1, y1] = box_of(doc, &page, b"MediaBox").context("页面缺少 MediaBox")?;
+    // Maps a point of the unrotated page to where the viewer would show it, origin at 0,0
+    let (a, b, c, d, e, f) = match rotate {
+        90 => (0.0, -1.0, 1.0, 0.0, 0.0 - y0, x1),
+        180 => (-1.0, 0.0, 0.0, -1.0, x1, y1),
+        _ => (0.0, 1.0, -1.0, 0.0, y1, 0.0 - x0),
+    };
+    let map = |r: [f32; 4]| {
+        let (px, py) = (a * r[0] + c * r[1] + e, b * r[0] + d * r[1] + f);
+        let (qx, qy) = (a * r[2] + c * r[3] + e, b * r[2] + d * r[3] + f);
+        [px.min(qx), py.min(qy), px.max(qx), py.max(qy)]
+    };
+    for key in BOXES {
+        if let Some(r) = box_of(doc, &page, key) {
+            page.set(key, map(r).iter().map(|&v| Object::Real(v)).collect::<Vec<_>>());
+        }
+    }
+    wrap_contents(doc, &mut page, &format!("{a} {b} {c} {d} {e} {f}"));
+    for_each_annot(doc, &mut page, |dict| {
+        let Ok(Object::Array(r)) = dict.get(b"Rect") else { return };
+        let nums: Vec<f32> = r.iter().filter_map(|o| o.as_float().ok()).collect();
+        if let [p, q, s, t] = nums[..] {
+            dict.set("Rect", map([p, q, s, t]).iter().map(|&v| Object::Real(v)).collect::<Vec<_>>());
+        }
+    });
+    page.remove(b"Rotate");
+
+    doc.objects.insert(page_id, Object::Dictionary(page));
+    Ok(true)
+}
+
+// Surround the page content with `q <matrix> cm … Q`
+fn wrap_contents(doc: &mut Document, page: &mut Dictionary, matrix: &str) {
+    let push_id = doc.add_object(Stream::new(Dictionary::new(), format!("q {} cm\n", matrix).into_bytes()));
+    let pop_id = doc.add_object(Stream::new(Dictionary::new(), b"\nQ\n".to_vec()));
+    let mut contents = vec![Object::Reference(push_id)];
+    match page.get(b"Contents") {
+        Ok(Object::Array(arr)) => contents.extend(arr.iter().cloned()),
+        Ok(obj @ Object::Reference(_)) => contents.push(obj.clone()),
+        _ => {}
+    }
+    contents.push(Object::Reference(pop_id));
+    page.set("Contents", contents);
+}
+
+fn for_each_annot(doc: &mut Document, page: &mut Dictionary, mut f: impl FnMut(&mut Dictionary)) {
+    let annots: Vec<Object> = match page.get(b"Annots") {
+        Ok(Object::Array(a)) => a.clone(),
+        Ok(Object::Reference(id)) => doc.get_object(*id).and_then(Object::as_array).cloned().unwrap_or_default(),
+        _ => Vec::new(),
+    };
+    for annot in annots {
+        if let Object::Reference(id) = annot {
+            if let Ok(d) = doc.get_dictionary_mut(id) { f(d); }
+        }
+    }
+    if let Ok(Object::Array(arr)) = page.get_mut(b"Annots") {
+        for a in arr.iter_mut() {
+            if let Object::Dictionary(d) = a { f(d); }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scale_halves_boxes_and_wraps_content() {
+        let mut doc = Document::with_version("1.5");
+        let content_id = doc.add_object(Stream::new(Dictionary::new(), b"0 0 m 100 100 l S".to_vec()));
+        let mut page = Dictionary::new();
+        page.set("Type", "Page");
+        page.set("MediaBox", vec![0.into(), 0.into(), 600.into(), 800.into()]);
+        page.set("Contents", Object::Reference(content_id));
+        let mut link = Dictionary::new();
+        link.set("Rect", vec![10.into(), 20.into(), 30.into(), 40.into()]);
+        page.set("Annots", vec![Object::Dictionary(link)]);
+        let page_id = doc.add_object(page);
+
+        scale_page(&mut doc, page_id, 0.5).unwrap();
+        assert_eq!(media_box(&doc, page_id), Some([0.0, 0.0, 300.0, 400.0]));
+        let page = doc.get_dictionary(page_id).unwrap();
+        let contents = page.get(b"Contents").unwrap().as_array().unwrap();
+        assert_eq!(contents.len(), 3);
+        let push = doc.get_object(contents[0].as_reference().unwrap()).unwrap().as_stream().unwrap();
+        assert_eq!(push.content, b"q 0.5 0 0 0.5 0 0 cm\n");
+        let annots = page.get(b"Annots").unwrap().as_array().unwrap();
+        let rect: Vec<f32> = annots[0].as_dict().unwrap().get(b"Rect").unwrap().as_array().unwrap().iter().map(|o| o.as_float().unwrap()).collect();
+        assert_eq!(rect, vec![5.0, 10.0, 15.0, 20.0]);
+    }
+
+    #[test]
+    fn bake_rotation_maps_corners_upright() {
+        let mut doc = Document::with_version("1.5");
+        let mut page = Dictionary::new();
+        page.set("Type", "Page");
+        page.set("MediaBox", vec![0.into(), 0.into(), 600.into(), 800.into()]);
+        page.set("Rotate", 90);
+        let mut link = Dictionary::new();
+        // Near the top-left corner of the unrotated page, i.e. top-right once turned clockwise
+        link.set("Rect", vec![0.into(), 780.into(), 20.into(), 800.into()]);
+        page.set("Annots", vec![Object::Dictionary(link)]);
+        let page_id = doc.add_object(page);
+
+        assert!(bake_rotation(&mut doc, page_id).unwrap());
+        assert_eq!(media_box(&doc, page_id), Some([0.0, 0.0, 800.0, 600.0]));
+        let page = doc.get_dictionary(page_id).unwrap();
+        assert!(!page.has(b"Rotate"));
+        let push = doc.get_object(page.get(b"Contents").unwrap().as_array().unwrap()[0].as_reference().unwrap()).unwrap().as_stream().unwrap();
+        assert_eq!(push.content, b"q 0 -1 1 0 0 600 cm\n");
+        let annots = page.get(b"Annots").unwrap().as_array().unwrap();
+        let rect: Vec<f32> = annots[0].as_dict().unwrap().get(b"Rect").unwrap().as_array().unwrap().iter().map(|o| o.as_float().unwrap()).collect();
+        assert_eq!(rect, vec![780.0, 580.0, 800.0, 600.0]);
+    }
+
+    // A 100x200 page with a link over its top half, on a 200x400 target
+    fn fitted(scale: bool) -> (Document, ObjectId) {
+        let mut doc = Document::with_version("1.5");
+        let content_id = doc.add_object(Stream::new(Dictionary::new(), b"0 0 m 100 200 l S".to_vec()));
+        let mut page = Dictionary::new();
+        page.set("Type", "Page");
+        page.set("MediaBox", vec![0.into(), 0.into(), 100.into(), 200.into()]);
+        page.set("Contents", Object::Reference(content_id));
+        let mut link = Dictionary::new();
+        link.set("Rect", vec![0.into(), 100.into(), 100.into(), 200.into()]);
+        page.set("Annots", vec![Object::Dictionary(link)]);
+        let page_id = doc.add_object(page);
+        // Given landscape, the target turns to match the portrait page
+        fit_to_size(&mut doc, page_id, 400.0, 200.0, scale).unwrap();
+        (doc, page_id)
+    }
+
+    #[test]
+    fn fit_to_size_centers_or_scales() {
+        let (doc, page_id) = fitted(false);
+        assert_eq!(media_box(&doc, page_id), Some([-50.0, -100.0, 150.0, 300.0]));
+        assert!(doc.get_dictionary(page_id).unwrap().get(b"Contents").unwrap().as_reference().is_ok());
+
+        let (doc, page_id) = fitted(true);
+        assert_eq!(media_box(&doc, page_id), Some([0.0, 0.0, 200.0, 400.0]));
+        let page = doc.get_dictionary(page_id).unwrap();
+        let contents = page.get(b"Contents").unwrap().as_array().unwrap();
+        let push = doc.get_object(contents[0].as_reference().unwrap()).unwrap().as_stream().unwrap();
+        assert_eq!(push.content, b"q 2 0 0 2 0 0 cm\n");
+        let annots = page.get(b"Annots").unwrap().as_array().unwrap();
+        let rect: Vec<f32> = annots[0].as_dict().unwrap().get(b"Rect").unwrap().as_array().unwrap().iter().map(|o| o.as_float().unwrap()).collect();
+        assert_eq!(rect, [0.0, 200.0, 200.0, 400.0]);
+    }
+
+    #[test]
+    fn rotate_to_orientation_counts_existing_rotation() {
+        let mut doc = Document::with_version("1.5");
+        let mut add = |w: i64, h: i64, rotate: i64| {
+            let mut page = Dictionary::new();
+            page.set("Type", "Page");
+            page.set("MediaBox", vec![0.into(), 0.into(), w.into(), h.into()]);
+            page.set("Rotate", rotate);
+            doc.add_object(page)
+        };
+        // Landscape box shown portrait by its /Rotate, a landscape page, and a square one
+        let (turned, wide, square) = (add(800, 600, 270), add(800, 600, 0), add(500, 500, 0));
+        assert!(!rotate_to_orientation(&mut doc, turned, false).unwrap());
+        assert!(rotate_to_orientation(&mut doc, wide, false).unwrap());
+        assert!(!rotate_to_orientation(&mut doc, square, false).unwrap());
+        assert_eq!(doc.get_dictionary(wide).unwrap().get(b"Rotate").unwrap().as_i64().unwrap(), 90);
+        assert!(rotate_to_orientation(&mut doc, turned, true).unwrap());
+        assert_eq!(doc.get_dictionary(turned).unwrap().get(b"Rotate").unwrap().as_i64().unwrap(), 0);
+    }
+
+    #[test]
+    fn pin_page_boxes_copies_boxes_from_ancestors() {
+        let mut doc = Document::with_version("1.5");
+        let trim_id = doc.add_object(vec![10.into(), 10.into(), 190.into(), 290.into()]);
+        let mut pages = Dictionary::new();
+        pages.set("Type", "Pages");
+        pages.set("MediaBox", vec![0.into(), 0.into(), 200.into(), 300.into()]);
+        pages.set("TrimBox", Object::Reference(trim_id));
+        let pages_id = doc.add_object(pages);
+        let mut page = Dictionary::new();
+        page.set("Type", "Page");
+        page.set("Parent", Object::Reference(pages_id));
+        page.set("BleedBox", vec![5.into(), 5.into(), 195.into(), 295.into()]);
+
+        pin_page_boxes(&doc, &mut page);
+        assert_eq!(box_of(&doc, &page, b"MediaBox"), Some([0.0, 0.0, 200.0, 300.0]));
+        assert_eq!(box_of(&doc, &page, b"BleedBox"), Some([5.0, 5.0, 195.0, 295.0]));
+        assert!(matches!(page.get(b"TrimBox"), Ok(Object::Array(_))));
+        assert_eq!(box_of(&doc, &page, b"TrimBox"), Some([10.0, 10.0, 190.0, 290.0]));
+        assert!(!page.has(b"ArtBox"));
+    }
+}
@@ -1,9 +1,16 @@
 use std::env;
+use std::path::Path;
 
+#[cfg_attr(not(feature = "tui"), allow(dead_code))]
 pub fn sanitize_path_input(raw: &str) -> String {
     let mut s = raw.trim().to_string();
-    if (s.starts_with('"') && s.ends_with('"')) || (s.starts_with('\'') && s.ends_with('\'')) {
-        if s.len() >= 2 { s = s[1..s.len()-1].to_string(); }
+    if ((s.starts_with('"') && s.ends_with('"')) || (s.starts_with('\'') && s.ends_with('\''))) && s.len() >= 2 {
+        s = s[1..s.len()-1].to_string();
+    }
+    // UNC (\\server\share) and extended-length (\\?\C:\...) paths are taken verbatim:
+    // no ~ expansion, no escape handling
+    if is_verbatim_prefix(&s) {
+        return s;
     }
     // expand ~ to home
     if s.starts_with('~') {
@@ -25,6 +32,35 @@ pub fn sanitize_path_input(raw: &str) -> String {
     s
 }
 
+fn is_verbatim_prefix(s: &str) -> bool {
+    s.starts_with(r"\\")
+}
+
+/// Set Unix permission bits (e.g. 0o600) on a file we created. A no-op elsewhere; callers warn once.
+pub(crate) fn set_mode(path: &Path, mode: u32) -> anyhow::Result<()> {
+    #[cfg(unix)]
+    {
+        use anyhow::Context;
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+            .with_context(|| format!("设置权限失败: {}", path.display()))?;
+    }
+    #[cfg(not(unix))]
+    let _ = (path, mode);
+    Ok(())
+}
+
+/// Open a file or directory with the desktop's default application. Fails in headless
+/// sessions (SSH without a display), which have nothing to hand off to.
+pub fn open_in_default_app(path: &Path) -> anyhow::Result<()> {
+    #[cfg(all(unix, not(target_os = "macos")))]
+    if env::var_os("DISPLAY").is_none() && env::var_os("WAYLAND_DISPLAY").is_none() {
+        anyhow::bail!("no display (headless session)");
+    }
+    opener::open(path)?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::sanitize_path_input;
@@ -49,6 +85,26 @@ mod tests {
         assert_eq!(sanitize_path_input("~/sub"), expect);
     }
 
+    #[test]
+    fn unc_and_extended_prefixes_untouched() {
+        for p in [r"\\server\share\file.pdf", r"\\?\C:\very\long\path\file.pdf"] {
+            assert_eq!(sanitize_path_input(p), p);
+            assert_eq!(sanitize_path_input(&format!("\"{}\"", p)), p);
+        }
+        // inside quotes a trailing backslash-space is part of the path, not an escape
+        assert_eq!(sanitize_path_input(r#""\\server\share\dir\ ""#), r"\\server\share\dir\ ");
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn windows_unc_and_extended_round_trip() {
+        for p in [r"\\server\share\My Docs\file.pdf", r"\\?\C:\Users\me\file.pdf", r"\\?\UNC\server\share\file.pdf"] {
+            assert_eq!(sanitize_path_input(p), p);
+            assert_eq!(sanitize_path_input(&format!("'{}'", p)), p);
+            assert_eq!(std::path::Path::new(&sanitize_path_input(p)), std::path::Path::new(p));
+        }
+    }
+
     #[test]
     fn windows_backslashes_kept() {
         // ensure we don't mangle generic backslashes that are not escapes
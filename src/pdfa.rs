@@ -0,0 +1,172 @@
+//! PDF/A-oriented cleanup for `merge --pdfa` (feature `pdfa`).
+//!
+//! A pragmatic pass, not a validator: it removes what PDF/A forbids and we can cheaply find
+//! (JavaScript, launch/media/form-reset actions, embedded files, reference XObjects, encryption),
+//! refuses non-embedded fonts, and writes Info plus a matching XMP packet. The output does not
+//! claim conformance (no `pdfaid` entry).
+
+use anyhow::Result;
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{info, meta, sanitize};
+
+// Action types PDF/A does not allow
+const FORBIDDEN_ACTIONS: [&[u8]; 9] = [
+    b"Launch", b"Sound", b"Movie", b"ResetForm", b"ImportData", b"Hide", b"SetOCGState", b"Rendition", b"Trans",
+];
+
+/// Clean `doc` in place. `catalog_id` gets the XMP `/Metadata`; `title` fills a missing `/Title`;
+/// `producer` goes into both Info and XMP (`None` leaves it out of both).
+/// Fails, listing them, when any font used by a page is not embedded.
+pub(crate) fn cleanup(doc: &mut Document, catalog_id: ObjectId, title: &str, producer: Option<&str>) -> Result<()> {
+    let missing: Vec<String> = info::collect_fonts(doc).into_iter().filter(|f| !f.embedded).map(|f| f.name).collect();
+    if !missing.is_empty() {
+        anyhow::bail!("--pdfa 需要嵌入所有字体，未嵌入: {}", missing.join(", "));
+    }
+
+    sanitize::strip_javascript(doc);
+    for obj in doc.objects.values_mut() {
+        match obj {
+            Object::Dictionary(d) => strip_external(d),
+            Object::Stream(s) => strip_external(&mut s.dict),
+            _ => {}
+        }
+    }
+    doc.trailer.remove(b"Encrypt");
+
+    let (pdf_date, xmp_date) = now_dates();
+    let mut info = meta::read_info(doc).unwrap_or_default();
+    if !info.has(b"Title") { info.set("Title", meta::encode_text(title)); }
+    match producer {
+        Some(producer) => info.set("Producer", meta::encode_text(producer)),
+        None => { info.remove(b"Producer"); }
+    }
+    if !info.has(b"CreationDate") { info.set("CreationDate", Object::string_literal(pdf_date.clone())); }
+    info.set("ModDate", Object::string_literal(pdf_date));
+    let doc_title = meta::decode_text(info.get(b"Title").and_then(Object::as_str).unwrap_or(title.as_bytes()));
+    let info_id = doc.add_object(info);
+    doc.trailer.set("Info", Object::Reference(info_id));
+
+    let mut xmp_dict = Dictionary::new();
+    xmp_dict.set("Type", "Metadata");
+    xmp_dict.set("Subtype", "XML");
+    let mut xmp = Stream::new(xmp_dict, xmp_packet(&doc_title, &xmp_date, producer).into_bytes());
+    // Keep the packet readable by tools that scan for it
+    xmp.allows_compression = false;
+    let xmp_id = doc.add_object(xmp);
+    if let Ok(catalog) = doc.get_dictionary_mut(catalog_id) {
+        catalog.set("Metadata", Object::Reference(xmp_id));
+    }
+    Ok(())
+}
+
+fn strip_external(d: &mut Dictionary) {
+    if matches!(d.get(b"A"), Ok(Object::Dictionary(a)) if is_forbidden_action(a)) {
+        d.remove(b"A");
+    }
+    // Names tree of attachments, reference XObjects, OPI proxies
+    d.remove(b"EmbeddedFiles");
+    if matches!(d.get(b"Subtype"), Ok(Object::Name(n)) if n == b"Form") {
+        d.remove(b"Ref");
+    }
+    d.remove(b"OPI");
+    for (_, v) in d.iter_mut() {
+        match v {
+            Object::Dictionary(inner) => strip_external(inner),
+            Object::Array(arr) => {
+                for item in arr.iter_mut() {
+                    if let Object::Dictionary(inner) = item { strip_external(inner); }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn is_forbidden_action(a: &Dictionary) -> bool {
+    matches!(a.get(b"S"), Ok(Object::Name(s)) if FORBIDDEN_ACTIONS.contains(&s.as_slice()))
+}
+
+fn xmp_packet(title: &str, date: &str, producer: Option<&str>) -> String {
+    let escape = |s: &str| s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+    let title = escape(title);
+    let producer = producer.map(|p| format!("\n   <pdf:Producer>{}</pdf:Producer>", escape(p))).unwrap_or_default();
+    format!(
+        r#"<?xpacket begin="" id="W5M0MpCehiHzreSzNTczkc9d"?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about=""
+    xmlns:dc="http://purl.org/dc/elements/1.1/"
+    xmlns:xmp="http://ns.adobe.com/xap/1.0/"
+    xmlns:pdf="http://ns.adobe.com/pdf/1.3/">
+   <dc:title><rdf:Alt><rdf:li xml:lang="x-default">{title}</rdf:li></rdf:Alt></dc:title>
+   <xmp:ModifyDate>{date}</xmp:ModifyDate>
+   <xmp:MetadataDate>{date}</xmp:MetadataDate>{producer}
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>
+<?xpacket end="w"?>"#
+    )
+}
+
+// Current UTC time as a PDF date and an XMP (ISO 8601) date
+fn now_dates() -> (String, String) {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (y, m, d) = civil_from_days((secs / 86_400) as i64);
+    let (hh, mm, ss) = (secs / 3600 % 24, secs / 60 % 60, secs % 60);
+    (
+        format!("D:{:04}{:02}{:02}{:02}{:02}{:02}Z", y, m, d, hh, mm, ss),
+        format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", y, m, d, hh, mm, ss),
+    )
+}
+
+// Days since 1970-01-01 to (year, month, day), proleptic Gregorian
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    (y, m, d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn civil_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+    }
+
+    #[test]
+    fn xmp_producer_follows_info() {
+        let packet = xmp_packet("t", "2024-01-01T00:00:00Z", Some("A&B"));
+        assert!(packet.contains("<pdf:Producer>A&amp;B</pdf:Producer>"), "{packet}");
+        assert!(!xmp_packet("t", "2024-01-01T00:00:00Z", None).contains("Producer"));
+    }
+
+    #[test]
+    fn removes_forbidden_actions_but_keeps_links() {
+        let mut launch = Dictionary::new();
+        launch.set("S", "Launch");
+        let mut uri = Dictionary::new();
+        uri.set("S", "URI");
+        let mut a = Dictionary::new();
+        a.set("A", launch);
+        let mut b = Dictionary::new();
+        b.set("A", uri);
+        let mut annots = Dictionary::new();
+        annots.set("Annots", vec![Object::Dictionary(a), Object::Dictionary(b)]);
+        strip_external(&mut annots);
+        let arr = annots.get(b"Annots").unwrap().as_array().unwrap();
+        assert!(!arr[0].as_dict().unwrap().has(b"A"));
+        assert!(arr[1].as_dict().unwrap().has(b"A"));
+    }
+}
@@ -1,16 +1,74 @@
 use std::borrow::Cow;
+use std::cell::RefCell;
 
 pub trait ProgressSink {
     fn set_len(&self, _len: u64) {}
     fn inc(&self, _n: u64) {}
     fn set_message(&self, _msg: Cow<'static, str>) {}
     fn finish(&self, _msg: Cow<'static, str>) {}
+    /// Sink for the work inside the current unit (e.g. the pages of one batch job). Sinks
+    /// with a single bar report nothing for it.
+    fn child(&self) -> &dyn ProgressSink { &NullProgress }
 }
 
-#[allow(dead_code)]
 pub struct NullProgress;
 impl ProgressSink for NullProgress {}
 
+/// One `ProgressSink` call, as delivered to a `ClosureProgress` callback.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProgressEvent {
+    SetLen(u64),
+    Inc(u64),
+    Message(Cow<'static, str>),
+    Finish(Cow<'static, str>),
+}
+
+/// Adapts an `FnMut(ProgressEvent)` closure into a `ProgressSink`.
+///
+/// ```
+/// use pdf_ops::merge::{self, MergeOptions};
+/// use pdf_ops::progress::{ClosureProgress, ProgressEvent};
+/// # use lopdf::{dictionary, Document, Object};
+/// # let dir = std::env::temp_dir().join(format!("pdf-ops-doctest-{}", std::process::id()));
+/// # std::fs::create_dir_all(&dir).unwrap();
+/// # for name in ["a.pdf", "b.pdf"] {
+/// #     let mut doc = Document::with_version("1.5");
+/// #     let pages_id = doc.new_object_id();
+/// #     let page_id = doc.add_object(dictionary! { "Type" => "Page", "Parent" => pages_id, "MediaBox" => vec![0.into(), 0.into(), 200.into(), 200.into()] });
+/// #     doc.objects.insert(pages_id, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => vec![page_id.into()], "Count" => 1 }));
+/// #     let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+/// #     doc.trailer.set("Root", catalog_id);
+/// #     doc.save(dir.join(name)).unwrap();
+/// # }
+/// let mut events = Vec::new();
+/// let sink = ClosureProgress::new(|e| events.push(e));
+/// let opts = MergeOptions { force: true, ..Default::default() };
+/// merge::run(&dir, &dir.join("out").join("merged.pdf"), &opts, &sink)?;
+/// drop(sink);
+///
+/// assert_eq!(events.first(), Some(&ProgressEvent::SetLen(2)));
+/// assert_eq!(events.iter().filter(|e| matches!(e, ProgressEvent::Inc(_))).count(), 2);
+/// assert!(matches!(events.last(), Some(ProgressEvent::Finish(_))));
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub struct ClosureProgress<F> {
+    f: RefCell<F>,
+}
+
+impl<F: FnMut(ProgressEvent)> ClosureProgress<F> {
+    pub fn new(f: F) -> Self { Self { f: RefCell::new(f) } }
+
+    fn emit(&self, event: ProgressEvent) { (self.f.borrow_mut())(event); }
+}
+
+impl<F: FnMut(ProgressEvent)> ProgressSink for ClosureProgress<F> {
+    fn set_len(&self, len: u64) { self.emit(ProgressEvent::SetLen(len)); }
+    fn inc(&self, n: u64) { self.emit(ProgressEvent::Inc(n)); }
+    fn set_message(&self, msg: Cow<'static, str>) { self.emit(ProgressEvent::Message(msg)); }
+    fn finish(&self, msg: Cow<'static, str>) { self.emit(ProgressEvent::Finish(msg)); }
+}
+
 pub struct IndicatifProgress {
     pb: indicatif::ProgressBar,
 }
@@ -19,7 +77,7 @@ impl IndicatifProgress {
     pub fn new() -> Self {
         let pb = indicatif::ProgressBar::new(0);
         // simple style; caller can customize later if needed
-        let _ = pb.set_style(
+        pb.set_style(
             indicatif::ProgressStyle::with_template("[{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}")
                 .unwrap()
                 .progress_chars("##-"),
@@ -28,9 +86,107 @@ impl IndicatifProgress {
     }
 }
 
+impl IndicatifProgress {
+    /// Finish and remove the bar from the terminal (e.g. one of several in a `MultiBarProgress`)
+    pub fn finish_and_clear(&self) { self.pb.finish_and_clear(); }
+}
+
+impl Default for IndicatifProgress {
+    fn default() -> Self { Self::new() }
+}
+
 impl ProgressSink for IndicatifProgress {
     fn set_len(&self, len: u64) { self.pb.set_length(len); }
     fn inc(&self, n: u64) { self.pb.inc(n); }
     fn set_message(&self, msg: Cow<'static, str>) { self.pb.set_message(msg); }
     fn finish(&self, msg: Cow<'static, str>) { self.pb.finish_with_message(msg); }
 }
+
+/// Stacked bars for batch runs: the sink itself counts jobs, `child()` tracks the current
+/// job and starts over each time a job calls `set_len`. Jobs running side by side each take
+/// their own bar from `add_bar` instead. The child bar only appears once it is used.
+pub struct MultiBarProgress {
+    multi: indicatif::MultiProgress,
+    parent: IndicatifProgress,
+    child: ChildBar,
+}
+
+struct ChildBar {
+    multi: indicatif::MultiProgress,
+    bar: std::sync::OnceLock<IndicatifProgress>,
+}
+
+impl MultiBarProgress {
+    pub fn new() -> Self { Self::with_multi(indicatif::MultiProgress::new()) }
+
+    fn with_multi(multi: indicatif::MultiProgress) -> Self {
+        let parent = IndicatifProgress { pb: multi.add(IndicatifProgress::new().pb) };
+        let child = ChildBar { multi: multi.clone(), bar: std::sync::OnceLock::new() };
+        Self { multi, parent, child }
+    }
+
+    /// Another bar under the parent, for one of several jobs running at once
+    pub fn add_bar(&self) -> IndicatifProgress {
+        IndicatifProgress { pb: self.multi.add(IndicatifProgress::new().pb) }
+    }
+}
+
+impl Default for MultiBarProgress {
+    fn default() -> Self { Self::new() }
+}
+
+impl ProgressSink for MultiBarProgress {
+    fn set_len(&self, len: u64) { self.parent.set_len(len); }
+    fn inc(&self, n: u64) { self.parent.inc(n); }
+    fn set_message(&self, msg: Cow<'static, str>) { self.parent.set_message(msg); }
+    fn finish(&self, msg: Cow<'static, str>) {
+        if let Some(bar) = self.child.bar.get() { bar.finish_and_clear(); }
+        self.parent.finish(msg);
+    }
+    fn child(&self) -> &dyn ProgressSink { &self.child }
+}
+
+impl ChildBar {
+    fn bar(&self) -> &IndicatifProgress {
+        self.bar.get_or_init(|| IndicatifProgress { pb: self.multi.add(IndicatifProgress::new().pb) })
+    }
+}
+
+impl ProgressSink for ChildBar {
+    fn set_len(&self, len: u64) {
+        let bar = self.bar();
+        bar.pb.reset();
+        bar.pb.set_message("");
+        bar.set_len(len);
+    }
+    fn inc(&self, n: u64) { self.bar().inc(n); }
+    fn set_message(&self, msg: Cow<'static, str>) { self.bar().set_message(msg); }
+    // Stays drawn until the next job resets it
+    fn finish(&self, msg: Cow<'static, str>) { self.bar().set_message(msg); }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multi_bar_tracks_jobs_and_pages() {
+        let sink = MultiBarProgress::with_multi(indicatif::MultiProgress::with_draw_target(indicatif::ProgressDrawTarget::hidden()));
+        assert!(sink.child.bar.get().is_none());
+        sink.set_len(2);
+        for (job, pages) in [3u64, 5].into_iter().enumerate() {
+            let child = sink.child();
+            child.set_len(pages);
+            assert_eq!(sink.child.bar().pb.position(), 0);
+            for _ in 0..pages { child.inc(1); }
+            child.finish(Cow::from("done"));
+            assert_eq!(sink.child.bar().pb.position(), pages);
+            assert_eq!(sink.child.bar().pb.length(), Some(pages));
+            sink.inc(1);
+            assert_eq!(sink.parent.pb.position(), job as u64 + 1);
+        }
+        assert_eq!(sink.parent.pb.length(), Some(2));
+        // Single-bar sinks hand out a no-op child
+        NullProgress.child().inc(1);
+    }
+}
@@ -0,0 +1,89 @@
+use lopdf::{Dictionary, Document, Object};
+
+// Entries that run script: additional actions, the open action, JavaScript source and the
+// document-level `/Names` JavaScript tree
+const SCRIPT_KEYS: [&[u8]; 4] = [b"AA", b"OpenAction", b"JS", b"JavaScript"];
+
+/// Remove JavaScript from every object: `/OpenAction`, `/AA`, `/JS`, `/A` entries holding a
+/// JavaScript action, and the `/Names` `/JavaScript` tree. Returns the number of entries removed.
+pub(crate) fn strip_javascript(doc: &mut Document) -> usize {
+    let mut removed = 0;
+    for obj in doc.objects.values_mut() {
+        match obj {
+            Object::Dictionary(d) => removed += strip_dict(d),
+            Object::Stream(s) => removed += strip_dict(&mut s.dict),
+            _ => {}
+        }
+    }
+    removed += strip_dict(&mut doc.trailer);
+    removed
+}
+
+fn strip_dict(d: &mut Dictionary) -> usize {
+    let mut removed = 0;
+    for key in SCRIPT_KEYS {
+        if d.remove(key).is_some() { removed += 1; }
+    }
+    if matches!(d.get(b"A"), Ok(Object::Dictionary(a)) if is_js_action(a)) {
+        d.remove(b"A");
+        removed += 1;
+    }
+    for (_, v) in d.iter_mut() {
+        match v {
+            Object::Dictionary(inner) => removed += strip_dict(inner),
+            Object::Array(arr) => {
+                for item in arr.iter_mut() {
+                    if let Object::Dictionary(inner) = item { removed += strip_dict(inner); }
+                }
+            }
+            _ => {}
+        }
+    }
+    removed
+}
+
+fn is_js_action(a: &Dictionary) -> bool {
+    matches!(a.get(b"S"), Ok(Object::Name(s)) if s == b"JavaScript")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removes_script_entries_everywhere() {
+        let mut doc = Document::with_version("1.5");
+        let mut js = Dictionary::new();
+        js.set("S", "JavaScript");
+        js.set("JS", Object::string_literal("app.alert(1)"));
+        let js_id = doc.add_object(js.clone());
+
+        let mut names = Dictionary::new();
+        names.set("JavaScript", Dictionary::new());
+        let names_id = doc.add_object(names);
+        let mut catalog = Dictionary::new();
+        catalog.set("Type", "Catalog");
+        catalog.set("OpenAction", Object::Reference(js_id));
+        catalog.set("Names", Object::Reference(names_id));
+        let catalog_id = doc.add_object(catalog);
+
+        let mut link = Dictionary::new();
+        link.set("Subtype", "Link");
+        link.set("A", js);
+        let mut page = Dictionary::new();
+        page.set("Type", "Page");
+        page.set("Annots", vec![Object::Dictionary(link)]);
+        page.set("AA", Dictionary::new());
+        let page_id = doc.add_object(page);
+
+        assert!(strip_javascript(&mut doc) >= 5);
+        let catalog = doc.get_dictionary(catalog_id).unwrap();
+        assert!(!catalog.has(b"OpenAction"));
+        assert!(!doc.get_dictionary(names_id).unwrap().has(b"JavaScript"));
+        assert!(!doc.get_dictionary(js_id).unwrap().has(b"JS"));
+        let page = doc.get_dictionary(page_id).unwrap();
+        assert!(!page.has(b"AA"));
+        let annots = page.get(b"Annots").unwrap().as_array().unwrap();
+        assert!(!annots[0].as_dict().unwrap().has(b"A"));
+    }
+}
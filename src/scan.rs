@@ -1,8 +1,10 @@
 use anyhow::{Context, Result};
-use globset::{Glob, GlobSet, GlobSetBuilder};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
-use std::sync::{mpsc, Arc, atomic::{AtomicBool, Ordering}};
+
+use crate::text;
+use std::sync::{mpsc, Arc, atomic::{AtomicU8, Ordering}};
 
 #[derive(Debug, Clone)]
 pub struct ScanConfig {
@@ -12,6 +14,10 @@ pub struct ScanConfig {
     pub extra_exclude_paths: Vec<PathBuf>,
     pub max_depth: Option<usize>,
     pub follow_links: bool,
+    /// Match include/exclude globs ignoring case (`*.PDF` matches `file.pdf`)
+    pub case_insensitive: bool,
+    /// Keep only files whose text contains this, ignoring case (see `text`); loads every candidate
+    pub contains: Option<String>,
 }
 
 impl Default for ScanConfig {
@@ -23,6 +29,8 @@ impl Default for ScanConfig {
             extra_exclude_paths: vec![],
             max_depth: None,
             follow_links: false,
+            case_insensitive: false,
+            contains: None,
         }
     }
 }
@@ -41,30 +49,47 @@ pub fn collect_pdfs(
         extra_exclude_paths: extra_exclude_paths.to_vec(),
         max_depth: None,
         follow_links: false,
+        case_insensitive: false,
+        contains: None,
     };
     collect_pdfs_cfg(&cfg)
 }
 
-fn build_globset(patterns: &[String]) -> Result<GlobSet> {
+// Globs match paths relative to the scan root. `*` also crosses '/' (so "*.pdf" matches in
+// subdirectories too) and `{a,b}` alternation is on; both are spelled out rather than left to
+// globset's defaults.
+pub(crate) fn build_globset(patterns: &[String], case_insensitive: bool) -> Result<GlobSet> {
     if patterns.is_empty() {
         return Ok(GlobSetBuilder::new().build()?);
     }
     let mut builder = GlobSetBuilder::new();
     for pat in patterns {
-        let g = Glob::new(pat).with_context(|| format!("无效的 GLOB: {}", pat))?;
+        let g = GlobBuilder::new(pat)
+            .literal_separator(false)
+            .empty_alternates(false)
+            .case_insensitive(case_insensitive)
+            .build()
+            .with_context(|| format!("无效的 GLOB: {}", pat))?;
         builder.add(g);
     }
     Ok(builder.build()?)
 }
 
 pub fn collect_pdfs_cfg(cfg: &ScanConfig) -> Result<Vec<PathBuf>> {
-    let include_set = build_globset(&cfg.includes).with_context(|| "包含规则无效".to_string())?;
-    let exclude_set = build_globset(&cfg.excludes).with_context(|| "排除规则无效".to_string())?;
+    let mut out = collect_pdfs_walk_order(cfg)?;
+    out.sort();
+    Ok(out)
+}
+
+/// Like `collect_pdfs_cfg`, but in directory-walk order (whatever the filesystem returns).
+pub fn collect_pdfs_walk_order(cfg: &ScanConfig) -> Result<Vec<PathBuf>> {
+    let include_set = build_globset(&cfg.includes, cfg.case_insensitive).with_context(|| "包含规则无效".to_string())?;
+    let exclude_set = build_globset(&cfg.excludes, cfg.case_insensitive).with_context(|| "排除规则无效".to_string())?;
 
     let mut wd = WalkDir::new(&cfg.input_dir).follow_links(cfg.follow_links);
     if let Some(d) = cfg.max_depth { wd = wd.max_depth(d); }
 
-    let mut out: Vec<PathBuf> = wd
+    let out: Vec<PathBuf> = wd
         .into_iter()
         .filter_map(Result::ok)
         .filter(|e| e.file_type().is_file())
@@ -76,10 +101,10 @@ pub fn collect_pdfs_cfg(cfg: &ScanConfig) -> Result<Vec<PathBuf>> {
             let exclude_hit = if exclude_set.is_empty() { false } else { exclude_set.is_match(rel) };
             include_ok && !exclude_hit
         })
+        .filter(|e| cfg.contains.as_deref().is_none_or(|q| text::pdf_contains(e.path(), q)))
         .map(|e| e.path().to_owned())
         .collect();
-
-    out.sort();
+    tracing::info!(name: "scan_complete", dir = %cfg.input_dir.display(), files = out.len(), "scan_complete");
     Ok(out)
 }
 
@@ -124,64 +149,206 @@ mod tests {
         doc.compress();
         doc.save(&p).unwrap();
 
-        let cfg = ScanConfig { input_dir: root.clone(), includes: vec![], excludes: vec![], extra_exclude_paths: vec![], max_depth: None, follow_links: false };
+        let cfg = ScanConfig { input_dir: root.clone(), includes: vec![], excludes: vec![], extra_exclude_paths: vec![], max_depth: None, follow_links: false, case_insensitive: false, contains: None };
         let files = collect_pdfs_cfg(&cfg).unwrap();
         assert_eq!(files.len(), 1);
         assert_eq!(files[0], p);
     }
+
+    #[test]
+    fn timeout_cancel_ends_scan_with_reason() {
+        let td = tempdir().unwrap();
+        fs::write(td.path().join("a.pdf"), b"%PDF-1.5").unwrap();
+        let cfg = ScanConfig { input_dir: td.path().to_path_buf(), ..ScanConfig::default() };
+        let (tx, rx) = mpsc::channel();
+        let cancel = CancelHandle::new();
+        cancel.cancel(CancelReason::Timeout);
+        // a later reason does not replace the first
+        cancel.cancel(CancelReason::UserRequested);
+        assert_eq!(cancel.reason(), Some(CancelReason::Timeout));
+        scan_worker(&cfg, &tx, &cancel);
+        drop(tx);
+        let events: Vec<ScanEvent> = rx.into_iter().collect();
+        assert!(matches!(events.as_slice(), [ScanEvent::Canceled(CancelReason::Timeout)]));
+
+        // Uncanceled, the same walk ends with Done
+        let (tx, rx) = mpsc::channel();
+        scan_worker(&cfg, &tx, &CancelHandle::new());
+        drop(tx);
+        assert!(matches!(rx.into_iter().last(), Some(ScanEvent::Done)));
+    }
+
+    #[test]
+    fn globs_match_case_only_when_asked() {
+        let upper = vec!["*.PDF".to_string()];
+        assert!(!build_globset(&upper, false).unwrap().is_match("file.pdf"));
+        assert!(build_globset(&upper, true).unwrap().is_match("file.pdf"));
+        assert!(build_globset(&upper, true).unwrap().is_match("sub/File.Pdf"));
+
+        let braces = vec!["{a,b}*.pdf".to_string()];
+        let set = build_globset(&braces, false).unwrap();
+        assert!(set.is_match("a1.pdf") && set.is_match("b2.pdf") && !set.is_match("c3.pdf"));
+        assert!(!set.is_match("A1.pdf"));
+        assert!(build_globset(&braces, true).unwrap().is_match("A1.pdf"));
+    }
+}
+
+/// One discovered PDF, as listed by `pdf-ops scan`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ScanEntry {
+    pub path: PathBuf,
+    pub size_bytes: u64,
+    /// Only when asked for; `None` also when the file cannot be loaded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pages: Option<usize>,
+}
+
+/// Size (and, with `with_pages`, page count) of a found file. Loading for the page count
+/// is the slow part, so it is opt-in.
+pub fn describe(path: &Path, with_pages: bool) -> ScanEntry {
+    let size_bytes = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let pages = if with_pages { lopdf::Document::load(path).ok().map(|d| d.get_pages().len()) } else { None };
+    ScanEntry { path: path.to_path_buf(), size_bytes, pages }
 }
 
 pub enum ScanEvent {
     Found(PathBuf),
     Error(String),
+    /// The walk finished
     Done,
+    /// The walk was stopped through the `CancelHandle`; sent instead of `Done`
+    Canceled(CancelReason),
+}
+
+/// Why a scan was stopped early.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CancelReason {
+    /// Nothing new turned up for too long
+    Timeout,
+    /// The user stopped it (Ctrl-C) or started another scan
+    UserRequested,
+    /// Stopped to stay within a memory or file-count budget
+    ResourceLimit,
 }
 
+impl CancelReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CancelReason::Timeout => "timeout",
+            CancelReason::UserRequested => "user-requested",
+            CancelReason::ResourceLimit => "resource-limit",
+        }
+    }
+
+    fn code(self) -> u8 {
+        match self {
+            CancelReason::Timeout => 1,
+            CancelReason::UserRequested => 2,
+            CancelReason::ResourceLimit => 3,
+        }
+    }
+
+    fn from_code(code: u8) -> Option<Self> {
+        match code {
+            1 => Some(CancelReason::Timeout),
+            2 => Some(CancelReason::UserRequested),
+            3 => Some(CancelReason::ResourceLimit),
+            _ => None,
+        }
+    }
+}
+
+/// Stops a `scan_stream` walk. The first reason given is the one reported.
 #[derive(Clone)]
-pub struct CancelHandle(Arc<AtomicBool>);
+pub struct CancelHandle(Arc<AtomicU8>);
 impl CancelHandle {
-    pub fn cancel(&self) { self.0.store(true, Ordering::Relaxed); }
-    pub fn is_canceled(&self) -> bool { self.0.load(Ordering::Relaxed) }
+    fn new() -> Self { CancelHandle(Arc::new(AtomicU8::new(0))) }
+    pub fn cancel(&self, reason: CancelReason) {
+        let _ = self.0.compare_exchange(0, reason.code(), Ordering::Relaxed, Ordering::Relaxed);
+    }
+    pub fn is_canceled(&self) -> bool { self.reason().is_some() }
+    pub fn reason(&self) -> Option<CancelReason> { CancelReason::from_code(self.0.load(Ordering::Relaxed)) }
 }
 
 pub fn scan_stream(cfg: ScanConfig) -> (mpsc::Receiver<ScanEvent>, CancelHandle) {
     let (tx, rx) = mpsc::channel();
-    let cancel = CancelHandle(Arc::new(AtomicBool::new(false)));
-    let cancel_clone = CancelHandle(cancel.0.clone());
-    std::thread::spawn(move || {
-        let include_set = match build_globset(&cfg.includes) {
-            Ok(s) => s,
-            Err(e) => { let _ = tx.send(ScanEvent::Error(e.to_string())); let _ = tx.send(ScanEvent::Done); return; }
-        };
-        let exclude_set = match build_globset(&cfg.excludes) {
-            Ok(s) => s,
-            Err(e) => { let _ = tx.send(ScanEvent::Error(e.to_string())); let _ = tx.send(ScanEvent::Done); return; }
-        };
-        let mut wd = WalkDir::new(&cfg.input_dir).follow_links(cfg.follow_links);
-        if let Some(d) = cfg.max_depth { wd = wd.max_depth(d); }
-        for ent in wd.into_iter() {
-            if cancel_clone.is_canceled() { break; }
-            match ent {
-                Ok(e) => {
-                    if !e.file_type().is_file() { continue; }
-                    let p = e.path();
-                    if !p.extension().map(|ext| ext.eq_ignore_ascii_case("pdf")).unwrap_or(false) { continue; }
-                    if cfg.extra_exclude_paths.iter().any(|x| p == x) { continue; }
-                    let rel = p.strip_prefix(&cfg.input_dir).unwrap_or(p);
-                    let include_ok = if include_set.is_empty() { true } else { include_set.is_match(rel) };
-                    let exclude_hit = if exclude_set.is_empty() { false } else { exclude_set.is_match(rel) };
-                    if include_ok && !exclude_hit {
-                        let _ = tx.send(ScanEvent::Found(p.to_path_buf()));
-                    }
+    let cancel = CancelHandle::new();
+    let cancel_clone = cancel.clone();
+    std::thread::spawn(move || scan_worker(&cfg, &tx, &cancel_clone));
+    (rx, cancel)
+}
+
+// Body of the `scan_stream` thread; ends with `Done` or `Canceled`
+fn scan_worker(cfg: &ScanConfig, tx: &mpsc::Sender<ScanEvent>, cancel: &CancelHandle) {
+    let include_set = match build_globset(&cfg.includes, cfg.case_insensitive) {
+        Ok(s) => s,
+        Err(e) => { let _ = tx.send(ScanEvent::Error(e.to_string())); let _ = tx.send(ScanEvent::Done); return; }
+    };
+    let exclude_set = match build_globset(&cfg.excludes, cfg.case_insensitive) {
+        Ok(s) => s,
+        Err(e) => { let _ = tx.send(ScanEvent::Error(e.to_string())); let _ = tx.send(ScanEvent::Done); return; }
+    };
+    let mut wd = WalkDir::new(&cfg.input_dir).follow_links(cfg.follow_links);
+    if let Some(d) = cfg.max_depth { wd = wd.max_depth(d); }
+    for ent in wd.into_iter() {
+        if let Some(reason) = cancel.reason() {
+            tracing::info!(name: "scan_canceled", dir = %cfg.input_dir.display(), reason = reason.as_str(), "scan_canceled");
+            let _ = tx.send(ScanEvent::Canceled(reason));
+            return;
+        }
+        match ent {
+            Ok(e) => {
+                if !e.file_type().is_file() { continue; }
+                let p = e.path();
+                if !p.extension().map(|ext| ext.eq_ignore_ascii_case("pdf")).unwrap_or(false) { continue; }
+                if cfg.extra_exclude_paths.iter().any(|x| p == x) { continue; }
+                let rel = p.strip_prefix(&cfg.input_dir).unwrap_or(p);
+                let include_ok = if include_set.is_empty() { true } else { include_set.is_match(rel) };
+                let exclude_hit = if exclude_set.is_empty() { false } else { exclude_set.is_match(rel) };
+                if include_ok && !exclude_hit && cfg.contains.as_deref().is_none_or(|q| text::pdf_contains(p, q)) {
+                    let _ = tx.send(ScanEvent::Found(p.to_path_buf()));
                 }
-                Err(e) => {
-                    // 忽略不可访问条目的错误，不中断整体扫描
-                    // 仅在需要时可发送一次性提示；此处直接跳过
-                    let _ = tx.send(ScanEvent::Error(e.to_string()));
+            }
+            Err(e) => {
+                // 忽略不可访问条目的错误，不中断整体扫描
+                // 仅在需要时可发送一次性提示；此处直接跳过
+                let _ = tx.send(ScanEvent::Error(e.to_string()));
+            }
+        }
+    }
+    let _ = tx.send(ScanEvent::Done);
+}
+
+/// Run `scan_stream` over `cfg` and print each entry as it arrives: its path (tab and page
+/// count with `with_pages`), or with `json` one element of a JSON array. Walk errors go to
+/// stderr. Ctrl-C stops the walk but still closes the JSON array; returns whether that happened.
+pub fn print_stream(cfg: ScanConfig, json: bool, with_pages: bool) -> bool {
+    use crate::interrupt;
+    let (rx, cancel) = scan_stream(cfg);
+    interrupt::set_graceful(true);
+    let mut found = 0;
+    if json { println!("["); }
+    loop {
+        match rx.recv_timeout(std::time::Duration::from_millis(100)) {
+            Ok(ScanEvent::Found(path)) => {
+                let entry = describe(&path, with_pages);
+                if json {
+                    let sep = if found == 0 { "  " } else { ", " };
+                    println!("{}{}", sep, serde_json::to_string(&entry).expect("serializable"));
+                } else if let (true, Some(pages)) = (with_pages, entry.pages) {
+                    println!("{}\t{}", entry.path.display(), pages);
+                } else {
+                    println!("{}", entry.path.display());
                 }
+                found += 1;
             }
+            Ok(ScanEvent::Error(e)) => eprintln!("⚠️ {}", e),
+            Ok(ScanEvent::Done | ScanEvent::Canceled(_)) | Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
         }
-        let _ = tx.send(ScanEvent::Done);
-    });
-    (rx, cancel)
+        if interrupt::stop_requested() { cancel.cancel(CancelReason::UserRequested); }
+    }
+    if json { println!("]"); }
+    interrupt::set_graceful(false);
+    interrupt::stop_requested()
 }
@@ -0,0 +1,138 @@
+use std::cmp::Ordering;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use crate::meta;
+
+/// Merge input ordering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortBy {
+    /// Path, lexical (scanner order)
+    #[default]
+    Name,
+    /// Modification time, oldest first
+    Mtime,
+    /// File size, smallest first
+    Size,
+    /// Info /Title (or first bookmark), natural order; untitled files last
+    Title,
+    /// Path with digit runs compared as numbers ("2.pdf" before "10.pdf")
+    Natural,
+    /// Directory-walk order, unsorted
+    Scan,
+    /// Random; reproducible with `--seed`
+    Shuffle,
+}
+
+/// Order `files` in place. `seed` only matters for `Shuffle` (`None` = seeded from the clock).
+pub(crate) fn sort_files(files: &mut [PathBuf], by: SortBy, seed: Option<u64>) {
+    match by {
+        SortBy::Name => files.sort(),
+        SortBy::Natural => files.sort_by(|a, b| natural_cmp(&a.to_string_lossy(), &b.to_string_lossy())),
+        SortBy::Scan => {}
+        SortBy::Shuffle => {
+            let seed = seed.unwrap_or_else(|| {
+                SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0)
+            });
+            shuffle(files, seed);
+        }
+        SortBy::Mtime => files.sort_by_cached_key(|p| {
+            std::fs::metadata(p).and_then(|m| m.modified()).unwrap_or(SystemTime::UNIX_EPOCH)
+        }),
+        SortBy::Size => files.sort_by_cached_key(|p| std::fs::metadata(p).map(|m| m.len()).unwrap_or(0)),
+        SortBy::Title => {
+            // Load each title once; the comparator runs O(n log n) times
+            let mut keyed: Vec<(Option<String>, PathBuf)> = files
+                .iter()
+                .map(|p| (lopdf::Document::load(p).ok().and_then(|d| meta::read_title(&d)), p.clone()))
+                .collect();
+            keyed.sort_by(|(ta, pa), (tb, pb)| match (ta, tb) {
+                (Some(a), Some(b)) => natural_cmp(a, b).then_with(|| pa.cmp(pb)),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => pa.cmp(pb),
+            });
+            for (slot, (_, p)) in files.iter_mut().zip(keyed) { *slot = p; }
+        }
+    }
+}
+
+// Fisher–Yates driven by splitmix64; stable across platforms and releases for a given seed
+fn shuffle<T>(items: &mut [T], seed: u64) {
+    let mut state = seed;
+    let mut next = || {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    };
+    for i in (1..items.len()).rev() {
+        let j = (next() % (i as u64 + 1)) as usize;
+        items.swap(i, j);
+    }
+}
+
+/// Case-insensitive comparison treating digit runs as numbers ("Chapter 2" < "Chapter 10").
+pub(crate) fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut ia, mut ib) = (a.chars().peekable(), b.chars().peekable());
+    loop {
+        match (ia.peek().copied(), ib.peek().copied()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(ca), Some(cb)) if ca.is_ascii_digit() && cb.is_ascii_digit() => {
+                let na = take_digits(&mut ia);
+                let nb = take_digits(&mut ib);
+                let (ta, tb) = (na.trim_start_matches('0'), nb.trim_start_matches('0'));
+                let ord = ta.len().cmp(&tb.len()).then_with(|| ta.cmp(tb));
+                if ord != Ordering::Equal { return ord; }
+            }
+            (Some(ca), Some(cb)) => {
+                let ord = ca.to_lowercase().cmp(cb.to_lowercase());
+                if ord != Ordering::Equal { return ord; }
+                ia.next();
+                ib.next();
+            }
+        }
+    }
+}
+
+fn take_digits(it: &mut std::iter::Peekable<std::str::Chars<'_>>) -> String {
+    let mut s = String::new();
+    while let Some(c) = it.peek().copied().filter(|c| c.is_ascii_digit()) {
+        s.push(c);
+        it.next();
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn natural_numbers_compare_by_value() {
+        assert_eq!(natural_cmp("Chapter 2", "Chapter 10"), Ordering::Less);
+        assert_eq!(natural_cmp("a10b", "a9b"), Ordering::Greater);
+        assert_eq!(natural_cmp("file007", "file7x"), Ordering::Less);
+        assert_eq!(natural_cmp("Intro", "intro 1"), Ordering::Less);
+    }
+
+    #[test]
+    fn natural_and_seeded_shuffle() {
+        let names = ["10.pdf", "2.pdf", "1.pdf", "3.pdf", "20.pdf", "4.pdf"];
+        let mut files: Vec<PathBuf> = names.iter().map(PathBuf::from).collect();
+        sort_files(&mut files, SortBy::Natural, None);
+        assert_eq!(files, ["1.pdf", "2.pdf", "3.pdf", "4.pdf", "10.pdf", "20.pdf"].map(PathBuf::from));
+
+        let natural = files.clone();
+        let mut a = natural.clone();
+        let mut b = natural.clone();
+        sort_files(&mut a, SortBy::Shuffle, Some(1));
+        sort_files(&mut b, SortBy::Shuffle, Some(1));
+        assert_eq!(a, b);
+        assert_ne!(a, natural);
+    }
+}
@@ -6,19 +6,67 @@ pub struct PageRange {
     pub end: Option<usize>,  // 1-based inclusive; None means open-ended
 }
 
+impl std::fmt::Display for PageRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.end {
+            Some(e) if e == self.start => write!(f, "{}", self.start),
+            Some(e) => write!(f, "{}-{}", self.start, e),
+            None => write!(f, "{}-", self.start),
+        }
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum SpecError {
     #[error("invalid number: {0}")]
     InvalidNumber(#[from] ParseIntError),
     #[error("invalid range segment: {0}")]
     InvalidSegment(String),
+    #[error("unknown page label: {0}")]
+    UnknownLabel(String),
+    #[error("empty segment between separators: {0}")]
+    EmptySegment(String),
 }
 
-// Parse spec like: "1-3,5,10-" (1-based)
+// Segments of a spec, separated by commas, semicolons or whitespace ("1-3; 5 10-"). Spaces
+// that cannot separate stay inside a segment: around a dash ("1 - 3", "1 -3", "1- 3") and in
+// the phrases "first N" / "last N". A dash left dangling ("5 -", "5- -7", a lone "-") is an
+// error rather than an open range.
+fn segments(spec: &str) -> Result<Vec<String>, SpecError> {
+    let is_number = |w: &str| !w.is_empty() && w.bytes().all(|b| b.is_ascii_digit());
+    let mut out: Vec<String> = Vec::new();
+    for part in spec.split([',', ';']) {
+        let mut seg: Vec<String> = Vec::new();
+        for word in part.split_whitespace() {
+            match seg.last_mut() {
+                Some(prev) if prev.ends_with('-') && word.starts_with('-') => {
+                    return Err(SpecError::InvalidSegment(format!("{} {}", prev, word)));
+                }
+                Some(prev) if word == "-" || prev == "-" || prev.ends_with(" -")
+                    || (prev.ends_with('-') && is_number(word))
+                    || (is_number(prev) && word.starts_with('-'))
+                    || prev.eq_ignore_ascii_case("first") || prev.eq_ignore_ascii_case("last") => {
+                    prev.push(' ');
+                    prev.push_str(word);
+                }
+                _ => seg.push(word.to_string()),
+            }
+        }
+        if let Some(bad) = seg.iter().find(|s| s.as_str() == "-" || s.ends_with(" -")) {
+            return Err(SpecError::InvalidSegment(bad.clone()));
+        }
+        out.extend(seg);
+    }
+    Ok(out)
+}
+
+// Parse spec like: "1-3,5,10-" (1-based); ';' and whitespace separate segments too
 pub fn parse_spec(spec: &str) -> Result<Vec<PageRange>, SpecError> {
     let mut out = Vec::new();
-    for raw in spec.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+    for raw in segments(spec)? {
+        let raw = raw.as_str();
         if let Some((a, b)) = raw.split_once('-') {
+            let (a, b) = (a.trim(), b.trim());
             let start = if a.is_empty() { 1 } else { a.parse::<usize>()? };
             let end = if b.is_empty() { None } else { Some(b.parse::<usize>()?) };
             if let Some(e) = end { if e < start { return Err(SpecError::InvalidSegment(raw.to_string())); } }
@@ -32,6 +80,132 @@ pub fn parse_spec(spec: &str) -> Result<Vec<PageRange>, SpecError> {
     Ok(out)
 }
 
+// For --strict-spec: the parsers skip empty segments, so a typo like "1,,3" or ",1" goes
+// unnoticed; this rejects them. One trailing separator ("1-3,") is still accepted.
+pub fn check_strict(spec: &str) -> Result<(), SpecError> {
+    let body = spec.trim();
+    let body = body.strip_suffix([',', ';']).unwrap_or(body);
+    if body.split([',', ';']).any(|part| part.trim().is_empty()) {
+        return Err(SpecError::EmptySegment(spec.to_string()));
+    }
+    Ok(())
+}
+
+// Like parse_spec, but segments may also be the phrases "first N" / "last N" (case-insensitive),
+// which need the document's page count: "last 2" of 10 pages is 9-10
+pub fn parse_spec_with_phrases(spec: &str, total_pages: usize) -> Result<Vec<PageRange>, SpecError> {
+    let mut out = Vec::new();
+    for raw in segments(spec)? {
+        let raw = raw.as_str();
+        let words: Vec<String> = raw.split_whitespace().map(|w| w.to_ascii_lowercase()).collect();
+        match words.as_slice() {
+            [word, n] if word == "first" || word == "last" => {
+                let n = n.parse::<usize>()?;
+                if n == 0 { return Err(SpecError::InvalidSegment(raw.to_string())); }
+                out.push(if word == "first" {
+                    PageRange { start: 1, end: Some(n) }
+                } else {
+                    PageRange { start: total_pages.saturating_sub(n) + 1, end: None }
+                });
+            }
+            _ => out.extend(parse_spec(raw)?),
+        }
+    }
+    Ok(out)
+}
+
+// Like parse_spec_with_phrases, but with the document's page labels (index = physical page - 1)
+// a segment may also use labels: "iv", "iv-vi", "A-3", "ii-" ("A-3" is tried as a whole label
+// before being read as a range). Plain numbers always mean physical pages.
+pub fn parse_spec_with_labels(spec: &str, total_pages: usize, labels: Option<&[String]>) -> Result<Vec<PageRange>, SpecError> {
+    let Some(labels) = labels else { return parse_spec_with_phrases(spec, total_pages) };
+    let page_of = |token: &str| -> Option<usize> {
+        token.parse::<usize>().ok().or_else(|| labels.iter().position(|l| l == token).map(|i| i + 1))
+    };
+    let mut out = Vec::new();
+    for raw in segments(spec)? {
+        let raw = raw.as_str();
+        let err = match parse_spec_with_phrases(raw, total_pages) {
+            Ok(r) => { out.extend(r); continue; }
+            Err(e) => e,
+        };
+        if let Some(p) = labels.iter().position(|l| l == raw) {
+            out.push(PageRange { start: p + 1, end: Some(p + 1) });
+            continue;
+        }
+        // Every '-' is a candidate separator, since labels may contain one themselves
+        let range = raw.match_indices('-').find_map(|(i, _)| {
+            let (a, b) = (raw[..i].trim(), raw[i + 1..].trim());
+            let start = if a.is_empty() { 1 } else { page_of(a)? };
+            let end = if b.is_empty() { None } else { Some(page_of(b)?) };
+            Some(PageRange { start, end })
+        });
+        match range {
+            Some(r) if r.end.is_some_and(|e| e < r.start) => return Err(SpecError::InvalidSegment(raw.to_string())),
+            Some(r) => out.push(r),
+            None if matches!(err, SpecError::InvalidNumber(_)) => return Err(SpecError::UnknownLabel(raw.to_string())),
+            None => return Err(err),
+        }
+    }
+    Ok(out)
+}
+
+/// A spec parsed once for applying to many documents (e.g. `merge --pages` over thousands of
+/// files). Plain numbers and ranges are parsed up front; segments that need the document
+/// ("last N", page labels) are kept as text and resolved by `resolve`.
+#[derive(Debug, Clone)]
+pub struct CompiledSpec {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Fixed(Vec<PageRange>),
+    PerDocument(String),
+}
+
+#[cfg(test)]
+thread_local! {
+    // Compilations on this thread, so tests can check a spec is not re-parsed per file
+    pub(crate) static COMPILED: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+impl CompiledSpec {
+    pub fn compile(spec: &str) -> Self {
+        #[cfg(test)]
+        COMPILED.with(|c| c.set(c.get() + 1));
+        // A malformed spec is kept whole, so `resolve` reports the same error as a direct parse
+        let segments = match segments(spec) {
+            Ok(segments) => segments
+                .into_iter()
+                .map(|raw| match parse_spec(&raw) {
+                    Ok(ranges) => Segment::Fixed(ranges),
+                    Err(_) => Segment::PerDocument(raw),
+                })
+                .collect(),
+            Err(_) => vec![Segment::PerDocument(spec.to_string())],
+        };
+        CompiledSpec { segments }
+    }
+
+    /// Whether `resolve` looks at the labels at all (so callers can skip reading them).
+    pub fn needs_labels(&self) -> bool {
+        self.segments.iter().any(|s| matches!(s, Segment::PerDocument(_)))
+    }
+
+    /// Same result as `parse_spec_with_labels` on the original spec.
+    pub fn resolve(&self, total_pages: usize, labels: Option<&[String]>) -> Result<Vec<PageRange>, SpecError> {
+        let mut out = Vec::new();
+        for seg in &self.segments {
+            match seg {
+                Segment::Fixed(ranges) => out.extend(ranges.iter().cloned()),
+                Segment::PerDocument(raw) => out.extend(parse_spec_with_labels(raw, total_pages, labels)?),
+            }
+        }
+        Ok(out)
+    }
+}
+
 // Expand to zero-based page indexes, deduped and sorted
 pub fn expand_to_indexes(ranges: &[PageRange], total_pages: usize) -> Vec<usize> {
     use std::collections::BTreeSet;
@@ -46,6 +220,11 @@ pub fn expand_to_indexes(ranges: &[PageRange], total_pages: usize) -> Vec<usize>
     set.into_iter().collect()
 }
 
+// Segments that reach past `total_pages` (they are clamped or dropped on expansion)
+pub fn out_of_range(ranges: &[PageRange], total_pages: usize) -> Vec<&PageRange> {
+    ranges.iter().filter(|r| r.start > total_pages || r.end.is_some_and(|e| e > total_pages)).collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -58,6 +237,57 @@ mod tests {
         assert_eq!(r[2], PageRange{ start:10, end: None});
     }
 
+    #[test]
+    fn semicolons_and_spaces_separate_like_commas() {
+        let commas = parse_spec("1-3,5,10-").unwrap();
+        assert_eq!(parse_spec("1-3; 5; 10-").unwrap(), commas);
+        assert_eq!(parse_spec("1-3 5 10-").unwrap(), commas);
+        assert_eq!(parse_spec(" 1 - 3 ;5,  10- ").unwrap(), commas);
+        assert_eq!(parse_spec_with_phrases("first 2; last 1", 10).unwrap(), parse_spec_with_phrases("first 2,last 1", 10).unwrap());
+    }
+
+    #[test]
+    fn spaces_around_a_dash_keep_the_range() {
+        let want = parse_spec("5-7").unwrap();
+        for spec in ["5 - 7", "5 -7", "5- 7", "5 -7, 9"] {
+            assert_eq!(parse_spec(spec).unwrap()[0], want[0], "{spec}");
+        }
+        assert_eq!(parse_spec_with_phrases("first 2, 5 -7", 10).unwrap()[1], want[0]);
+        // A standalone "-N" after a range is still its own segment
+        assert_eq!(parse_spec("1-3 -5").unwrap(), parse_spec("1-3,-5").unwrap());
+        for spec in ["5 -", "5- -7", "-", "1, - ,3", "5 - ; 7"] {
+            assert!(matches!(parse_spec(spec), Err(SpecError::InvalidSegment(_))), "{spec}");
+        }
+        assert!(CompiledSpec::compile("5- -7").resolve(10, None).is_err());
+    }
+
+    #[test]
+    fn empty_segments_are_dropped_unless_strict() {
+        assert_eq!(parse_spec("1,,3").unwrap(), parse_spec("1,3").unwrap());
+        assert_eq!(parse_spec(",1").unwrap(), parse_spec("1").unwrap());
+        assert!(matches!(check_strict("1,,3"), Err(SpecError::EmptySegment(_))));
+        assert!(check_strict(",1").is_err());
+        assert!(check_strict("1; ;3").is_err());
+        assert!(check_strict("").is_err());
+        assert!(check_strict("1-3,").is_ok());
+        assert!(check_strict("1-3; 5 10-").is_ok());
+    }
+
+    #[test]
+    fn compiled_spec_matches_direct_parse() {
+        let labels: Vec<String> = ["i", "ii", "1", "2", "3", "4"].iter().map(|s| s.to_string()).collect();
+        for spec in ["1-3,5", "2-; last 2", "ii-2, first 1", "-2 4-"] {
+            let compiled = CompiledSpec::compile(spec);
+            for total in [4, 6] {
+                let want = parse_spec_with_labels(spec, total, Some(&labels[..total])).unwrap();
+                assert_eq!(compiled.resolve(total, Some(&labels[..total])).unwrap(), want, "{spec} / {total}");
+            }
+        }
+        assert!(!CompiledSpec::compile("1-3,5").needs_labels());
+        assert!(CompiledSpec::compile("1, last 2").needs_labels());
+        assert!(CompiledSpec::compile("1-x").resolve(4, None).is_err());
+    }
+
     #[test]
     fn expand_clamped_and_sorted() {
         let r = vec![PageRange{start:2, end:Some(4)}, PageRange{start:4, end:Some(6)}];
@@ -73,4 +303,41 @@ mod tests {
         // -2 => 1..=2 => 0,1 ; 4- => 4..=5 => 3,4
         assert_eq!(idx, vec![0,1,3,4]);
     }
+
+    #[test]
+    fn first_and_last_phrases() {
+        let first = parse_spec_with_phrases("first 3", 10).unwrap();
+        assert_eq!(expand_to_indexes(&first, 10), vec![0, 1, 2]);
+        let last = parse_spec_with_phrases("Last 2", 10).unwrap();
+        assert_eq!(expand_to_indexes(&last, 10), vec![8, 9]);
+        // more than the document has: everything
+        assert_eq!(expand_to_indexes(&parse_spec_with_phrases("last 20", 5).unwrap(), 5), vec![0, 1, 2, 3, 4]);
+        // mixes with plain segments
+        assert_eq!(expand_to_indexes(&parse_spec_with_phrases("first 1, 5, last 1", 10).unwrap(), 10), vec![0, 4, 9]);
+        assert!(parse_spec_with_phrases("last 0", 10).is_err());
+        assert!(parse_spec_with_phrases("middle 2", 10).is_err());
+    }
+
+    #[test]
+    fn label_tokens_resolve_to_physical_pages() {
+        let labels: Vec<String> = ["i", "ii", "iii", "iv", "v", "vi", "1", "2", "A-1", "A-2"].iter().map(|s| s.to_string()).collect();
+        let pick = |spec: &str| expand_to_indexes(&parse_spec_with_labels(spec, 10, Some(&labels)).unwrap(), 10);
+        assert_eq!(pick("iv-vi"), vec![3, 4, 5]);
+        assert_eq!(pick("ii, A-2"), vec![1, 9]);
+        assert_eq!(pick("A-1-"), vec![8, 9]);
+        assert_eq!(pick("vi-8, last 1"), vec![5, 6, 7, 9]);
+        // Numbers stay physical
+        assert_eq!(pick("1-2"), vec![0, 1]);
+        assert!(matches!(parse_spec_with_labels("xx", 10, Some(&labels)), Err(SpecError::UnknownLabel(_))));
+        assert!(matches!(parse_spec_with_labels("vi-iv", 10, Some(&labels)), Err(SpecError::InvalidSegment(_))));
+        // Without labels, labels are just bad numbers
+        assert!(matches!(parse_spec_with_labels("iv", 10, None), Err(SpecError::InvalidNumber(_))));
+    }
+
+    #[test]
+    fn flags_out_of_range_segments() {
+        let r = parse_spec("1-3,8-12,15,20-").unwrap();
+        let bad: Vec<String> = out_of_range(&r, 10).iter().map(|r| r.to_string()).collect();
+        assert_eq!(bad, vec!["8-12", "15", "20-"]);
+    }
 }
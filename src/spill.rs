@@ -0,0 +1,169 @@
+//! `merge --spill`: bounded memory for very large merges.
+//!
+//! After each input is processed, its objects (except page dictionaries, which still get their
+//! `/Parent` at the end) are serialized to `<output>.spill` next to the output and dropped from
+//! memory. The final write copies that body into the output, appends the objects still in memory
+//! and writes one cross-reference table for everything. Serialization is lopdf's own, so every
+//! object is written exactly as `Document::save` would write it; what differs from the in-memory
+//! path is the object order in the file and that a classic xref table is always used. The cost is
+//! an extra write and read of the whole body, on the output's filesystem.
+
+use anyhow::{Context, Result};
+use lopdf::xref::XrefType;
+use lopdf::{Document, Object, ObjectId};
+use std::collections::{BTreeMap, HashSet};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::interrupt::{self, Partial};
+
+pub(crate) struct Spill {
+    path: PathBuf,
+    file: BufWriter<File>,
+    len: u64,
+    // Offset of each spilled object within the spill file
+    offsets: BTreeMap<ObjectId, u64>,
+    _guard: Partial,
+}
+
+impl Spill {
+    pub(crate) fn create(output: &Path) -> Result<Self> {
+        let mut name = output.as_os_str().to_owned();
+        name.push(".spill");
+        let path = PathBuf::from(name);
+        let guard = Partial::new(path.clone());
+        let file = File::create(&path).with_context(|| format!("创建临时文件失败: {}", path.display()))?;
+        Ok(Self { path, file: BufWriter::new(file), len: 0, offsets: BTreeMap::new(), _guard: guard })
+    }
+
+    /// Number of objects written out so far.
+    pub(crate) fn objects(&self) -> usize {
+        self.offsets.len()
+    }
+
+    /// Move every object of `doc` except `keep` to the spill file.
+    pub(crate) fn spill(&mut self, doc: &mut Document, keep: &HashSet<ObjectId>) -> Result<()> {
+        let ids: Vec<ObjectId> = doc.objects.keys().filter(|id| !keep.contains(id)).copied().collect();
+        for id in ids {
+            let Some(obj) = doc.objects.remove(&id) else { continue };
+            let bytes = serialize(id, obj)?;
+            // Object and xref streams are never written (see `Document::save`)
+            if bytes.is_empty() { continue; }
+            self.file.write_all(&bytes).with_context(|| format!("写入临时文件失败: {}", self.path.display()))?;
+            self.offsets.insert(id, self.len);
+            self.len += bytes.len() as u64;
+        }
+        Ok(())
+    }
+
+    /// Write the complete file: the spilled objects, then what is left in `doc`, then the xref
+    /// table and `doc.trailer`.
+    pub(crate) fn finish(mut self, doc: &mut Document, output: &Path) -> Result<()> {
+        self.file.flush().with_context(|| format!("写入临时文件失败: {}", self.path.display()))?;
+        interrupt::write_via_part(output, |tmp| {
+            let mut out = BufWriter::new(File::create(tmp)?);
+            let header = format!("%PDF-{}\n", doc.version);
+            out.write_all(header.as_bytes())?;
+            let base = header.len() as u64;
+            std::io::copy(&mut File::open(&self.path)?, &mut out)?;
+
+            let mut offsets: BTreeMap<u32, (u64, u16)> = self.offsets.iter().map(|(&(id, gen), &off)| (id, (base + off, gen))).collect();
+            let mut pos = base + self.len;
+            for (id, obj) in std::mem::take(&mut doc.objects) {
+                let bytes = serialize(id, obj)?;
+                if bytes.is_empty() { continue; }
+                out.write_all(&bytes)?;
+                offsets.insert(id.0, (pos, id.1));
+                pos += bytes.len() as u64;
+            }
+
+            let size = doc.max_id + 1;
+            write!(out, "xref\n0 {}\n0000000000 65535 f \n", size)?;
+            for id in 1..size {
+                match offsets.get(&id) {
+                    Some((off, gen)) => writeln!(out, "{:010} {:05} n ", off, gen)?,
+                    None => out.write_all(b"0000000000 65535 f \n")?,
+                }
+            }
+            out.write_all(b"trailer\n")?;
+            out.write_all(&trailer_bytes(doc)?)?;
+            write!(out, "\nstartxref\n{}\n%%EOF", pos)?;
+            out.flush()?;
+            Ok(())
+        })
+    }
+}
+
+impl Drop for Spill {
+    fn drop(&mut self) { std::fs::remove_file(&self.path).ok(); }
+}
+
+// `N G obj … endobj` as lopdf writes it, cut out of a one-object document. Its `max_id` stays 0,
+// so the xref lopdf appends is a single entry; `startxref` marks where the object ends.
+fn serialize(id: ObjectId, obj: Object) -> Result<Vec<u8>> {
+    let mut one = Document::with_version("1.5");
+    one.reference_table.cross_reference_type = XrefType::CrossReferenceTable;
+    one.objects.insert(id, obj);
+    let mut buf = Vec::new();
+    one.save_to(&mut buf)?;
+    let start = buf.iter().position(|&b| b == b'\n').map_or(0, |i| i + 1);
+    let end = startxref(&buf).context("序列化对象失败")?;
+    Ok(buf[start..end].to_vec())
+}
+
+// The trailer dictionary lopdf would write for `doc` (with `/Size`), without its objects
+fn trailer_bytes(doc: &Document) -> Result<Vec<u8>> {
+    let mut empty = Document::with_version("1.5");
+    empty.reference_table.cross_reference_type = XrefType::CrossReferenceTable;
+    empty.max_id = doc.max_id;
+    empty.trailer = doc.trailer.clone();
+    let mut buf = Vec::new();
+    empty.save_to(&mut buf)?;
+    let start = find(&buf, b"trailer\n").context("序列化 trailer 失败")? + b"trailer\n".len();
+    let end = start + find(&buf[start..], b"\nstartxref").context("序列化 trailer 失败")?;
+    Ok(buf[start..end].to_vec())
+}
+
+fn startxref(buf: &[u8]) -> Option<usize> {
+    let key = b"startxref\n";
+    let at = buf.windows(key.len()).rposition(|w| w == key)? + key.len();
+    let digits = buf[at..].iter().take_while(|b| b.is_ascii_digit()).count();
+    std::str::from_utf8(&buf[at..at + digits]).ok()?.parse().ok()
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{dictionary, Stream};
+
+    #[test]
+    fn spilled_document_loads_back_unchanged() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("out.pdf");
+        let mut doc = Document::with_version("1.5");
+        let content = doc.add_object(Stream::new(dictionary! {}, b"0 0 m 10 10 l S".to_vec()));
+        let page = doc.new_object_id();
+        let pages = doc.add_object(dictionary! { "Type" => "Pages", "Kids" => vec![page.into()], "Count" => 1 });
+        doc.objects.insert(page, Object::Dictionary(dictionary! {
+            "Type" => "Page", "Parent" => pages, "Contents" => content, "MediaBox" => vec![0.into(), 0.into(), 10.into(), 10.into()],
+        }));
+        let catalog = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages });
+        doc.trailer.set("Root", catalog);
+
+        let mut spill = Spill::create(&output).unwrap();
+        spill.spill(&mut doc, &HashSet::from([page])).unwrap();
+        assert_eq!((spill.objects(), doc.objects.len()), (3, 1));
+        spill.finish(&mut doc, &output).unwrap();
+
+        let loaded = Document::load(&output).unwrap();
+        let page_id = *loaded.get_pages().get(&1).unwrap();
+        assert_eq!(page_id, page);
+        assert_eq!(loaded.get_page_content(page_id).unwrap(), b"0 0 m 10 10 l S");
+        assert!(!dir.path().join("out.pdf.spill").exists());
+    }
+}
@@ -1,11 +1,258 @@
-use lopdf::{Dictionary, Document, Object, ObjectId};
-use std::path::Path;
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, UNIX_EPOCH};
 use anyhow::{Result, Context};
-use crate::progress::ProgressSink;
+use crate::confirm;
+use crate::load;
+use crate::meta::{self, XmpMode};
+use crate::outline;
+use crate::page::{self, inherit_page_attributes};
+use crate::pathutil;
+use crate::progress::{MultiBarProgress, NullProgress, ProgressSink};
+use crate::sanitize;
+use crate::scan::{self, ScanConfig};
 
 use crate::spec::{self, PageRange};
+use crate::version::{self, PdfVersion};
 
-pub fn run(input: &Path, out_dir: &Path, each: bool, ranges_spec: Option<&str>, pattern: &str, force: bool, progress: &dyn ProgressSink) -> Result<()> {
+/// How split groups pages into outputs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SplitMode {
+    /// One output per page
+    Each,
+    /// N pages per output (the last one may be shorter)
+    Every(usize),
+    /// N outputs of near-equal size (earlier ones take the remainder)
+    Into(usize),
+    /// One output per range
+    Ranges(Vec<PageRange>),
+}
+
+impl SplitMode {
+    pub fn ranges(spec_str: &str) -> Result<Self> {
+        let ranges = spec::parse_spec(spec_str).with_context(|| format!("解析页码范围失败: {}", spec_str))?;
+        if ranges.is_empty() { anyhow::bail!("页码范围为空: {}", spec_str); }
+        Ok(SplitMode::Ranges(ranges))
+    }
+
+    /// Groups for a document of `total_pages` pages (ranges are clamped later, at extraction)
+    pub fn groups(&self, total_pages: usize) -> Vec<PageRange> {
+        let fixed = |n: usize| -> Vec<PageRange> {
+            (1..=total_pages).step_by(n).map(|s| PageRange { start: s, end: Some((s + n - 1).min(total_pages)) }).collect()
+        };
+        match self {
+            SplitMode::Each => fixed(1),
+            SplitMode::Every(n) => fixed((*n).max(1)),
+            SplitMode::Into(n) => {
+                let n = (*n).clamp(1, total_pages.max(1));
+                let (base, rem) = (total_pages / n, total_pages % n);
+                let mut start = 1;
+                (0..n).filter_map(|i| {
+                    let len = base + usize::from(i < rem);
+                    if len == 0 { return None; }
+                    let r = PageRange { start, end: Some(start + len - 1) };
+                    start += len;
+                    Some(r)
+                }).collect()
+            }
+            SplitMode::Ranges(r) => r.clone(),
+        }
+    }
+}
+
+impl std::str::FromStr for SplitMode {
+    type Err = anyhow::Error;
+
+    /// `each`, `every:N`, `into:N` or `ranges:SPEC`
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if s == "each" { return Ok(SplitMode::Each); }
+        if let Some(n) = s.strip_prefix("every:") {
+            let n: usize = n.trim().parse().with_context(|| format!("无效的页数: {}", n))?;
+            if n == 0 { anyhow::bail!("every:N 要求 N >= 1"); }
+            return Ok(SplitMode::Every(n));
+        }
+        if let Some(n) = s.strip_prefix("into:") {
+            let n: usize = n.trim().parse().with_context(|| format!("无效的份数: {}", n))?;
+            if n == 0 { anyhow::bail!("into:N 要求 N >= 1"); }
+            return Ok(SplitMode::Into(n));
+        }
+        if let Some(spec_str) = s.strip_prefix("ranges:") {
+            return SplitMode::ranges(spec_str);
+        }
+        anyhow::bail!("无效的分割模式: {} (可用: each | every:N | into:N | ranges:SPEC)", s)
+    }
+}
+
+/// An explicitly named output of `split --out NAME=SPEC`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedOutput {
+    /// File name; `.pdf` is appended unless already present
+    pub name: String,
+    /// Page spec, resolved against the input's page count
+    pub spec: String,
+}
+
+impl std::str::FromStr for NamedOutput {
+    type Err = anyhow::Error;
+
+    /// `NAME=SPEC`, e.g. `chapter1=1-5` or `appendix=20-`
+    fn from_str(s: &str) -> Result<Self> {
+        let Some((name, spec)) = s.split_once('=') else { anyhow::bail!("无效的输出定义: {} (应为 NAME=SPEC)", s) };
+        let (name, spec) = (name.trim(), spec.trim());
+        if name.is_empty() || name.contains(['/', '\\']) { anyhow::bail!("无效的输出名: {:?}", name); }
+        if spec.is_empty() { anyhow::bail!("输出 {} 的页码范围为空", name); }
+        Ok(NamedOutput { name: name.to_string(), spec: spec.to_string() })
+    }
+}
+
+impl NamedOutput {
+    fn file_name(&self) -> String {
+        if self.name.to_ascii_lowercase().ends_with(".pdf") { self.name.clone() } else { format!("{}.pdf", self.name) }
+    }
+}
+
+/// A per-input pre-filter of `split --pages-for GLOB=SPEC`: inputs whose file name matches
+/// `glob` are cut down to the pages of `spec` before being split.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PagesRule {
+    pub glob: String,
+    pub spec: String,
+}
+
+impl std::str::FromStr for PagesRule {
+    type Err = anyhow::Error;
+
+    /// `GLOB=SPEC`, e.g. `*cover*=1` or `scan-*.pdf=2-`
+    fn from_str(s: &str) -> Result<Self> {
+        let Some((glob, spec)) = s.rsplit_once('=') else { anyhow::bail!("无效的页码规则: {} (应为 GLOB=SPEC)", s) };
+        let (glob, spec) = (glob.trim(), spec.trim());
+        globset::Glob::new(glob).with_context(|| format!("无效的 glob: {}", glob))?;
+        if spec.is_empty() { anyhow::bail!("规则 {} 的页码范围为空", glob); }
+        Ok(PagesRule { glob: glob.to_string(), spec: spec.to_string() })
+    }
+}
+
+impl PagesRule {
+    /// The spec of the first rule matching `input`'s file name.
+    fn spec_for<'a>(rules: &'a [PagesRule], input: &Path) -> Option<&'a str> {
+        let name = input.file_name()?;
+        rules.iter()
+            .find(|r| globset::Glob::new(&r.glob).is_ok_and(|g| g.compile_matcher().is_match(name)))
+            .map(|r| r.spec.as_str())
+    }
+}
+
+/// Where `run_dir` puts each input's parts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DirStructure {
+    /// Only PDFs directly in the input directory, all parts in the output directory
+    #[default]
+    Flat,
+    /// PDFs at any depth, each one's parts under its directory relative to the input directory
+    Mirror,
+}
+
+/// How bookmark titles become file names in `run_bookmarks`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum TitleSanitize {
+    /// ASCII letters, digits and `-` only; whitespace becomes `-`, everything else is dropped
+    Strict,
+    /// Like `keep`, with whitespace also turned into `_`
+    SpacesToUnderscore,
+    /// As written, except characters no filesystem accepts (`/\:*?"<>|`, control) become `_`
+    #[default]
+    Keep,
+}
+
+impl TitleSanitize {
+    /// File-name form of `title`, cut to at most `max_len` characters. May be empty.
+    pub fn apply(self, title: &str, max_len: Option<usize>) -> String {
+        let title = title.trim();
+        let unsafe_char = |c: char| c.is_control() || "/\\:*?\"<>|".contains(c);
+        let mut out: String = match self {
+            TitleSanitize::Keep => title.chars().map(|c| if unsafe_char(c) { '_' } else { c }).collect(),
+            TitleSanitize::SpacesToUnderscore => title.chars().map(|c| if unsafe_char(c) || c.is_whitespace() { '_' } else { c }).collect(),
+            TitleSanitize::Strict => {
+                let mut s = String::new();
+                for c in title.chars() {
+                    if c.is_ascii_alphanumeric() { s.push(c); }
+                    else if (c == '-' || c.is_whitespace()) && !s.is_empty() && !s.ends_with('-') { s.push('-'); }
+                }
+                s
+            }
+        };
+        if let Some(n) = max_len {
+            if let Some((cut, _)) = out.char_indices().nth(n) { out.truncate(cut); }
+        }
+        out.trim_end_matches(['-', '_', ' ']).to_string()
+    }
+}
+
+/// One-line preview: group count and the first few ranges
+pub fn summarize_groups(groups: &[PageRange]) -> String {
+    const SHOWN: usize = 5;
+    let mut parts: Vec<String> = groups.iter().take(SHOWN).map(|g| g.to_string()).collect();
+    if groups.len() > SHOWN { parts.push("…".into()); }
+    format!("分组: {} 个 · {}", groups.len(), parts.join(", "))
+}
+
+#[derive(Debug, Clone)]
+pub struct SplitOptions {
+    pub mode: SplitMode,
+    /// Output filename pattern, see `fill_pattern`
+    pub pattern: String,
+    /// `{index}` of the first part
+    pub index_base: usize,
+    pub force: bool,
+    /// Reload the source for every group instead of keeping it in memory
+    pub low_memory: bool,
+    /// Print the group summary before splitting
+    pub preview: bool,
+    /// Reload each written part and check its page count
+    pub verify: bool,
+    /// Remove `/OpenAction`, `/AA`, `/JS` and JavaScript actions from each part
+    pub strip_javascript: bool,
+    /// Extra load attempts on I/O errors, with exponential backoff
+    pub retries: u32,
+    /// Unix permission bits for every part
+    pub chmod: Option<u32>,
+    /// Scale every page's content and boxes by this factor (vector, no rasterizing)
+    pub scale: Option<f32>,
+    /// Copy every page box (Crop/Bleed/Trim/Art, not just MediaBox) onto the extracted pages
+    pub preserve_page_boxes: bool,
+    /// Deepest outline level whose bookmarks start a new part in `run_bookmarks` (1 = chapters)
+    pub toc_level: usize,
+    /// How `run_bookmarks` turns chapter titles into file names
+    pub title_sanitize: TitleSanitize,
+    /// Cut chapter titles to this many characters before naming files
+    pub title_max_len: Option<usize>,
+    /// Header version of every part; fails if a part's content needs a newer one
+    pub pdf_version: PdfVersion,
+    /// Catalog XMP of every part: dropped, copied from the input, or stripped from everything
+    pub xmp: XmpMode,
+    /// Split only this bookmarked section of the input (see `outline::section_range`)
+    pub section: Option<String>,
+    /// Pre-filters for `run`: the first rule matching an input's file name picks the pages that get split
+    pub pages_for: Vec<PagesRule>,
+    /// `run`, `run_named` and `run_bookmarks` stop with `confirm::NeedsConfirmation` before writing
+    /// when they would create more parts
+    pub confirm_above: Option<usize>,
+    /// Layout of `run_dir`'s output directory
+    pub dir_structure: DirStructure,
+}
+
+impl Default for SplitOptions {
+    fn default() -> Self {
+        Self { mode: SplitMode::Each, pattern: "{base}-{start}-{end}.pdf".into(), index_base: 1, force: false, low_memory: false, preview: false, verify: false, strip_javascript: false, retries: 0, chmod: None, scale: None, preserve_page_boxes: true, toc_level: 1, title_sanitize: TitleSanitize::Keep, title_max_len: None, pdf_version: PdfVersion::default(), xmp: XmpMode::default(), section: None, pages_for: Vec::new(), confirm_above: None, dir_structure: DirStructure::Flat }
+    }
+}
+
+pub fn run(input: &Path, out_dir: &Path, opts: &SplitOptions, progress: &dyn ProgressSink) -> Result<()> {
     std::fs::create_dir_all(out_dir)
         .with_context(|| format!("创建输出目录失败: {}", out_dir.display()))?;
 
@@ -13,99 +260,521 @@ pub fn run(input: &Path, out_dir: &Path, each: bool, ranges_spec: Option<&str>,
         .and_then(|s| s.to_str())
         .unwrap_or("output");
 
-    let pdf = Document::load(input).with_context(|| format!("加载 PDF 失败: {}", input.display()))?;
-    let total_pages = pdf.get_pages().len();
-    if total_pages == 0 { anyhow::bail!("输入 PDF 没有可用页面"); }
-
-    // Determine groups
-    let groups: Vec<PageRange> = if each {
-        (1..=total_pages).map(|p| PageRange { start: p, end: Some(p) }).collect()
-    } else if let Some(spec_str) = ranges_spec {
-        spec::parse_spec(spec_str).with_context(|| format!("解析页码范围失败: {}", spec_str))?
-    } else {
-        anyhow::bail!("请使用 --each 或 --ranges 指定分割方式");
+    // Parsed once; every group is extracted from this in-memory template.
+    // In low-memory mode the template is dropped and each group reloads the file instead.
+    let load = || load::load_pdf(input, opts.retries, progress);
+    let mut template = Some(load()?);
+    let source_pages = template.as_ref().map(|d| d.get_pages().len()).unwrap_or(0);
+    if source_pages == 0 { anyhow::bail!("输入 PDF 没有可用页面"); }
+    // Source page indexes that take part; groups below count only these, but output names use
+    // the source page numbers
+    let rule = PagesRule::spec_for(&opts.pages_for, input);
+    let selected: Vec<usize> = match (&opts.section, rule) {
+        (Some(name), _) => {
+            let source = template.as_ref().expect("loaded above");
+            let (start, end) = outline::section_range(&outline::read_outline(source), source_pages, name)?;
+            (start - 1..end).collect()
+        }
+        (None, Some(spec_str)) => {
+            let ranges = spec::parse_spec_with_phrases(spec_str, source_pages)
+                .with_context(|| format!("解析页码范围失败: {}", spec_str))?;
+            let selected = spec::expand_to_indexes(&ranges, source_pages);
+            if selected.is_empty() { anyhow::bail!("页码范围 {} 未选中任何页面 (总页数 {})", spec_str, source_pages); }
+            selected
+        }
+        (None, None) => (0..source_pages).collect(),
     };
+    let total_pages = selected.len();
+    let xmp = if opts.xmp == XmpMode::Copy { template.as_ref().and_then(meta::read_xmp) } else { None };
+    if opts.low_memory { template = None; }
+
+    if let SplitMode::Into(n) = opts.mode {
+        if n > total_pages {
+            eprintln!("⚠️ 份数 {} 超过总页数 {}，按 {} 份分割", n, total_pages, total_pages);
+        }
+    }
+    let groups = opts.mode.groups(total_pages);
+    confirm::check("创建", groups.len(), opts.confirm_above)?;
+    if opts.preview { println!("{}", summarize_groups(&groups)); }
 
     progress.set_len(groups.len() as u64);
     progress.set_message(std::borrow::Cow::from("准备分割..."));
+    let mut failures: Vec<String> = Vec::new();
 
     for (idx, g) in groups.iter().enumerate() {
         let start = g.start.max(1);
         let end = g.end.unwrap_or(total_pages).min(total_pages);
         if end < start { continue; }
 
-        let mut out_doc = Document::with_version("1.5");
-        let mut page_ids: Vec<ObjectId> = Vec::new();
-
-        // Load fresh copy to avoid side effects
-        let mut part_pdf = Document::load(input).with_context(|| format!("加载 PDF 失败: {}", input.display()))?;
-        let offset = out_doc.max_id + 1;
-        part_pdf.renumber_objects_with(offset);
-        out_doc.max_id = part_pdf.max_id;
-
-        // collect pages in selected range (1-based)
-        let pages_map = part_pdf.get_pages();
-        for (i, (_, pid)) in pages_map.into_iter().enumerate() {
-            let p1 = i + 1; // 1-based
-            if p1 >= start && p1 <= end {
-                page_ids.push(pid);
-            }
+        let indexes: Vec<usize> = selected[start - 1..end].to_vec();
+        let out_doc = match &template {
+            Some(source) => extract_pages(source, &indexes, opts.preserve_page_boxes)?,
+            None => extract_pages(&load()?, &indexes, opts.preserve_page_boxes)?,
+        };
+        let out_name = fill_pattern(&opts.pattern, base, selected[start - 1] + 1, selected[end - 1] + 1, opts.index_base + idx);
+        write_part(out_doc, &out_dir.join(out_name), end - start + 1, xmp.as_ref(), opts, &mut failures)?;
+        progress.inc(1);
+    }
+    if !failures.is_empty() {
+        progress.finish(std::borrow::Cow::from("分割校验失败"));
+        anyhow::bail!("{} 个输出校验失败:\n{}", failures.len(), failures.join("\n"));
+    }
+    progress.finish(std::borrow::Cow::from("分割完成"));
+    tracing::info!(name: "split_complete", input = %input.display(), parts = groups.len(), "split_complete");
+    Ok(())
+}
+
+/// Split every PDF directly under `input_dir` into `out_dir` (same `opts` for each), up to `jobs`
+/// files at a time. With `DirStructure::Mirror` PDFs in subdirectories are split too, into the
+/// same subdirectory of `out_dir` (outputs already inside `out_dir` are not picked up again).
+/// `progress` counts files; each file in flight gets its own bar. Results are in file-name order
+/// whatever order the workers finish in; one failing file does not stop the rest.
+pub fn run_dir(input_dir: &Path, out_dir: &Path, opts: &SplitOptions, jobs: usize, progress: &MultiBarProgress) -> Result<Vec<(PathBuf, Result<()>)>> {
+    let mirror = opts.dir_structure == DirStructure::Mirror;
+    let cfg = ScanConfig { input_dir: input_dir.to_path_buf(), includes: Vec::new(), excludes: Vec::new(), extra_exclude_paths: Vec::new(), max_depth: if mirror { None } else { Some(1) }, follow_links: false, case_insensitive: false, contains: None };
+    let mut files = scan::collect_pdfs_cfg(&cfg)?;
+    if mirror && out_dir != input_dir {
+        files.retain(|f| !f.starts_with(out_dir));
+    }
+    if files.is_empty() { anyhow::bail!("目录中没有 PDF: {}", input_dir.display()); }
+    // Created once up front so the workers never race on it
+    std::fs::create_dir_all(out_dir)
+        .with_context(|| format!("创建输出目录失败: {}", out_dir.display()))?;
+
+    progress.set_len(files.len() as u64);
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<Result<()>>>> = Mutex::new(files.iter().map(|_| None).collect());
+    std::thread::scope(|s| {
+        for _ in 0..jobs.clamp(1, files.len()) {
+            s.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(file) = files.get(i) else { break };
+                let bar = progress.add_bar();
+                let target = match file.parent().and_then(|p| p.strip_prefix(input_dir).ok()) {
+                    Some(rel) if mirror => out_dir.join(rel),
+                    _ => out_dir.to_path_buf(),
+                };
+                let res = run(file, &target, opts, &bar);
+                bar.finish_and_clear();
+                progress.inc(1);
+                if let Ok(mut r) = results.lock() { r[i] = Some(res); }
+            });
         }
+    });
+    progress.finish(std::borrow::Cow::from("分割完成"));
+    let results = results.into_inner().unwrap_or_default();
+    Ok(files.into_iter().zip(results).map(|(f, r)| (f, r.unwrap_or_else(|| Err(anyhow::anyhow!("未处理"))))).collect())
+}
 
-        // extend objects (includes resources), then rebuild tree
-        out_doc.objects.extend(part_pdf.objects);
+/// Default state file of `watch`, kept in the output directory.
+pub const WATCH_STATE_FILE: &str = ".pdf-ops-watch.json";
 
-        let pages_id = out_doc.new_object_id();
-        for &pid in &page_ids {
-            let page_obj = out_doc
-                .objects
-                .get_mut(&pid)
-                .ok_or_else(|| anyhow::anyhow!("页面对象不存在: {:?}", pid))?;
-            match page_obj.as_dict_mut() {
-                Ok(page_dict) => {
-                    page_dict.set("Parent", Object::Reference(pages_id));
-                }
-                Err(_) => {
-                    anyhow::bail!("页面对象不是字典: {:?}", pid);
-                }
+/// What `watch` already split: input file names with the modification time they had
+/// (nanoseconds since the epoch). Saved after every file, so a restarted watch skips them; a
+/// file whose modification time changes is split again.
+#[derive(Debug)]
+pub struct WatchState {
+    path: PathBuf,
+    done: BTreeMap<String, u64>,
+}
+
+impl WatchState {
+    /// Read the state file at `path`; a missing file is an empty state.
+    pub fn load(path: &Path) -> Result<Self> {
+        let done = match std::fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text).with_context(|| format!("解析监视状态失败: {}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e).with_context(|| format!("读取监视状态失败: {}", path.display())),
+        };
+        Ok(Self { path: path.to_path_buf(), done })
+    }
+
+    fn is_done(&self, name: &str, mtime: u64) -> bool {
+        self.done.get(name) == Some(&mtime)
+    }
+
+    fn record(&mut self, name: String, mtime: u64) -> Result<()> {
+        self.done.insert(name, mtime);
+        let json = serde_json::to_string_pretty(&self.done).expect("serializable");
+        crate::interrupt::write_via_part(&self.path, |tmp| Ok(std::fs::write(tmp, json)?))
+            .with_context(|| format!("写入监视状态失败: {}", self.path.display()))
+    }
+}
+
+/// Split every PDF directly under `input_dir` that is not in `state` yet, then keep polling
+/// every `interval` for new or changed ones until `stop` returns true. A file is split once
+/// its size and modification time are the same on two polls in a row, so one still being
+/// copied in is left for later. Files are split one at a time; `report` gets each result, and
+/// a failed file is recorded too so it is not retried until it changes.
+pub fn watch(
+    input_dir: &Path,
+    out_dir: &Path,
+    opts: &SplitOptions,
+    state: &mut WatchState,
+    interval: Duration,
+    stop: &dyn Fn() -> bool,
+    report: &mut dyn FnMut(&Path, &Result<()>),
+) -> Result<()> {
+    std::fs::create_dir_all(out_dir)
+        .with_context(|| format!("创建输出目录失败: {}", out_dir.display()))?;
+    // Parts written next to the inputs would be picked up and split again
+    if input_dir.canonicalize()? == out_dir.canonicalize()? {
+        anyhow::bail!("--watch 的输出目录不能是输入目录: {}", out_dir.display());
+    }
+    let cfg = ScanConfig { input_dir: input_dir.to_path_buf(), includes: Vec::new(), excludes: Vec::new(), extra_exclude_paths: Vec::new(), max_depth: Some(1), follow_links: false, case_insensitive: false, contains: None };
+    // Size and modification time at the last poll, for files not split yet
+    let mut pending: HashMap<PathBuf, (u64, u64)> = HashMap::new();
+    while !stop() {
+        for file in scan::collect_pdfs_cfg(&cfg)? {
+            let Ok(meta) = std::fs::metadata(&file) else { continue };
+            let mtime = meta.modified().ok().and_then(|t| t.duration_since(UNIX_EPOCH).ok()).map_or(0, |d| d.as_nanos() as u64);
+            let name = file.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+            if state.is_done(&name, mtime) { continue; }
+            let stamp = (meta.len(), mtime);
+            if pending.insert(file.clone(), stamp) != Some(stamp) { continue; }
+            pending.remove(&file);
+            let res = run(&file, out_dir, opts, &NullProgress);
+            report(&file, &res);
+            state.record(name, mtime)?;
+            if stop() { return Ok(()); }
+        }
+        let slept = Instant::now();
+        while slept.elapsed() < interval && !stop() {
+            std::thread::sleep(interval.min(Duration::from_millis(100)));
+        }
+    }
+    Ok(())
+}
+
+/// `split --watch`: `watch` with the state kept at `state_path`, printing each result, until
+/// Ctrl-C, which stops it between files.
+pub fn run_watch(input_dir: &Path, out_dir: &Path, state_path: &Path, interval: Duration, opts: &SplitOptions) -> Result<()> {
+    let mut state = WatchState::load(state_path)?;
+    println!("👀 监视 {} -> {}（Ctrl-C 停止）", input_dir.display(), out_dir.display());
+    crate::interrupt::set_graceful(true);
+    let res = watch(input_dir, out_dir, opts, &mut state, interval, &crate::interrupt::stop_requested, &mut |file, res| {
+        match res {
+            Ok(()) => println!("  ✅ {}", file.display()),
+            Err(e) => {
+                tracing::error!(name: "split_failed", input = %file.display(), error = %format!("{:#}", e), "split_failed");
+                println!("  ❌ {}: {:#}", file.display(), e);
             }
         }
-        let kids: Vec<Object> = page_ids.iter().map(|&id| Object::Reference(id)).collect();
-        let mut pages_dict = Dictionary::new();
-        pages_dict.set("Type", "Pages");
-        pages_dict.set("Kids", Object::Array(kids));
-        pages_dict.set("Count", page_ids.len() as i64);
-        out_doc.objects.insert(pages_id, Object::Dictionary(pages_dict));
-
-        let catalog_id = out_doc.new_object_id();
-        let mut catalog_dict = Dictionary::new();
-        catalog_dict.set("Type", "Catalog");
-        catalog_dict.set("Pages", Object::Reference(pages_id));
-        out_doc.objects.insert(catalog_id, Object::Dictionary(catalog_dict));
-
-        out_doc.trailer = Dictionary::new();
-        out_doc.trailer.set("Root", Object::Reference(catalog_id));
-        out_doc.compress();
-
-        let out_name = fill_pattern(pattern, base, start, end, idx + 1);
-        let mut out_path = out_dir.join(out_name);
-        if out_path.exists() && !force {
-            out_path = ensure_unique_path(&out_path);
-        }
-        if let Some(parent) = out_path.parent() { std::fs::create_dir_all(parent).ok(); }
-        out_doc.save(&out_path).with_context(|| format!("写入输出失败: {}", out_path.display()))?;
+    });
+    crate::interrupt::set_graceful(false);
+    res?;
+    println!("⏹️ 已停止监视");
+    Ok(())
+}
+
+/// Write one output per `NamedOutput`, each with the pages of its own spec (in document order).
+/// Every spec is checked against the input before anything is written.
+pub fn run_named(input: &Path, out_dir: &Path, outputs: &[NamedOutput], opts: &SplitOptions, progress: &dyn ProgressSink) -> Result<()> {
+    std::fs::create_dir_all(out_dir)
+        .with_context(|| format!("创建输出目录失败: {}", out_dir.display()))?;
+    let source = load::load_pdf(input, opts.retries, progress)?;
+    let total_pages = source.get_pages().len();
+    if total_pages == 0 { anyhow::bail!("输入 PDF 没有可用页面"); }
+
+    let mut seen = BTreeSet::new();
+    let mut plan: Vec<(String, Vec<usize>)> = Vec::new();
+    for out in outputs {
+        let file_name = out.file_name();
+        if !seen.insert(file_name.clone()) { anyhow::bail!("输出名重复: {}", file_name); }
+        let ranges = spec::parse_spec_with_phrases(&out.spec, total_pages)
+            .with_context(|| format!("解析页码范围失败: {}", out.spec))?;
+        let indexes = spec::expand_to_indexes(&ranges, total_pages);
+        if indexes.is_empty() { anyhow::bail!("输出 {} 没有页面: {} (总页数 {})", file_name, out.spec, total_pages); }
+        plan.push((file_name, indexes));
+    }
+    confirm::check("创建", plan.len(), opts.confirm_above)?;
+    write_plan(input, &source, out_dir, &plan, opts, progress)
+}
+
+/// Split at top-level bookmarks, one output per chapter named after its title. With `max`,
+/// a chapter longer than `max` pages is cut into `max`-page pieces named `{title}-1`,
+/// `{title}-2`, …. Pages before the first bookmark go to `{base}-front`. Titles are cleaned up
+/// per `opts.title_sanitize` and `opts.title_max_len`.
+pub fn run_bookmarks(input: &Path, out_dir: &Path, max: Option<usize>, opts: &SplitOptions, progress: &dyn ProgressSink) -> Result<()> {
+    std::fs::create_dir_all(out_dir)
+        .with_context(|| format!("创建输出目录失败: {}", out_dir.display()))?;
+    let source = load::load_pdf(input, opts.retries, progress)?;
+    let total_pages = source.get_pages().len();
+    if total_pages == 0 { anyhow::bail!("输入 PDF 没有可用页面"); }
+    let base = input.file_stem().and_then(|s| s.to_str()).unwrap_or("output");
+
+    let items = outline::read_outline(&source);
+    let depth = items.iter().map(|i| i.level).max().unwrap_or(0);
+    if depth > 0 && opts.toc_level > depth {
+        anyhow::bail!("书签只有 {} 级，无法按第 {} 级分割 (--toc-level)", depth, opts.toc_level);
+    }
+    let chapters = bookmark_chapters(&items, total_pages, base, opts);
+    if chapters.is_empty() { anyhow::bail!("输入 PDF 没有可用的顶层书签"); }
+    let mut seen = BTreeSet::new();
+    let mut plan: Vec<(String, Vec<usize>)> = Vec::new();
+    for (title, range) in chapters {
+        let len = range.end.unwrap_or(total_pages) - range.start + 1;
+        let pieces = match max {
+            Some(n) if len > n => SplitMode::Every(n).groups(len),
+            _ => vec![PageRange { start: 1, end: Some(len) }],
+        };
+        let numbered = pieces.len() > 1;
+        for (i, piece) in pieces.iter().enumerate() {
+            let stem = if numbered { format!("{}-{}", title, i + 1) } else { title.clone() };
+            let name = unique_name(&mut seen, &stem);
+            let first = range.start - 1 + piece.start;
+            let last = range.start - 1 + piece.end.unwrap_or(len);
+            plan.push((name, (first - 1..last).collect()));
+        }
+    }
+    confirm::check("创建", plan.len(), opts.confirm_above)?;
+    write_plan(input, &source, out_dir, &plan, opts, progress)
+}
+
+// `<stem>.pdf`, or `<stem>-2.pdf`, `<stem>-3.pdf`, … when an earlier part took it; compared
+// ignoring case, since "Intro" and "intro" are one file on case-insensitive filesystems
+fn unique_name(seen: &mut BTreeSet<String>, stem: &str) -> String {
+    let mut name = format!("{}.pdf", stem);
+    let mut dup = 1;
+    while !seen.insert(name.to_lowercase()) {
+        dup += 1;
+        name = format!("{}-{}.pdf", stem, dup);
+    }
+    name
+}
+
+// Bookmarks down to `opts.toc_level` as (file-safe title, page range), in page order; a
+// chapter's own pages before its first section stay a part of their own
+fn bookmark_chapters(items: &[outline::OutlineItem], total_pages: usize, base: &str, opts: &SplitOptions) -> Vec<(String, PageRange)> {
+    let mut starts: Vec<(usize, &str)> = Vec::new();
+    for item in items.iter().filter(|i| i.level <= opts.toc_level.max(1)) {
+        let Some(page) = item.page.filter(|p| (1..=total_pages).contains(p)) else { continue };
+        // A chapter sharing its first page with an earlier one would be empty
+        if starts.iter().any(|&(p, _)| p == page) { continue; }
+        starts.push((page, &item.title));
+    }
+    starts.sort_by_key(|&(p, _)| p);
+    let mut out = Vec::new();
+    if let Some(&(first, _)) = starts.first() {
+        if first > 1 { out.push((format!("{}-front", base), PageRange { start: 1, end: Some(first - 1) })); }
+    }
+    for (i, &(start, title)) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).map(|&(p, _)| p - 1).unwrap_or(total_pages);
+        let mut name = opts.title_sanitize.apply(title, opts.title_max_len);
+        if name.is_empty() { name = format!("{}-{}", base, i + 1); }
+        out.push((name, PageRange { start, end: Some(end) }));
+    }
+    out
+}
+
+// Write every (file name, 0-based page indexes) entry of `plan` from `source`
+fn write_plan(input: &Path, source: &Document, out_dir: &Path, plan: &[(String, Vec<usize>)], opts: &SplitOptions, progress: &dyn ProgressSink) -> Result<()> {
+    if opts.preview {
+        for (name, idx) in plan { println!("{}: {} 页", name, idx.len()); }
+    }
+
+    progress.set_len(plan.len() as u64);
+    progress.set_message(std::borrow::Cow::from("准备分割..."));
+    let mut failures: Vec<String> = Vec::new();
+    let xmp = if opts.xmp == XmpMode::Copy { meta::read_xmp(source) } else { None };
+    for (name, indexes) in plan {
+        let out_doc = extract_pages(source, indexes, opts.preserve_page_boxes)?;
+        write_part(out_doc, &out_dir.join(name), indexes.len(), xmp.as_ref(), opts, &mut failures)?;
         progress.inc(1);
     }
+    if !failures.is_empty() {
+        progress.finish(std::borrow::Cow::from("分割校验失败"));
+        anyhow::bail!("{} 个输出校验失败:\n{}", failures.len(), failures.join("\n"));
+    }
     progress.finish(std::borrow::Cow::from("分割完成"));
+    tracing::info!(name: "split_complete", input = %input.display(), parts = plan.len(), "split_complete");
+    Ok(())
+}
+
+// Scale, sanitize, compress and save one part, then apply --chmod and --verify.
+// Verify failures are collected so the remaining parts are still written.
+// `xmp` is the input's catalog metadata to copy onto the part, if any.
+fn write_part(mut out_doc: Document, out_path: &Path, expected_pages: usize, xmp: Option<&Stream>, opts: &SplitOptions, failures: &mut Vec<String>) -> Result<()> {
+    if let Some(factor) = opts.scale {
+        for page_id in out_doc.get_pages().into_values() {
+            page::scale_page(&mut out_doc, page_id, factor)?;
+        }
+    }
+    if opts.strip_javascript {
+        sanitize::strip_javascript(&mut out_doc);
+    }
+    if let Some(xmp) = xmp {
+        meta::set_catalog_xmp(&mut out_doc, xmp.clone())?;
+    }
+    if opts.xmp == XmpMode::Strip {
+        meta::strip_xmp(&mut out_doc);
+    }
+    version::apply(&mut out_doc, opts.pdf_version)?;
+    out_doc.compress();
+
+    let mut out_path = out_path.to_path_buf();
+    if out_path.exists() && !opts.force {
+        out_path = ensure_unique_path(&out_path);
+    }
+    if let Some(parent) = out_path.parent() { std::fs::create_dir_all(parent).ok(); }
+    crate::interrupt::save(&mut out_doc, &out_path)?;
+    if let Some(mode) = opts.chmod { pathutil::set_mode(&out_path, mode)?; }
+    tracing::info!(name: "part_written", path = %out_path.display(), pages = expected_pages, "part_written");
+    if opts.verify {
+        if let Err(e) = verify_output(&out_path, expected_pages) {
+            failures.push(format!("{:#}", e));
+        }
+    }
     Ok(())
 }
 
+const BAD_SUFFIX: &str = "bad";
+
+/// Reload a written part and check it has `expected` pages. A part that fails is renamed
+/// to `<name>.bad` so it cannot be mistaken for a good output.
+pub(crate) fn verify_output(path: &Path, expected: usize) -> Result<()> {
+    let found = Document::load(path).map(|d| d.get_pages().len());
+    let problem = match found {
+        Ok(n) if n == expected => return Ok(()),
+        Ok(n) => format!("页数 {} ≠ 预期 {}", n, expected),
+        Err(e) => format!("无法重新加载: {}", e),
+    };
+    let mut flagged = path.as_os_str().to_owned();
+    flagged.push(".");
+    flagged.push(BAD_SUFFIX);
+    std::fs::rename(path, &flagged).with_context(|| format!("标记损坏输出失败: {}", path.display()))?;
+    anyhow::bail!("{}: {} (已重命名为 {})", path.display(), problem, Path::new(&flagged).display())
+}
+
+// Build a standalone document with pages start..=end (1-based) of `source`.
+// Only objects reachable from the selected pages are deep-cloned, so each group costs
+// roughly the size of its own pages rather than the whole file.
+pub(crate) fn extract_range(source: &Document, start: usize, end: usize) -> Result<Document> {
+    let indexes: Vec<usize> = (start.max(1) - 1..end).collect();
+    extract_pages(source, &indexes, true)
+}
+
+// Same, for arbitrary 0-based page indexes (kept in document order). `pin_boxes` also copies
+// page boxes held by ancestors (see `page::pin_page_boxes`), not just the inheritable ones.
+fn extract_pages(source: &Document, indexes: &[usize], pin_boxes: bool) -> Result<Document> {
+    let wanted: BTreeSet<usize> = indexes.iter().copied().collect();
+    let page_ids: Vec<ObjectId> = source
+        .get_pages()
+        .into_values()
+        .enumerate()
+        .filter(|(i, _)| wanted.contains(i))
+        .map(|(_, pid)| pid)
+        .collect();
+    let selected: BTreeSet<ObjectId> = page_ids.iter().copied().collect();
+
+    let mut out_doc = Document::with_version("1.5");
+    out_doc.max_id = source.max_id;
+
+    // Pages first: inherited attributes are resolved since the old page tree is not copied
+    let mut pending: Vec<ObjectId> = Vec::new();
+    for &pid in &page_ids {
+        let mut page = source
+            .get_dictionary(pid)
+            .map_err(|_| anyhow::anyhow!("页面对象不是字典: {:?}", pid))?
+            .clone();
+        inherit_page_attributes(source, &mut page);
+        if pin_boxes { page::pin_page_boxes(source, &mut page); }
+        page.remove(b"Parent");
+        collect_dict_refs(&page, &mut pending);
+        out_doc.objects.insert(pid, Object::Dictionary(page));
+    }
+    // Then everything they reference (resources, content streams, annotations, ...)
+    while let Some(id) = pending.pop() {
+        if out_doc.objects.contains_key(&id) { continue; }
+        // Dangling references stay dangling (read as null)
+        let Ok(obj) = source.get_object(id) else { continue };
+        // Unselected pages reached via links/annotations are not pulled in
+        if obj.as_dict().map(is_page_dict).unwrap_or(false) && !selected.contains(&id) { continue; }
+        collect_refs(obj, &mut pending);
+        out_doc.objects.insert(id, obj.clone());
+    }
+
+    let pages_id = out_doc.new_object_id();
+    for &pid in &page_ids {
+        let page_obj = out_doc
+            .objects
+            .get_mut(&pid)
+            .ok_or_else(|| anyhow::anyhow!("页面对象不存在: {:?}", pid))?;
+        match page_obj.as_dict_mut() {
+            Ok(page_dict) => {
+                page_dict.set("Parent", Object::Reference(pages_id));
+            }
+            Err(_) => {
+                anyhow::bail!("页面对象不是字典: {:?}", pid);
+            }
+        }
+    }
+    let kids: Vec<Object> = page_ids.iter().map(|&id| Object::Reference(id)).collect();
+    let mut pages_dict = Dictionary::new();
+    pages_dict.set("Type", "Pages");
+    pages_dict.set("Kids", Object::Array(kids));
+    pages_dict.set("Count", page_ids.len() as i64);
+    out_doc.objects.insert(pages_id, Object::Dictionary(pages_dict));
+
+    let catalog_id = out_doc.new_object_id();
+    let mut catalog_dict = Dictionary::new();
+    catalog_dict.set("Type", "Catalog");
+    catalog_dict.set("Pages", Object::Reference(pages_id));
+    out_doc.objects.insert(catalog_id, Object::Dictionary(catalog_dict));
+
+    out_doc.trailer = Dictionary::new();
+    out_doc.trailer.set("Root", Object::Reference(catalog_id));
+    Ok(out_doc)
+}
+
+fn is_page_dict(d: &Dictionary) -> bool {
+    matches!(d.get(b"Type"), Ok(Object::Name(n)) if n == b"Page" || n == b"Pages")
+}
+
+// Push every reference held by `obj`
+fn collect_refs(obj: &Object, out: &mut Vec<ObjectId>) {
+    match obj {
+        Object::Reference(id) => out.push(*id),
+        Object::Array(arr) => arr.iter().for_each(|o| collect_refs(o, out)),
+        Object::Dictionary(d) => collect_dict_refs(d, out),
+        Object::Stream(st) => collect_dict_refs(&st.dict, out),
+        _ => {}
+    }
+}
+
+// A page node's /Parent is skipped so the old page tree isn't dragged along
+fn collect_dict_refs(d: &Dictionary, out: &mut Vec<ObjectId>) {
+    let page_like = is_page_dict(d);
+    for (k, v) in d.iter() {
+        if page_like && k == b"Parent" { continue; }
+        collect_refs(v, out);
+    }
+}
+
+// Replace {base},{start},{end},{index}; numeric tokens accept zero-padding like {index:03}.
+// Unknown tokens are kept verbatim.
 fn fill_pattern(pattern: &str, base: &str, start: usize, end: usize, index: usize) -> String {
-    pattern
-        .replace("{base}", base)
-        .replace("{start}", &start.to_string())
-        .replace("{end}", &end.to_string())
-        .replace("{index}", &index.to_string())
+    let mut out = String::with_capacity(pattern.len());
+    let mut rest = pattern;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let Some(close) = after.find('}') else { out.push_str(&rest[open..]); return out; };
+        let token = &after[..close];
+        let (name, width) = match token.split_once(':') {
+            Some((n, w)) => (n, w.parse::<usize>().ok()),
+            None => (token, None),
+        };
+        let num = match name { "start" => Some(start), "end" => Some(end), "index" => Some(index), _ => None };
+        match (name, num) {
+            ("base", _) if width.is_none() => out.push_str(base),
+            (_, Some(n)) => out.push_str(&format!("{:0w$}", n, w = width.unwrap_or(0))),
+            _ => { out.push('{'); out.push_str(token); out.push('}'); }
+        }
+        rest = &after[close + 1..];
+    }
+    out.push_str(rest);
+    out
 }
 
 fn ensure_unique_path(p: &std::path::Path) -> std::path::PathBuf {
@@ -124,3 +793,150 @@ fn ensure_unique_path(p: &std::path::Path) -> std::path::PathBuf {
         if i > 10000 { return cand; }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::Stream;
+
+    #[test]
+    fn pages_rules_match_file_names_first_wins() {
+        let rules: Vec<PagesRule> = ["*cover*=1", "*.pdf=2-"].iter().map(|r| r.parse().unwrap()).collect();
+        assert_eq!(PagesRule::spec_for(&rules, Path::new("in/book-cover.pdf")), Some("1"));
+        assert_eq!(PagesRule::spec_for(&rules, Path::new("in/book.pdf")), Some("2-"));
+        assert_eq!(PagesRule::spec_for(&rules[..1], Path::new("in/book.pdf")), None);
+        assert!("[bad=1".parse::<PagesRule>().is_err());
+        assert!("*.pdf=".parse::<PagesRule>().is_err());
+    }
+
+    #[test]
+    fn bookmark_chapters_cover_front_matter_and_skip_dangling() {
+        let item = |title: &str, level, page| outline::OutlineItem { title: title.into(), level, page };
+        let items = [item("Intro", 1, Some(3)), item("1.1", 2, Some(4)), item("Guide/Ref", 1, Some(6)), item("Lost", 1, None), item("", 1, Some(9))];
+        let chapters = bookmark_chapters(&items, 10, "manual", &SplitOptions::default());
+        let got: Vec<(String, usize, Option<usize>)> = chapters.into_iter().map(|(t, r)| (t, r.start, r.end)).collect();
+        assert_eq!(got, vec![
+            ("manual-front".into(), 1, Some(2)),
+            ("Intro".into(), 3, Some(5)),
+            ("Guide_Ref".into(), 6, Some(8)),
+            ("manual-3".into(), 9, Some(10)),
+        ]);
+    }
+
+    #[test]
+    fn repeated_names_get_numbered_suffixes() {
+        let mut seen = BTreeSet::new();
+        let names: Vec<String> = ["Intro", "Intro", "intro", "Intro-2"].iter().map(|s| unique_name(&mut seen, s)).collect();
+        assert_eq!(names, ["Intro.pdf", "Intro-2.pdf", "intro-3.pdf", "Intro-2-2.pdf"]);
+    }
+
+    #[test]
+    fn title_sanitize_modes() {
+        let title = " Chapter 1: Intro / Überblick — Café ";
+        assert_eq!(TitleSanitize::Keep.apply(title, None), "Chapter 1_ Intro _ Überblick — Café");
+        assert_eq!(TitleSanitize::SpacesToUnderscore.apply(title, None), "Chapter_1__Intro___Überblick_—_Café");
+        assert_eq!(TitleSanitize::Strict.apply(title, None), "Chapter-1-Intro-berblick-Caf");
+        // Cut by characters, not bytes, and without a dangling separator
+        assert_eq!(TitleSanitize::Keep.apply(title, Some(20)), "Chapter 1_ Intro _ Ü");
+        assert_eq!(TitleSanitize::Strict.apply(title, Some(10)), "Chapter-1");
+        assert_eq!(TitleSanitize::Strict.apply("//:", None), "");
+    }
+
+    // n pages sharing one inherited Resources dict, each with its own content stream
+    fn doc_with_pages(n: usize) -> Document {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let res_id = doc.add_object(Object::Dictionary(Dictionary::new()));
+        let mut kids = Vec::new();
+        for i in 0..n {
+            let cid = doc.add_object(Object::Stream(Stream::new(Dictionary::new(), format!("(p{}) Tj", i + 1).into_bytes())));
+            let mut page = Dictionary::new();
+            page.set("Type", "Page");
+            page.set("Parent", Object::Reference(pages_id));
+            page.set("Contents", Object::Reference(cid));
+            kids.push(Object::Reference(doc.add_object(Object::Dictionary(page))));
+        }
+        let mut pages = Dictionary::new();
+        pages.set("Type", "Pages");
+        pages.set("Count", n as i64);
+        pages.set("Kids", Object::Array(kids));
+        pages.set("Resources", Object::Reference(res_id));
+        pages.set("MediaBox", vec![0.into(), 0.into(), 200.into(), 200.into()]);
+        doc.objects.insert(pages_id, Object::Dictionary(pages));
+        let catalog_id = doc.add_object(Object::Dictionary(Dictionary::from_iter(vec![
+            ("Type", Object::Name(b"Catalog".to_vec())),
+            ("Pages", Object::Reference(pages_id)),
+        ])));
+        doc.trailer.set("Root", Object::Reference(catalog_id));
+        doc
+    }
+
+    #[test]
+    fn split_modes_parse_to_groups() {
+        let pr = |start, end| PageRange { start, end: Some(end) };
+        let each: SplitMode = "each".parse().unwrap();
+        assert_eq!(each.groups(3), vec![pr(1, 1), pr(2, 2), pr(3, 3)]);
+        let every: SplitMode = "every:4".parse().unwrap();
+        assert_eq!(every.groups(10), vec![pr(1, 4), pr(5, 8), pr(9, 10)]);
+        let ranges: SplitMode = "ranges:1-3,7-".parse().unwrap();
+        assert_eq!(ranges.groups(10), vec![pr(1, 3), PageRange { start: 7, end: None }]);
+        let into: SplitMode = "into:3".parse().unwrap();
+        assert_eq!(into.groups(10), vec![pr(1, 4), pr(5, 7), pr(8, 10)]);
+        assert_eq!(into.groups(2), vec![pr(1, 1), pr(2, 2)]);
+        assert!("every:0".parse::<SplitMode>().is_err());
+        assert!("into:0".parse::<SplitMode>().is_err());
+        assert!("ranges:".parse::<SplitMode>().is_err());
+        assert!("chunks".parse::<SplitMode>().is_err());
+    }
+
+    #[test]
+    fn named_outputs_parse() {
+        let o: NamedOutput = "chapter1 = 1-5".parse().unwrap();
+        assert_eq!((o.name.as_str(), o.spec.as_str(), o.file_name().as_str()), ("chapter1", "1-5", "chapter1.pdf"));
+        assert_eq!("Intro.PDF=1".parse::<NamedOutput>().unwrap().file_name(), "Intro.PDF");
+        for bad in ["chapter1", "=1-5", "a/b=1", "x="] {
+            assert!(bad.parse::<NamedOutput>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn summary_lists_first_ranges() {
+        let groups = SplitMode::Every(1).groups(7);
+        assert_eq!(summarize_groups(&groups), "分组: 7 个 · 1, 2, 3, 4, 5, …");
+    }
+
+    #[test]
+    fn extract_copies_only_reachable_objects() {
+        let source = doc_with_pages(10);
+        let part = extract_range(&source, 3, 4).unwrap();
+        let pages = part.get_pages();
+        assert_eq!(pages.len(), 2);
+        let first = pages[&1];
+        assert!(String::from_utf8_lossy(&part.get_page_content(first).unwrap()).contains("(p3)"));
+        // 2 pages + 2 contents + shared resources + new Pages + Catalog
+        assert_eq!(part.objects.len(), 7);
+        // inherited attributes are resolved onto the page
+        let page = part.get_dictionary(first).unwrap();
+        assert!(page.has(b"MediaBox") && page.has(b"Resources"));
+    }
+
+    #[test]
+    fn pattern_tokens_and_padding() {
+        assert_eq!(fill_pattern("{base}-{start}-{end}.pdf", "doc", 1, 3, 1), "doc-1-3.pdf");
+        assert_eq!(fill_pattern("{base}-{index:03}.pdf", "doc", 1, 1, 7), "doc-007.pdf");
+        assert_eq!(fill_pattern("{base}-{other}-{index", "doc", 1, 1, 2), "doc-{other}-{index");
+    }
+
+    #[test]
+    fn verify_flags_page_count_mismatch() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("part.pdf");
+        extract_range(&doc_with_pages(5), 2, 4).unwrap().save(&path).unwrap();
+        verify_output(&path, 3).unwrap();
+
+        let err = verify_output(&path, 4).unwrap_err().to_string();
+        assert!(err.contains("页数 3 ≠ 预期 4"), "{}", err);
+        assert!(!path.exists());
+        assert!(dir.path().join("part.pdf.bad").exists());
+    }
+}
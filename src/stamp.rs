@@ -0,0 +1,129 @@
+use anyhow::{Context, Result};
+use lopdf::content::{Content, Operation};
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream};
+
+use crate::page::{box_of, inherit_page_attributes};
+
+/// Page corner for stamped text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
+const FONT_RES: &str = "FPdfOpsStamp";
+const FONT_SIZE: f32 = 8.0;
+const MARGIN: f32 = 12.0;
+
+/// Add the Helvetica font object used by `stamp_page`; add once per document.
+pub(crate) fn add_font(doc: &mut Document) -> ObjectId {
+    let mut font = Dictionary::new();
+    font.set("Type", "Font");
+    font.set("Subtype", "Type1");
+    font.set("BaseFont", "Helvetica");
+    font.set("Encoding", "WinAnsiEncoding");
+    doc.add_object(font)
+}
+
+/// Draw `text` small and grey in a corner of the page, on top of the existing content.
+/// The standard font has no CJK glyphs, so characters outside printable ASCII become `?`.
+pub(crate) fn stamp_page(doc: &mut Document, page_id: ObjectId, font_id: ObjectId, text: &str, corner: Corner) -> Result<()> {
+    let mut page = doc.get_dictionary(page_id).context("读取页面失败")?.clone();
+    // Materialize inherited Resources/MediaBox so they can be extended on the page itself
+    inherit_page_attributes(doc, &mut page);
+
+    let [llx, lly, urx, ury] = box_of(doc, &page, b"MediaBox").unwrap_or([0.0, 0.0, 612.0, 792.0]);
+    let text: String = text.chars().map(|c| if (' '..='~').contains(&c) { c } else { '?' }).collect();
+    // Helvetica averages about half an em per glyph; close enough for a corner label
+    let width = text.len() as f32 * FONT_SIZE * 0.5;
+    let x = match corner {
+        Corner::TopLeft | Corner::BottomLeft => llx + MARGIN,
+        Corner::TopRight | Corner::BottomRight => (urx - MARGIN - width).max(llx),
+    };
+    let y = match corner {
+        Corner::TopLeft | Corner::TopRight => ury - MARGIN - FONT_SIZE,
+        Corner::BottomLeft | Corner::BottomRight => lly + MARGIN,
+    };
+
+    let mut resources = match page.get(b"Resources") {
+        Ok(Object::Reference(id)) => doc.get_dictionary(*id).cloned().unwrap_or_default(),
+        Ok(Object::Dictionary(d)) => d.clone(),
+        _ => Dictionary::new(),
+    };
+    let mut fonts = match resources.get(b"Font") {
+        Ok(Object::Reference(id)) => doc.get_dictionary(*id).cloned().unwrap_or_default(),
+        Ok(Object::Dictionary(d)) => d.clone(),
+        _ => Dictionary::new(),
+    };
+    fonts.set(FONT_RES, Object::Reference(font_id));
+    resources.set("Font", fonts);
+    page.set("Resources", resources);
+
+    let stamp = Content {
+        operations: vec![
+            // closes the `q` pushed in front of the original content
+            Operation::new("Q", vec![]),
+            Operation::new("q", vec![]),
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec![Object::Name(FONT_RES.into()), FONT_SIZE.into()]),
+            Operation::new("g", vec![0.4.into()]),
+            Operation::new("Td", vec![x.into(), y.into()]),
+            Operation::new("Tj", vec![Object::string_literal(text)]),
+            Operation::new("ET", vec![]),
+            Operation::new("Q", vec![]),
+        ],
+    };
+    let push_id = doc.add_object(Stream::new(Dictionary::new(), b"q\n".to_vec()));
+    let stamp_id = doc.add_object(Stream::new(Dictionary::new(), stamp.encode()?));
+    let mut contents = vec![Object::Reference(push_id)];
+    match page.get(b"Contents") {
+        Ok(Object::Array(arr)) => contents.extend(arr.iter().cloned()),
+        Ok(obj @ Object::Reference(_)) => contents.push(obj.clone()),
+        _ => {}
+    }
+    contents.push(Object::Reference(stamp_id));
+    page.set("Contents", contents);
+
+    doc.objects.insert(page_id, Object::Dictionary(page));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn one_page(contents: Option<&[u8]>) -> (Document, ObjectId) {
+        let mut doc = Document::with_version("1.5");
+        let mut page = Dictionary::new();
+        page.set("Type", "Page");
+        page.set("MediaBox", vec![0.into(), 0.into(), 200.into(), 300.into()]);
+        if let Some(bytes) = contents {
+            let cid = doc.add_object(Stream::new(Dictionary::new(), bytes.to_vec()));
+            page.set("Contents", Object::Reference(cid));
+        }
+        let page_id = doc.add_object(page);
+        (doc, page_id)
+    }
+
+    #[test]
+    fn stamp_wraps_original_and_adds_font() {
+        let (mut doc, page_id) = one_page(Some(b"1 0 0 1 50 50 cm"));
+        let font_id = add_font(&mut doc);
+        stamp_page(&mut doc, page_id, font_id, "报告 a.pdf", Corner::TopLeft).unwrap();
+
+        let content = String::from_utf8(doc.get_page_content(page_id).unwrap()).unwrap();
+        assert!(content.starts_with("q\n"));
+        assert!(content.contains("cm"));
+        assert!(content.contains("(?? a.pdf) Tj"), "{}", content);
+        // top-left: x = margin, y = height - margin - font size
+        assert!(content.contains("12 280 Td"), "{}", content);
+
+        let page = doc.get_dictionary(page_id).unwrap();
+        let fonts = page.get(b"Resources").unwrap().as_dict().unwrap().get(b"Font").unwrap().as_dict().unwrap();
+        assert_eq!(fonts.get(FONT_RES.as_bytes()).unwrap().as_reference().unwrap(), font_id);
+    }
+}
@@ -0,0 +1,331 @@
+//! Crude page text extraction, for `extract-text` and `--contains`.
+//!
+//! Only strings shown by `Tj`, `TJ`, `'` and `"` are read. A font's `/ToUnicode` CMap is used when
+//! present (`bfchar`/`bfrange` entries); otherwise simple fonts are decoded by their base encoding
+//! (`/Differences` are ignored) and composite (Type0) fonts yield nothing. Strings shown without a
+//! known font are decoded like text strings (UTF-16 with BOM, otherwise UTF-8). Form XObjects are
+//! not entered, and layout is reduced to line breaks at line moves.
+
+use anyhow::{Context, Result};
+use lopdf::content::Content;
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::{meta, spec};
+
+// A TJ adjustment at least this large (thousandths of an em, leftwards) reads as a word gap
+const TJ_SPACE: f32 = 200.0;
+
+/// Text of one page, as printed by `extract-text`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PageText {
+    /// 1-based page number
+    pub page: usize,
+    pub text: String,
+}
+
+/// Code → Unicode mapping from a ToUnicode CMap.
+#[derive(Debug, Default)]
+struct ToUnicode {
+    map: HashMap<u32, String>,
+    // Bytes per code, from the first source code seen (1 for simple fonts, usually 2 for Type0)
+    code_len: usize,
+}
+
+impl ToUnicode {
+    fn parse(data: &[u8]) -> Self {
+        let tokens = cmap_tokens(data);
+        let mut cmap = ToUnicode::default();
+        let mut i = 0;
+        while i < tokens.len() {
+            match tokens[i] {
+                CmapToken::Word("beginbfchar") => {
+                    i += 1;
+                    while let (Some(CmapToken::Hex(src)), Some(CmapToken::Hex(dst))) = (tokens.get(i), tokens.get(i + 1)) {
+                        cmap.insert(src, utf16_hex(dst));
+                        i += 2;
+                    }
+                }
+                CmapToken::Word("beginbfrange") => {
+                    i += 1;
+                    while let (Some(CmapToken::Hex(lo)), Some(CmapToken::Hex(hi))) = (tokens.get(i), tokens.get(i + 1)) {
+                        let (lo_code, hi_code) = (code_value(lo), code_value(hi));
+                        match tokens.get(i + 2) {
+                            Some(CmapToken::Hex(dst)) => {
+                                let mut units = utf16_units(dst);
+                                for code in lo_code..=hi_code.min(lo_code.saturating_add(0xFFFF)) {
+                                    cmap.insert_code(lo.len(), code, String::from_utf16_lossy(&units));
+                                    if let Some(last) = units.last_mut() { *last = last.wrapping_add(1); }
+                                }
+                                i += 3;
+                            }
+                            Some(CmapToken::Open) => {
+                                // u64, so stepping past the top of a 4-byte code space cannot overflow
+                                let mut code = u64::from(lo_code);
+                                i += 3;
+                                while let Some(CmapToken::Hex(dst)) = tokens.get(i) {
+                                    if code <= u64::from(hi_code) { cmap.insert_code(lo.len(), code as u32, utf16_hex(dst)); }
+                                    code += 1;
+                                    i += 1;
+                                }
+                                if let Some(CmapToken::Close) = tokens.get(i) { i += 1; }
+                            }
+                            _ => break,
+                        }
+                    }
+                }
+                _ => i += 1,
+            }
+        }
+        cmap
+    }
+
+    fn insert(&mut self, src: &[u8], text: String) {
+        self.insert_code(src.len(), code_value(src), text);
+    }
+
+    fn insert_code(&mut self, len: usize, code: u32, text: String) {
+        if self.code_len == 0 { self.code_len = len.clamp(1, 4); }
+        self.map.insert(code, text);
+    }
+}
+
+enum CmapToken<'a> {
+    Hex(Vec<u8>),
+    Word(&'a str),
+    Open,
+    Close,
+}
+
+// Just enough of PostScript for CMap bodies: hex strings, bare words and array brackets;
+// dictionaries, names and literal strings in the header are skipped over as words
+fn cmap_tokens(data: &[u8]) -> Vec<CmapToken<'_>> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < data.len() {
+        match data[i] {
+            b if b.is_ascii_whitespace() => i += 1,
+            b'%' => while i < data.len() && data[i] != b'\n' && data[i] != b'\r' { i += 1 },
+            b'<' if data.get(i + 1) == Some(&b'<') => i += 2,
+            b'>' if data.get(i + 1) == Some(&b'>') => i += 2,
+            b'<' => {
+                let end = data[i..].iter().position(|&b| b == b'>').map_or(data.len(), |p| i + p);
+                let digits: Vec<u8> = data[i + 1..end].iter().copied().filter(u8::is_ascii_hexdigit).collect();
+                let bytes = digits.chunks(2).map(|c| {
+                    let hi = (c[0] as char).to_digit(16).unwrap_or(0);
+                    let lo = c.get(1).and_then(|&d| (d as char).to_digit(16)).unwrap_or(0);
+                    (hi * 16 + lo) as u8
+                }).collect();
+                out.push(CmapToken::Hex(bytes));
+                i = end + 1;
+            }
+            b'[' => { out.push(CmapToken::Open); i += 1; }
+            b']' => { out.push(CmapToken::Close); i += 1; }
+            _ => {
+                let start = i;
+                while i < data.len() && !data[i].is_ascii_whitespace() && !b"<>[]%".contains(&data[i]) { i += 1; }
+                out.push(CmapToken::Word(std::str::from_utf8(&data[start..i]).unwrap_or("")));
+            }
+        }
+    }
+    out
+}
+
+fn code_value(bytes: &[u8]) -> u32 {
+    bytes.iter().take(4).fold(0, |acc, &b| (acc << 8) | b as u32)
+}
+
+fn utf16_units(bytes: &[u8]) -> Vec<u16> {
+    bytes.chunks(2).map(|c| u16::from_be_bytes([c[0], c.get(1).copied().unwrap_or(0)])).collect()
+}
+
+fn utf16_hex(bytes: &[u8]) -> String {
+    String::from_utf16_lossy(&utf16_units(bytes))
+}
+
+/// How to turn the bytes of a shown string into text, per font.
+#[derive(Debug)]
+enum FontText {
+    ToUnicode(ToUnicode),
+    Simple(String),
+    // Composite font without ToUnicode: its codes mean nothing without the font program
+    Opaque,
+}
+
+impl FontText {
+    fn from_font(doc: &Document, font: &Dictionary) -> Self {
+        let to_unicode = font.get(b"ToUnicode").ok()
+            .and_then(|o| o.as_reference().ok())
+            .and_then(|id| doc.get_object(id).ok())
+            .and_then(|o| o.as_stream().ok())
+            .map(|s| ToUnicode::parse(&s.decompressed_content().unwrap_or_else(|_| s.content.clone())));
+        if let Some(cmap) = to_unicode.filter(|c| !c.map.is_empty()) {
+            return FontText::ToUnicode(cmap);
+        }
+        if font.get(b"Subtype").and_then(Object::as_name_str).ok() == Some("Type0") {
+            return FontText::Opaque;
+        }
+        let encoding = match font.get(b"Encoding") {
+            Ok(Object::Name(name)) => String::from_utf8_lossy(name).into_owned(),
+            Ok(Object::Reference(id)) => base_encoding(doc.get_dictionary(*id).ok()),
+            Ok(Object::Dictionary(dict)) => base_encoding(Some(dict)),
+            _ => "StandardEncoding".to_string(),
+        };
+        FontText::Simple(encoding)
+    }
+
+    fn decode(&self, bytes: &[u8]) -> String {
+        match self {
+            FontText::ToUnicode(cmap) => bytes.chunks(cmap.code_len.max(1))
+                .filter_map(|code| cmap.map.get(&code_value(code)).map(String::as_str))
+                .collect(),
+            FontText::Simple(encoding) => Document::decode_text(Some(encoding), bytes),
+            FontText::Opaque => String::new(),
+        }
+    }
+}
+
+fn base_encoding(dict: Option<&Dictionary>) -> String {
+    dict.and_then(|d| d.get(b"BaseEncoding").ok())
+        .and_then(|o| o.as_name_str().ok())
+        .unwrap_or("StandardEncoding")
+        .to_string()
+}
+
+/// Text shown on a page, in content order. Line moves become a line break; pieces of one line
+/// are joined as they are, since layout programs often split words across operations.
+pub(crate) fn page_text(doc: &Document, page_id: ObjectId) -> String {
+    let Ok(data) = doc.get_page_content(page_id) else { return String::new() };
+    let Ok(content) = Content::decode(&data) else { return String::new() };
+    let fonts: HashMap<Vec<u8>, FontText> = doc.get_page_fonts(page_id).into_iter()
+        .map(|(name, font)| (name, FontText::from_font(doc, font)))
+        .collect();
+    let mut font: Option<&FontText> = None;
+    let decode = |font: Option<&FontText>, s: &[u8]| font.map_or_else(|| meta::decode_text(s), |f| f.decode(s));
+    let mut text = String::new();
+    for op in &content.operations {
+        match (op.operator.as_str(), op.operands.as_slice()) {
+            ("Tf", [Object::Name(name), _]) => font = fonts.get(name),
+            ("Tj", [Object::String(s, _)]) => text.push_str(&decode(font, s)),
+            ("'", [Object::String(s, _)]) | ("\"", [_, _, Object::String(s, _)]) => {
+                text.push('\n');
+                text.push_str(&decode(font, s));
+            }
+            ("TJ", [Object::Array(items)]) => {
+                for item in items {
+                    match item {
+                        Object::String(s, _) => text.push_str(&decode(font, s)),
+                        Object::Integer(n) if (*n as f32) <= -TJ_SPACE => text.push(' '),
+                        Object::Real(n) if *n <= -TJ_SPACE => text.push(' '),
+                        _ => {}
+                    }
+                }
+            }
+            // A move along the line only separates words
+            ("Td" | "TD", [_, ty]) if ty.as_float().is_ok_and(|y| y == 0.0) => text.push(' '),
+            ("Td" | "TD" | "T*" | "Tm" | "ET", _) => text.push('\n'),
+            _ => {}
+        }
+    }
+    let lines: Vec<&str> = text.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+    lines.join("\n")
+}
+
+/// Whether some page of `doc` shows `query`, ignoring case and how whitespace is laid out.
+/// Stops at the first matching page.
+pub(crate) fn doc_contains(doc: &Document, query: &str) -> bool {
+    let normalize = |s: &str| s.to_lowercase().split_whitespace().collect::<Vec<_>>().join(" ");
+    let query = normalize(query);
+    doc.get_pages().into_values().any(|pid| normalize(&page_text(doc, pid)).contains(&query))
+}
+
+/// `doc_contains` for a file; files that cannot be loaded do not match.
+pub fn pdf_contains(path: &Path, query: &str) -> bool {
+    Document::load(path).map(|doc| doc_contains(&doc, query)).unwrap_or(false)
+}
+
+/// Text of the pages of `path` selected by `pages` (a page spec; all pages when `None`).
+pub fn extract(path: &Path, pages: Option<&str>) -> Result<Vec<PageText>> {
+    let doc = Document::load(path).with_context(|| format!("加载 PDF 失败: {}", path.display()))?;
+    let page_ids = doc.get_pages();
+    let total = page_ids.len();
+    let selected: Vec<usize> = match pages {
+        Some(spec_str) => {
+            let ranges = spec::parse_spec_with_phrases(spec_str, total)
+                .with_context(|| format!("解析页码范围失败: {}", spec_str))?;
+            let selected = spec::expand_to_indexes(&ranges, total);
+            if selected.is_empty() { anyhow::bail!("页码范围 {} 未选中任何页面 (总页数 {})", spec_str, total); }
+            selected
+        }
+        None => (0..total).collect(),
+    };
+    Ok(selected.into_iter()
+        .filter_map(|idx| page_ids.get(&(idx as u32 + 1)).map(|&pid| PageText { page: idx + 1, text: page_text(&doc, pid) }))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{dictionary, Stream};
+
+    fn doc_with_page(content: &[u8]) -> (Document, ObjectId) {
+        let mut doc = Document::with_version("1.5");
+        let cid = doc.add_object(Object::Stream(Stream::new(Dictionary::new(), content.to_vec())));
+        let mut page = Dictionary::new();
+        page.set("Type", "Page");
+        page.set("Contents", Object::Reference(cid));
+        let page_id = doc.add_object(Object::Dictionary(page));
+        (doc, page_id)
+    }
+
+    #[test]
+    fn text_from_show_operators() {
+        let (doc, pid) = doc_with_page(b"BT /F1 12 Tf 10 10 Td (Inv) Tj (oice) Tj 0 -14 Td [(No) -300 (42)] TJ (next) ' ET");
+        assert_eq!(page_text(&doc, pid), "Invoice\nNo 42\nnext");
+    }
+
+    #[test]
+    fn utf16_strings_decode() {
+        let (doc, pid) = doc_with_page(b"BT <FEFF00520065006300650069007000740020> Tj ET");
+        assert!(page_text(&doc, pid).contains("Receipt"));
+    }
+
+    #[test]
+    fn to_unicode_cmap_maps_two_byte_codes() {
+        let (mut doc, pid) = doc_with_page(b"BT /F1 12 Tf <000100020003> Tj ET");
+        let cmap = b"/CIDInit /ProcSet findresource begin 12 dict begin begincmap\n\
+            1 begincodespacerange <0000> <FFFF> endcodespacerange\n\
+            1 beginbfchar <0001> <0048> endbfchar\n\
+            1 beginbfrange <0002> <0003> [<00E9> <006C>] endbfrange\n\
+            endcmap end end";
+        let cmap_id = doc.add_object(Stream::new(dictionary! {}, cmap.to_vec()));
+        let font = doc.add_object(dictionary! { "Type" => "Font", "Subtype" => "Type0", "ToUnicode" => cmap_id });
+        let page = doc.get_object_mut(pid).and_then(Object::as_dict_mut).unwrap();
+        page.set("Resources", dictionary! { "Font" => dictionary! { "F1" => font } });
+        assert_eq!(page_text(&doc, pid), "Hél");
+    }
+
+    #[test]
+    fn four_byte_ranges_at_the_top_of_the_code_space() {
+        let cmap = ToUnicode::parse(b"begincmap 2 beginbfrange\n\
+            <FFFFFFF0> <FFFFFFFF> <0041>\n\
+            <FFFFFFFE> <FFFFFFFF> [<0058> <0059> <005A>]\n\
+            endbfrange endcmap");
+        assert_eq!(cmap.map[&0xFFFF_FFF0], "A");
+        assert_eq!(cmap.map[&0xFFFF_FFFD], "N");
+        assert_eq!(cmap.map[&0xFFFF_FFFF], "Y");
+        assert_eq!(cmap.code_len, 4);
+    }
+
+    #[test]
+    fn simple_font_uses_its_base_encoding() {
+        let (mut doc, pid) = doc_with_page(b"BT /F1 12 Tf <43AE74E9> Tj ET");
+        let font = doc.add_object(dictionary! { "Type" => "Font", "Subtype" => "Type1", "Encoding" => "WinAnsiEncoding" });
+        let page = doc.get_object_mut(pid).and_then(Object::as_dict_mut).unwrap();
+        page.set("Resources", dictionary! { "Font" => dictionary! { "F1" => font } });
+        assert_eq!(page_text(&doc, pid), "C®té");
+    }
+}
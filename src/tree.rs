@@ -0,0 +1,87 @@
+//! Shared readers for untrusted object graphs: references to dictionaries and arrays, and the
+//! name and number trees (`/PageLabels`, `/EmbeddedFiles`, `/Dests`, …) built from them.
+
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use std::collections::HashSet;
+use std::ops::ControlFlow;
+
+// Depth limit for name and number trees; cycles are cut by the visited set
+const MAX_DEPTH: usize = 32;
+
+/// `obj` as a dictionary, following one reference.
+pub(crate) fn deref_dict<'a>(doc: &'a Document, obj: Option<&'a Object>) -> Option<&'a Dictionary> {
+    match obj? {
+        Object::Reference(id) => doc.get_dictionary(*id).ok(),
+        Object::Dictionary(d) => Some(d),
+        _ => None,
+    }
+}
+
+/// `obj` as an array, following one reference.
+pub(crate) fn deref_array<'a>(doc: &'a Document, obj: Option<&'a Object>) -> Option<&'a Vec<Object>> {
+    match obj? {
+        Object::Reference(id) => doc.get_object(*id).ok()?.as_array().ok(),
+        Object::Array(a) => Some(a),
+        _ => None,
+    }
+}
+
+/// Call `visit` with the `leaves` array (`/Names` of a name tree, `/Nums` of a number tree) of
+/// every node from `root` down, in tree order, until it breaks. A kid seen before and nodes
+/// deeper than the depth limit are skipped, so a malformed tree is still read only once.
+pub(crate) fn walk_leaves<'a, B>(
+    doc: &'a Document,
+    root: &'a Dictionary,
+    leaves: &[u8],
+    visit: &mut dyn FnMut(&'a [Object]) -> ControlFlow<B>,
+) -> ControlFlow<B> {
+    walk(doc, root, leaves, 0, &mut HashSet::new(), visit)
+}
+
+fn walk<'a, B>(
+    doc: &'a Document,
+    node: &'a Dictionary,
+    leaves: &[u8],
+    depth: usize,
+    seen: &mut HashSet<ObjectId>,
+    visit: &mut dyn FnMut(&'a [Object]) -> ControlFlow<B>,
+) -> ControlFlow<B> {
+    if depth > MAX_DEPTH { return ControlFlow::Continue(()); }
+    if let Some(items) = deref_array(doc, node.get(leaves).ok()) {
+        visit(items)?;
+    }
+    if let Some(kids) = deref_array(doc, node.get(b"Kids").ok()) {
+        for kid in kids {
+            if let Object::Reference(id) = kid {
+                if !seen.insert(*id) { continue; }
+            }
+            if let Some(child) = deref_dict(doc, Some(kid)) {
+                walk(doc, child, leaves, depth + 1, seen, visit)?;
+            }
+        }
+    }
+    ControlFlow::Continue(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::dictionary;
+
+    #[test]
+    fn repeated_kids_are_walked_once() {
+        let mut doc = Document::with_version("1.5");
+        let node_id = doc.new_object_id();
+        let node = dictionary! {
+            "Names" => vec![Object::string_literal("a"), 1.into()],
+            "Kids" => vec![node_id.into(), node_id.into()],
+        };
+        doc.objects.insert(node_id, Object::Dictionary(node));
+        let root = dictionary! { "Kids" => vec![node_id.into(), node_id.into()] };
+
+        let mut visits = 0;
+        let flow: ControlFlow<()> = walk_leaves(&doc, &root, b"Names", &mut |_| { visits += 1; ControlFlow::Continue(()) });
+        assert_eq!(flow, ControlFlow::Continue(()));
+        assert_eq!(visits, 1);
+    }
+}
@@ -2,12 +2,13 @@
 
 use anyhow::Result;
 use crossterm::{execute, terminal::{enable_raw_mode, disable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen}};
-use crossterm::event::{self, Event, KeyCode};
+use crossterm::event::{self, Event, KeyCode, KeyModifiers};
 use ratatui::{prelude::*, widgets::*};
-use std::{io::stdout, path::PathBuf, sync::mpsc, thread, time::Duration, sync::{atomic::{Ordering, AtomicU64}}};
-use crate::pathutil::sanitize_path_input;
+use std::{io::stdout, path::{Path, PathBuf}, sync::mpsc, thread, time::{Duration, Instant}, sync::{atomic::{Ordering, AtomicBool, AtomicU64}}, collections::VecDeque};
+use crate::pathutil::{self, sanitize_path_input};
 
-use crate::scan::{self, ScanConfig, ScanEvent, CancelHandle};
+use crate::scan::{self, ScanConfig, ScanEvent, CancelHandle, CancelReason};
+use crate::split::SplitMode;
 mod theme;
 use theme::Theme;
 use lopdf;
@@ -16,13 +17,37 @@ struct FileItem {
     name: String,
     path: PathBuf,
     checked: bool,
+    // filled in by the background probe; None while pending
+    probe: Option<FileStatus>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum FileStatus {
+    Ok(usize), // page count
+    Encrypted,
+    Corrupt(String),
+}
+
+impl FileStatus {
+    fn marker(&self) -> &'static str {
+        match self { FileStatus::Ok(_) => "", FileStatus::Encrypted => "🔒 ", FileStatus::Corrupt(_) => "! " }
+    }
+
+    // why the file can't be merged, if it can't
+    fn problem(&self) -> Option<String> {
+        match self {
+            FileStatus::Ok(_) => None,
+            FileStatus::Encrypted => Some("encrypted".into()),
+            FileStatus::Corrupt(e) => Some(format!("cannot be loaded ({})", e)),
+        }
+    }
 }
 
 #[derive(Copy, Clone, PartialEq, Eq)]
 enum Focus { Left, Right }
 
 #[derive(Copy, Clone, PartialEq, Eq)]
-enum InputMode { None, EditOutput, EditPages, PickMode, FilesMenu, EditInput, PickDepth, OptionsMenu, PickOverwrite, EditSplitSuffix, EditSplitRange, ConfirmLarge, Help }
+enum InputMode { None, EditOutput, EditPages, PickMode, FilesMenu, EditInput, PickDepth, OptionsMenu, PickOverwrite, EditSplitSuffix, EditSplitRange, EditIncludes, EditExcludes, ConfirmLarge, ConfirmOrder, Help }
 
 #[derive(Copy, Clone, PartialEq, Eq)]
 enum Mode { Merge, Split }
@@ -37,6 +62,11 @@ struct AppState {
     status: String,
     scanning: bool,
     scan_depth: Option<usize>,
+    // depth `\` returns to when leaving ∞
+    last_finite_depth: usize,
+    // glob filters for the next scan (relative to input_dir)
+    includes: Vec<String>,
+    excludes: Vec<String>,
     cancel: Option<CancelHandle>,
     // selection/order panel
     order: Vec<usize>, // indexes into files
@@ -62,16 +92,29 @@ struct AppState {
     options_menu_index: usize,
     overwrite_pick_index: usize,
     theme: Theme,
+    // index into theme::BUILTIN
+    theme_index: usize,
     output_auto_follow: bool,
+    // show the page-contribution order before each merge
+    confirm_order: bool,
     overwrite_policy: OverwritePolicy,
     split_suffix: String,
     split_group: usize,
     // pending split confirmation
     pend_input: Option<PathBuf>,
     pend_out_dir: Option<PathBuf>,
-    pend_ranges: Option<String>,
-    pend_each: bool,
+    pend_mode: Option<SplitMode>,
     pend_expected: usize,
+    // last successful job output (merge file or split directory)
+    last_output: Option<PathBuf>,
+    // directory holding the last output (for `O`)
+    last_output_dir: Option<PathBuf>,
+    // status/error history (toggle with L)
+    log: VecDeque<String>,
+    log_visible: bool,
+    log_scroll: usize, // lines scrolled up from the newest entry
+    log_last_status: String,
+    started: Instant,
 }
 
 impl AppState {
@@ -84,6 +127,9 @@ impl AppState {
             status: String::from("Quit: q  Focus: Tab  Select: Space  Move: ↑/↓/j/k  Reorder: u/d/U/D  Rescan: r  Depth: [ ] \\  Output: o  Pages: p  Force: F  Run: Enter"),
             scanning: true,
             scan_depth: Some(1),
+            last_finite_depth: 1,
+            includes: Vec::new(),
+            excludes: Vec::new(),
             cancel: None,
             order: Vec::new(),
             order_selected: 0,
@@ -104,25 +150,77 @@ impl AppState {
             options_menu_index: 0,
             overwrite_pick_index: 1, // default to Suffix
             theme: Theme::gitui_dark(),
+            theme_index: 0,
             output_auto_follow: true,
+            confirm_order: true,
             overwrite_policy: OverwritePolicy::Suffix,
             split_suffix: "_{index}".into(),
             split_group: 1,
             pend_input: None,
             pend_out_dir: None,
-            pend_ranges: None,
-            pend_each: true,
+            pend_mode: None,
             pend_expected: 0,
+            last_output: None,
+            last_output_dir: None,
+            log: VecDeque::new(),
+            log_visible: false,
+            log_scroll: 0,
+            log_last_status: String::new(),
+            started: Instant::now(),
+        }
+    }
+
+    fn set_theme(&mut self, index: usize) {
+        self.theme_index = index;
+        self.theme = theme::by_name(theme::BUILTIN[index]).unwrap_or_else(Theme::gitui_dark);
+    }
+
+    fn set_depth(&mut self, depth: Option<usize>) {
+        if let Some(d) = depth { self.last_finite_depth = d; }
+        self.scan_depth = depth;
+    }
+
+    // `[` / `]`: one level shallower (not below 1) or deeper; from ∞, `[` goes back to the last finite depth
+    fn step_depth(&mut self, deeper: bool) {
+        match (self.scan_depth, deeper) {
+            (None, true) => {}
+            (None, false) => self.set_depth(Some(self.last_finite_depth)),
+            (Some(d), true) => self.set_depth(Some(d + 1)),
+            (Some(d), false) => self.set_depth(Some(d.saturating_sub(1).max(1))),
+        }
+    }
+
+    // `\`: toggle between ∞ and the last finite depth
+    fn toggle_infinite_depth(&mut self) {
+        self.scan_depth = match self.scan_depth { Some(_) => None, None => Some(self.last_finite_depth) };
+    }
+
+    fn push_log(&mut self, msg: &str) {
+        let t = self.started.elapsed().as_secs_f32();
+        self.log.push_back(format!("[{:>7.1}s] {}", t, msg));
+        while self.log.len() > LOG_CAPACITY { self.log.pop_front(); }
+    }
+
+    // record the status line when it changed (progress updates are not routed here)
+    fn log_status_change(&mut self) {
+        if self.status != self.log_last_status {
+            self.log_last_status = self.status.clone();
+            let msg = self.status.clone();
+            self.push_log(&msg);
         }
     }
 }
 
+const LOG_CAPACITY: usize = 200;
+
 enum UiMsg {
-    Found(PathBuf),
+    FoundBatch(Vec<PathBuf>),
     Error(String),
     Done,
+    Canceled(CancelReason),
     Progress { pos: u64, len: u64, msg: String },
-    JobDone(Result<()>, String),
+    JobDone(Result<()>, String, PathBuf),
+    FileStatus(PathBuf, FileStatus),
 }
 
 struct TuiProgress {
@@ -142,8 +240,20 @@ impl crate::progress::ProgressSink for TuiProgress {
     fn finish(&self, msg: std::borrow::Cow<'static, str>) { let _ = self.tx.send(UiMsg::Progress{ pos: self.len.load(Ordering::Relaxed), len: self.len.load(Ordering::Relaxed), msg: msg.into_owned() }); }
 }
 
-pub fn run(_theme: Option<String>, _theme_file: Option<PathBuf>, input_dir: PathBuf) -> Result<()> {
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Leave raw mode and the alternate screen if the TUI is running. For signal handlers that exit
+/// the process without going through `run`'s own teardown.
+pub fn restore_terminal() {
+    if ACTIVE.swap(false, Ordering::SeqCst) {
+        let _ = disable_raw_mode();
+        let _ = execute!(std::io::stdout(), LeaveAlternateScreen);
+    }
+}
+
+pub fn run(theme_name: Option<String>, _theme_file: Option<PathBuf>, input_dir: PathBuf) -> Result<()> {
     enable_raw_mode()?;
+    ACTIVE.store(true, Ordering::SeqCst);
     let mut out = stdout();
     execute!(out, EnterAlternateScreen)?;
     let backend = ratatui::backend::CrosstermBackend::new(out);
@@ -151,6 +261,7 @@ pub fn run(_theme: Option<String>, _theme_file: Option<PathBuf>, input_dir: Path
 
     let (tx, rx) = mpsc::channel::<UiMsg>();
     let mut app = AppState::new(input_dir);
+    app.set_theme(theme::resolve(theme_name.as_deref()));
     app.status = "Ready".into();
 
     // spawn initial scan
@@ -161,30 +272,59 @@ pub fn run(_theme: Option<String>, _theme_file: Option<PathBuf>, input_dir: Path
         // handle channel messages
         while let Ok(msg) = rx.try_recv() {
             match msg {
-                UiMsg::Found(p) => {
-                    app.files.push(FileItem{ name: p.file_name().and_then(|s| s.to_str()).unwrap_or("?").to_string(), path: p, checked: false });
+                UiMsg::FoundBatch(paths) => {
+                    app.files.extend(paths.into_iter().map(|p| FileItem{ name: p.file_name().and_then(|s| s.to_str()).unwrap_or("?").to_string(), path: p, checked: false, probe: None }));
                     if app.selected >= app.files.len() { app.selected = app.files.len().saturating_sub(1); }
                 }
-                UiMsg::Error(e) => { app.status = format!("Scan error: {}", e); }
+                UiMsg::Error(e) => {
+                    // always logged, even if the status line moves on before the next draw
+                    app.status = format!("Scan error: {}", e);
+                    app.log_last_status = app.status.clone();
+                    app.push_log(&app.log_last_status.clone());
+                }
                 UiMsg::Done => { app.scanning = false; }
+                // a rescan cancels the previous scan itself; only other reasons are worth reporting
+                UiMsg::Canceled(CancelReason::UserRequested) => {}
+                UiMsg::Canceled(reason) => {
+                    app.scanning = false;
+                    app.status = match reason {
+                        CancelReason::Timeout => "Scan stopped: no new files for 10s (timeout)".to_string(),
+                        other => format!("Scan stopped: {}", other.as_str()),
+                    };
+                    app.log_last_status = app.status.clone();
+                    app.push_log(&app.log_last_status.clone());
+                }
+                UiMsg::FileStatus(p, st) => {
+                    // results from a canceled scan simply find no match
+                    if let Some(item) = app.files.iter_mut().find(|it| it.path == p) { item.probe = Some(st); }
+                }
                 UiMsg::Progress { pos, len, msg } => {
                     let msg_part = if msg.is_empty() { String::new() } else { format!(" · {}", msg) };
                     app.status = format!("Progress: {}/{}{}", pos, len, msg_part);
                 }
-                UiMsg::JobDone(res, note) => {
+                UiMsg::JobDone(res, note, output) => {
                     app.job_running = false;
                     match res {
-                        Ok(()) => app.status = format!("✓ Done: {}", note),
+                        Ok(()) => {
+                            app.status = format!("✓ Done: {}", note);
+                            app.last_output_dir = output_dir_of(&output);
+                            app.last_output = Some(output);
+                        }
                         Err(e) => app.status = format!("× Failed: {} · {}", note, e),
                     }
+                    app.log_last_status = app.status.clone();
+                    app.push_log(&app.log_last_status.clone());
                 }
             }
         }
 
+        app.log_status_change();
         terminal.draw(|f| draw(f, &app))?;
 
         if event::poll(Duration::from_millis(100))? {
             if let Event::Key(key) = event::read()? {
+                // Raw mode swallows SIGINT, so Ctrl-C arrives here as a key
+                if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) { break; }
                 // input overlay handling
                 if app.input_mode != InputMode::None {
                     match key.code {
@@ -235,7 +375,7 @@ pub fn run(_theme: Option<String>, _theme_file: Option<PathBuf>, input_dir: Path
                                     rescan(&mut app, tx.clone());
                                 }
                                 InputMode::PickDepth => {
-                                    app.scan_depth = match app.depth_pick_index { 0 => Some(1), 1 => Some(2), 2 => Some(3), _ => None };
+                                    app.set_depth(match app.depth_pick_index { 0 => Some(1), 1 => Some(2), 2 => Some(3), _ => None });
                                     let label = match app.scan_depth { Some(d)=>d.to_string(), None=>"∞".into() };
                                     app.status = format!("Depth: {}", label);
                                     rescan(&mut app, tx.clone());
@@ -272,6 +412,18 @@ pub fn run(_theme: Option<String>, _theme_file: Option<PathBuf>, input_dir: Path
                                             app.status = "Edit split suffix (use {index}): Enter to save, Esc to cancel".into();
                                             continue;
                                         }
+                                        5 => { // Merge order confirmation toggle
+                                            app.confirm_order = !app.confirm_order;
+                                            app.status = format!("Confirm merge order: {}", if app.confirm_order {"On"} else {"Off"});
+                                        }
+                                        6 | 7 => { // Include / exclude globs
+                                            let include = app.options_menu_index == 6;
+                                            app.input_mode = if include { InputMode::EditIncludes } else { InputMode::EditExcludes };
+                                            app.input_buffer = if include { app.includes.join("; ") } else { app.excludes.join("; ") };
+                                            app.input_cursor = app.input_buffer.len();
+                                            app.status = format!("Edit {} globs (separate with ;, empty = none): Enter to save, Esc to cancel", if include {"include"} else {"exclude"});
+                                            continue;
+                                        }
                                         _ => {}
                                     }
                                 }
@@ -288,7 +440,19 @@ pub fn run(_theme: Option<String>, _theme_file: Option<PathBuf>, input_dir: Path
                                     app.split_suffix = app.input_buffer.clone();
                                     app.status = format!("Split suffix: {}", app.split_suffix);
                                 }
+                                InputMode::EditIncludes | InputMode::EditExcludes => {
+                                    let globs = match parse_glob_list(&app.input_buffer) {
+                                        Ok(g) => g,
+                                        // Keep the overlay open so the pattern can be fixed
+                                        Err(e) => { app.status = format!("Invalid glob: {}", e); continue; }
+                                    };
+                                    let include = app.input_mode == InputMode::EditIncludes;
+                                    app.status = format!("{}: {}", if include {"Include"} else {"Exclude"}, if globs.is_empty() { "(none)".to_string() } else { globs.join("; ") });
+                                    if include { app.includes = globs; } else { app.excludes = globs; }
+                                    rescan(&mut app, tx.clone());
+                                }
                                 InputMode::ConfirmLarge => { /* Enter = no-op (prefer y/N) */ }
+                                InputMode::ConfirmOrder => { spawn_merge_job(&mut app, tx.clone()); }
                                 InputMode::Help => { /* Enter closes help; handled after this match */ }
                                 InputMode::None => {}
                             }
@@ -301,7 +465,7 @@ pub fn run(_theme: Option<String>, _theme_file: Option<PathBuf>, input_dir: Path
                                 InputMode::PickMode => { app.mode_pick_index = (app.mode_pick_index+1).min(1); }
                                 InputMode::FilesMenu => { app.files_menu_index = (app.files_menu_index+1).min(1); }
                                 InputMode::PickDepth => { app.depth_pick_index = (app.depth_pick_index+1).min(2); }
-                                InputMode::OptionsMenu => { app.options_menu_index = (app.options_menu_index+1).min(4); }
+                                InputMode::OptionsMenu => { app.options_menu_index = (app.options_menu_index+1).min(7); }
                                 InputMode::PickOverwrite => { app.overwrite_pick_index = (app.overwrite_pick_index+1).min(1); }
                                 _ => {}
                             }
@@ -327,17 +491,21 @@ pub fn run(_theme: Option<String>, _theme_file: Option<PathBuf>, input_dir: Path
                                 if let (Some(inp), Some(outd)) = (app.pend_input.clone(), app.pend_out_dir.clone()) {
                                     let pattern = format!("{{base}}{}.pdf", app.split_suffix);
                                     let force = matches!(app.overwrite_policy, OverwritePolicy::Force) || app.force;
-                                    let ranges = app.pend_ranges.clone();
-                                    let each = app.pend_each;
+                                    let mode = app.pend_mode.take().unwrap_or(SplitMode::Each);
                                     app.input_mode = InputMode::None;
-                                    app.pend_input=None; app.pend_out_dir=None; app.pend_ranges=None; app.pend_expected=0; app.pend_each=true;
-                                    spawn_split_job_params(inp, outd, each, ranges, pattern, force, tx.clone());
+                                    app.pend_input=None; app.pend_out_dir=None; app.pend_expected=0;
+                                    spawn_split_job_params(inp, outd, mode, pattern, force, tx.clone());
                                 }
+                            } else if matches!(app.input_mode, InputMode::ConfirmOrder) {
+                                app.input_mode = InputMode::None;
+                                spawn_merge_job(&mut app, tx.clone());
                             } else { app.input_buffer.insert(app.input_cursor, 'y'); app.input_cursor+=1; }
                         }
                         KeyCode::Char('n') | KeyCode::Char('N') => {
                             if matches!(app.input_mode, InputMode::ConfirmLarge) {
-                                app.input_mode = InputMode::None; app.pend_input=None; app.pend_out_dir=None; app.pend_ranges=None; app.pend_expected=0; app.pend_each=true; app.status = "Canceled".into();
+                                app.input_mode = InputMode::None; app.pend_input=None; app.pend_out_dir=None; app.pend_mode=None; app.pend_expected=0; app.status = "Canceled".into();
+                            } else if matches!(app.input_mode, InputMode::ConfirmOrder) {
+                                app.input_mode = InputMode::None; app.status = "Canceled".into();
                             } else { app.input_buffer.insert(app.input_cursor, 'n'); app.input_cursor+=1; }
                         }
                         KeyCode::Char(c) => { app.input_buffer.insert(app.input_cursor, c); app.input_cursor+=1; }
@@ -371,7 +539,7 @@ pub fn run(_theme: Option<String>, _theme_file: Option<PathBuf>, input_dir: Path
                             } else if app.top_index==2 {
                                 app.input_mode = InputMode::OptionsMenu;
                                 app.options_menu_index = 0;
-                                app.status = "Options: Depth / Output auto-follow / Overwrite / Split suffix".into();
+                                app.status = "Options: Depth / Output auto-follow / Overwrite / Split range / Split suffix / Confirm order / Include / Exclude".into();
                             } else if app.top_index==3 {
                                 app.input_mode = InputMode::Help;
                                 app.input_buffer.clear();
@@ -380,6 +548,10 @@ pub fn run(_theme: Option<String>, _theme_file: Option<PathBuf>, input_dir: Path
                         } else {
                             if !app.job_running && !app.order.is_empty() {
                                 match app.mode {
+                                    Mode::Merge if app.confirm_order => {
+                                        app.input_mode = InputMode::ConfirmOrder;
+                                        app.status = "Merge order · Run: y/Enter · Cancel: n/Esc".into();
+                                    }
                                     Mode::Merge => spawn_merge_job(&mut app, tx.clone()),
                                     Mode::Split => {
                                         // preflight: compute groups and expected count
@@ -387,23 +559,19 @@ pub fn run(_theme: Option<String>, _theme_file: Option<PathBuf>, input_dir: Path
                                             let out_dir = choose_out_dir(&app.input_dir, &app.output);
                                             let group = app.split_group.max(1);
                                             let pages = match lopdf::Document::load(&first) { Ok(d)=> d.get_pages().len(), Err(_)=>0 };
-                                            let (each, ranges, expected) = if group<=1 { (true, None, pages) } else {
-                                                let ranges = make_ranges_spec(pages, group);
-                                                let expected = (pages + group - 1)/group;
-                                                (false, Some(ranges), expected)
-                                            };
+                                            let mode = if group<=1 { SplitMode::Each } else { SplitMode::Every(group) };
+                                            let expected = mode.groups(pages).len();
                                             if expected>20 {
                                                 app.pend_input = Some(first);
                                                 app.pend_out_dir = Some(out_dir);
-                                                app.pend_ranges = ranges;
-                                                app.pend_each = each;
+                                                app.pend_mode = Some(mode);
                                                 app.pend_expected = expected;
                                                 app.input_mode = InputMode::ConfirmLarge;
                                                 app.status = format!("This will create {} files. Proceed? (y/N)", app.pend_expected);
                                             } else {
                                                 let pattern = format!("{{base}}{}.pdf", app.split_suffix);
                                                 let force = matches!(app.overwrite_policy, OverwritePolicy::Force) || app.force;
-                                                spawn_split_job_params(first, out_dir, each, ranges, pattern, force, tx.clone());
+                                                spawn_split_job_params(first, out_dir, mode, pattern, force, tx.clone());
                                             }
                                         }
                                     }
@@ -427,6 +595,10 @@ pub fn run(_theme: Option<String>, _theme_file: Option<PathBuf>, input_dir: Path
                     KeyCode::Char(' ') => {
                         if app.focus == Focus::Left {
                             if let Some(item) = app.files.get_mut(app.selected) {
+                                if let Some(why) = item.probe.as_ref().and_then(FileStatus::problem).filter(|_| !item.checked) {
+                                    app.status = format!("Cannot select {}: {}", item.name, why);
+                                    continue;
+                                }
                                 item.checked = !item.checked;
                                 if item.checked { app.order.push(app.selected); app.order_selected = app.order.len().saturating_sub(1); }
                                 else { if let Some(pos) = app.order.iter().position(|&i| i==app.selected) { app.order.remove(pos); app.order_selected = app.order_selected.min(app.order.len().saturating_sub(1)); } }
@@ -438,8 +610,46 @@ pub fn run(_theme: Option<String>, _theme_file: Option<PathBuf>, input_dir: Path
                     KeyCode::Char('d') if app.focus==Focus::Right => { if !app.order.is_empty() && app.order_selected+1<app.order.len() { let i=app.order_selected; app.order.swap(i,i+1); app.order_selected+=1; } }
                     KeyCode::Char('U') if app.focus==Focus::Right => { if !app.order.is_empty() { let idx=app.order.remove(app.order_selected); app.order.insert(0, idx); app.order_selected=0; } }
                     KeyCode::Char('D') if app.focus==Focus::Right => { if !app.order.is_empty() { let idx=app.order.remove(app.order_selected); let last=app.order.len(); app.order.insert(last, idx); app.order_selected=last; } }
-                    // rescan only (depth moved to Options)
                     KeyCode::Char('r') => { rescan(&mut app, tx.clone()); }
+                    // depth: [ shallower, ] deeper, \ toggles ∞; each rescans right away
+                    KeyCode::Char(c @ ('[' | ']' | '\\')) => {
+                        let before = app.scan_depth;
+                        if c == '\\' { app.toggle_infinite_depth(); } else { app.step_depth(c == ']'); }
+                        let label = app.scan_depth.map(|d| d.to_string()).unwrap_or("∞".into());
+                        if app.scan_depth == before {
+                            app.status = format!("Depth: {} (unchanged)", label);
+                        } else {
+                            spawn_scan(&mut app, tx.clone());
+                            app.status = format!("Depth: {} · Rescanning...", label);
+                        }
+                    }
+                    // log pane
+                    KeyCode::Char('L') => { app.log_visible = !app.log_visible; app.log_scroll = 0; }
+                    KeyCode::PageUp if app.log_visible => { app.log_scroll = (app.log_scroll + 5).min(app.log.len().saturating_sub(1)); }
+                    KeyCode::PageDown if app.log_visible => { app.log_scroll = app.log_scroll.saturating_sub(5); }
+                    // copy last output path
+                    KeyCode::Char('y') => {
+                        if let Some(path) = &app.last_output {
+                            app.status = match copy_to_clipboard(&path.display().to_string()) {
+                                Ok(()) => "Copied path".into(),
+                                Err(_) => "Clipboard unavailable".into(),
+                            };
+                        }
+                    }
+                    // cycle built-in themes
+                    KeyCode::Char('t') => {
+                        app.set_theme((app.theme_index + 1) % theme::BUILTIN.len());
+                        app.status = format!("Theme: {}", theme::BUILTIN[app.theme_index]);
+                    }
+                    // open the output folder in the system file manager
+                    KeyCode::Char('O') => {
+                        if let Some(dir) = &app.last_output_dir {
+                            app.status = match pathutil::open_in_default_app(dir) {
+                                Ok(()) => format!("Opened {}", dir.display()),
+                                Err(e) => format!("Cannot open folder: {}", e),
+                            };
+                        }
+                    }
                     // force toggle
                     KeyCode::Char('F') => { app.force = !app.force; app.status = format!("Force overwrite: {}", if app.force {"On"} else {"Off"}); }
                     // edit options (Output path moved to Files menu)
@@ -451,6 +661,9 @@ pub fn run(_theme: Option<String>, _theme_file: Option<PathBuf>, input_dir: Path
         }
     }
 
+    ACTIVE.store(false, Ordering::SeqCst);
+    // A job still running when the user quit dies with the process; drop what it was writing
+    crate::interrupt::remove_partial_outputs();
     disable_raw_mode()?;
     let mut out2 = std::io::stdout();
     execute!(out2, LeaveAlternateScreen)?;
@@ -459,7 +672,7 @@ pub fn run(_theme: Option<String>, _theme_file: Option<PathBuf>, input_dir: Path
 
 fn spawn_scan(app: &mut AppState, tx: mpsc::Sender<UiMsg>) {
     // cancel previous
-    if let Some(c) = &app.cancel { c.cancel(); }
+    if let Some(c) = &app.cancel { c.cancel(CancelReason::UserRequested); }
     app.scanning = true;
     app.files.clear();
     app.selected = 0;
@@ -467,30 +680,56 @@ fn spawn_scan(app: &mut AppState, tx: mpsc::Sender<UiMsg>) {
     let dir = app.input_dir.clone();
     let (rx, cancel) = scan::scan_stream(ScanConfig{
         input_dir: dir,
-        includes: vec![], excludes: vec![], extra_exclude_paths: vec![],
-        max_depth: depth, follow_links: false,
+        includes: app.includes.clone(), excludes: app.excludes.clone(), extra_exclude_paths: vec![],
+        max_depth: depth, follow_links: false, case_insensitive: false, contains: None,
     });
     app.cancel = Some(cancel.clone());
-    // forward messages to UI channel，若长时间无结果则自动取消释放资源
+    // probe each found file (page count, encrypted/corrupt) off the UI thread
+    let (probe_tx, probe_rx) = mpsc::channel::<PathBuf>();
+    let status_tx = tx.clone();
+    thread::spawn(move || {
+        for p in probe_rx {
+            let st = probe_file(&p);
+            if status_tx.send(UiMsg::FileStatus(p, st)).is_err() { break; }
+        }
+    });
+    // forward messages to UI channel in batches，若长时间无结果则自动取消释放资源
     thread::spawn(move || {
         use std::sync::mpsc::RecvTimeoutError;
         use std::time::{Duration, Instant};
         let timeout = Duration::from_secs(10);
         let mut last = Instant::now();
+        let mut batch = FoundBatcher::new(SCAN_BATCH_MAX, SCAN_BATCH_INTERVAL);
         loop {
-            match rx.recv_timeout(Duration::from_millis(500)) {
+            match rx.recv_timeout(SCAN_BATCH_INTERVAL) {
                 Ok(ev) => {
                     match ev {
-                        ScanEvent::Found(p) => { last = Instant::now(); let _ = tx.send(UiMsg::Found(p)); }
-                        ScanEvent::Error(e) => { let _ = tx.send(UiMsg::Error(e)); }
-                        ScanEvent::Done => { let _ = tx.send(UiMsg::Done); break; }
+                        ScanEvent::Found(p) => {
+                            last = Instant::now();
+                            let _ = probe_tx.send(p.clone());
+                            if let Some(paths) = batch.push(p, last) { let _ = tx.send(UiMsg::FoundBatch(paths)); }
+                        }
+                        ScanEvent::Error(e) => {
+                            if let Some(paths) = batch.take() { let _ = tx.send(UiMsg::FoundBatch(paths)); }
+                            let _ = tx.send(UiMsg::Error(e));
+                        }
+                        ScanEvent::Done => {
+                            if let Some(paths) = batch.take() { let _ = tx.send(UiMsg::FoundBatch(paths)); }
+                            let _ = tx.send(UiMsg::Done);
+                            break;
+                        }
+                        ScanEvent::Canceled(reason) => {
+                            if let Some(paths) = batch.take() { let _ = tx.send(UiMsg::FoundBatch(paths)); }
+                            let _ = tx.send(UiMsg::Canceled(reason));
+                            break;
+                        }
                     }
                 }
                 Err(RecvTimeoutError::Timeout) => {
+                    if let Some(paths) = batch.flush_due(Instant::now()) { let _ = tx.send(UiMsg::FoundBatch(paths)); }
                     if last.elapsed() >= timeout {
-                        cancel.cancel();
-                        let _ = tx.send(UiMsg::Error("Scan timeout, canceled to free resources".into()));
-                        // 继续等待 Done 来收尾
+                        // 扫描线程随后发送 Canceled(Timeout) 收尾
+                        cancel.cancel(CancelReason::Timeout);
                     }
                 }
                 Err(RecvTimeoutError::Disconnected) => break,
@@ -499,6 +738,63 @@ fn spawn_scan(app: &mut AppState, tx: mpsc::Sender<UiMsg>) {
     });
 }
 
+// Found files go to the UI in batches: at most this many per message...
+const SCAN_BATCH_MAX: usize = 256;
+// ...and none waits longer than this for its batch to be sent
+const SCAN_BATCH_INTERVAL: std::time::Duration = std::time::Duration::from_millis(50);
+
+// Collects scan results so the UI thread gets a few large updates instead of one per file
+struct FoundBatcher {
+    paths: Vec<PathBuf>,
+    // when the oldest pending path arrived
+    since: Option<std::time::Instant>,
+    max_len: usize,
+    max_age: std::time::Duration,
+}
+
+impl FoundBatcher {
+    fn new(max_len: usize, max_age: std::time::Duration) -> Self {
+        Self { paths: Vec::new(), since: None, max_len: max_len.max(1), max_age }
+    }
+
+    // Add a path; returns the batch when it is full or its oldest entry has waited long enough
+    fn push(&mut self, path: PathBuf, now: std::time::Instant) -> Option<Vec<PathBuf>> {
+        self.since.get_or_insert(now);
+        self.paths.push(path);
+        if self.paths.len() >= self.max_len { return self.take(); }
+        self.flush_due(now)
+    }
+
+    // The pending batch if its oldest entry has waited at least `max_age`
+    fn flush_due(&mut self, now: std::time::Instant) -> Option<Vec<PathBuf>> {
+        match self.since {
+            Some(since) if now.duration_since(since) >= self.max_age => self.take(),
+            _ => None,
+        }
+    }
+
+    // Whatever is pending, regardless of thresholds
+    fn take(&mut self) -> Option<Vec<PathBuf>> {
+        self.since = None;
+        if self.paths.is_empty() { None } else { Some(std::mem::take(&mut self.paths)) }
+    }
+}
+
+fn probe_file(path: &Path) -> FileStatus {
+    match lopdf::Document::load(path) {
+        Ok(doc) if doc.is_encrypted() => FileStatus::Encrypted,
+        Ok(doc) => FileStatus::Ok(doc.get_pages().len()),
+        Err(e) => FileStatus::Corrupt(e.to_string()),
+    }
+}
+
+// "*.pdf; scans/**" -> ["*.pdf", "scans/**"], rejecting patterns the scanner would reject
+fn parse_glob_list(input: &str) -> std::result::Result<Vec<String>, String> {
+    let globs: Vec<String> = input.split(';').map(str::trim).filter(|g| !g.is_empty()).map(String::from).collect();
+    scan::build_globset(&globs, false).map_err(|e| format!("{:#}", e))?;
+    Ok(globs)
+}
+
 fn rescan(app: &mut AppState, tx: mpsc::Sender<UiMsg>) {
     app.status = "Rescanning...".into();
     spawn_scan(app, tx);
@@ -560,17 +856,30 @@ fn draw(f: &mut ratatui::Frame<'_>, app: &AppState) {
             .title(Span::styled("Info", Style::default().add_modifier(Modifier::BOLD))));
     f.render_widget(info_para, top[1]);
 
-    // Main area: split into two columns
+    // Main area: optional log pane below, lists split into two columns
+    let (lists_area, log_area) = if app.log_visible {
+        let v = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(chunks[1]);
+        (v[0], Some(v[1]))
+    } else { (chunks[1], None) };
     let main = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
-        .split(chunks[1]);
+        .split(lists_area);
 
     // Left list (all files)
     let items: Vec<ListItem> = app.files.iter().enumerate().map(|(_i, it)| {
         let mark = if it.checked { "[x]" } else { "[ ]" };
-        let line = Line::from(format!("{} {}", mark, it.name));
-        ListItem::new(line)
+        let (flag, pages) = match &it.probe {
+            Some(FileStatus::Ok(n)) => ("", format!("  ({}p)", n)),
+            Some(st) => (st.marker(), String::new()),
+            None => ("", String::new()),
+        };
+        let line = Line::from(format!("{} {}{}{}", mark, flag, it.name, pages));
+        let style = if it.probe.as_ref().is_some_and(|st| st.problem().is_some()) { Style::default().fg(app.theme.err) } else { Style::default() };
+        ListItem::new(line).style(style)
     }).collect();
     let list = List::new(items)
         .style(Style::default().fg(app.theme.fg).add_modifier(Modifier::BOLD))
@@ -601,6 +910,21 @@ fn draw(f: &mut ratatui::Frame<'_>, app: &AppState) {
     if !app.order.is_empty() { sel_state.select(Some(app.order_selected)); }
     f.render_stateful_widget(sel_list, main[1], &mut sel_state);
 
+    if let Some(area) = log_area {
+        // newest at the bottom; log_scroll moves the window up
+        let inner_h = area.height.saturating_sub(2) as usize;
+        let end = app.log.len().saturating_sub(app.log_scroll);
+        let start = end.saturating_sub(inner_h);
+        let lines: Vec<Line> = app.log.iter().skip(start).take(end - start).map(|l| Line::from(l.as_str())).collect();
+        let log_para = Paragraph::new(lines)
+            .style(Style::default().fg(app.theme.fg))
+            .block(Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(app.theme.border).add_modifier(Modifier::BOLD))
+                .title(Span::styled(format!("Log ({}/{}) · PgUp/PgDn", end, app.log.len()), Style::default().add_modifier(Modifier::BOLD))));
+        f.render_widget(log_para, area);
+    }
+
     // Status + Help bar (split bottom area into three lines)
     let footer = Layout::default()
         .direction(Direction::Vertical)
@@ -612,7 +936,7 @@ fn draw(f: &mut ratatui::Frame<'_>, app: &AppState) {
     let help_basic = Paragraph::new("Quit: q  Cancel: Esc  Focus: Tab  Move: ↑/↓/j/k  Select: Space  Run: Enter")
         .style(Style::default().fg(app.theme.fg).add_modifier(Modifier::BOLD));
     f.render_widget(help_basic, footer[1]);
-    let help_adv = Paragraph::new("Reorder: u/d/U/D  Rescan: r  Pages: p  Force: F  Options: Depth/Range/Overwrite/Follow")
+    let help_adv = Paragraph::new("Reorder: u/d/U/D  Rescan: r  Depth: [ ] \\  Pages: p  Force: F  Copy path: y  Open dir: O  Log: L  Theme: t  Options: Depth/Range/Overwrite/Follow")
         .style(Style::default().fg(app.theme.fg).add_modifier(Modifier::BOLD));
     f.render_widget(help_adv, footer[2]);
 
@@ -620,6 +944,7 @@ fn draw(f: &mut ratatui::Frame<'_>, app: &AppState) {
         if app.input_mode != InputMode::None {
         let (popup_w, popup_h) = match app.input_mode {
             InputMode::FilesMenu | InputMode::OptionsMenu | InputMode::PickDepth | InputMode::PickOverwrite => (60, 40),
+            InputMode::ConfirmOrder => (70, 60),
             InputMode::Help => (80, 60),
             _ => (60, 20),
         };
@@ -659,6 +984,9 @@ fn draw(f: &mut ratatui::Frame<'_>, app: &AppState) {
                     format!("Overwrite: {}", desc_over),
                     format!("Split range: {}", app.split_group),
                     format!("Split suffix: {}", app.split_suffix),
+                    format!("Confirm merge order: {}", if app.confirm_order {"On"} else {"Off"}),
+                    format!("Include globs: {}", if app.includes.is_empty() { "(all)".to_string() } else { app.includes.join("; ") }),
+                    format!("Exclude globs: {}", if app.excludes.is_empty() { "(none)".to_string() } else { app.excludes.join("; ") }),
                 ];
                 let items: Vec<ListItem> = opts.iter().enumerate().map(|(i, s)|{
                     let mark = if i==app.options_menu_index {">"} else {" "};
@@ -703,6 +1031,15 @@ fn draw(f: &mut ratatui::Frame<'_>, app: &AppState) {
                 f.render_widget(Clear, area);
                 f.render_widget(p, area);
             }
+            InputMode::ConfirmOrder => {
+                let lines: Vec<Line> = merge_order_lines(app).into_iter().map(Line::from).collect();
+                let p = Paragraph::new(lines)
+                    .block(Block::default().title("Merge Order · y/Enter=Run · n/Esc=Cancel").borders(Borders::ALL))
+                    .wrap(ratatui::widgets::Wrap{ trim: false })
+                    .style(Style::default().fg(app.theme.fg).add_modifier(Modifier::BOLD));
+                f.render_widget(Clear, area);
+                f.render_widget(p, area);
+            }
             _ => {
                 // 输入态：显示可编辑文本并插入可见光标符号
                 let (title, show_cursor) = match app.input_mode {
@@ -711,6 +1048,8 @@ fn draw(f: &mut ratatui::Frame<'_>, app: &AppState) {
                     InputMode::EditPages => ("Page Ranges", true),
                     InputMode::EditSplitSuffix => ("Split Suffix", true),
                     InputMode::EditSplitRange => ("Split Range (pages per file)", true),
+                    InputMode::EditIncludes => ("Include Globs (; separated)", true),
+                    InputMode::EditExcludes => ("Exclude Globs (; separated)", true),
                     _ => ("", false),
                 };
                 if show_cursor {
@@ -731,13 +1070,19 @@ fn draw(f: &mut ratatui::Frame<'_>, app: &AppState) {
 Mode\n\
 - Files: set Input/Output paths\n\
 - Mode: Merge / Split\n\
-- Options: Depth (1/2/3/∞), Split range (pages per file), Overwrite (Force/Suffix), Output auto-follow\n\
+- Options: Depth (1/2/3/∞), Split range (pages per file), Overwrite (Force/Suffix), Output auto-follow, Include/Exclude globs (; separated)\n\
 Controls\n\
 - Toggle top/menu focus: g\n\
 - Navigate: Tab / ← →, ↑/↓/j/k\n\
-- Select/Run: Space / Enter\n\
+- Scan depth: [ shallower (min 1), ] deeper, \\ toggle ∞ (rescans at once)\n\
+- Select/Run: Space / Enter (🔒 encrypted and ! unreadable files can't be selected)\n\
+- Copy last output path: y\n\
+- Open output folder: O\n\
+- Log pane (status/error history): L, scroll PgUp/PgDn\n\
+- Cycle theme: t\n\
 - Cancel: Esc   Quit: q\n\
 Notes\n\
+- Merge: the final order with each file's page count (after the pages spec) is shown first; turn off in Options (Confirm merge order).\n\
 - Split: if estimated outputs > 20, confirmation is required.\n\
 - Suffix strategy avoids overwriting by appending _1/_2/...\n\
 - Paths: supports spaces, quotes, and ~ expansion.";
@@ -773,6 +1118,39 @@ fn ensure_unique_path(p: &PathBuf) -> PathBuf {
     p.clone()
 }
 
+// One line per file in merge order with the pages it contributes after the pages spec, then
+// the total. Counts come from the background probe, so files still being probed show "?".
+fn merge_order_lines(app: &AppState) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut total = Some(0usize);
+    for (pos, item) in app.order.iter().filter_map(|&i| app.files.get(i)).enumerate() {
+        let count = match &item.probe {
+            Some(FileStatus::Ok(n)) => contributed_pages(*n, app.pages.as_deref()),
+            Some(st) => Err(st.problem().unwrap_or_default()),
+            None => Err("?".into()),
+        };
+        let desc = match &count {
+            Ok(n) => format!("{} pages", n),
+            Err(e) if e == "?" => "? pages (probing)".into(),
+            Err(e) => format!("! {}", e),
+        };
+        total = total.zip(count.ok()).map(|(t, n)| t + n);
+        lines.push(format!("{:>3}. {}  ({})", pos + 1, item.name, desc));
+    }
+    lines.push(String::new());
+    lines.push(match total {
+        Some(t) => format!("Total: {} pages from {} files", t, app.order.len()),
+        None => format!("Total: ? pages from {} files", app.order.len()),
+    });
+    lines
+}
+
+fn contributed_pages(total: usize, pages: Option<&str>) -> std::result::Result<usize, String> {
+    let Some(spec) = pages else { return Ok(total) };
+    let ranges = crate::spec::parse_spec_with_phrases(spec, total).map_err(|e| format!("pages spec: {}", e))?;
+    Ok(crate::spec::expand_to_indexes(&ranges, total).len())
+}
+
 fn spawn_merge_job(app: &mut AppState, tx: mpsc::Sender<UiMsg>) {
     app.job_running = true;
     let files: Vec<PathBuf> = app.order.iter().filter_map(|&i| app.files.get(i)).map(|it| it.path.clone()).collect();
@@ -786,9 +1164,10 @@ fn spawn_merge_job(app: &mut AppState, tx: mpsc::Sender<UiMsg>) {
     let tx2 = tx.clone();
     thread::spawn(move || {
         let prog = TuiProgress::new(tx2.clone());
-        let res = crate::merge::run_with_files(&files, &final_output, pages.as_deref(), force, &prog);
+        let opts = crate::merge::MergeOptions { pages, force, ..Default::default() };
+        let res = crate::merge::run_with_files(&files, &final_output, &opts, &prog).map(|_| ());
         let note = format!("{}", final_output.display());
-        let _ = tx2.send(UiMsg::JobDone(res, note));
+        let _ = tx2.send(UiMsg::JobDone(res, note, final_output));
     });
 }
 
@@ -803,28 +1182,29 @@ fn choose_out_dir(input_dir: &PathBuf, output: &PathBuf) -> PathBuf {
 
 // split job handled via spawn_split_job_params after preflight
 
-fn make_ranges_spec(total: usize, group: usize) -> String {
-    if total==0 || group==0 { return String::new(); }
-    let mut parts = Vec::new();
-    let mut start = 1usize;
-    while start <= total {
-        let end = (start + group - 1).min(total);
-        parts.push(format!("{}-{}", start, end));
-        start = end + 1;
-    }
-    parts.join(",")
-}
-
-fn spawn_split_job_params(input: PathBuf, out_dir: PathBuf, each: bool, ranges: Option<String>, pattern: String, force: bool, tx: mpsc::Sender<UiMsg>) {
+fn spawn_split_job_params(input: PathBuf, out_dir: PathBuf, mode: SplitMode, pattern: String, force: bool, tx: mpsc::Sender<UiMsg>) {
     let tx2 = tx.clone();
     thread::spawn(move || {
         let prog = TuiProgress::new(tx2.clone());
-        let res = crate::split::run(&input, &out_dir, each, ranges.as_deref(), &pattern, force, &prog);
+        let opts = crate::split::SplitOptions { mode, pattern, force, ..Default::default() };
+        let res = crate::split::run(&input, &out_dir, &opts, &prog);
         let note = format!("{} -> {}", input.display(), out_dir.display());
-        let _ = tx2.send(UiMsg::JobDone(res, note));
+        let _ = tx2.send(UiMsg::JobDone(res, note, out_dir));
     });
 }
 
+// No clipboard (headless/SSH) surfaces as an error; callers report it in the status line
+fn copy_to_clipboard(text: &str) -> Result<()> {
+    let mut cb = arboard::Clipboard::new()?;
+    cb.set_text(text.to_string())?;
+    Ok(())
+}
+
+// Split jobs report their output directory, merge jobs the output file
+fn output_dir_of(output: &Path) -> Option<PathBuf> {
+    if output.is_dir() { Some(output.to_path_buf()) } else { output.parent().map(Path::to_path_buf) }
+}
+
 fn centered_rect(pct_x: u16, pct_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
         .direction(Direction::Vertical)
@@ -844,3 +1224,118 @@ fn centered_rect(pct_x: u16, pct_y: u16, r: Rect) -> Rect {
         .split(popup_layout[1]);
     horizontal[1]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_is_bounded_and_skips_unchanged_status() {
+        let mut app = AppState::new(PathBuf::from("."));
+        for i in 0..(LOG_CAPACITY + 50) { app.push_log(&format!("msg {}", i)); }
+        assert_eq!(app.log.len(), LOG_CAPACITY);
+        assert!(app.log.back().unwrap().ends_with(&format!("msg {}", LOG_CAPACITY + 49)));
+
+        app.log.clear();
+        app.status = "Ready".into();
+        app.log_status_change();
+        app.log_status_change();
+        assert_eq!(app.log.len(), 1);
+    }
+
+    #[test]
+    fn output_dir_is_split_dir_or_merge_parent() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(output_dir_of(dir.path()).unwrap(), dir.path());
+        assert_eq!(output_dir_of(&dir.path().join("merged.pdf")).unwrap(), dir.path());
+        assert!(AppState::new(PathBuf::from(".")).last_output_dir.is_none());
+    }
+
+    #[test]
+    fn probe_flags_unreadable_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let bad = dir.path().join("bad.pdf");
+        std::fs::write(&bad, b"not a pdf").unwrap();
+        let st = probe_file(&bad);
+        assert!(matches!(st, FileStatus::Corrupt(_)));
+        assert_eq!(st.marker(), "! ");
+        assert!(st.problem().unwrap().starts_with("cannot be loaded"));
+        assert_eq!(FileStatus::Encrypted.problem().as_deref(), Some("encrypted"));
+        assert!(FileStatus::Ok(3).problem().is_none());
+    }
+
+    #[test]
+    fn order_preview_counts_pages_after_spec() {
+        let mut app = AppState::new(PathBuf::from("."));
+        for (name, probe) in [("a.pdf", Some(FileStatus::Ok(10))), ("b.pdf", Some(FileStatus::Ok(2))), ("c.pdf", None)] {
+            app.files.push(FileItem { name: name.into(), path: PathBuf::from(name), checked: true, probe });
+        }
+        app.order = vec![1, 0];
+        app.pages = Some("1-3".into());
+        let lines = merge_order_lines(&app);
+        assert_eq!(lines[0], "  1. b.pdf  (2 pages)");
+        assert_eq!(lines[1], "  2. a.pdf  (3 pages)");
+        assert_eq!(lines.last().unwrap(), "Total: 5 pages from 2 files");
+
+        app.order.push(2);
+        assert!(merge_order_lines(&app)[2].ends_with("(? pages (probing))"));
+        assert_eq!(merge_order_lines(&app).last().unwrap(), "Total: ? pages from 3 files");
+    }
+
+    #[test]
+    fn found_batches_flush_on_count_and_age() {
+        use std::time::{Duration, Instant};
+        let t0 = Instant::now();
+        let mut batch = FoundBatcher::new(3, Duration::from_millis(50));
+        assert_eq!(batch.push("a.pdf".into(), t0), None);
+        assert_eq!(batch.push("b.pdf".into(), t0 + Duration::from_millis(10)), None);
+        assert_eq!(batch.push("c.pdf".into(), t0 + Duration::from_millis(20)).unwrap(), [PathBuf::from("a.pdf"), "b.pdf".into(), "c.pdf".into()]);
+
+        // The age counts from the oldest pending path, not the latest
+        assert_eq!(batch.push("d.pdf".into(), t0 + Duration::from_millis(30)), None);
+        assert_eq!(batch.flush_due(t0 + Duration::from_millis(79)), None);
+        assert_eq!(batch.flush_due(t0 + Duration::from_millis(80)).unwrap(), [PathBuf::from("d.pdf")]);
+        assert_eq!(batch.flush_due(t0 + Duration::from_secs(5)), None);
+        assert_eq!(batch.push("e.pdf".into(), t0 + Duration::from_secs(6)), None);
+        assert_eq!(batch.take().unwrap(), [PathBuf::from("e.pdf")]);
+        assert_eq!(batch.take(), None);
+    }
+
+    #[test]
+    fn glob_list_splits_and_validates() {
+        assert_eq!(parse_glob_list(" *.pdf ; scans/{a,b}/** ;").unwrap(), ["*.pdf", "scans/{a,b}/**"]);
+        assert!(parse_glob_list("").unwrap().is_empty());
+        assert!(parse_glob_list("ok/*.pdf; [unclosed").is_err());
+    }
+
+    #[test]
+    fn depth_keys_step_and_toggle_infinite() {
+        let mut app = AppState::new(PathBuf::from("."));
+        app.step_depth(false);
+        assert_eq!(app.scan_depth, Some(1));
+        app.step_depth(true);
+        app.step_depth(true);
+        assert_eq!(app.scan_depth, Some(3));
+        app.toggle_infinite_depth();
+        assert_eq!(app.scan_depth, None);
+        app.step_depth(true);
+        assert_eq!(app.scan_depth, None);
+        app.toggle_infinite_depth();
+        assert_eq!(app.scan_depth, Some(3));
+        app.toggle_infinite_depth();
+        app.step_depth(false);
+        assert_eq!(app.scan_depth, Some(3));
+        app.step_depth(false);
+        assert_eq!(app.scan_depth, Some(2));
+    }
+
+    #[test]
+    fn theme_cycles_through_builtins() {
+        let mut app = AppState::new(PathBuf::from("."));
+        app.set_theme(theme::resolve(Some("light")));
+        assert_eq!(theme::BUILTIN[app.theme_index], "light");
+        app.set_theme((app.theme_index + 1) % theme::BUILTIN.len());
+        assert_eq!(app.theme_index, 0);
+        assert_eq!(theme::resolve(Some("nope")), 0);
+    }
+}
@@ -13,6 +13,7 @@ pub struct Theme {
     pub sel_highlight_bg: Color,
     pub sel_highlight_fg: Color,
     pub ok: Color,
+    pub err: Color,
 }
 
 impl Theme {
@@ -28,6 +29,7 @@ impl Theme {
             sel_highlight_bg: Color::Green,
             sel_highlight_fg: Color::Black,
             ok: Color::Green,
+            err: Color::Red,
         }
     }
 
@@ -42,14 +44,25 @@ impl Theme {
             sel_highlight_bg: Color::Rgb(200, 230, 201),
             sel_highlight_fg: Color::Rgb(0, 0, 0),
             ok: Color::Rgb(46, 160, 67),
+            err: Color::Rgb(211, 47, 47),
         }
     }
 }
 
-#[allow(dead_code)]
-pub fn resolve(name: Option<String>) -> Theme {
-    match name.as_deref() {
-        Some("light") => Theme::light(),
-        _ => Theme::gitui_dark(),
+/// Built-in theme names, in `t` cycling order.
+pub const BUILTIN: &[&str] = &["gitui-dark", "light"];
+
+pub fn by_name(name: &str) -> Option<Theme> {
+    match name {
+        "gitui-dark" | "gitui_dark" => Some(Theme::gitui_dark()),
+        "light" => Some(Theme::light()),
+        _ => None,
     }
 }
+
+/// Index into `BUILTIN` for a `--theme` value; unknown or missing names fall back to gitui-dark.
+pub fn resolve(name: Option<&str>) -> usize {
+    name.map(|n| n.replace('_', "-"))
+        .and_then(|n| BUILTIN.iter().position(|b| *b == n))
+        .unwrap_or(0)
+}
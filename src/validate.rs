@@ -0,0 +1,163 @@
+//! `validate`: structural checks on a loaded PDF, for files the other commands might choke on.
+//!
+//! Checked: the trailer `/Root` points at a catalog, the catalog has a `/Pages` tree, every
+//! `/Kids` and `/Annots` entry resolves, each `/Pages` node's `/Count` matches the pages below
+//! it, and every page has a MediaBox (itself or inherited). Content streams are not parsed.
+
+use anyhow::{Context, Result};
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+
+use crate::page;
+
+// Guards against absurdly deep page trees (cycles are caught by the visited set)
+const MAX_DEPTH: usize = 64;
+
+/// One structural problem, tied to the object it was found on when there is one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub object: Option<ObjectId>,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.object {
+            Some((id, gen)) => write!(f, "{} {} R: {}", id, gen, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Load `path` and list its structural problems; empty when none were found. Fails only when
+/// the file cannot be loaded at all.
+pub fn validate(path: &Path) -> Result<Vec<Problem>> {
+    let doc = Document::load(path).with_context(|| format!("加载 PDF 失败: {}", path.display()))?;
+    Ok(check(&doc))
+}
+
+fn check(doc: &Document) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let mut report = |object: Option<ObjectId>, message: String| problems.push(Problem { object, message });
+    let root = match doc.trailer.get(b"Root") {
+        Ok(Object::Reference(id)) => *id,
+        Ok(_) => { report(None, "trailer 的 /Root 不是间接引用".into()); return problems; }
+        Err(_) => { report(None, "trailer 缺少 /Root".into()); return problems; }
+    };
+    let Ok(catalog) = doc.get_dictionary(root) else {
+        report(Some(root), "/Root 指向的对象不存在或不是字典".into());
+        return problems;
+    };
+    if catalog.get(b"Type").and_then(Object::as_name_str).ok() != Some("Catalog") {
+        report(Some(root), "目录的 /Type 不是 /Catalog".into());
+    }
+    let pages = match catalog.get(b"Pages") {
+        Ok(Object::Reference(id)) if doc.get_dictionary(*id).is_ok() => *id,
+        Ok(Object::Reference(id)) => { report(Some(root), format!("/Pages 指向的对象 {} {} R 不存在或不是字典", id.0, id.1)); return problems; }
+        _ => { report(Some(root), "目录缺少 /Pages 引用".into()); return problems; }
+    };
+    let mut visited = HashSet::new();
+    walk(doc, pages, 0, &mut visited, &mut report);
+    problems
+}
+
+// Check the subtree at `id` and return how many pages it holds
+fn walk(doc: &Document, id: ObjectId, depth: usize, visited: &mut HashSet<ObjectId>, report: &mut impl FnMut(Option<ObjectId>, String)) -> usize {
+    if !visited.insert(id) {
+        report(Some(id), "页面树中出现循环或重复引用".into());
+        return 0;
+    }
+    let Ok(node) = doc.get_dictionary(id) else { return 0 };
+    match node.get(b"Type").and_then(Object::as_name_str).ok() {
+        Some("Pages") => {
+            if depth >= MAX_DEPTH {
+                report(Some(id), format!("页面树超过 {} 层", MAX_DEPTH));
+                return 0;
+            }
+            let kids = match node.get(b"Kids").and_then(Object::as_array) {
+                Ok(kids) => kids,
+                Err(_) => { report(Some(id), "/Pages 节点缺少 /Kids 数组".into()); return 0; }
+            };
+            let mut count = 0;
+            for kid in kids {
+                match kid {
+                    Object::Reference(kid_id) if doc.get_dictionary(*kid_id).is_ok() => count += walk(doc, *kid_id, depth + 1, visited, report),
+                    Object::Reference(kid_id) => report(Some(id), format!("/Kids 中的 {} {} R 不存在或不是字典", kid_id.0, kid_id.1)),
+                    _ => report(Some(id), "/Kids 中有非引用的条目".into()),
+                }
+            }
+            match node.get(b"Count").and_then(Object::as_i64) {
+                Ok(n) if n == count as i64 => {}
+                Ok(n) => report(Some(id), format!("/Count 为 {}，实际页数为 {}", n, count)),
+                Err(_) => report(Some(id), "/Pages 节点缺少 /Count".into()),
+            }
+            count
+        }
+        Some("Page") => {
+            check_page(doc, id, node, report);
+            1
+        }
+        _ => {
+            report(Some(id), "页面树节点的 /Type 既不是 /Pages 也不是 /Page".into());
+            0
+        }
+    }
+}
+
+fn check_page(doc: &Document, id: ObjectId, page: &Dictionary, report: &mut impl FnMut(Option<ObjectId>, String)) {
+    if page::media_box(doc, id).is_none() {
+        report(Some(id), "页面缺少有效的 /MediaBox（包括继承）".into());
+    }
+    let annots = match page.get(b"Annots") {
+        Ok(Object::Array(a)) => a,
+        Ok(Object::Reference(r)) => match doc.get_object(*r).and_then(Object::as_array) {
+            Ok(a) => a,
+            Err(_) => { report(Some(id), format!("/Annots 指向的 {} {} R 不存在或不是数组", r.0, r.1)); return; }
+        },
+        _ => return,
+    };
+    for annot in annots {
+        if let Object::Reference(r) = annot {
+            if doc.get_object(*r).is_err() {
+                report(Some(id), format!("/Annots 中的 {} {} R 不存在", r.0, r.1));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::dictionary;
+
+    fn one_page_doc() -> (Document, ObjectId, ObjectId) {
+        let mut doc = Document::with_version("1.5");
+        let pages = doc.new_object_id();
+        let page = doc.add_object(dictionary! { "Type" => "Page", "Parent" => pages, "MediaBox" => vec![0.into(), 0.into(), 10.into(), 10.into()] });
+        doc.objects.insert(pages, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => vec![page.into()], "Count" => 1 }));
+        let catalog = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages });
+        doc.trailer.set("Root", catalog);
+        (doc, pages, page)
+    }
+
+    #[test]
+    fn well_formed_document_has_no_problems() {
+        assert_eq!(check(&one_page_doc().0), []);
+    }
+
+    #[test]
+    fn dangling_references_and_missing_boxes_are_reported() {
+        let (mut doc, pages, page) = one_page_doc();
+        let page_dict = doc.get_dictionary_mut(page).unwrap();
+        page_dict.remove(b"MediaBox");
+        page_dict.set("Annots", vec![Object::Reference((99, 0))]);
+        doc.get_dictionary_mut(pages).unwrap().set("Kids", vec![page.into(), Object::Reference((98, 0))]);
+        let messages: Vec<String> = check(&doc).iter().map(|p| p.to_string()).collect();
+        assert_eq!(messages.len(), 3, "{messages:?}");
+        assert!(messages[0].contains("/MediaBox"));
+        assert!(messages[1].contains("99 0 R"));
+        assert!(messages[2].contains("98 0 R"));
+    }
+}
@@ -0,0 +1,103 @@
+//! `--pdf-version`: the header version written files declare, and what it rules out.
+//!
+//! Our own output only needs 1.5 for its cross-reference stream (lopdf never packs objects
+//! into object streams), and `apply` switches to a classic table below that. What can still
+//! need a newer version is content carried over from the inputs; `apply` refuses to label
+//! such a document too low.
+
+use anyhow::Result;
+use lopdf::xref::XrefType;
+use lopdf::{Document, Object};
+
+/// Output PDF version. Defaults to 1.5, what every output was written as before the flag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, clap::ValueEnum, serde::Deserialize)]
+pub enum PdfVersion {
+    #[value(name = "1.4")]
+    #[serde(rename = "1.4")]
+    V1_4,
+    #[default]
+    #[value(name = "1.5")]
+    #[serde(rename = "1.5")]
+    V1_5,
+    #[value(name = "1.6")]
+    #[serde(rename = "1.6")]
+    V1_6,
+    #[value(name = "1.7")]
+    #[serde(rename = "1.7")]
+    V1_7,
+}
+
+impl PdfVersion {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PdfVersion::V1_4 => "1.4",
+            PdfVersion::V1_5 => "1.5",
+            PdfVersion::V1_6 => "1.6",
+            PdfVersion::V1_7 => "1.7",
+        }
+    }
+}
+
+/// Set `doc`'s header version, failing if it uses something `version` does not have.
+pub(crate) fn apply(doc: &mut Document, version: PdfVersion) -> Result<()> {
+    // Cross-reference streams arrived in 1.5
+    doc.reference_table.cross_reference_type = if version < PdfVersion::V1_5 { XrefType::CrossReferenceTable } else { XrefType::CrossReferenceStream };
+    let missing: Vec<String> = requirements(doc)
+        .into_iter()
+        .filter(|&(_, needed)| needed > version)
+        .map(|(feature, needed)| format!("{}（需要 PDF {}）", feature, needed.as_str()))
+        .collect();
+    if !missing.is_empty() {
+        anyhow::bail!("输出版本 PDF {} 不支持: {}", version.as_str(), missing.join("，"));
+    }
+    doc.version = version.as_str().to_string();
+    Ok(())
+}
+
+// Version-dependent features present in `doc`, each with the first version that has it
+fn requirements(doc: &Document) -> Vec<(&'static str, PdfVersion)> {
+    let mut found = Vec::new();
+    let (mut jpx, mut optional_content) = (false, false);
+    for obj in doc.objects.values() {
+        let dict = match obj {
+            Object::Dictionary(d) => d,
+            Object::Stream(s) => {
+                jpx |= uses_filter(s.dict.get(b"Filter").ok(), b"JPXDecode");
+                &s.dict
+            }
+            _ => continue,
+        };
+        optional_content |= dict.has(b"OC") || dict.has(b"OCProperties");
+    }
+    if jpx { found.push(("JPEG 2000 图像 (JPXDecode)", PdfVersion::V1_5)); }
+    if optional_content { found.push(("可选内容（图层）", PdfVersion::V1_5)); }
+    found
+}
+
+fn uses_filter(filter: Option<&Object>, name: &[u8]) -> bool {
+    match filter {
+        Some(Object::Name(n)) => n == name,
+        Some(Object::Array(a)) => a.iter().any(|f| matches!(f, Object::Name(n) if n == name)),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{dictionary, Stream};
+
+    #[test]
+    fn newer_features_block_older_versions() {
+        let mut doc = Document::with_version("1.5");
+        apply(&mut doc, PdfVersion::V1_4).unwrap();
+        assert_eq!(doc.version, "1.4");
+        assert!(matches!(doc.reference_table.cross_reference_type, XrefType::CrossReferenceTable));
+
+        doc.add_object(Stream::new(dictionary! { "Subtype" => "Image", "Filter" => vec![Object::Name(b"JPXDecode".to_vec())] }, Vec::new()));
+        let err = apply(&mut doc, PdfVersion::V1_4).unwrap_err();
+        assert!(err.to_string().contains("JPXDecode"), "{err}");
+        apply(&mut doc, PdfVersion::V1_7).unwrap();
+        assert_eq!(doc.version, "1.7");
+    }
+}
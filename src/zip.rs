@@ -0,0 +1,63 @@
+//! `pdf-ops zip front.pdf back.pdf`: interleave two single-sided scans into duplex order.
+
+use anyhow::{Context, Result};
+use lopdf::{Document, Object, ObjectId};
+use std::path::Path;
+
+use crate::split;
+
+#[derive(Debug, Clone, Default)]
+pub struct ZipOptions {
+    /// The back sides were scanned last page first (flipping the whole stack)
+    pub reverse_back: bool,
+    pub force: bool,
+}
+
+/// Write `front[0], back[0], front[1], back[1], …` to `output`. Both inputs must have the same
+/// page count. Returns the number of pages written.
+pub fn run(front: &Path, back: &Path, output: &Path, opts: &ZipOptions) -> Result<usize> {
+    if output.exists() && !opts.force {
+        anyhow::bail!("输出文件已存在: {} (使用 --force 覆盖)", output.display());
+    }
+    let load = |p: &Path| Document::load(p).with_context(|| format!("加载 PDF 失败: {}", p.display()));
+    let (front_doc, back_doc) = (load(front)?, load(back)?);
+    let (nf, nb) = (front_doc.get_pages().len(), back_doc.get_pages().len());
+    if nf == 0 { anyhow::bail!("输入 PDF 没有可用页面: {}", front.display()); }
+    if nf != nb {
+        anyhow::bail!("正反面页数不一致: {} 有 {} 页，{} 有 {} 页", front.display(), nf, back.display(), nb);
+    }
+
+    // Only what the pages reference, with inherited attributes already resolved
+    let mut doc = split::extract_range(&front_doc, 1, nf)?;
+    let mut backs = split::extract_range(&back_doc, 1, nb)?;
+    backs.renumber_objects_with(doc.max_id + 1);
+    let pages_id: ObjectId = doc.catalog()?.get(b"Pages")?.as_reference()?;
+    let front_ids: Vec<ObjectId> = doc.get_pages().into_values().collect();
+    let mut back_ids: Vec<ObjectId> = backs.get_pages().into_values().collect();
+    if opts.reverse_back { back_ids.reverse(); }
+
+    // The back part's own Catalog and Pages are dropped; its pages join the front's tree
+    let back_tree: Vec<ObjectId> = {
+        let catalog = backs.trailer.get(b"Root").and_then(Object::as_reference)?;
+        vec![catalog, backs.catalog()?.get(b"Pages")?.as_reference()?]
+    };
+    for (id, mut obj) in backs.objects {
+        if back_tree.contains(&id) { continue; }
+        if back_ids.contains(&id) {
+            if let Ok(d) = obj.as_dict_mut() { d.set("Parent", Object::Reference(pages_id)); }
+        }
+        doc.objects.insert(id, obj);
+    }
+    doc.max_id = doc.max_id.max(backs.max_id);
+
+    let kids: Vec<Object> = front_ids.iter().zip(&back_ids).flat_map(|(&f, &b)| [Object::Reference(f), Object::Reference(b)]).collect();
+    let total = kids.len();
+    let pages = doc.get_dictionary_mut(pages_id)?;
+    pages.set("Kids", kids);
+    pages.set("Count", total as i64);
+
+    doc.compress();
+    if let Some(parent) = output.parent() { std::fs::create_dir_all(parent).ok(); }
+    crate::interrupt::save(&mut doc, output)?;
+    Ok(total)
+}
@@ -7,16 +7,25 @@ use std::path::PathBuf;
 use lopdf::{Document, Dictionary, Object, ObjectId};
 
 fn create_pdf(dir: &std::path::Path, name: &str, pages: usize) -> PathBuf {
+    create_pdf_with_contents(dir, name, &vec![None; pages])
+}
+
+// One page per entry; `Some(bytes)` becomes the page's content stream
+fn create_pdf_with_contents(dir: &std::path::Path, name: &str, contents: &[Option<&[u8]>]) -> PathBuf {
     let mut doc = Document::with_version("1.5");
     let mut page_ids: Vec<ObjectId> = Vec::new();
 
-    for _ in 0..pages {
+    for content in contents {
         let page_id = doc.new_object_id();
         let mut page = Dictionary::new();
         page.set("Type", "Page");
         // Minimal content: empty resources and media box
         page.set("Resources", Dictionary::new());
         page.set("MediaBox", vec![0.into(), 0.into(), 200.into(), 200.into()]);
+        if let Some(bytes) = content {
+            let cid = doc.add_object(Object::Stream(lopdf::Stream::new(Dictionary::new(), bytes.to_vec())));
+            page.set("Contents", Object::Reference(cid));
+        }
         doc.objects.insert(page_id, Object::Dictionary(page));
         page_ids.push(page_id);
     }
@@ -31,7 +40,7 @@ fn create_pdf(dir: &std::path::Path, name: &str, pages: usize) -> PathBuf {
     let mut pages_dict = Dictionary::new();
     pages_dict.set("Type", "Pages");
     pages_dict.set("Kids", Object::Array(kids));
-    pages_dict.set("Count", pages as i64);
+    pages_dict.set("Count", contents.len() as i64);
     doc.objects.insert(pages_id, Object::Dictionary(pages_dict));
 
     let catalog_id = doc.new_object_id();
@@ -47,6 +56,65 @@ fn create_pdf(dir: &std::path::Path, name: &str, pages: usize) -> PathBuf {
     path
 }
 
+fn set_info(path: &std::path::Path, key: &str, value: &str) {
+    let mut doc = Document::load(path).unwrap();
+    let info_id = match doc.trailer.get(b"Info") {
+        Ok(Object::Reference(id)) => *id,
+        _ => {
+            let id = doc.add_object(Object::Dictionary(Dictionary::new()));
+            doc.trailer.set("Info", Object::Reference(id));
+            id
+        }
+    };
+    doc.get_dictionary_mut(info_id).unwrap().set(key, Object::string_literal(value));
+    doc.save(path).unwrap();
+}
+
+// PDF 1.5 with every non-stream object packed into an object stream, indexed by a
+// cross-reference stream (the layout most modern producers emit)
+fn create_objstm_pdf(dir: &std::path::Path, name: &str, pages: usize) -> PathBuf {
+    use std::io::Write;
+    let mut objs = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        format!("<< /Type /Pages /Kids [{}] /Count {} >>", (3..3 + pages).map(|i| format!("{} 0 R", i)).collect::<Vec<_>>().join(" "), pages),
+    ];
+    objs.extend((0..pages).map(|_| "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 200 200] /Resources << >> >>".to_string()));
+    let (mut header, mut body) = (Vec::new(), String::new());
+    for (k, o) in objs.iter().enumerate() {
+        header.push(format!("{} {}", k + 1, body.len()));
+        body.push_str(o);
+        body.push('\n');
+    }
+    let header = header.join(" ") + "\n";
+    let (objstm_num, xref_num) = (objs.len() + 1, objs.len() + 2);
+
+    let mut out = b"%PDF-1.5\n".to_vec();
+    let objstm_off = out.len();
+    write!(out, "{} 0 obj\n<< /Type /ObjStm /N {} /First {} /Length {} >>\nstream\n{}{}\nendstream\nendobj\n",
+        objstm_num, objs.len(), header.len(), header.len() + body.len(), header, body).unwrap();
+    let xref_off = out.len();
+    // /W [1 4 2]: type, offset or containing stream, generation or index
+    let entry = |data: &mut Vec<u8>, t: u8, a: u32, b: u16| { data.push(t); data.extend(a.to_be_bytes()); data.extend(b.to_be_bytes()); };
+    let mut data = Vec::new();
+    entry(&mut data, 0, 0, 0xffff);
+    for k in 0..objs.len() { entry(&mut data, 2, objstm_num as u32, k as u16); }
+    entry(&mut data, 1, objstm_off as u32, 0);
+    entry(&mut data, 1, xref_off as u32, 0);
+    write!(out, "{} 0 obj\n<< /Type /XRef /Size {} /W [1 4 2] /Root 1 0 R /Length {} >>\nstream\n", xref_num, xref_num + 1, data.len()).unwrap();
+    out.extend(&data);
+    write!(out, "\nendstream\nendobj\nstartxref\n{}\n%%EOF\n", xref_off).unwrap();
+
+    let path = dir.join(name);
+    fs::write(&path, out).unwrap();
+    path
+}
+
+// Decoded content stream of each page, in page order
+fn page_contents(path: &std::path::Path) -> Vec<String> {
+    let pdf = Document::load(path).unwrap();
+    pdf.get_pages().values().map(|&id| String::from_utf8_lossy(&pdf.get_page_content(id).unwrap()).into_owned()).collect()
+}
+
 fn page_count(path: &std::path::Path) -> usize {
     let pdf = Document::load(path).unwrap();
     pdf.get_pages().len()
@@ -122,3 +190,2331 @@ fn split_defaults_to_each_and_ranges() {
     }
     assert_eq!(count2, 2);
 }
+
+#[test]
+fn merge_first_pages_overrides_spec_for_first_file() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    let in_dir = root.join("in");
+    fs::create_dir_all(&in_dir).unwrap();
+    create_pdf(&in_dir, "a.pdf", 3);
+    create_pdf(&in_dir, "b.pdf", 3);
+    create_pdf(&in_dir, "c.pdf", 4);
+
+    // a keeps only its cover (1), b/c use --pages 1-2 => 1 + 2 + 2
+    let out1 = root.join("out1.pdf");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "-i"]).arg(&in_dir)
+        .args(["-o"]).arg(&out1)
+        .args(["--first-pages", "1", "--pages", "1-2"])
+        .assert().success();
+    assert_eq!(page_count(&out1), 5);
+
+    // without --pages the remaining files are taken whole => 1 + 3 + 4
+    let out2 = root.join("out2.pdf");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "-i"]).arg(&in_dir)
+        .args(["-o"]).arg(&out2)
+        .args(["--first-pages", "1"])
+        .assert().success();
+    assert_eq!(page_count(&out2), 8);
+}
+
+#[test]
+fn merge_exclude_blank_drops_empty_pages() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    let in_dir = root.join("in");
+    fs::create_dir_all(&in_dir).unwrap();
+    let text: &[u8] = b"BT /F1 12 Tf 20 20 Td (page) Tj ET";
+    // real, blank (no /Contents), real, blank (trivial stream)
+    create_pdf_with_contents(&in_dir, "scan.pdf", &[Some(text), None, Some(text), Some(b"q Q")]);
+
+    let out = root.join("out.pdf");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "-i"]).arg(&in_dir)
+        .args(["-o"]).arg(&out)
+        .arg("--exclude-blank")
+        .assert().success();
+    assert_eq!(page_count(&out), 2);
+
+    // default keeps every page
+    let out_all = root.join("out_all.pdf");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "-i"]).arg(&in_dir)
+        .args(["-o"]).arg(&out_all)
+        .assert().success();
+    assert_eq!(page_count(&out_all), 4);
+}
+
+#[test]
+fn merge_max_output_pages_guard() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    let in_dir = root.join("in");
+    fs::create_dir_all(&in_dir).unwrap();
+    create_pdf(&in_dir, "a.pdf", 2);
+    create_pdf(&in_dir, "b.pdf", 3);
+
+    let out = root.join("out.pdf");
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "-i"]).arg(&in_dir)
+        .args(["-o"]).arg(&out)
+        .args(["--max-output-pages", "4"])
+        .assert().failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).to_string();
+    assert!(stderr.contains("输出页数 5 超过上限 4"), "stderr: {}", stderr);
+    assert!(!out.exists());
+
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "-i"]).arg(&in_dir)
+        .args(["-o"]).arg(&out)
+        .args(["--max-output-pages", "5"])
+        .assert().success();
+    assert_eq!(page_count(&out), 5);
+}
+
+#[test]
+fn merge_sort_by_title_uses_natural_order() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    let in_dir = root.join("in");
+    fs::create_dir_all(&in_dir).unwrap();
+    let a = create_pdf_with_contents(&in_dir, "a.pdf", &[Some(b"(ch10) Tj")]);
+    set_info(&a, "Title", "Chapter 10");
+    let b = create_pdf_with_contents(&in_dir, "b.pdf", &[Some(b"(ch2) Tj")]);
+    set_info(&b, "Title", "Chapter 2");
+    // untitled sorts last even though its name sorts first
+    create_pdf_with_contents(&in_dir, "0-untitled.pdf", &[Some(b"(none) Tj")]);
+
+    let out = root.join("out.pdf");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "-i"]).arg(&in_dir)
+        .args(["-o"]).arg(&out)
+        .args(["--sort-by", "title"])
+        .assert().success();
+    let contents = page_contents(&out);
+    assert_eq!(contents.len(), 3);
+    assert!(contents[0].contains("(ch2)"));
+    assert!(contents[1].contains("(ch10)"));
+    assert!(contents[2].contains("(none)"));
+}
+
+#[test]
+fn config_file_seeds_split_pattern_unless_overridden() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    let input = create_pdf(root, "in.pdf", 3);
+    let cfg = root.join("pdf-ops.toml");
+    fs::write(&cfg, "[split]\npattern = \"{base}-{index:03}.pdf\"\n").unwrap();
+
+    let out_dir = root.join("out");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "-i"]).arg(&input)
+        .args(["-d"]).arg(&out_dir)
+        .args(["--config"]).arg(&cfg)
+        .assert().success();
+    for name in ["in-001.pdf", "in-002.pdf", "in-003.pdf"] {
+        assert!(out_dir.join(name).exists(), "missing {}", name);
+    }
+
+    let out_dir2 = root.join("out2");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "-i"]).arg(&input)
+        .args(["-d"]).arg(&out_dir2)
+        .args(["--config"]).arg(&cfg)
+        .args(["--pattern", "p{start}.pdf"])
+        .assert().success();
+    assert!(out_dir2.join("p1.pdf").exists());
+    assert!(!out_dir2.join("in-001.pdf").exists());
+}
+
+#[test]
+fn split_loads_once_and_keeps_page_contents() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    let markers: Vec<String> = (1..=20).map(|i| format!("(p{}) Tj", i)).collect();
+    let contents: Vec<Option<&[u8]>> = markers.iter().map(|m| Some(m.as_bytes())).collect();
+    let input = create_pdf_with_contents(root, "in.pdf", &contents);
+
+    let out_dir = root.join("each");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "-i"]).arg(&input)
+        .args(["-d"]).arg(&out_dir)
+        .assert().success();
+    for i in 1..=20 {
+        let part = page_contents(&out_dir.join(format!("in-{}-{}.pdf", i, i)));
+        assert_eq!(part.len(), 1);
+        assert!(part[0].contains(&format!("(p{})", i)));
+    }
+
+    let out_dir2 = root.join("ranges");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "-i"]).arg(&input)
+        .args(["-d"]).arg(&out_dir2)
+        .args(["--ranges", "1-5,6-"])
+        .assert().success();
+    let first = page_contents(&out_dir2.join("in-1-5.pdf"));
+    let second = page_contents(&out_dir2.join("in-6-20.pdf"));
+    assert_eq!((first.len(), second.len()), (5, 15));
+    assert!(first[4].contains("(p5)") && second[0].contains("(p6)") && second[14].contains("(p20)"));
+}
+
+#[test]
+fn split_fifty_pages_cached_and_low_memory() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    let markers: Vec<String> = (1..=50).map(|i| format!("(p{}) Tj", i)).collect();
+    let contents: Vec<Option<&[u8]>> = markers.iter().map(|m| Some(m.as_bytes())).collect();
+    let input = create_pdf_with_contents(root, "in.pdf", &contents);
+
+    for (sub, extra) in [("cached", None), ("low", Some("--low-memory"))] {
+        let out_dir = root.join(sub);
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")));
+        cmd.args(["split", "-i"]).arg(&input).args(["-d"]).arg(&out_dir);
+        if let Some(flag) = extra { cmd.arg(flag); }
+        cmd.assert().success();
+        assert_eq!(fs::read_dir(&out_dir).unwrap().count(), 50);
+        for i in 1..=50 {
+            let part = page_contents(&out_dir.join(format!("in-{}-{}.pdf", i, i)));
+            assert_eq!(part.len(), 1);
+            assert!(part[0].contains(&format!("(p{})", i)));
+        }
+    }
+}
+
+#[test]
+fn split_mode_every_prints_preview() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    let input = create_pdf(root, "in.pdf", 5);
+    let out_dir = root.join("out");
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "-i"]).arg(&input)
+        .args(["-d"]).arg(&out_dir)
+        .args(["--mode", "every:2"])
+        .assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    assert!(stdout.contains("分组: 3 个 · 1-2, 3-4, 5"), "stdout: {}", stdout);
+    assert_eq!(page_count(&out_dir.join("in-5-5.pdf")), 1);
+    assert_eq!(page_count(&out_dir.join("in-1-2.pdf")), 2);
+
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "-i"]).arg(&input)
+        .args(["-d"]).arg(&out_dir)
+        .args(["--mode", "every:2", "--ranges", "1-2"])
+        .assert().failure();
+}
+
+#[test]
+fn merge_timings_lists_every_file() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    let in_dir = root.join("in");
+    fs::create_dir_all(in_dir.join("sub")).unwrap();
+    create_pdf(&in_dir, "a.pdf", 1);
+    create_pdf(&in_dir, "b.pdf", 2);
+    create_pdf(&in_dir.join("sub"), "c.pdf", 1);
+
+    let out = root.join("out.pdf");
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "-i"]).arg(&in_dir)
+        .args(["-o"]).arg(&out)
+        .arg("--timings")
+        .assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    for name in ["a.pdf", "b.pdf", "c.pdf"] {
+        assert!(stdout.lines().any(|l| l.contains(" ms ") && l.ends_with(name)), "missing {} in: {}", name, stdout);
+    }
+    assert!(stdout.contains("总计:"));
+}
+
+#[test]
+fn merge_metadata_merged_unions_keywords() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    let in_dir = root.join("in");
+    fs::create_dir_all(&in_dir).unwrap();
+    let a = create_pdf(&in_dir, "a.pdf", 1);
+    let b = create_pdf(&in_dir, "b.pdf", 1);
+    set_info(&a, "Keywords", "invoice, 2023");
+    set_info(&a, "Author", "Ann");
+    set_info(&b, "Keywords", "receipt; 2023");
+    set_info(&b, "Author", "Bo");
+
+    let out = root.join("out.pdf");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "-i"]).arg(&in_dir)
+        .args(["-o"]).arg(&out)
+        .args(["--metadata", "merged"])
+        .assert().success();
+
+    let pdf = Document::load(&out).unwrap();
+    let info_id = pdf.trailer.get(b"Info").unwrap().as_reference().unwrap();
+    let info = pdf.get_dictionary(info_id).unwrap();
+    let text = |k: &[u8]| String::from_utf8_lossy(info.get(k).unwrap().as_str().unwrap()).into_owned();
+    assert_eq!(text(b"Keywords"), "invoice, 2023, receipt");
+    assert_eq!(text(b"Author"), "Ann; Bo");
+}
+
+#[test]
+fn merge_sets_or_strips_producer() {
+    let dir = tempdir().unwrap();
+    let in_dir = dir.path().join("in");
+    fs::create_dir_all(&in_dir).unwrap();
+    let a = create_pdf(&in_dir, "a.pdf", 1);
+    create_pdf(&in_dir, "b.pdf", 1);
+    set_info(&a, "Producer", "Some Other Tool");
+
+    let producer = |extra: &[&str]| {
+        let out = dir.path().join("out.pdf");
+        Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+            .args(["merge", "--force", "--metadata", "first", "-i"]).arg(&in_dir).arg("-o").arg(&out).args(extra)
+            .assert().success();
+        let pdf = Document::load(&out).unwrap();
+        let info_id = pdf.trailer.get(b"Info").ok()?.as_reference().unwrap();
+        let value = pdf.get_dictionary(info_id).unwrap().get(b"Producer").ok()?;
+        Some(String::from_utf8_lossy(value.as_str().unwrap()).into_owned())
+    };
+    assert_eq!(producer(&[]).as_deref(), Some("pdf-ops"));
+    assert_eq!(producer(&["--producer", "Acme Scan"]).as_deref(), Some("Acme Scan"));
+    assert_eq!(producer(&["--strip-producer"]), None);
+
+    // A lone input is not copied verbatim when that would keep another tool's /Producer
+    fs::remove_file(in_dir.join("b.pdf")).unwrap();
+    assert_eq!(producer(&[]).as_deref(), Some("pdf-ops"));
+    set_info(&a, "Producer", "pdf-ops");
+    let out = dir.path().join("out.pdf");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--force", "-i"]).arg(&in_dir).arg("-o").arg(&out)
+        .assert().success();
+    assert_eq!(fs::read(&out).unwrap(), fs::read(&a).unwrap());
+}
+
+#[test]
+fn split_into_three_parts() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    let input = create_pdf(root, "in.pdf", 10);
+    let out_dir = root.join("out");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "-q", "-i"]).arg(&input)
+        .args(["-d"]).arg(&out_dir)
+        .args(["--into", "3"])
+        .assert().success();
+    assert_eq!(page_count(&out_dir.join("in-1-4.pdf")), 4);
+    assert_eq!(page_count(&out_dir.join("in-5-7.pdf")), 3);
+    assert_eq!(page_count(&out_dir.join("in-8-10.pdf")), 3);
+    assert_eq!(fs::read_dir(&out_dir).unwrap().count(), 3);
+}
+
+// (title, 1-based page) of each top-level bookmark
+fn top_level_bookmarks(path: &std::path::Path) -> Vec<(String, usize)> {
+    let pdf = Document::load(path).unwrap();
+    let pages: std::collections::BTreeMap<ObjectId, usize> = pdf.get_pages().into_iter().map(|(n, id)| (id, n as usize)).collect();
+    let outlines = pdf.catalog().unwrap().get(b"Outlines").unwrap().as_reference().unwrap();
+    let mut cur = pdf.get_dictionary(outlines).unwrap().get(b"First").ok().map(|o| o.as_reference().unwrap());
+    let mut out = Vec::new();
+    while let Some(id) = cur {
+        let item = pdf.get_dictionary(id).unwrap();
+        let title = String::from_utf8_lossy(item.get(b"Title").unwrap().as_str().unwrap()).into_owned();
+        let dest = item.get(b"Dest").unwrap().as_array().unwrap()[0].as_reference().unwrap();
+        out.push((title, pages[&dest]));
+        cur = item.get(b"Next").ok().map(|o| o.as_reference().unwrap());
+    }
+    out
+}
+
+#[test]
+fn merge_bookmark_files_marks_each_input() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    let in_dir = root.join("in");
+    fs::create_dir_all(&in_dir).unwrap();
+    create_pdf(&in_dir, "a.pdf", 2);
+    create_pdf(&in_dir, "b.pdf", 3);
+    create_pdf(&in_dir, "c.pdf", 1);
+
+    let out = root.join("out.pdf");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "-i"]).arg(&in_dir)
+        .args(["-o"]).arg(&out)
+        .arg("--bookmark-files")
+        .assert().success();
+
+    assert_eq!(top_level_bookmarks(&out), vec![("a".into(), 1), ("b".into(), 3), ("c".into(), 6)]);
+}
+
+#[test]
+fn explain_spec_lists_pages_and_flags_overflow() {
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["explain-spec", "-2,5,9-12,30", "--total", "10"])
+        .assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    assert!(stdout.contains("页码 (5 页): 1, 2, 5, 9, 10"), "stdout: {}", stdout);
+    assert!(stdout.contains("超出范围: 9-12"));
+    assert!(stdout.contains("超出范围: 30"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["explain-spec", "3-1", "--total", "10"])
+        .assert().failure();
+}
+
+#[test]
+fn input_list_json_sets_order_and_per_file_pages() {
+    let dir = tempdir().unwrap();
+    let docs = dir.path().join("docs");
+    fs::create_dir_all(&docs).unwrap();
+    create_pdf_with_contents(&docs, "a.pdf", &[Some(b"(a1) Tj"), Some(b"(a2) Tj"), Some(b"(a3) Tj")]);
+    create_pdf_with_contents(&docs, "b.pdf", &[Some(b"(b1) Tj"), Some(b"(b2) Tj")]);
+    let list = dir.path().join("job.json");
+    fs::write(&list, r#"[{"path": "docs/b.pdf", "pages": "2"}, {"path": "docs/a.pdf"}]"#).unwrap();
+
+    let output = dir.path().join("out.pdf");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--pages", "1-2", "--input-list-json"]).arg(&list).arg("-o").arg(&output)
+        .assert().success();
+    // b.pdf first with its own spec; a.pdf falls back to --pages
+    assert_eq!(page_contents(&output), ["(b2) Tj", "(a1) Tj", "(a2) Tj"]);
+
+    fs::write(&list, r#"[{"path": "docs/a.pdf"}, {"path": "docs/missing.pdf"}]"#).unwrap();
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--force", "--input-list-json"]).arg(&list).arg("-o").arg(&output)
+        .assert().failure();
+    assert!(String::from_utf8_lossy(&assert.get_output().stderr).contains("missing.pdf"));
+}
+
+#[test]
+fn report_duplicates_names_repeated_output_pages() {
+    let dir = tempdir().unwrap();
+    let input_dir = dir.path().join("in");
+    fs::create_dir_all(&input_dir).unwrap();
+    create_pdf_with_contents(&input_dir, "a.pdf", &[Some(b"(cover) Tj"), Some(b"(scan 1) Tj")]);
+    create_pdf_with_contents(&input_dir, "b.pdf", &[Some(b"(scan 2) Tj"), Some(b"(scan 1) Tj"), Some(b"(cover) Tj")]);
+    let output = dir.path().join("out.pdf");
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--detect-duplicates-across-merge", "-i"]).arg(&input_dir).arg("-o").arg(&output)
+        .assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    assert!(stdout.contains("重复页: 4 (同第 2 页), 5 (同第 1 页)"), "stdout: {}", stdout);
+    // Report only: every page is still there
+    assert_eq!(page_count(&output), 5);
+}
+
+#[test]
+fn strict_spec_rejects_empty_segments() {
+    let explain = |extra: &[&str]| Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["explain-spec", "1,,3", "--total", "5"]).args(extra).assert();
+    let stdout = String::from_utf8_lossy(&explain(&[]).success().get_output().stdout).to_string();
+    assert!(stdout.contains("页码 (2 页): 1, 3"), "stdout: {}", stdout);
+    let assert = explain(&["--strict-spec"]).failure();
+    assert!(String::from_utf8_lossy(&assert.get_output().stderr).contains("empty segment"));
+}
+
+#[test]
+fn merge_annotate_source_stamps_filenames() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    let in_dir = root.join("in");
+    fs::create_dir_all(&in_dir).unwrap();
+    create_pdf_with_contents(&in_dir, "a.pdf", &[Some(b"0 0 10 10 re f"), None]);
+    create_pdf(&in_dir, "b.pdf", 1);
+
+    let out = root.join("out.pdf");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "-i"]).arg(&in_dir)
+        .args(["-o"]).arg(&out)
+        .args(["--annotate-source", "--annotate-position", "top-left"])
+        .assert().success();
+
+    let contents = page_contents(&out);
+    assert_eq!(contents.len(), 3);
+    assert!(contents[0].contains("re f") && contents[0].contains("(a.pdf) Tj"), "{}", contents[0]);
+    assert!(contents[1].contains("(a.pdf) Tj"));
+    assert!(contents[2].contains("(b.pdf) Tj"));
+}
+
+#[test]
+fn info_reports_page_dimensions() {
+    let dir = tempdir().unwrap();
+    let input = create_pdf(dir.path(), "in.pdf", 2);
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["info", "-i"]).arg(&input)
+        .assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    assert!(stdout.contains("页数: 2"), "stdout: {}", stdout);
+    assert!(stdout.contains("200 x 200 pt  (2.78 x 2.78 in)"), "stdout: {}", stdout);
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["info", "--json", "-i"]).arg(&input)
+        .assert().success();
+    let json: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+    let pages = json["pages"].as_array().unwrap();
+    assert_eq!(pages.len(), 2);
+    assert_eq!(pages[0]["width_pt"], 200.0);
+    assert_eq!(pages[0]["height_pt"], 200.0);
+    assert!(pages[0]["flags"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn merge_covers_takes_first_page_of_each() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    let in_dir = root.join("in");
+    fs::create_dir_all(&in_dir).unwrap();
+    for (name, n) in [("a.pdf", 3), ("b.pdf", 5), ("c.pdf", 2)] {
+        let contents: Vec<Vec<u8>> = (1..=n).map(|p| format!("% {} page {}\n0 0 1 1 re f", name, p).into_bytes()).collect();
+        let refs: Vec<Option<&[u8]>> = contents.iter().map(|c| Some(c.as_slice())).collect();
+        create_pdf_with_contents(&in_dir, name, &refs);
+    }
+
+    let out = root.join("out.pdf");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "-i"]).arg(&in_dir)
+        .args(["-o"]).arg(&out)
+        .arg("--covers")
+        .assert().success();
+
+    let contents = page_contents(&out);
+    assert_eq!(contents.len(), 3);
+    for (content, name) in contents.iter().zip(["a.pdf", "b.pdf", "c.pdf"]) {
+        assert!(content.contains(&format!("{} page 1", name)), "{}", content);
+    }
+    // Pruned inputs: no later page's content stream is carried along
+    let raw = Document::load(&out).unwrap();
+    assert!(raw.objects.values().filter_map(|o| o.as_stream().ok()).all(|s| {
+        let data = s.decompressed_content().unwrap_or_else(|_| s.content.clone());
+        !String::from_utf8_lossy(&data).contains("page 2")
+    }));
+}
+
+#[test]
+fn merge_summary_reports_written_size() {
+    use pdf_ops::merge::{self, MergeOptions};
+    use pdf_ops::progress::NullProgress;
+
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    let in_dir = root.join("in");
+    fs::create_dir_all(&in_dir).unwrap();
+    create_pdf(&in_dir, "a.pdf", 2);
+    create_pdf(&in_dir, "b.pdf", 3);
+
+    let out = root.join("out.pdf");
+    let summary = merge::run(&in_dir, &out, &MergeOptions::default(), &NullProgress).unwrap();
+    assert_eq!(summary.bytes, fs::metadata(&out).unwrap().len());
+    assert_eq!(summary.pages, 5);
+    assert_eq!(summary.inputs.len(), 2);
+    assert!(summary.inputs.iter().all(|i| i.objects > 0));
+}
+
+#[test]
+fn split_verify_passes_on_normal_split() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    let input = create_pdf(root, "in.pdf", 6);
+    let out_dir = root.join("out");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "-q", "--verify", "-i"]).arg(&input)
+        .args(["-d"]).arg(&out_dir)
+        .args(["--ranges", "1-2,3-6"])
+        .assert().success();
+    assert_eq!(page_count(&out_dir.join("in-1-2.pdf")), 2);
+    assert_eq!(page_count(&out_dir.join("in-3-6.pdf")), 4);
+    assert!(fs::read_dir(&out_dir).unwrap().all(|e| !e.unwrap().path().to_string_lossy().ends_with(".bad")));
+}
+
+#[test]
+fn merge_group_by_subdir_writes_one_file_per_subdir() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    let in_dir = root.join("in");
+    for sub in ["alpha", "beta", "empty"] {
+        fs::create_dir_all(in_dir.join(sub)).unwrap();
+    }
+    create_pdf(&in_dir.join("alpha"), "1.pdf", 2);
+    create_pdf(&in_dir.join("alpha"), "2.pdf", 1);
+    create_pdf(&in_dir.join("beta"), "x.pdf", 4);
+    create_pdf(&in_dir, "loose.pdf", 5);
+
+    let out_dir = root.join("out");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--group-by-subdir", "-i"]).arg(&in_dir)
+        .args(["-o"]).arg(&out_dir)
+        .assert().success();
+
+    assert_eq!(page_count(&out_dir.join("alpha.pdf")), 3);
+    assert_eq!(page_count(&out_dir.join("beta.pdf")), 4);
+    assert_eq!(fs::read_dir(&out_dir).unwrap().count(), 2);
+}
+
+#[test]
+fn merge_shuffle_with_seed_is_reproducible() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    let in_dir = root.join("in");
+    fs::create_dir_all(&in_dir).unwrap();
+    for i in 1..=8 {
+        let content = format!("% file {}\n0 0 1 1 re f", i);
+        create_pdf_with_contents(&in_dir, &format!("{}.pdf", i), &[Some(content.as_bytes())]);
+    }
+
+    let run = |extra: &[&str], name: &str| -> Vec<String> {
+        let out = root.join(name);
+        Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+            .args(["merge", "-i"]).arg(&in_dir)
+            .args(["-o"]).arg(&out)
+            .args(extra)
+            .assert().success();
+        page_contents(&out).iter().map(|c| c.lines().next().unwrap().to_string()).collect()
+    };
+    let natural = run(&["--page-order", "natural"], "natural.pdf");
+    assert_eq!(natural, (1..=8).map(|i| format!("% file {}", i)).collect::<Vec<_>>());
+    let first = run(&["--page-order", "shuffle", "--seed", "1"], "s1.pdf");
+    let second = run(&["--page-order", "shuffle", "--seed", "1"], "s2.pdf");
+    assert_eq!(first, second);
+    assert_ne!(first, natural);
+}
+
+#[test]
+fn merge_and_split_object_stream_inputs() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    let in_dir = root.join("in");
+    fs::create_dir_all(&in_dir).unwrap();
+    let a = create_objstm_pdf(&in_dir, "a.pdf", 3);
+    create_objstm_pdf(&in_dir, "b.pdf", 2);
+    assert_eq!(page_count(&a), 3);
+
+    let out = root.join("out.pdf");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "-i"]).arg(&in_dir)
+        .args(["-o"]).arg(&out)
+        .assert().success();
+    assert_eq!(page_count(&out), 5);
+    let merged = Document::load(&out).unwrap();
+    // Input containers are not carried over (the output may still use its own xref stream)
+    assert!(merged.objects.values().all(|o| o.type_name().ok() != Some("ObjStm")));
+    let summary = pdf_ops::merge::run(&in_dir, &root.join("lib.pdf"), &Default::default(), &pdf_ops::progress::NullProgress).unwrap();
+    assert_eq!(summary.inputs.iter().map(|i| i.objects).collect::<Vec<_>>(), vec![5, 4]);
+
+    let out_dir = root.join("parts");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "-q", "-i"]).arg(&a)
+        .args(["-d"]).arg(&out_dir)
+        .args(["--ranges", "1-2,3"])
+        .assert().success();
+    assert_eq!(page_count(&out_dir.join("a-1-2.pdf")), 2);
+    assert_eq!(page_count(&out_dir.join("a-3-3.pdf")), 1);
+}
+
+// Add a JavaScript /OpenAction to the catalog and an /AA to every page
+fn add_javascript(path: &std::path::Path) {
+    let mut doc = Document::load(path).unwrap();
+    let mut action = Dictionary::new();
+    action.set("S", "JavaScript");
+    action.set("JS", Object::string_literal("app.alert('hi')"));
+    let action_id = doc.add_object(action);
+    let root = doc.trailer.get(b"Root").unwrap().as_reference().unwrap();
+    doc.get_dictionary_mut(root).unwrap().set("OpenAction", Object::Reference(action_id));
+    for (_, pid) in doc.get_pages() {
+        let mut aa = Dictionary::new();
+        aa.set("O", Object::Reference(action_id));
+        doc.get_dictionary_mut(pid).unwrap().set("AA", aa);
+    }
+    doc.save(path).unwrap();
+}
+
+fn has_script(path: &std::path::Path) -> bool {
+    let raw = fs::read(path).unwrap();
+    let pdf = Document::load(path).unwrap();
+    let catalog = pdf.catalog().unwrap();
+    catalog.has(b"OpenAction") || String::from_utf8_lossy(&raw).contains("/JS") || pdf.get_pages().values().any(|&p| pdf.get_dictionary(p).unwrap().has(b"AA"))
+}
+
+#[test]
+fn strip_javascript_on_merge_and_split() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    let in_dir = root.join("in");
+    fs::create_dir_all(&in_dir).unwrap();
+    let a = create_pdf(&in_dir, "a.pdf", 2);
+    add_javascript(&a);
+
+    let out = root.join("out.pdf");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--strip-javascript", "-i"]).arg(&in_dir)
+        .args(["-o"]).arg(&out)
+        .assert().success();
+    assert!(!Document::load(&out).unwrap().catalog().unwrap().has(b"OpenAction"));
+    assert!(!has_script(&out));
+
+    let out_dir = root.join("parts");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "-q", "--strip-javascript", "-i"]).arg(&a)
+        .args(["-d"]).arg(&out_dir)
+        .assert().success();
+    assert!(!has_script(&out_dir.join("a-1-1.pdf")));
+
+    // Without the flag page actions survive a split
+    let kept = root.join("kept");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "-q", "-i"]).arg(&a)
+        .args(["-d"]).arg(&kept)
+        .assert().success();
+    assert!(has_script(&kept.join("a-1-1.pdf")));
+}
+
+// Give page 1 a non-embedded standard Helvetica as /F1
+fn add_helvetica(path: &std::path::Path) {
+    let mut doc = Document::load(path).unwrap();
+    let mut font = Dictionary::new();
+    font.set("Type", "Font");
+    font.set("Subtype", "Type1");
+    font.set("BaseFont", "Helvetica");
+    let font_id = doc.add_object(font);
+    let page = doc.get_pages()[&1];
+    let mut fonts = Dictionary::new();
+    fonts.set("F1", Object::Reference(font_id));
+    let mut res = Dictionary::new();
+    res.set("Font", fonts);
+    doc.get_dictionary_mut(page).unwrap().set("Resources", res);
+    doc.save(path).unwrap();
+}
+
+#[test]
+fn info_fonts_flags_non_embedded_helvetica() {
+    let dir = tempdir().unwrap();
+    let input = create_pdf_with_contents(dir.path(), "in.pdf", &[Some(b"BT /F1 12 Tf (hi) Tj ET")]);
+    add_helvetica(&input);
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["info", "--fonts", "-i"]).arg(&input)
+        .assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    assert!(stdout.contains("Helvetica (Type1)  ⚠️ 未嵌入  页: 1"), "stdout: {}", stdout);
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["info", "--fonts", "--json", "-i"]).arg(&input)
+        .assert().success();
+    let json: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+    assert_eq!(json["fonts"][0]["name"], "Helvetica");
+    assert_eq!(json["fonts"][0]["embedded"], false);
+}
+
+#[cfg(feature = "grayscale")]
+#[test]
+fn grayscale_turns_rgb_fill_grey() {
+    let dir = tempdir().unwrap();
+    let input_dir = dir.path().join("in");
+    fs::create_dir_all(&input_dir).unwrap();
+    create_pdf_with_contents(&input_dir, "a.pdf", &[Some(b"1 0 0 rg 0 0 50 50 re f")]);
+    create_pdf_with_contents(&input_dir, "b.pdf", &[Some(b"0 0 0 1 k 0 0 50 50 re f")]);
+    let output = dir.path().join("out.pdf");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--grayscale", "-i"]).arg(&input_dir).arg("-o").arg(&output)
+        .assert().success();
+    let contents = page_contents(&output);
+    assert!(contents[0].starts_with("0.299 g\n"), "{:?}", contents);
+    assert!(contents[1].starts_with("0 g\n"), "{:?}", contents);
+}
+
+#[cfg(feature = "pdfa")]
+#[test]
+fn pdfa_strips_javascript_and_rejects_unembedded_fonts() {
+    let dir = tempdir().unwrap();
+    let input_dir = dir.path().join("in");
+    fs::create_dir_all(&input_dir).unwrap();
+    let a = create_pdf(&input_dir, "a.pdf", 2);
+    add_javascript(&a);
+    let output = dir.path().join("archive.pdf");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--pdfa", "-i"]).arg(&input_dir).arg("-o").arg(&output)
+        .assert().success();
+    assert!(!has_script(&output));
+    let pdf = Document::load(&output).unwrap();
+    assert!(pdf.catalog().unwrap().has(b"Metadata"));
+    let info_id = pdf.trailer.get(b"Info").unwrap().as_reference().unwrap();
+    assert_eq!(pdf.get_dictionary(info_id).unwrap().get(b"Title").unwrap().as_str().unwrap(), b"archive");
+
+    // --producer reaches Info and XMP alike; --strip-producer leaves it out of both
+    let xmp_of = |pdf: &Document| {
+        let id = pdf.catalog().unwrap().get(b"Metadata").unwrap().as_reference().unwrap();
+        String::from_utf8_lossy(&pdf.get_object(id).unwrap().as_stream().unwrap().content).into_owned()
+    };
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--pdfa", "--force", "--producer", "Acme", "-i"]).arg(&input_dir).arg("-o").arg(&output)
+        .assert().success();
+    let pdf = Document::load(&output).unwrap();
+    let info_id = pdf.trailer.get(b"Info").unwrap().as_reference().unwrap();
+    assert_eq!(pdf.get_dictionary(info_id).unwrap().get(b"Producer").unwrap().as_str().unwrap(), b"Acme");
+    assert!(xmp_of(&pdf).contains("<pdf:Producer>Acme</pdf:Producer>"));
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--pdfa", "--force", "--strip-producer", "-i"]).arg(&input_dir).arg("-o").arg(&output)
+        .assert().success();
+    let pdf = Document::load(&output).unwrap();
+    let info_id = pdf.trailer.get(b"Info").unwrap().as_reference().unwrap();
+    assert!(!pdf.get_dictionary(info_id).unwrap().has(b"Producer"));
+    assert!(!xmp_of(&pdf).contains("Producer"));
+
+    let b = create_pdf_with_contents(&input_dir, "b.pdf", &[Some(b"BT /F1 12 Tf (hi) Tj ET")]);
+    add_helvetica(&b);
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--pdfa", "--force", "-i"]).arg(&input_dir).arg("-o").arg(&output)
+        .assert().failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).to_string();
+    assert!(stderr.contains("未嵌入: Helvetica"), "stderr: {}", stderr);
+}
+
+#[test]
+fn checksum_manifest_lists_inputs_and_output() {
+    use sha2::{Digest, Sha256};
+    let hex = |p: &std::path::Path| -> String { Sha256::digest(fs::read(p).unwrap()).iter().map(|b| format!("{:02x}", b)).collect() };
+    let dir = tempdir().unwrap();
+    let input_dir = dir.path().join("in");
+    fs::create_dir_all(&input_dir).unwrap();
+    let a = create_pdf(&input_dir, "a.pdf", 1);
+    let b = create_pdf(&input_dir, "b.pdf", 2);
+    let output = dir.path().join("merged.pdf");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--checksum-manifest", "-i"]).arg(&input_dir).arg("-o").arg(&output)
+        .assert().success();
+
+    let manifest = fs::read_to_string(dir.path().join("merged.pdf.sha256")).unwrap();
+    let lines: Vec<&str> = manifest.lines().collect();
+    assert_eq!(lines.len(), 3);
+    for (line, path) in lines.iter().zip([&a, &b, &output]) {
+        assert_eq!(*line, format!("{}  {}", hex(path), path.display()));
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn chmod_sets_output_mode() {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = |p: &std::path::Path| fs::metadata(p).unwrap().permissions().mode() & 0o7777;
+    let dir = tempdir().unwrap();
+    let input_dir = dir.path().join("in");
+    fs::create_dir_all(&input_dir).unwrap();
+    let input = create_pdf(&input_dir, "a.pdf", 2);
+    let output = dir.path().join("merged.pdf");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--chmod", "600", "-i"]).arg(&input_dir).arg("-o").arg(&output)
+        .assert().success();
+    assert_eq!(mode(&output), 0o600);
+
+    let out_dir = dir.path().join("parts");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "--chmod", "0640", "-i"]).arg(&input).arg("-d").arg(&out_dir)
+        .assert().success();
+    for entry in fs::read_dir(&out_dir).unwrap() {
+        assert_eq!(mode(&entry.unwrap().path()), 0o640);
+    }
+
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "--chmod", "999", "-i"]).arg(&input).arg("-d").arg(&out_dir)
+        .assert().failure();
+}
+
+#[test]
+fn merge_emits_structured_completion_event() {
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+
+    // Records (event name, [(field, value)]) for every event
+    type Events = Arc<Mutex<Vec<(String, Vec<(String, String)>)>>>;
+    struct Capture(Events);
+    struct Fields(Vec<(String, String)>);
+    impl Visit for Fields {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.0.push((field.name().to_string(), format!("{:?}", value)));
+        }
+    }
+    impl<S: tracing::Subscriber> Layer<S> for Capture {
+        fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+            let mut fields = Fields(Vec::new());
+            event.record(&mut fields);
+            self.0.lock().unwrap().push((event.metadata().name().to_string(), fields.0));
+        }
+    }
+
+    let dir = tempdir().unwrap();
+    let a = create_pdf(dir.path(), "a.pdf", 2);
+    let b = create_pdf(dir.path(), "b.pdf", 3);
+    let output = dir.path().join("merged.pdf");
+    let events: Events = Default::default();
+    let subscriber = tracing_subscriber::registry().with(Capture(events.clone()));
+    tracing::subscriber::with_default(subscriber, || {
+        pdf_ops::merge::run_with_files(&[a, b], &output, &Default::default(), &pdf_ops::progress::NullProgress).unwrap();
+    });
+
+    let events = events.lock().unwrap();
+    assert_eq!(events.iter().filter(|(name, _)| name == "file_loaded").count(), 2);
+    let (_, fields) = events.iter().find(|(name, _)| name == "merge_complete").expect("merge_complete event");
+    assert!(fields.contains(&("pages".to_string(), "5".to_string())), "{:?}", fields);
+}
+
+#[test]
+fn split_named_outputs_with_own_specs() {
+    let dir = tempdir().unwrap();
+    let bodies: Vec<String> = (1..=22).map(|i| format!("% page {}", i)).collect();
+    let contents: Vec<Option<&[u8]>> = bodies.iter().map(|b| Some(b.as_bytes())).collect();
+    let input = create_pdf_with_contents(dir.path(), "book.pdf", &contents);
+    let out_dir = dir.path().join("out");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "--out", "chapter1=1-5", "--out", "appendix=20-", "-i"]).arg(&input).arg("-d").arg(&out_dir)
+        .assert().success();
+
+    let mut names: Vec<String> = fs::read_dir(&out_dir).unwrap().map(|e| e.unwrap().file_name().to_string_lossy().into_owned()).collect();
+    names.sort();
+    assert_eq!(names, vec!["appendix.pdf", "chapter1.pdf"]);
+    assert_eq!(page_contents(&out_dir.join("chapter1.pdf")), bodies[..5].to_vec());
+    assert_eq!(page_contents(&out_dir.join("appendix.pdf")), bodies[19..].to_vec());
+
+    // A spec matching no page fails before anything is written
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "--out", "x=30-", "-i"]).arg(&input).arg("-d").arg(dir.path().join("none"))
+        .assert().failure();
+    assert!(!dir.path().join("none").join("x.pdf").exists());
+}
+
+#[test]
+fn batch_runs_merge_and_split_rows() {
+    let dir = tempdir().unwrap();
+    let in_dir = dir.path().join("in");
+    fs::create_dir_all(&in_dir).unwrap();
+    create_pdf(&in_dir, "a.pdf", 2);
+    create_pdf(&in_dir, "b.pdf", 3);
+    create_pdf(dir.path(), "book.pdf", 4);
+    let jobs = dir.path().join("jobs.csv");
+    fs::write(&jobs, "op,input,output,pages\nmerge,in,merged.pdf,1\nsplit,book.pdf,parts,\"1-2,3-\"\n").unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .arg("batch").arg(&jobs)
+        .assert().success();
+    assert_eq!(page_count(&dir.path().join("merged.pdf")), 2);
+    assert_eq!(page_count(&dir.path().join("parts").join("book-1-2.pdf")), 2);
+    assert_eq!(page_count(&dir.path().join("parts").join("book-3-4.pdf")), 2);
+
+    // A bad row is reported and fails the run; with --fail-fast later rows are skipped
+    fs::write(&jobs, "op,input,output,pages\nsplit,missing.pdf,x,\nmerge,in,again.pdf,\n").unwrap();
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["batch", "--fail-fast"]).arg(&jobs)
+        .assert().failure();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    assert!(stdout.contains("成功 0，失败 1，跳过 1"), "stdout: {}", stdout);
+    assert!(!dir.path().join("again.pdf").exists());
+}
+
+#[test]
+fn append_adds_pages_and_keeps_target_metadata() {
+    let dir = tempdir().unwrap();
+    let in_dir = dir.path().join("in");
+    fs::create_dir_all(&in_dir).unwrap();
+    create_pdf_with_contents(&in_dir, "log.pdf", &[Some(b"% t1"), Some(b"% t2"), Some(b"% t3")]);
+    set_info(&in_dir.join("log.pdf"), "Title", "Journal");
+    // A target with bookmarks and Info, as merge writes them
+    let target = dir.path().join("journal.pdf");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--bookmark-files", "--metadata", "first", "-i"]).arg(&in_dir).arg("-o").arg(&target)
+        .assert().success();
+    let add = create_pdf_with_contents(dir.path(), "new.pdf", &[Some(b"% n1"), Some(b"% n2")]);
+
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["append", "--to"]).arg(&target).arg("--add").arg(&add)
+        .assert().success();
+
+    assert_eq!(page_contents(&target), vec!["% t1", "% t2", "% t3", "% n1", "% n2"]);
+    let pdf = Document::load(&target).unwrap();
+    let info_id = pdf.trailer.get(b"Info").unwrap().as_reference().unwrap();
+    assert_eq!(pdf.get_dictionary(info_id).unwrap().get(b"Title").unwrap().as_str().unwrap(), b"Journal");
+    assert_eq!(top_level_bookmarks(&target), vec![("log".to_string(), 1)]);
+
+    // --output leaves the target alone
+    let copy = dir.path().join("copy.pdf");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["append", "--to"]).arg(&target).arg("--add").arg(&add).arg("--add").arg(&add).arg("-o").arg(&copy)
+        .assert().success();
+    assert_eq!(page_count(&copy), 9);
+    assert_eq!(page_count(&target), 5);
+}
+
+#[test]
+fn split_scale_halves_mediabox() {
+    let dir = tempdir().unwrap();
+    let input = create_pdf(dir.path(), "in.pdf", 3);
+    let out_dir = dir.path().join("out");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "--scale", "0.5", "--ranges", "2-2", "-i"]).arg(&input).arg("-d").arg(&out_dir)
+        .assert().success();
+    let pdf = Document::load(out_dir.join("in-2-2.pdf")).unwrap();
+    let page = pdf.get_dictionary(pdf.get_pages()[&1]).unwrap();
+    let mb: Vec<f32> = page.get(b"MediaBox").unwrap().as_array().unwrap().iter().map(|o| o.as_float().unwrap()).collect();
+    assert_eq!(mb, vec![0.0, 0.0, 100.0, 100.0]);
+
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "--scale", "0", "-i"]).arg(&input).arg("-d").arg(&out_dir)
+        .assert().failure();
+}
+
+#[test]
+fn outputs_are_written_through_part_files() {
+    let dir = tempdir().unwrap();
+    let input = create_pdf(dir.path(), "in.pdf", 3);
+    let out_dir = dir.path().join("parts");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "-i"]).arg(&input).arg("-d").arg(&out_dir)
+        .assert().success();
+    let names: Vec<String> = fs::read_dir(&out_dir).unwrap().map(|e| e.unwrap().file_name().to_string_lossy().into_owned()).collect();
+    assert_eq!(names.len(), 3);
+    assert!(names.iter().all(|n| n.ends_with(".pdf")), "leftover temp files: {:?}", names);
+
+    // What an interrupt finds mid-write: nothing registered once every save has completed
+    assert_eq!(pdf_ops::interrupt::remove_partial_outputs(), 0);
+}
+
+#[test]
+fn merge_bake_rotation_uprights_rotated_pages() {
+    let dir = tempdir().unwrap();
+    let input_dir = dir.path().join("in");
+    fs::create_dir_all(&input_dir).unwrap();
+    // a.pdf: portrait 200x300 shown as landscape via /Rotate 90; b.pdf: plain portrait
+    for (name, rotate) in [("a.pdf", Some(90)), ("b.pdf", None)] {
+        let path = create_pdf(&input_dir, name, 1);
+        let mut doc = Document::load(&path).unwrap();
+        let pid = doc.get_pages()[&1];
+        let page = doc.get_dictionary_mut(pid).unwrap();
+        page.set("MediaBox", vec![0.into(), 0.into(), 200.into(), 300.into()]);
+        if let Some(r) = rotate { page.set("Rotate", r); }
+        doc.save(&path).unwrap();
+    }
+    let output = dir.path().join("merged.pdf");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--bake-rotation", "-i"]).arg(&input_dir).arg("-o").arg(&output)
+        .assert().success();
+
+    let pdf = Document::load(&output).unwrap();
+    let pages = pdf.get_pages();
+    let mut boxes = Vec::new();
+    for n in [1, 2] {
+        let page = pdf.get_dictionary(pages[&n]).unwrap();
+        assert_eq!(page.get(b"Rotate").and_then(Object::as_i64).unwrap_or(0), 0);
+        let mb: Vec<f32> = page.get(b"MediaBox").unwrap().as_array().unwrap().iter().map(|o| o.as_float().unwrap()).collect();
+        boxes.push(mb);
+    }
+    assert_eq!(boxes[0], vec![0.0, 0.0, 300.0, 200.0]);
+    assert_eq!(boxes[1], vec![0.0, 0.0, 200.0, 300.0]);
+}
+
+#[test]
+fn split_even_at_bookmarks_cuts_long_chapters() {
+    let dir = tempdir().unwrap();
+    let input_dir = dir.path().join("in");
+    fs::create_dir_all(&input_dir).unwrap();
+    create_pdf(&input_dir, "ch1.pdf", 3);
+    create_pdf(&input_dir, "ch2.pdf", 10);
+    create_pdf(&input_dir, "ch3.pdf", 2);
+    // --bookmark-files gives the manual one top-level bookmark per chapter
+    let manual = dir.path().join("manual.pdf");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--bookmark-files", "-i"]).arg(&input_dir).arg("-o").arg(&manual)
+        .assert().success();
+
+    let out_dir = dir.path().join("out");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "--even-split-at-bookmarks", "--max", "4", "-i"]).arg(&manual).arg("-d").arg(&out_dir)
+        .assert().success();
+    let mut names: Vec<String> = fs::read_dir(&out_dir).unwrap().map(|e| e.unwrap().file_name().to_string_lossy().into_owned()).collect();
+    names.sort();
+    assert_eq!(names, ["ch1.pdf", "ch2-1.pdf", "ch2-2.pdf", "ch2-3.pdf", "ch3.pdf"]);
+    let counts: Vec<usize> = names.iter().map(|n| page_count(&out_dir.join(n))).collect();
+    assert_eq!(counts, [3, 4, 4, 2, 2]);
+
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "--max", "4", "-i"]).arg(&manual).arg("-d").arg(&out_dir)
+        .assert().failure();
+}
+
+#[test]
+fn split_toc_level_two_cuts_at_sections() {
+    let dir = tempdir().unwrap();
+    let book = create_pdf(dir.path(), "book.pdf", 6);
+    let mut doc = Document::load(&book).unwrap();
+    let pages = doc.get_pages();
+    let ch1 = doc.add_bookmark(lopdf::Bookmark::new("Ch1".into(), [0.0; 3], 0, pages[&1]), None);
+    doc.add_bookmark(lopdf::Bookmark::new("S1a".into(), [0.0; 3], 0, pages[&1]), Some(ch1));
+    doc.add_bookmark(lopdf::Bookmark::new("S1b".into(), [0.0; 3], 0, pages[&2]), Some(ch1));
+    let ch2 = doc.add_bookmark(lopdf::Bookmark::new("Ch2".into(), [0.0; 3], 0, pages[&4]), None);
+    doc.add_bookmark(lopdf::Bookmark::new("S2a".into(), [0.0; 3], 0, pages[&5]), Some(ch2));
+    let outline_id = doc.build_outline().unwrap();
+    doc.catalog_mut().unwrap().set("Outlines", outline_id);
+    doc.save(&book).unwrap();
+
+    let split = |level: &str| {
+        let out_dir = dir.path().join(format!("level{level}"));
+        Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+            .args(["split", "--quiet", "--even-split-at-bookmarks", "--toc-level", level, "-i"]).arg(&book).arg("-d").arg(&out_dir)
+            .assert().success();
+        let mut names: Vec<String> = fs::read_dir(&out_dir).unwrap().map(|e| e.unwrap().file_name().to_string_lossy().into_owned()).collect();
+        names.sort();
+        let counts: Vec<usize> = names.iter().map(|n| page_count(&out_dir.join(n))).collect();
+        (names, counts)
+    };
+    assert_eq!(split("1"), (vec!["Ch1.pdf".to_string(), "Ch2.pdf".into()], vec![3, 3]));
+    // Ch1 and S1a share page 1, so the chapter title names that part; Ch2's page 4 precedes its section
+    assert_eq!(split("2"), (vec!["Ch1.pdf".to_string(), "Ch2.pdf".into(), "S1b.pdf".into(), "S2a.pdf".into()], vec![1, 1, 2, 2]));
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "--even-split-at-bookmarks", "--toc-level", "3", "-i"]).arg(&book).arg("-d").arg(dir.path().join("level3"))
+        .assert().failure();
+    assert!(String::from_utf8_lossy(&assert.get_output().stderr).contains("--toc-level"));
+}
+
+#[test]
+fn split_duplicate_bookmark_titles_get_distinct_names() {
+    let dir = tempdir().unwrap();
+    let book = create_pdf(dir.path(), "book.pdf", 4);
+    let mut doc = Document::load(&book).unwrap();
+    let pages = doc.get_pages();
+    doc.add_bookmark(lopdf::Bookmark::new("Introduction".into(), [0.0; 3], 0, pages[&1]), None);
+    doc.add_bookmark(lopdf::Bookmark::new("Introduction".into(), [0.0; 3], 0, pages[&3]), None);
+    let outline_id = doc.build_outline().unwrap();
+    doc.catalog_mut().unwrap().set("Outlines", outline_id);
+    doc.save(&book).unwrap();
+
+    let out_dir = dir.path().join("out");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "--quiet", "--even-split-at-bookmarks", "-i"]).arg(&book).arg("-d").arg(&out_dir)
+        .assert().success();
+    assert_eq!(page_count(&out_dir.join("Introduction.pdf")), 2);
+    assert_eq!(page_count(&out_dir.join("Introduction-2.pdf")), 2);
+}
+
+#[test]
+fn split_watch_splits_new_files_once() {
+    let dir = tempdir().unwrap();
+    let inbox = dir.path().join("inbox");
+    let out_dir = dir.path().join("out");
+    fs::create_dir_all(&inbox).unwrap();
+    create_pdf(&inbox, "old.pdf", 2);
+    let wait_for = |path: &std::path::Path| {
+        let started = std::time::Instant::now();
+        while !path.exists() {
+            assert!(started.elapsed() < std::time::Duration::from_secs(20), "timed out waiting for {}", path.display());
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+    };
+    let watch = || Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "--quiet", "--watch", "--watch-interval", "50", "-i"]).arg(&inbox).arg("-d").arg(&out_dir)
+        .stdout(std::process::Stdio::null())
+        .spawn().unwrap();
+
+    let mut child = watch();
+    wait_for(&out_dir.join("old-2-2.pdf"));
+    child.kill().unwrap();
+    child.wait().unwrap();
+    for entry in fs::read_dir(&out_dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_some_and(|e| e == "pdf") { fs::remove_file(path).unwrap(); }
+    }
+
+    // A restarted watch remembers old.pdf, and picks up a file dropped in while it runs
+    let mut child = watch();
+    create_pdf(&inbox, "new.pdf", 1);
+    wait_for(&out_dir.join("new-1-1.pdf"));
+    std::thread::sleep(std::time::Duration::from_millis(300));
+    child.kill().unwrap();
+    child.wait().unwrap();
+    assert!(!out_dir.join("old-1-1.pdf").exists());
+}
+
+#[test]
+fn page_boxes_survive_split_and_merge() {
+    let dir = tempdir().unwrap();
+    let input_dir = dir.path().join("in");
+    fs::create_dir_all(&input_dir).unwrap();
+    // TrimBox held by the /Pages node, so it only survives if copied onto each page
+    let input = create_pdf(&input_dir, "print.pdf", 2);
+    let mut doc = Document::load(&input).unwrap();
+    let pages_id = doc.catalog().unwrap().get(b"Pages").unwrap().as_reference().unwrap();
+    doc.get_dictionary_mut(pages_id).unwrap().set("TrimBox", vec![10.into(), 10.into(), 190.into(), 190.into()]);
+    doc.save(&input).unwrap();
+    let trim_box = |path: &std::path::Path| -> Option<Vec<f32>> {
+        let pdf = Document::load(path).unwrap();
+        let page = pdf.get_dictionary(pdf.get_pages()[&1]).unwrap();
+        page.get(b"TrimBox").ok().map(|b| b.as_array().unwrap().iter().map(|o| o.as_float().unwrap()).collect())
+    };
+
+    let out_dir = dir.path().join("parts");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "-i"]).arg(&input).arg("-d").arg(&out_dir)
+        .assert().success();
+    assert_eq!(trim_box(&out_dir.join("print-1-1.pdf")), Some(vec![10.0, 10.0, 190.0, 190.0]));
+
+    let merged = dir.path().join("merged.pdf");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--no-passthrough", "-i"]).arg(&input_dir).arg("-o").arg(&merged)
+        .assert().success();
+    assert_eq!(trim_box(&merged), Some(vec![10.0, 10.0, 190.0, 190.0]));
+
+    let bare = dir.path().join("bare");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "--preserve-page-boxes", "false", "-i"]).arg(&input).arg("-d").arg(&bare)
+        .assert().success();
+    assert_eq!(trim_box(&bare.join("print-1-1.pdf")), None);
+}
+
+#[test]
+fn merge_single_match_is_copied_verbatim() {
+    let dir = tempdir().unwrap();
+    let input = create_pdf(dir.path(), "only.pdf", 3);
+    // Already what a rebuild would write as /Producer, so nothing stands in the way of a copy
+    set_info(&input, "Producer", "pdf-ops");
+    create_pdf(dir.path(), "other.pdf", 1);
+    let original = fs::read(&input).unwrap();
+
+    let copied = dir.path().join("out").join("copied.pdf");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--include", "only*.pdf", "-i"]).arg(dir.path()).arg("-o").arg(&copied)
+        .assert().success();
+    assert_eq!(fs::read(&copied).unwrap(), original);
+
+    // Opting out, or asking for a page selection, rebuilds the file
+    for (name, extra) in [("rebuilt.pdf", "--no-passthrough"), ("selected.pdf", "--pages=1-3")] {
+        let out = dir.path().join("out").join(name);
+        Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+            .args(["merge", extra, "--include", "only*.pdf", "-i"]).arg(dir.path()).arg("-o").arg(&out)
+            .assert().success();
+        assert_ne!(fs::read(&out).unwrap(), original, "{name}");
+        assert_eq!(page_count(&out), 3);
+    }
+}
+
+#[test]
+fn merge_with_no_pages_left_fails_with_reason() {
+    let dir = tempdir().unwrap();
+    let input_dir = dir.path().join("in");
+    fs::create_dir_all(&input_dir).unwrap();
+    create_pdf(&input_dir, "a.pdf", 2);
+    create_pdf(&input_dir, "b.pdf", 3);
+    let output = dir.path().join("merged.pdf");
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--pages", "5-9", "-i"]).arg(&input_dir).arg("-o").arg(&output)
+        .assert().failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("未从任何输入中选中页面") && stderr.contains("共 5 页"), "{stderr}");
+    assert!(!output.exists());
+
+    // Inputs without a single page are reported as such
+    let empty_dir = dir.path().join("empty");
+    fs::create_dir_all(&empty_dir).unwrap();
+    create_pdf(&empty_dir, "x.pdf", 0);
+    create_pdf(&empty_dir, "y.pdf", 0);
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "-i"]).arg(&empty_dir).arg("-o").arg(&output)
+        .assert().failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("输入文件中没有任何页面"), "{stderr}");
+    assert!(!output.exists());
+}
+
+#[test]
+fn output_metadata_file_has_title_and_bookmarks() {
+    let dir = tempdir().unwrap();
+    let input_dir = dir.path().join("in");
+    fs::create_dir_all(&input_dir).unwrap();
+    let first = create_pdf(&input_dir, "a.pdf", 2);
+    create_pdf(&input_dir, "b.pdf", 1);
+    set_info(&first, "Title", "Annual Report");
+    let merged = dir.path().join("merged.pdf");
+    let sidecar = dir.path().join("merged.json");
+
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--metadata", "first", "--bookmark-files", "-i"]).arg(&input_dir).arg("-o").arg(&merged)
+        .arg("--output-metadata-file").arg(&sidecar)
+        .assert().success();
+    let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&sidecar).unwrap()).unwrap();
+    assert_eq!(json["title"], "Annual Report");
+    assert_eq!(json["page_count"], 3);
+    let bookmarks: Vec<(&str, u64)> = json["bookmarks"].as_array().unwrap().iter()
+        .map(|b| (b["title"].as_str().unwrap(), b["page"].as_u64().unwrap()))
+        .collect();
+    assert_eq!(bookmarks, [("a", 1), ("b", 3)]);
+
+    // info writes the same document description for an existing file
+    let from_info = dir.path().join("info.json");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["info", "-i"]).arg(&merged).arg("--output-metadata-file").arg(&from_info)
+        .assert().success();
+    let info: serde_json::Value = serde_json::from_str(&fs::read_to_string(&from_info).unwrap()).unwrap();
+    assert_eq!((&info["title"], &info["bookmarks"]), (&json["title"], &json["bookmarks"]));
+}
+
+#[test]
+fn info_transparency_reports_extgstate_alpha() {
+    let dir = tempdir().unwrap();
+    let input = create_pdf(dir.path(), "in.pdf", 2);
+    let mut doc = Document::load(&input).unwrap();
+    let page2 = doc.get_pages()[&2];
+    let mut gs = Dictionary::new();
+    gs.set("Type", "ExtGState");
+    gs.set("ca", 0.4);
+    let gs_id = doc.add_object(gs);
+    let mut states = Dictionary::new();
+    states.set("GS1", Object::Reference(gs_id));
+    let mut res = Dictionary::new();
+    res.set("ExtGState", states);
+    doc.get_dictionary_mut(page2).unwrap().set("Resources", res);
+    doc.save(&input).unwrap();
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["info", "--transparency", "-i"]).arg(&input)
+        .assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    assert!(stdout.contains("透明度 (1 页"), "stdout: {}", stdout);
+    assert!(stdout.contains("     2  ⚠️ 不透明度 < 1"), "stdout: {}", stdout);
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["info", "--transparency", "--json", "-i"]).arg(&input)
+        .assert().success();
+    let json: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+    assert_eq!(json["transparency"], serde_json::json!([{ "page": 2, "kinds": ["alpha"] }]));
+}
+
+#[test]
+fn merge_pages_by_label() {
+    let dir = tempdir().unwrap();
+    let input_dir = dir.path().join("in");
+    fs::create_dir_all(&input_dir).unwrap();
+    let contents: Vec<Vec<u8>> = (1..=6).map(|n| format!("(p{}) Tj", n).into_bytes()).collect();
+    let contents: Vec<Option<&[u8]>> = contents.iter().map(|c| Some(c.as_slice())).collect();
+    let input = create_pdf_with_contents(&input_dir, "book.pdf", &contents);
+    // Front matter i-iv, then 1, 2
+    let mut doc = Document::load(&input).unwrap();
+    let mut roman = Dictionary::new();
+    roman.set("S", "r");
+    let mut decimal = Dictionary::new();
+    decimal.set("S", "D");
+    let mut labels = Dictionary::new();
+    labels.set("Nums", vec![0.into(), roman.into(), 4.into(), decimal.into()]);
+    let catalog = doc.trailer.get(b"Root").unwrap().as_reference().unwrap();
+    doc.get_dictionary_mut(catalog).unwrap().set("PageLabels", labels);
+    doc.save(&input).unwrap();
+
+    let output = dir.path().join("merged.pdf");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--pages", "ii-iii,6", "-i"]).arg(&input_dir).arg("-o").arg(&output)
+        .assert().success();
+    assert_eq!(page_contents(&output), ["(p2) Tj", "(p3) Tj", "(p6) Tj"]);
+
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--force", "--pages", "xii", "-i"]).arg(&input_dir).arg("-o").arg(&output)
+        .assert().failure();
+}
+
+#[test]
+fn merge_dedupe_pages_drops_repeated_page() {
+    let dir = tempdir().unwrap();
+    let input_dir = dir.path().join("in");
+    fs::create_dir_all(&input_dir).unwrap();
+    // Page 2 was scanned twice
+    create_pdf_with_contents(&input_dir, "scan.pdf", &[Some(b"(p1) Tj"), Some(b"(p2) Tj"), Some(b"(p2) Tj"), Some(b"(p3) Tj")]);
+    let output = dir.path().join("merged.pdf");
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--dedupe-pages", "-i"]).arg(&input_dir).arg("-o").arg(&output)
+        .assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    assert!(stdout.contains("已去除重复页: 1"), "stdout: {}", stdout);
+    assert_eq!(page_contents(&output), ["(p1) Tj", "(p2) Tj", "(p3) Tj"]);
+
+    // Off by default
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--force", "-i"]).arg(&input_dir).arg("-o").arg(&output)
+        .assert().success();
+    assert_eq!(page_count(&output), 4);
+}
+
+#[test]
+fn info_formats_csv_and_json_for_several_files() {
+    let dir = tempdir().unwrap();
+    let a = create_pdf(dir.path(), "a.pdf", 2);
+    let b = create_pdf(dir.path(), "b.pdf", 3);
+    set_info(&a, "Title", "Annual, Report");
+    set_info(&a, "Author", "Finance");
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["info", "--format", "csv", "-i"]).arg(&a).arg("-i").arg(&b)
+        .assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    let mut reader = csv::Reader::from_reader(stdout.as_bytes());
+    assert_eq!(reader.headers().unwrap(), vec!["path", "pages", "version", "title", "author", "size_bytes"]);
+    let rows: Vec<csv::StringRecord> = reader.records().map(Result::unwrap).collect();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(&rows[0][0], a.display().to_string());
+    assert_eq!((&rows[0][1], &rows[0][3], &rows[0][4]), ("2", "Annual, Report", "Finance"));
+    assert_eq!((&rows[1][1], &rows[1][3]), ("3", ""));
+    assert_eq!(rows[1][5].parse::<u64>().unwrap(), fs::metadata(&b).unwrap().len());
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["info", "--output-format", "json", "-i"]).arg(&a).arg("-i").arg(&b)
+        .assert().success();
+    let json: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+    let docs = json.as_array().unwrap();
+    assert_eq!(docs.len(), 2);
+    assert_eq!(docs[0]["page_count"], 2);
+    assert_eq!(docs[0]["author"], "Finance");
+    assert_eq!(docs[1]["pages"].as_array().unwrap().len(), 3);
+
+    // One file: a plain object, as --json always printed
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["info", "--json", "-i"]).arg(&b)
+        .assert().success();
+    let json: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+    assert_eq!(json["page_count"], 3);
+}
+
+#[test]
+fn split_directory_with_parallel_jobs() {
+    let dir = tempdir().unwrap();
+    let input_dir = dir.path().join("in");
+    fs::create_dir_all(&input_dir).unwrap();
+    for (name, pages) in [("a.pdf", 2), ("b.pdf", 3), ("c.pdf", 1)] {
+        create_pdf(&input_dir, name, pages);
+    }
+    let out_dir = dir.path().join("out");
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "--jobs", "3", "--quiet", "--mode", "every:2", "-i"]).arg(&input_dir).arg("-d").arg(&out_dir)
+        .assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    assert!(stdout.contains("成功 3，失败 0"), "stdout: {}", stdout);
+
+    let mut names: Vec<String> = fs::read_dir(&out_dir).unwrap().map(|e| e.unwrap().file_name().to_string_lossy().into_owned()).collect();
+    names.sort();
+    assert_eq!(names, ["a-1-2.pdf", "b-1-2.pdf", "b-3-3.pdf", "c-1-1.pdf"]);
+    let counts: Vec<usize> = names.iter().map(|n| page_count(&out_dir.join(n))).collect();
+    assert_eq!(counts, [2, 2, 1, 1]);
+}
+
+#[test]
+fn split_directory_mirror_keeps_subdirectories() {
+    let dir = tempdir().unwrap();
+    let input_dir = dir.path().join("in");
+    for sub in ["a", "b"] {
+        fs::create_dir_all(input_dir.join(sub)).unwrap();
+        create_pdf(&input_dir.join(sub), "doc.pdf", 2);
+    }
+    create_pdf(&input_dir, "top.pdf", 1);
+    // An earlier run's output inside the input tree is not split again
+    let out_dir = input_dir.join("out");
+    fs::create_dir_all(&out_dir).unwrap();
+    create_pdf(&out_dir, "old.pdf", 1);
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "--quiet", "--output-dir-structure", "mirror", "-i"]).arg(&input_dir).arg("-d").arg(&out_dir)
+        .assert().success();
+
+    let names = |d: &std::path::Path| {
+        let mut names: Vec<String> = fs::read_dir(d).unwrap().map(|e| e.unwrap().file_name().to_string_lossy().into_owned()).collect();
+        names.sort();
+        names
+    };
+    assert_eq!(names(&out_dir), ["a", "b", "old.pdf", "top-1-1.pdf"]);
+    assert_eq!(names(&out_dir.join("a")), ["doc-1-1.pdf", "doc-2-2.pdf"]);
+    assert_eq!(names(&out_dir.join("b")), ["doc-1-1.pdf", "doc-2-2.pdf"]);
+}
+
+#[test]
+fn split_directory_pages_for_prefilters_matching_files() {
+    let dir = tempdir().unwrap();
+    let input_dir = dir.path().join("in");
+    fs::create_dir_all(&input_dir).unwrap();
+    create_pdf_with_contents(&input_dir, "book-cover.pdf", &[Some(b"(C1) Tj"), Some(b"(C2) Tj"), Some(b"(C3) Tj")]);
+    create_pdf(&input_dir, "book.pdf", 2);
+    let out_dir = dir.path().join("out");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "--quiet", "--pages-for", "*cover*=2-3", "-i"]).arg(&input_dir).arg("-d").arg(&out_dir)
+        .assert().success();
+
+    // Names carry the source page numbers, not positions within the selection
+    let mut names: Vec<String> = fs::read_dir(&out_dir).unwrap().map(|e| e.unwrap().file_name().to_string_lossy().into_owned()).collect();
+    names.sort();
+    assert_eq!(names, ["book-1-1.pdf", "book-2-2.pdf", "book-cover-2-2.pdf", "book-cover-3-3.pdf"]);
+    assert_eq!(page_contents(&out_dir.join("book-cover-2-2.pdf")), ["(C2) Tj"]);
+}
+
+#[test]
+fn section_selects_bookmarked_pages() {
+    let dir = tempdir().unwrap();
+    let input_dir = dir.path().join("in");
+    fs::create_dir_all(&input_dir).unwrap();
+    let contents: Vec<Option<&[u8]>> = vec![Some(b"(P1) Tj"), Some(b"(P2) Tj"), Some(b"(P3) Tj"), Some(b"(P4) Tj"), Some(b"(P5) Tj")];
+    let report = create_pdf_with_contents(&input_dir, "report.pdf", &contents);
+    let mut doc = Document::load(&report).unwrap();
+    let pages = doc.get_pages();
+    for (title, page) in [("Intro", 1), ("Results", 3), ("Discussion", 5)] {
+        doc.add_bookmark(lopdf::Bookmark::new(title.into(), [0.0; 3], 0, pages[&page]), None);
+    }
+    let outline_id = doc.build_outline().unwrap();
+    doc.catalog_mut().unwrap().set("Outlines", outline_id);
+    doc.save(&report).unwrap();
+
+    let merged = dir.path().join("results.pdf");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--section", "Results", "-i"]).arg(&input_dir).arg("-o").arg(&merged)
+        .assert().success();
+    assert_eq!(page_contents(&merged), ["(P3) Tj", "(P4) Tj"]);
+
+    let out_dir = dir.path().join("parts");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "--quiet", "--section", "results", "-i"]).arg(&report).arg("-d").arg(&out_dir)
+        .assert().success();
+    let mut names: Vec<String> = fs::read_dir(&out_dir).unwrap().map(|e| e.unwrap().file_name().to_string_lossy().into_owned()).collect();
+    names.sort();
+    assert_eq!(names, ["report-3-3.pdf", "report-4-4.pdf"]);
+    assert_eq!(page_contents(&out_dir.join("report-4-4.pdf")), ["(P4) Tj"]);
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--force", "--section", "Methods", "-i"]).arg(&input_dir).arg("-o").arg(&merged)
+        .assert().failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("未找到章节") && stderr.contains("\"Results\""), "{stderr}");
+}
+
+#[test]
+fn merge_renumber_labels_and_stamps_across_inputs() {
+    let dir = tempdir().unwrap();
+    let input_dir = dir.path().join("in");
+    fs::create_dir_all(&input_dir).unwrap();
+    // The first input numbers itself i, ii; the merge must not keep that
+    let front = create_pdf_with_contents(&input_dir, "a.pdf", &[Some(b"(A1) Tj"), Some(b"(A2) Tj")]);
+    let mut doc = Document::load(&front).unwrap();
+    let mut roman = Dictionary::new();
+    roman.set("S", "r");
+    let mut labels = Dictionary::new();
+    labels.set("Nums", vec![0.into(), roman.into()]);
+    doc.catalog_mut().unwrap().set("PageLabels", labels);
+    doc.save(&front).unwrap();
+    create_pdf_with_contents(&input_dir, "b.pdf", &[Some(b"(B1) Tj"), Some(b"(B2) Tj")]);
+
+    let merged = dir.path().join("merged.pdf");
+    let sidecar = dir.path().join("merged.json");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--renumber", "both", "-i"]).arg(&input_dir).arg("-o").arg(&merged)
+        .arg("--output-metadata-file").arg(&sidecar)
+        .assert().success();
+    let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&sidecar).unwrap()).unwrap();
+    assert_eq!(json["page_labels"], serde_json::json!(["1", "2", "3", "4"]));
+    let stamped: Vec<bool> = page_contents(&merged).iter().enumerate()
+        .map(|(i, c)| c.contains(&format!("({}) Tj", i + 1)))
+        .collect();
+    assert_eq!(stamped, [true; 4]);
+
+    // Labels only, from another start
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--force", "--renumber", "--start-at", "5", "-i"]).arg(&input_dir).arg("-o").arg(&merged)
+        .arg("--output-metadata-file").arg(&sidecar)
+        .assert().success();
+    let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&sidecar).unwrap()).unwrap();
+    assert_eq!(json["page_labels"], serde_json::json!(["5", "6", "7", "8"]));
+    assert_eq!(page_contents(&merged)[2], "(B1) Tj");
+}
+
+// Without a display there is nothing to open: both commands still succeed and only warn
+#[cfg(all(unix, not(target_os = "macos")))]
+#[test]
+fn open_flag_warns_in_headless_session() {
+    let dir = tempdir().unwrap();
+    let input_dir = dir.path().join("in");
+    fs::create_dir_all(&input_dir).unwrap();
+    let input = create_pdf(&input_dir, "a.pdf", 2);
+    let merged = dir.path().join("merged.pdf");
+    let out_dir = dir.path().join("parts");
+
+    let runs = [
+        vec!["merge".into(), "--open".into(), "-i".into(), input_dir.clone().into_os_string(), "-o".into(), merged.clone().into_os_string()],
+        vec!["split".into(), "--open-after".into(), "-i".into(), input.into_os_string(), "-d".into(), out_dir.clone().into_os_string()],
+    ];
+    for args in runs {
+        let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+            .args(&args).env_remove("DISPLAY").env_remove("WAYLAND_DISPLAY")
+            .assert().success();
+        let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+        assert!(stderr.contains("无法打开") && stderr.contains("headless"), "{stderr}");
+    }
+    assert_eq!(page_count(&merged), 2);
+    assert!(out_dir.join("a-2-2.pdf").exists());
+}
+
+// Catalog /Metadata XMP stream, plus one on page 1
+fn add_xmp(path: &std::path::Path, packet: &[u8]) {
+    let mut doc = Document::load(path).unwrap();
+    let mut dict = Dictionary::new();
+    dict.set("Type", "Metadata");
+    dict.set("Subtype", "XML");
+    let id = doc.add_object(lopdf::Stream::new(dict.clone(), packet.to_vec()));
+    doc.catalog_mut().unwrap().set("Metadata", id);
+    let page_xmp = doc.add_object(lopdf::Stream::new(dict, b"<page/>".to_vec()));
+    let page1 = doc.get_pages()[&1];
+    doc.get_dictionary_mut(page1).unwrap().set("Metadata", page_xmp);
+    doc.save(path).unwrap();
+}
+
+// (catalog XMP stream data and whether it has a /Filter, number of page-level /Metadata)
+fn xmp_state(path: &std::path::Path) -> (Option<(Vec<u8>, bool)>, usize) {
+    let doc = Document::load(path).unwrap();
+    let catalog = doc.catalog().unwrap().get(b"Metadata").ok().map(|r| {
+        let s = doc.get_object(r.as_reference().unwrap()).unwrap().as_stream().unwrap();
+        (s.content.clone(), s.dict.has(b"Filter"))
+    });
+    let pages = doc.get_pages().values().filter(|&&id| doc.get_dictionary(id).unwrap().has(b"Metadata")).count();
+    (catalog, pages)
+}
+
+#[test]
+fn xmp_is_copied_or_stripped_on_request() {
+    let dir = tempdir().unwrap();
+    let input_dir = dir.path().join("in");
+    fs::create_dir_all(&input_dir).unwrap();
+    let packet: &[u8] = b"<?xpacket begin=\"\"?><x:xmpmeta xmlns:x=\"adobe:ns:meta/\"/><?xpacket end=\"w\"?>";
+    add_xmp(&create_pdf(&input_dir, "a.pdf", 2), packet);
+    create_pdf(&input_dir, "b.pdf", 1);
+    let merge = |name: &str, flag: Option<&str>| {
+        let out = dir.path().join(name);
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")));
+        cmd.args(["merge", "-i"]).arg(&input_dir).arg("-o").arg(&out);
+        if let Some(flag) = flag { cmd.arg(flag); }
+        cmd.assert().success();
+        xmp_state(&out)
+    };
+
+    assert_eq!(merge("plain.pdf", None), (None, 1));
+    assert_eq!(merge("copied.pdf", Some("--copy-xmp")), (Some((packet.to_vec(), false)), 1));
+    assert_eq!(merge("stripped.pdf", Some("--strip-xmp")), (None, 0));
+
+    let out_dir = dir.path().join("parts");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "--quiet", "--copy-xmp", "-i"]).arg(input_dir.join("a.pdf")).arg("-d").arg(&out_dir)
+        .assert().success();
+    assert_eq!(xmp_state(&out_dir.join("a-2-2.pdf")), (Some((packet.to_vec(), false)), 0));
+}
+
+#[test]
+fn zip_interleaves_front_and_reversed_back() {
+    let dir = tempdir().unwrap();
+    let front = create_pdf_with_contents(dir.path(), "front.pdf", &[Some(b"(F1) Tj"), Some(b"(F2) Tj"), Some(b"(F3) Tj")]);
+    // Second pass through the feeder with the stack flipped: last back side first
+    let back = create_pdf_with_contents(dir.path(), "back.pdf", &[Some(b"(B3) Tj"), Some(b"(B2) Tj"), Some(b"(B1) Tj")]);
+    let output = dir.path().join("duplex.pdf");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .arg("zip").arg(&front).arg(&back).arg("--reverse-back").arg("-o").arg(&output)
+        .assert().success();
+    assert_eq!(page_contents(&output), ["(F1) Tj", "(B1) Tj", "(F2) Tj", "(B2) Tj", "(F3) Tj", "(B3) Tj"]);
+
+    // Exists without --force; mismatched page counts
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .arg("zip").arg(&front).arg(&back).arg("-o").arg(&output)
+        .assert().failure();
+    let short = create_pdf(dir.path(), "short.pdf", 2);
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .arg("zip").arg(&front).arg(&short).arg("--force").arg("-o").arg(&output)
+        .assert().failure();
+}
+
+#[test]
+fn pdf_version_sets_output_header() {
+    let dir = tempdir().unwrap();
+    let in_dir = dir.path().join("in");
+    std::fs::create_dir_all(&in_dir).unwrap();
+    let input = create_pdf(&in_dir, "a.pdf", 2);
+    let header = |p: &std::path::Path| std::fs::read(p).unwrap()[..8].to_vec();
+
+    let merged = dir.path().join("merged.pdf");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "-i"]).arg(&in_dir).arg("-o").arg(&merged)
+        .args(["--pdf-version", "1.4"])
+        .assert().success();
+    assert_eq!(header(&merged), b"%PDF-1.4");
+    // 1.4 has no cross-reference streams, so the file must still load as a classic table
+    assert_eq!(page_count(&merged), 2);
+
+    let out_dir = dir.path().join("parts");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "-i"]).arg(&input).arg("-d").arg(&out_dir)
+        .args(["--pdf-version", "1.7"])
+        .assert().success();
+    assert_eq!(header(&out_dir.join("a-1-1.pdf")), b"%PDF-1.7");
+
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "-i"]).arg(&in_dir).arg("-o").arg(&merged).arg("--force")
+        .args(["--pdf-version", "2.0"])
+        .assert().failure();
+}
+
+// Embedded file names listed in the catalog's flat /Names /EmbeddedFiles tree
+fn embedded_file_names(path: &std::path::Path) -> Vec<String> {
+    let doc = Document::load(path).unwrap();
+    let Ok(names) = doc.catalog().unwrap().get(b"Names").and_then(Object::as_reference) else { return Vec::new() };
+    let tree = doc.get_dictionary(names).unwrap().get(b"EmbeddedFiles").unwrap().as_dict().unwrap();
+    tree.get(b"Names").unwrap().as_array().unwrap().chunks(2)
+        .map(|pair| String::from_utf8(pair[0].as_str().unwrap().to_vec()).unwrap())
+        .collect()
+}
+
+#[test]
+fn merge_attachments_keep_preserves_embedded_files() {
+    use lopdf::dictionary;
+    let dir = tempdir().unwrap();
+    let in_dir = dir.path().join("in");
+    fs::create_dir_all(&in_dir).unwrap();
+    for name in ["a.pdf", "b.pdf"] {
+        let path = create_pdf(&in_dir, name, 1);
+        let mut doc = Document::load(&path).unwrap();
+        let file = doc.add_object(lopdf::Stream::new(dictionary! { "Type" => "EmbeddedFile" }, b"id,total\n1,42\n".to_vec()));
+        let spec = doc.add_object(dictionary! {
+            "Type" => "Filespec", "F" => Object::string_literal("data.csv"), "EF" => dictionary! { "F" => file },
+        });
+        let names = doc.add_object(dictionary! {
+            "EmbeddedFiles" => dictionary! { "Names" => vec![Object::string_literal("data.csv"), spec.into()] },
+        });
+        doc.catalog_mut().unwrap().set("Names", names);
+        doc.save(&path).unwrap();
+    }
+
+    let output = dir.path().join("merged.pdf");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "-i"]).arg(&in_dir).arg("-o").arg(&output)
+        .args(["--attachments", "keep"])
+        .assert().success();
+    assert_eq!(embedded_file_names(&output), ["data (2).csv", "data.csv"]);
+
+    // The attached bytes come along
+    let doc = Document::load(&output).unwrap();
+    let has_payload = doc.objects.values().any(|o| matches!(o, Object::Stream(s) if s.decompressed_content().unwrap_or_else(|_| s.content.clone()) == b"id,total\n1,42\n"));
+    assert!(has_payload);
+
+    // Default: the trees are not carried over
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "-i"]).arg(&in_dir).arg("-o").arg(&output).arg("--force")
+        .assert().success();
+    assert!(embedded_file_names(&output).is_empty());
+}
+
+#[test]
+fn merge_max_file_size_skips_large_input() {
+    let dir = tempdir().unwrap();
+    let in_dir = dir.path().join("in");
+    fs::create_dir_all(&in_dir).unwrap();
+    let small = create_pdf(&in_dir, "a.pdf", 1);
+    let large = create_pdf(&in_dir, "b.pdf", 40);
+    let (small_len, large_len) = (fs::metadata(&small).unwrap().len(), fs::metadata(&large).unwrap().len());
+    let limit = ((small_len + large_len) / 2).to_string();
+    let output = dir.path().join("merged.pdf");
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "-i"]).arg(&in_dir).arg("-o").arg(&output)
+        .args(["--max-file-size", &limit])
+        .assert().failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).to_string();
+    assert!(stderr.contains("--max-file-size"), "stderr: {}", stderr);
+    assert!(!output.exists());
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "-i"]).arg(&in_dir).arg("-o").arg(&output)
+        .args(["--max-file-size", &limit, "--skip-errors"])
+        .assert().success();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).to_string();
+    assert!(stderr.contains("已跳过") && stderr.contains("b.pdf"), "stderr: {}", stderr);
+    assert_eq!(page_count(&output), 1);
+}
+
+#[test]
+fn scan_lists_pdfs_as_lines_or_json() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    create_pdf(root, "a.pdf", 2);
+    fs::create_dir_all(root.join("sub/deeper")).unwrap();
+    create_pdf(&root.join("sub"), "b.pdf", 3);
+    create_pdf(&root.join("sub/deeper"), "c.pdf", 1);
+    fs::write(root.join("notes.txt"), "not a pdf").unwrap();
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["scan", "-i"]).arg(root)
+        .assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    let mut names: Vec<&str> = stdout.lines().map(|l| l.rsplit(['/', '\\']).next().unwrap()).collect();
+    names.sort();
+    assert_eq!(names, ["a.pdf", "b.pdf", "c.pdf"]);
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["scan", "-i"]).arg(root)
+        .args(["--depth", "2", "--json", "--with-pages"])
+        .assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    let json: serde_json::Value = serde_json::from_str(&stdout).expect("valid JSON");
+    let mut found: Vec<(String, u64)> = json.as_array().unwrap().iter().map(|e| {
+        let path = e["path"].as_str().unwrap();
+        assert!(e["size_bytes"].as_u64().unwrap() > 0);
+        (path.rsplit(['/', '\\']).next().unwrap().to_string(), e["pages"].as_u64().unwrap())
+    }).collect();
+    found.sort();
+    assert_eq!(found, [("a.pdf".to_string(), 2), ("b.pdf".to_string(), 3)]);
+}
+
+fn trailer_id(path: &std::path::Path) -> Vec<Vec<u8>> {
+    let doc = Document::load(path).unwrap();
+    doc.trailer.get(b"ID").unwrap().as_array().unwrap().iter().map(|p| p.as_str().unwrap().to_vec()).collect()
+}
+
+#[test]
+fn merge_trailer_id_random_deterministic_and_kept() {
+    let dir = tempdir().unwrap();
+    let in_dir = dir.path().join("in");
+    fs::create_dir_all(&in_dir).unwrap();
+    create_pdf(&in_dir, "a.pdf", 1);
+    create_pdf(&in_dir, "b.pdf", 2);
+    let merge = |out: &str, extra: &[&str]| {
+        let output = dir.path().join(out);
+        Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+            .args(["merge", "-i"]).arg(&in_dir).arg("-o").arg(&output).args(extra)
+            .assert().success();
+        output
+    };
+
+    let random = trailer_id(&merge("r.pdf", &["--trailer-id"]));
+    assert_eq!(random.len(), 2);
+    assert_eq!(random[0].len(), 16);
+    let (d1, d2) = (merge("d1.pdf", &["--deterministic"]), merge("d2.pdf", &["--deterministic"]));
+    assert_eq!(trailer_id(&d1), trailer_id(&d2));
+    assert_ne!(trailer_id(&d1), random);
+    assert!(Document::load(merge("none.pdf", &[])).unwrap().trailer.get(b"ID").is_err());
+
+    // The first input's own /ID survives --metadata first
+    let first = in_dir.join("a.pdf");
+    let mut doc = Document::load(&first).unwrap();
+    let id = Object::string_literal("0123456789abcdef");
+    doc.trailer.set("ID", vec![id.clone(), id]);
+    doc.save(&first).unwrap();
+    let kept = trailer_id(&merge("kept.pdf", &["--metadata", "first", "--deterministic"]));
+    assert_eq!(kept, [b"0123456789abcdef".to_vec(), b"0123456789abcdef".to_vec()]);
+}
+
+#[test]
+fn merge_check_compares_without_writing() {
+    let dir = tempdir().unwrap();
+    let input_dir = dir.path().join("in");
+    fs::create_dir_all(&input_dir).unwrap();
+    create_pdf(&input_dir, "a.pdf", 2);
+    create_pdf(&input_dir, "b.pdf", 1);
+    let output = dir.path().join("merged.pdf");
+    let merge = |extra: &[&str]| {
+        Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+            .arg("merge").args(extra).arg("-i").arg(&input_dir).arg("-o").arg(&output)
+            .assert()
+    };
+
+    // No output yet counts as a change
+    merge(&["--check"]).code(1);
+    assert!(!output.exists());
+
+    merge(&[]).success();
+    let written = fs::read(&output).unwrap();
+    merge(&["--check"]).success();
+    merge(&["--check", "pages"]).success();
+
+    create_pdf(&input_dir, "c.pdf", 1);
+    let assert = merge(&["--check"]).code(1);
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("3 -> 4"), "{stdout}");
+    assert_eq!(fs::read(&output).unwrap(), written);
+}
+
+#[test]
+fn glob_case_insensitive_matches_differently_cased_names() {
+    let dir = tempdir().unwrap();
+    let input_dir = dir.path().join("in");
+    fs::create_dir_all(&input_dir).unwrap();
+    create_pdf(&input_dir, "Report.PDF", 2);
+    create_pdf(&input_dir, "notes.pdf", 1);
+    let scan = |extra: &[&str]| {
+        let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+            .args(["scan", "--include", "report*"]).args(extra).arg("-i").arg(&input_dir)
+            .assert().success();
+        String::from_utf8_lossy(&assert.get_output().stdout).into_owned()
+    };
+    assert!(!scan(&[]).contains("Report.PDF"));
+    let found = scan(&["--glob-case-insensitive"]);
+    assert!(found.contains("Report.PDF") && !found.contains("notes.pdf"), "{found}");
+
+    let output = dir.path().join("merged.pdf");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--no-passthrough", "--glob-case-insensitive", "--include", "*.pdf", "-i"]).arg(&input_dir).arg("-o").arg(&output)
+        .assert().success();
+    assert_eq!(page_count(&output), 3);
+}
+
+#[test]
+fn contains_selects_only_pdfs_showing_the_text() {
+    let dir = tempdir().unwrap();
+    let input_dir = dir.path().join("in");
+    fs::create_dir_all(&input_dir).unwrap();
+    create_pdf_with_contents(&input_dir, "a.pdf", &[Some(b"BT /F1 12 Tf 10 10 Td (Receipt) Tj ET")]);
+    create_pdf_with_contents(&input_dir, "b.pdf", &[Some(b"BT /F1 12 Tf 10 10 Td (Invoice 42) Tj ET"), None]);
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["scan", "--contains", "invoice", "-i"]).arg(&input_dir)
+        .assert().success();
+    let found = String::from_utf8_lossy(&assert.get_output().stdout).into_owned();
+    assert!(found.contains("b.pdf") && !found.contains("a.pdf"), "{found}");
+
+    let output = dir.path().join("merged.pdf");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--no-passthrough", "--content-search", "INVOICE", "-i"]).arg(&input_dir).arg("-o").arg(&output)
+        .assert().success();
+    assert_eq!(page_count(&output), 2);
+}
+
+#[test]
+fn extract_text_prints_selected_pages() {
+    let dir = tempdir().unwrap();
+    let pdf = create_pdf_with_contents(dir.path(), "in.pdf", &[
+        Some(b"BT /F1 12 Tf 10 20 Td (Invoice ) Tj [(No) -300 (42)] TJ 0 -14 Td (Due soon) Tj ET"),
+        Some(b"BT /F1 12 Tf 10 20 Td (Total: 99) Tj ET"),
+    ]);
+    let run = |extra: &[&str]| {
+        let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+            .args(["extract-text", "-i"]).arg(&pdf).args(extra)
+            .assert().success();
+        String::from_utf8_lossy(&assert.get_output().stdout).into_owned()
+    };
+    let all = run(&[]);
+    assert!(all.contains("Invoice No 42\nDue soon") && all.contains("Total: 99"), "{all}");
+    let first = run(&["--pages", "1"]);
+    assert!(first.contains("Invoice No 42") && !first.contains("Total"), "{first}");
+
+    let json: serde_json::Value = serde_json::from_str(&run(&["--pages", "2", "--json"])).unwrap();
+    assert_eq!(json, serde_json::json!([{ "page": 2, "text": "Total: 99" }]));
+}
+
+#[test]
+fn keep_open_action_carries_first_input_navigation() {
+    let dir = tempdir().unwrap();
+    let input_dir = dir.path().join("in");
+    fs::create_dir_all(&input_dir).unwrap();
+    let a = create_pdf(&input_dir, "a.pdf", 2);
+    create_pdf(&input_dir, "b.pdf", 1);
+    let mut doc = Document::load(&a).unwrap();
+    let second = *doc.get_pages().get(&2).unwrap();
+    let catalog = doc.catalog_mut().unwrap();
+    catalog.set("PageLayout", "TwoColumnLeft");
+    catalog.set("PageMode", "UseThumbs");
+    catalog.set("OpenAction", vec![Object::Reference(second), "Fit".into()]);
+    doc.save(&a).unwrap();
+
+    let merge = |extra: &[&str]| {
+        let output = dir.path().join("merged.pdf");
+        Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+            .args(["merge", "--force", "-i"]).arg(&input_dir).arg("-o").arg(&output).args(extra)
+            .assert().success();
+        Document::load(&output).unwrap()
+    };
+    assert!(!merge(&[]).catalog().unwrap().has(b"PageLayout"));
+
+    let out = merge(&["--keep-open-action", "--strip-javascript"]);
+    let catalog = out.catalog().unwrap();
+    assert_eq!(catalog.get(b"PageLayout").unwrap().as_name_str().unwrap(), "TwoColumnLeft");
+    assert_eq!(catalog.get(b"PageMode").unwrap().as_name_str().unwrap(), "UseThumbs");
+    let dest = catalog.get(b"OpenAction").unwrap().as_array().unwrap();
+    assert_eq!(dest[0].as_reference().unwrap(), *out.get_pages().get(&2).unwrap());
+}
+
+#[test]
+fn keep_viewer_preferences_keeps_reading_direction() {
+    use lopdf::dictionary;
+    let dir = tempdir().unwrap();
+    let input_dir = dir.path().join("in");
+    fs::create_dir_all(&input_dir).unwrap();
+    let a = create_pdf(&input_dir, "a.pdf", 1);
+    create_pdf(&input_dir, "b.pdf", 1);
+    let mut doc = Document::load(&a).unwrap();
+    let prefs = doc.add_object(dictionary! { "Direction" => "R2L", "DisplayDocTitle" => true });
+    doc.catalog_mut().unwrap().set("ViewerPreferences", prefs);
+    doc.save(&a).unwrap();
+
+    let merge = |extra: &[&str]| {
+        let output = dir.path().join("merged.pdf");
+        Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+            .args(["merge", "--force", "-i"]).arg(&input_dir).arg("-o").arg(&output).args(extra)
+            .assert().success();
+        Document::load(&output).unwrap()
+    };
+    assert!(!merge(&[]).catalog().unwrap().has(b"ViewerPreferences"));
+
+    let out = merge(&["--keep-viewer-preferences"]);
+    let prefs = out.catalog().unwrap().get(b"ViewerPreferences").unwrap().as_dict().unwrap();
+    assert_eq!(prefs.get(b"Direction").unwrap().as_name_str().unwrap(), "R2L");
+    assert!(prefs.get(b"DisplayDocTitle").unwrap().as_bool().unwrap());
+}
+
+#[test]
+fn large_jobs_need_yes_when_not_interactive() {
+    let dir = tempdir().unwrap();
+    let input = create_pdf(dir.path(), "big.pdf", 5);
+    let out_dir = dir.path().join("parts");
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "--confirm-threshold", "3", "-i"]).arg(&input).arg("-d").arg(&out_dir)
+        .assert().failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("--yes"), "{stderr}");
+    assert!(fs::read_dir(&out_dir).map_or(true, |d| d.count() == 0));
+
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "--confirm-threshold", "3", "--yes", "-i"]).arg(&input).arg("-d").arg(&out_dir)
+        .assert().success();
+    assert_eq!(fs::read_dir(&out_dir).unwrap().count(), 5);
+
+    let named_dir = dir.path().join("named");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "--confirm-threshold", "1", "--out", "a=1", "--out", "b=2-", "-i"]).arg(&input).arg("-d").arg(&named_dir)
+        .assert().failure();
+    assert!(fs::read_dir(&named_dir).map_or(true, |d| d.count() == 0));
+
+    let output = dir.path().join("merged.pdf");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--confirm-threshold", "4", "-i"]).arg(&out_dir).arg("-o").arg(&output)
+        .assert().failure();
+    assert!(!output.exists());
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--confirm-threshold", "4", "-y", "-i"]).arg(&out_dir).arg("-o").arg(&output)
+        .assert().success();
+    assert_eq!(page_count(&output), 5);
+}
+
+#[test]
+fn rotate_auto_turns_only_pages_off_target() {
+    let dir = tempdir().unwrap();
+    let input_dir = dir.path().join("in");
+    fs::create_dir_all(&input_dir).unwrap();
+    let pdf = create_pdf(&input_dir, "mixed.pdf", 2);
+    let mut doc = Document::load(&pdf).unwrap();
+    let pages: Vec<ObjectId> = doc.get_pages().into_values().collect();
+    for (pid, (w, h)) in pages.iter().zip([(842, 595), (595, 842)]) {
+        doc.get_dictionary_mut(*pid).unwrap().set("MediaBox", vec![0.into(), 0.into(), w.into(), h.into()]);
+    }
+    doc.save(&pdf).unwrap();
+
+    let output = dir.path().join("merged.pdf");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--rotate-auto", "--target", "portrait", "-i"]).arg(&input_dir).arg("-o").arg(&output)
+        .assert().success();
+    let out = Document::load(&output).unwrap();
+    let rotations: Vec<i64> = out.get_pages().into_values()
+        .map(|pid| out.get_dictionary(pid).unwrap().get(b"Rotate").and_then(Object::as_i64).unwrap_or(0))
+        .collect();
+    assert_eq!(rotations, [90, 0]);
+}
+
+#[test]
+fn normalize_centers_or_scales_pages_to_target_size() {
+    let dir = tempdir().unwrap();
+    let pdf = create_pdf_with_contents(dir.path(), "small.pdf", &[Some(b"0 0 m 200 200 l S")]);
+    for (align, media_box) in [("center", [-100.0, -100.0, 300.0, 300.0]), ("scale", [0.0, 0.0, 400.0, 400.0])] {
+        let output = dir.path().join(format!("{align}.pdf"));
+        Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+            .args(["merge", "--normalize", "400x400", "--align-page-size", align, "-i"]).arg(&pdf).arg("-o").arg(&output)
+            .assert().success();
+        let out = Document::load(&output).unwrap();
+        let pid = *out.get_pages().values().next().unwrap();
+        let mb: Vec<f32> = out.get_dictionary(pid).unwrap().get(b"MediaBox").unwrap().as_array().unwrap()
+            .iter().map(|o| o.as_float().unwrap()).collect();
+        assert_eq!(mb, media_box, "{align}");
+        let contents = page_contents(&output).concat();
+        assert_eq!(contents.contains("2 0 0 2 0 0 cm"), align == "scale", "{contents}");
+    }
+}
+
+#[test]
+fn validate_reports_wrong_page_count() {
+    let dir = tempdir().unwrap();
+    let good = create_pdf(dir.path(), "good.pdf", 3);
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["validate", "-i"]).arg(&good)
+        .assert().success();
+    assert!(String::from_utf8_lossy(&assert.get_output().stdout).contains("未发现结构问题"));
+
+    let bad = create_pdf(dir.path(), "bad.pdf", 3);
+    let mut doc = Document::load(&bad).unwrap();
+    let pages_id = doc.catalog().unwrap().get(b"Pages").unwrap().as_reference().unwrap();
+    doc.get_dictionary_mut(pages_id).unwrap().set("Count", 5);
+    doc.save(&bad).unwrap();
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["validate", "-i"]).arg(&bad)
+        .assert().code(1);
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains(&format!("{} {} R: /Count 为 5，实际页数为 3", pages_id.0, pages_id.1)), "{stdout}");
+}
+
+#[test]
+fn separators_go_between_inputs() {
+    let dir = tempdir().unwrap();
+    let input_dir = dir.path().join("in");
+    fs::create_dir_all(&input_dir).unwrap();
+    for name in ["a.pdf", "b.pdf", "c.pdf"] {
+        create_pdf_with_contents(&input_dir, name, &[Some(b"(doc) Tj")]);
+    }
+    let output = dir.path().join("merged.pdf");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--separator-blank", "-i"]).arg(&input_dir).arg("-o").arg(&output)
+        .assert().success();
+    assert_eq!(page_count(&output), 5);
+    let doc = Document::load(&output).unwrap();
+    for (n, pid) in doc.get_pages() {
+        let has_content = doc.get_dictionary(pid).unwrap().has(b"Contents");
+        assert_eq!(has_content, n % 2 == 1, "page {n}");
+        let media: Vec<f32> = doc.get_dictionary(pid).unwrap().get(b"MediaBox").unwrap().as_array().unwrap().iter().map(|o| o.as_float().unwrap()).collect();
+        assert_eq!(media, [0.0, 0.0, 200.0, 200.0]);
+    }
+
+    let separator = create_pdf_with_contents(dir.path(), "sep.pdf", &[Some(b"(sep1) Tj"), Some(b"(sep2) Tj")]);
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--force", "--separator"]).arg(&separator).arg("-i").arg(&input_dir).arg("-o").arg(&output)
+        .assert().success();
+    assert_eq!(page_contents(&output), ["(doc) Tj", "(sep1) Tj", "(sep2) Tj", "(doc) Tj", "(sep1) Tj", "(sep2) Tj", "(doc) Tj"]);
+}
+
+#[test]
+fn completions_cover_subcommands() {
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["completions", "bash"])
+        .assert().success();
+    let script = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(script.contains("merge") && script.contains("split") && script.contains("--even-split-at-bookmarks"));
+}
+
+#[test]
+fn man_pages_document_subcommand_flags() {
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["man", "split"])
+        .assert().success();
+    let page = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(page.contains(".TH pdf-ops-split 1"), "{page}");
+    assert!(page.contains(r"\-\-ranges") && page.contains(r"\-\-pattern"));
+
+    let dir = tempdir().unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["man", "--out-dir"]).arg(dir.path())
+        .assert().success();
+    assert!(dir.path().join("pdf-ops.1").exists() && dir.path().join("pdf-ops-merge.1").exists());
+    assert!(!dir.path().join("pdf-ops-help.1").exists());
+}
+
+#[test]
+fn prefix_and_suffix_shape_generated_output_names() {
+    let dir = tempdir().unwrap();
+    let input_dir = dir.path().join("in");
+    fs::create_dir_all(input_dir.join("ch1")).unwrap();
+    create_pdf(&input_dir.join("ch1"), "a.pdf", 1);
+    create_pdf(&input_dir, "b.pdf", 2);
+    let out_dir = dir.path().join("outdir");
+
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--prefix", "a_", "--suffix", "_v1", "-i"]).arg(&input_dir)
+        .arg("-o").arg(format!("{}/", out_dir.display()))
+        .assert().success();
+    assert_eq!(page_count(&out_dir.join("a_merged_v1.pdf")), 3);
+
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--group-by-subdir", "--prefix", "archive_", "-i"]).arg(&input_dir).arg("-o").arg(&out_dir)
+        .assert().success();
+    assert_eq!(page_count(&out_dir.join("archive_ch1.pdf")), 1);
+
+    // A plain file name has nothing generated to decorate
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["merge", "--prefix", "a_", "-i"]).arg(&input_dir).arg("-o").arg(dir.path().join("x.pdf"))
+        .assert().failure();
+}
+
+// Stored (uncompressed) zip with the given entries, in order
+#[cfg(feature = "zip")]
+fn create_zip(path: &std::path::Path, entries: &[(&str, &[u8])]) {
+    fn crc32(data: &[u8]) -> u32 {
+        let mut crc = !0u32;
+        for &b in data {
+            crc ^= b as u32;
+            for _ in 0..8 { crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 }; }
+        }
+        !crc
+    }
+    let (mut out, mut central) = (Vec::new(), Vec::new());
+    for (name, data) in entries {
+        let (crc, len, offset) = (crc32(data), data.len() as u32, out.len() as u32);
+        out.extend(0x0403_4b50u32.to_le_bytes());
+        out.extend([20, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        out.extend(crc.to_le_bytes());
+        out.extend(len.to_le_bytes());
+        out.extend(len.to_le_bytes());
+        out.extend((name.len() as u16).to_le_bytes());
+        out.extend([0, 0]);
+        out.extend(name.as_bytes());
+        out.extend(*data);
+        central.extend(0x0201_4b50u32.to_le_bytes());
+        central.extend([20, 0, 20, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        central.extend(crc.to_le_bytes());
+        central.extend(len.to_le_bytes());
+        central.extend(len.to_le_bytes());
+        central.extend((name.len() as u16).to_le_bytes());
+        central.extend([0; 12]);
+        central.extend(offset.to_le_bytes());
+        central.extend(name.as_bytes());
+    }
+    let cd_offset = out.len() as u32;
+    out.extend(&central);
+    out.extend(0x0605_4b50u32.to_le_bytes());
+    out.extend([0, 0, 0, 0]);
+    out.extend((entries.len() as u16).to_le_bytes());
+    out.extend((entries.len() as u16).to_le_bytes());
+    out.extend((central.len() as u32).to_le_bytes());
+    out.extend(cd_offset.to_le_bytes());
+    out.extend([0, 0]);
+    fs::write(path, out).unwrap();
+}
+
+#[cfg(feature = "zip")]
+#[test]
+fn merge_from_zip_extracts_and_merges_in_order() {
+    let dir = tempdir().unwrap();
+    let src = dir.path().join("src");
+    fs::create_dir_all(&src).unwrap();
+    let two = create_pdf_with_contents(&src, "2.pdf", &[Some(b"% two")]);
+    let ten = create_pdf_with_contents(&src, "10.pdf", &[Some(b"% ten")]);
+    let skip = create_pdf_with_contents(&src, "skip.pdf", &[Some(b"% skip")]);
+    let archive = dir.path().join("scans.zip");
+    create_zip(&archive, &[
+        ("scans/10.pdf", &fs::read(&ten).unwrap()),
+        ("scans/readme.txt", b"not a pdf"),
+        ("scans/2.pdf", &fs::read(&two).unwrap()),
+        ("scans/skip.pdf", &fs::read(&skip).unwrap()),
+    ]);
+
+    let merge = |sort: &str, name: &str| {
+        let output = dir.path().join(name);
+        Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+            .args(["merge", "--exclude", "*skip*", "--sort-by", sort, "--from-zip"]).arg(&archive).arg("-o").arg(&output)
+            .assert().success();
+        page_contents(&output)
+    };
+    assert_eq!(merge("natural", "natural.pdf"), ["% two", "% ten"]);
+    assert_eq!(merge("scan", "entries.pdf"), ["% ten", "% two"]);
+}
+
+#[test]
+fn spill_output_matches_in_memory_merge() {
+    let dir = tempdir().unwrap();
+    let input_dir = dir.path().join("in");
+    fs::create_dir_all(&input_dir).unwrap();
+    create_pdf_with_contents(&input_dir, "a.pdf", &[Some(b"% a1"), Some(b"% a2")]);
+    create_pdf_with_contents(&input_dir, "b.pdf", &[Some(b"% b1")]);
+    create_pdf_with_contents(&input_dir, "c.pdf", &[Some(b"% c1"), None, Some(b"% c3")]);
+    let merge = |name: &str, extra: &[&str]| {
+        let output = dir.path().join(name);
+        Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+            .args(["merge", "--bookmark-files", "--renumber", "both", "--annotate-source"]).args(extra)
+            .arg("-i").arg(&input_dir).arg("-o").arg(&output)
+            .assert().success();
+        output
+    };
+    let (memory, spilled) = (merge("memory.pdf", &[]), merge("spilled.pdf", &["--spill"]));
+    assert!(!dir.path().join("spilled.pdf.spill").exists());
+
+    // Same objects under the same numbers; only their order in the file and the xref form differ
+    let load = |p: &std::path::Path| {
+        let mut doc = Document::load(p).unwrap();
+        // The in-memory output's cross-reference stream loads as an object too
+        doc.objects.retain(|_, o| o.type_name().ok() != Some("XRef"));
+        for obj in doc.objects.values_mut() {
+            if let lopdf::Object::Stream(s) = obj { s.start_position = None; }
+        }
+        doc
+    };
+    let (a, b) = (load(&memory), load(&spilled));
+    assert_eq!(a.objects.len(), b.objects.len());
+    for (id, obj) in &a.objects {
+        assert!(b.objects.get(id) == Some(obj), "object {:?} differs", id);
+    }
+    assert_eq!(a.trailer.get(b"Root").unwrap(), b.trailer.get(b"Root").unwrap());
+    assert_eq!(page_contents(&spilled), page_contents(&memory));
+    assert_eq!(page_count(&spilled), 6);
+}
+
+#[test]
+fn spilled_blank_separator_keeps_inherited_page_size() {
+    let dir = tempdir().unwrap();
+    let input_dir = dir.path().join("in");
+    fs::create_dir_all(&input_dir).unwrap();
+    // a.pdf's page takes its MediaBox from the page tree, which --spill moves out of memory
+    let a = create_pdf_with_contents(&input_dir, "a.pdf", &[Some(b"(a) Tj")]);
+    let mut doc = Document::load(&a).unwrap();
+    let pid = doc.get_pages()[&1];
+    let pages_id = doc.get_dictionary(pid).unwrap().get(b"Parent").unwrap().as_reference().unwrap();
+    doc.get_dictionary_mut(pid).unwrap().remove(b"MediaBox");
+    doc.get_dictionary_mut(pages_id).unwrap().set("MediaBox", vec![0.into(), 0.into(), 300.into(), 400.into()]);
+    doc.save(&a).unwrap();
+    create_pdf_with_contents(&input_dir, "b.pdf", &[Some(b"(b) Tj")]);
+
+    for (name, extra) in [("memory.pdf", &[][..]), ("spilled.pdf", &["--spill"][..])] {
+        let output = dir.path().join(name);
+        Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+            .args(["merge", "--separator-blank", "--preserve-page-boxes", "false"]).args(extra)
+            .arg("-i").arg(&input_dir).arg("-o").arg(&output)
+            .assert().success();
+        let doc = Document::load(&output).unwrap();
+        let separator = doc.get_dictionary(doc.get_pages()[&2]).unwrap();
+        let media: Vec<f32> = separator.get(b"MediaBox").unwrap().as_array().unwrap().iter().map(|o| o.as_float().unwrap()).collect();
+        assert_eq!(media, [0.0, 0.0, 300.0, 400.0], "{name}");
+    }
+}
+
+#[test]
+fn split_index_base_offsets_index_token() {
+    let dir = tempdir().unwrap();
+    let input = create_pdf(dir.path(), "doc.pdf", 3);
+    let out = dir.path().join("out");
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "--each", "--index-base", "100", "--pattern", "{base}-{index}.pdf", "-i"]).arg(&input).arg("-d").arg(&out)
+        .assert().success();
+    for n in [100, 101, 102] {
+        assert_eq!(page_count(&out.join(format!("doc-{n}.pdf"))), 1);
+    }
+
+    // Padding applies to the offset number
+    Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")))
+        .args(["split", "--each", "--output-index-base", "9", "--pattern", "p{index:03}.pdf", "-i"]).arg(&input).arg("-d").arg(&out)
+        .assert().success();
+    assert!(out.join("p009.pdf").exists() && out.join("p011.pdf").exists());
+}
+
+#[test]
+fn on_empty_policies_for_a_directory_without_pdfs() {
+    let dir = tempdir().unwrap();
+    let input_dir = dir.path().join("empty");
+    fs::create_dir_all(&input_dir).unwrap();
+    let output = dir.path().join("merged.pdf");
+    let merge = |policy: Option<&str>| {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!(env!("CARGO_PKG_NAME")));
+        cmd.arg("merge").arg("-i").arg(&input_dir).arg("-o").arg(&output);
+        if let Some(p) = policy { cmd.args(["--on-empty", p]); }
+        cmd.assert()
+    };
+
+    for policy in [None, Some("error")] {
+        let assert = merge(policy).failure();
+        assert!(String::from_utf8_lossy(&assert.get_output().stderr).contains("未在目录中找到 PDF"));
+    }
+
+    let assert = merge(Some("skip")).success();
+    assert!(assert.get_output().stdout.is_empty());
+    assert!(!output.exists());
+
+    merge(Some("touch")).success();
+    assert_eq!(page_count(&output), 0);
+}